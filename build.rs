@@ -1,4 +1,3 @@
-use std::ffi::OsString;
 use std::path::PathBuf;
 use std::process::Command;
 use std::{env, process};
@@ -8,20 +7,25 @@ fn main() {
     println!("cargo:rerun-if-changed=src/shaders/pixel_shader.hlsl");
     println!("cargo:rerun-if-changed=src/shaders/shader.hlsli");
 
+    // Only the viewer needs shaders, and fxc.exe only exists on Windows.
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        return;
+    }
+
     let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
     let profile = env::var_os("PROFILE").unwrap();
     let out_dir = PathBuf::from(&manifest_dir).join("target").join(profile);
     let shader_src_dir = PathBuf::from(&manifest_dir).join("src").join("shaders");
     let fxc_exe_path =
         "C:/\"Program Files (x86)\"/\"Windows Kits\"/10/bin/10.0.22000.0/x64/fxc.exe";
-    let include_path = vec!["/I", &shader_src_dir.to_str().unwrap()].join(" ");
+    let include_path = ["/I", shader_src_dir.to_str().unwrap()].join(" ");
 
     let paths = std::fs::read_dir(&shader_src_dir).unwrap();
     for path in paths {
         let entry = path.unwrap();
 
         let is_header = match entry.path().extension() {
-            Some(ext) => ext == OsString::from("hlsli"),
+            Some(ext) => ext == "hlsli",
             None => true,
         };
 
@@ -31,7 +35,7 @@ fn main() {
         let original_path = entry.path();
         let path = entry.path().with_extension("cso");
         let out_filename = PathBuf::from(&out_dir).join(path.file_name().unwrap());
-        let out_name = vec!["/Fo", out_filename.to_str().unwrap()].join(" ");
+        let out_name = ["/Fo", out_filename.to_str().unwrap()].join(" ");
 
         let shader_model = if original_path
             .file_name()
@@ -0,0 +1,347 @@
+pub mod accessor {
+
+    //! Accessor decoding for what the gltf crate's typed readers reject: integer component
+    //! types on any attribute (KHR_mesh_quantization) and sparse substitution.
+
+    use gltf::accessor::{DataType, Dimensions};
+
+    pub fn component_size(data_type: DataType) -> usize {
+        match data_type {
+            DataType::I8 | DataType::U8 => 1,
+            DataType::I16 | DataType::U16 => 2,
+            DataType::U32 | DataType::F32 => 4,
+        }
+    }
+
+    /// Rows of a matrix column, or the component count of other types.
+    fn column_rows(dimensions: Dimensions) -> usize {
+        match dimensions {
+            Dimensions::Mat2 => 2,
+            Dimensions::Mat3 => 3,
+            Dimensions::Mat4 => 4,
+            _ => dimensions.multiplicity(),
+        }
+    }
+
+    /// Elements of an accessor, tightly packed, with sparse values applied.
+    #[derive(Clone, Debug)]
+    pub struct Accessor {
+        pub data_type: DataType,
+        pub components: usize,
+        pub normalized: bool,
+        pub count: usize,
+        pub bytes: Vec<u8>,
+    }
+
+    /// Copies `count` elements starting at `offset` with `stride` bytes between them,
+    /// dropping the column padding of small matrices.
+    fn gather(
+        data: &[u8],
+        offset: usize,
+        stride: Option<usize>,
+        count: usize,
+        dimensions: Dimensions,
+        size: usize,
+    ) -> Option<Vec<u8>> {
+        let rows = column_rows(dimensions);
+        let columns = dimensions.multiplicity() / rows;
+        let column_bytes = rows * size;
+        // Matrix columns start on 4-byte boundaries.
+        let column_stride = if columns > 1 {
+            (column_bytes + 3) & !3
+        } else {
+            column_bytes
+        };
+        let element_bytes = column_stride * columns;
+        let stride = stride.unwrap_or(element_bytes);
+
+        let mut bytes = Vec::with_capacity(count * column_bytes * columns);
+        for i in 0..count {
+            for column in 0..columns {
+                let start = offset + i * stride + column * column_stride;
+                bytes.extend_from_slice(data.get(start..start + column_bytes)?);
+            }
+        }
+        Some(bytes)
+    }
+
+    fn view_data<'a>(view: &gltf::buffer::View, buffers: &'a [gltf::buffer::Data]) -> &'a [u8] {
+        let buffer = &buffers[view.buffer().index()];
+        let end = (view.offset() + view.length()).min(buffer.len());
+        &buffer[view.offset().min(end)..end]
+    }
+
+    impl Accessor {
+        /// `None` when a view or sparse index points outside its buffer.
+        pub fn read(accessor: &gltf::Accessor, buffers: &[gltf::buffer::Data]) -> Option<Self> {
+            let data_type = accessor.data_type();
+            let dimensions = accessor.dimensions();
+            let size = component_size(data_type);
+            let element_bytes = dimensions.multiplicity() * size;
+            let count = accessor.count();
+
+            // Accessors without a view start out zeroed, usually to be filled by sparse.
+            let mut bytes = match accessor.view() {
+                Some(view) => gather(
+                    view_data(&view, buffers),
+                    accessor.offset(),
+                    view.stride(),
+                    count,
+                    dimensions,
+                    size,
+                )?,
+                None => vec![0; count * element_bytes],
+            };
+
+            if let Some(sparse) = accessor.sparse() {
+                let indices = sparse.indices();
+                let index_type = match indices.index_type() {
+                    gltf::accessor::sparse::IndexType::U8 => DataType::U8,
+                    gltf::accessor::sparse::IndexType::U16 => DataType::U16,
+                    gltf::accessor::sparse::IndexType::U32 => DataType::U32,
+                };
+                let index_data = gather(
+                    view_data(&indices.view(), buffers),
+                    indices.offset(),
+                    None,
+                    sparse.count(),
+                    Dimensions::Scalar,
+                    component_size(index_type),
+                )?;
+                let values = sparse.values();
+                let value_data = gather(
+                    view_data(&values.view(), buffers),
+                    values.offset(),
+                    None,
+                    sparse.count(),
+                    dimensions,
+                    size,
+                )?;
+
+                let index_size = component_size(index_type);
+                for (index, value) in index_data
+                    .chunks_exact(index_size)
+                    .zip(value_data.chunks_exact(element_bytes))
+                {
+                    let index = read_uint(index) as usize;
+                    let start = index * element_bytes;
+                    bytes
+                        .get_mut(start..start + element_bytes)?
+                        .copy_from_slice(value);
+                }
+            }
+
+            Some(Accessor {
+                data_type,
+                components: dimensions.multiplicity(),
+                normalized: accessor.normalized(),
+                count,
+                bytes,
+            })
+        }
+
+        /// Normalized integers that vertex fetch can expand with an SNORM or UNORM format.
+        pub fn is_quantized(&self) -> bool {
+            self.normalized && self.data_type != DataType::F32 && self.data_type != DataType::U32
+        }
+
+        /// Bytes of element `i`.
+        pub fn element(&self, i: usize) -> &[u8] {
+            let size = self.components * component_size(self.data_type);
+            &self.bytes[i * size..(i + 1) * size]
+        }
+
+        /// The elements at `sources`, in that order.
+        pub fn select(&self, sources: &[usize]) -> Accessor {
+            let element_bytes = self.components * component_size(self.data_type);
+            let mut bytes = Vec::with_capacity(sources.len() * element_bytes);
+            for &i in sources {
+                bytes.extend_from_slice(self.element(i));
+            }
+            Accessor {
+                count: sources.len(),
+                bytes,
+                ..*self
+            }
+        }
+
+        /// Component `c` of element `i` as a float. Normalized integers map to [0, 1] or
+        /// [-1, 1] as the glTF spec defines, other integers convert unchanged.
+        pub fn component(&self, i: usize, c: usize) -> f32 {
+            let size = component_size(self.data_type);
+            let start = (i * self.components + c) * size;
+            let bytes = &self.bytes[start..start + size];
+            let (value, scale) = match self.data_type {
+                DataType::I8 => (bytes[0] as i8 as f32, 127.0),
+                DataType::U8 => (bytes[0] as f32, 255.0),
+                DataType::I16 => (i16::from_le_bytes([bytes[0], bytes[1]]) as f32, 32767.0),
+                DataType::U16 => (u16::from_le_bytes([bytes[0], bytes[1]]) as f32, 65535.0),
+                DataType::U32 => (read_uint(bytes) as f32, 1.0),
+                DataType::F32 => {
+                    return f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+            };
+            if self.normalized {
+                (value / scale).max(-1.0)
+            } else {
+                value
+            }
+        }
+
+        /// Every element as `N` floats; components past the accessor's are zero.
+        pub fn to_f32<const N: usize>(&self) -> Vec<[f32; N]> {
+            (0..self.count)
+                .map(|i| {
+                    let mut out = [0f32; N];
+                    for (c, o) in out.iter_mut().enumerate().take(self.components) {
+                        *o = self.component(i, c);
+                    }
+                    out
+                })
+                .collect()
+        }
+
+        /// All components of all elements, in order.
+        pub fn to_f32_flat(&self) -> Vec<f32> {
+            (0..self.count * self.components)
+                .map(|k| self.component(k / self.components, k % self.components))
+                .collect()
+        }
+
+        /// First component of every element as an unsigned integer, for indices.
+        pub fn to_u32(&self) -> Vec<u32> {
+            (0..self.count)
+                .map(|i| read_uint(&self.element(i)[..component_size(self.data_type)]))
+                .collect()
+        }
+
+        /// Unsigned integer elements as `N` u16s, for joint indices.
+        pub fn to_u16<const N: usize>(&self) -> Vec<[u16; N]> {
+            let size = component_size(self.data_type);
+            (0..self.count)
+                .map(|i| {
+                    let mut out = [0u16; N];
+                    let element = self.element(i);
+                    for (o, c) in out.iter_mut().zip(element.chunks_exact(size)) {
+                        *o = read_uint(c) as u16;
+                    }
+                    out
+                })
+                .collect()
+        }
+    }
+
+    /// Little-endian unsigned integer of one, two or four bytes.
+    fn read_uint(bytes: &[u8]) -> u32 {
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| value << 8 | byte as u32)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Strided normalized bytes, floats with sparse substitutions, a padded byte matrix
+        /// and normalized shorts.
+        fn document() -> (gltf::Document, Vec<gltf::buffer::Data>) {
+            let mut bin = Vec::new();
+            for [x, y] in [[127i8, -127], [-128, 0], [64, -64], [0, 127]] {
+                bin.extend_from_slice(&[x as u8, y as u8, 0xee, 0xee]);
+            }
+            for v in [0f32, 1.0, 2.0, 3.0, 4.0] {
+                bin.extend_from_slice(&v.to_le_bytes());
+            }
+            bin.extend_from_slice(&[1, 3, 0, 0]);
+            for v in [10f32, 30.0] {
+                bin.extend_from_slice(&v.to_le_bytes());
+            }
+            bin.extend_from_slice(&[1, 2, 0xee, 0xee, 3, 4, 0xee, 0xee]);
+            for v in [65535u16, 32768] {
+                bin.extend_from_slice(&v.to_le_bytes());
+            }
+
+            let sparse = r#""sparse": { "count": 2, "indices": { "bufferView": 2, "componentType": 5121 },
+                           "values": { "bufferView": 3 } }"#;
+            let json = format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "buffers": [{{ "byteLength": {} }}],
+                    "bufferViews": [
+                        {{ "buffer": 0, "byteLength": 16, "byteStride": 4 }},
+                        {{ "buffer": 0, "byteOffset": 16, "byteLength": 20 }},
+                        {{ "buffer": 0, "byteOffset": 36, "byteLength": 2 }},
+                        {{ "buffer": 0, "byteOffset": 40, "byteLength": 8 }},
+                        {{ "buffer": 0, "byteOffset": 48, "byteLength": 8 }},
+                        {{ "buffer": 0, "byteOffset": 56, "byteLength": 4 }}
+                    ],
+                    "accessors": [
+                        {{ "bufferView": 0, "componentType": 5120, "normalized": true,
+                           "count": 4, "type": "VEC2" }},
+                        {{ "bufferView": 1, "componentType": 5126, "count": 5, "type": "SCALAR",
+                           {sparse} }},
+                        {{ "componentType": 5126, "count": 4, "type": "SCALAR", {sparse} }},
+                        {{ "componentType": 5126, "count": 2, "type": "SCALAR", {sparse} }},
+                        {{ "bufferView": 4, "componentType": 5121, "count": 1, "type": "MAT2" }},
+                        {{ "bufferView": 5, "componentType": 5123, "normalized": true,
+                           "count": 2, "type": "SCALAR" }}
+                    ]
+                }}"#,
+                bin.len(),
+                sparse = sparse
+            );
+            let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+            (gltf.document, vec![gltf::buffer::Data(bin)])
+        }
+
+        fn read(index: usize) -> Option<Accessor> {
+            let (document, buffers) = document();
+            Accessor::read(&document.accessors().nth(index).unwrap(), &buffers)
+        }
+
+        #[test]
+        fn normalized_integers_map_to_the_unit_range() {
+            let bytes = read(0).unwrap();
+            assert!(bytes.is_quantized());
+            let third = 64.0 / 127.0;
+            assert_eq!(
+                bytes.to_f32::<2>(),
+                [[1.0, -1.0], [-1.0, 0.0], [third, -third], [0.0, 1.0]]
+            );
+            assert_eq!(bytes.to_f32::<3>()[0], [1.0, -1.0, 0.0]);
+
+            let shorts = read(5).unwrap();
+            assert!(shorts.is_quantized());
+            assert_eq!(shorts.to_f32_flat(), [1.0, 32768.0 / 65535.0]);
+        }
+
+        #[test]
+        fn strided_elements_are_packed() {
+            let bytes = read(0).unwrap();
+            assert_eq!(bytes.bytes.len(), 8);
+            let picked = bytes.select(&[3, 0]);
+            assert_eq!(picked.count, 2);
+            assert_eq!(picked.bytes, [0, 127, 127, 129]);
+        }
+
+        #[test]
+        fn sparse_values_replace_elements() {
+            assert_eq!(read(1).unwrap().to_f32_flat(), [0.0, 10.0, 2.0, 30.0, 4.0]);
+            // Without a view the base is zeros.
+            assert_eq!(read(2).unwrap().to_f32_flat(), [0.0, 10.0, 0.0, 30.0]);
+            // Index 3 is past the end of a two element accessor.
+            assert!(read(3).is_none());
+        }
+
+        #[test]
+        fn matrix_columns_drop_their_padding() {
+            let matrix = read(4).unwrap();
+            assert!(!matrix.is_quantized());
+            assert_eq!(matrix.components, 4);
+            assert_eq!(matrix.bytes, [1, 2, 3, 4]);
+            assert_eq!(matrix.to_u16::<4>(), [[1, 2, 3, 4]]);
+            assert_eq!(matrix.to_u32(), [1]);
+        }
+    }
+}
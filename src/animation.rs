@@ -0,0 +1,401 @@
+pub mod animation {
+
+    use crate::math::math::{quat_normalize, quat_slerp};
+    use crate::scene::scene::Pose;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Interpolation {
+        Step,
+        Linear,
+        CubicSpline,
+    }
+
+    /// Node property a channel drives.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Property {
+        Translation,
+        Rotation,
+        Scale,
+        /// Morph target weights of the node's mesh.
+        Weights,
+    }
+
+    /// Keyframes of one channel. `values` holds `components` floats per key, or three times
+    /// as many for cubic splines, stored as in-tangent, value, out-tangent.
+    #[derive(Clone, Debug)]
+    pub struct Sampler {
+        pub times: Vec<f32>,
+        pub values: Vec<f32>,
+        pub components: usize,
+        pub interpolation: Interpolation,
+    }
+
+    impl Sampler {
+        fn element(&self, key: usize, part: usize) -> &[f32] {
+            let n = self.components;
+            let stride = if self.interpolation == Interpolation::CubicSpline {
+                3
+            } else {
+                1
+            };
+            let start = (key * stride + part) * n;
+            &self.values[start..start + n]
+        }
+
+        fn value(&self, key: usize) -> &[f32] {
+            match self.interpolation {
+                Interpolation::CubicSpline => self.element(key, 1),
+                _ => self.element(key, 0),
+            }
+        }
+
+        /// Writes the value at `time` into `out`, holding the first and last keys outside
+        /// the keyframe range. Rotations are slerped, or normalized after the spline.
+        pub fn sample(&self, time: f32, rotation: bool, out: &mut [f32]) {
+            let n = self.components;
+            if self.times.is_empty() {
+                return;
+            }
+
+            let next = self.times.partition_point(|&t| t <= time);
+            if next == 0 || next == self.times.len() {
+                let key = next.saturating_sub(1);
+                out[..n].copy_from_slice(self.value(key));
+                return;
+            }
+
+            let key = next - 1;
+            let dt = self.times[next] - self.times[key];
+            let u = if dt > 0.0 {
+                (time - self.times[key]) / dt
+            } else {
+                0.0
+            };
+
+            match self.interpolation {
+                Interpolation::Step => out[..n].copy_from_slice(self.value(key)),
+                Interpolation::Linear if rotation => {
+                    let a = self.value(key);
+                    let b = self.value(next);
+                    let q = quat_slerp([a[0], a[1], a[2], a[3]], [b[0], b[1], b[2], b[3]], u);
+                    out[..4].copy_from_slice(&q);
+                }
+                Interpolation::Linear => {
+                    let a = self.value(key);
+                    let b = self.value(next);
+                    for ((o, a), b) in out.iter_mut().zip(a).zip(b) {
+                        *o = a + (b - a) * u;
+                    }
+                }
+                Interpolation::CubicSpline => {
+                    let p0 = self.element(key, 1);
+                    let m0 = self.element(key, 2);
+                    let m1 = self.element(next, 0);
+                    let p1 = self.element(next, 1);
+                    for (c, o) in out[..n].iter_mut().enumerate() {
+                        *o = hermite(p0[c], m0[c] * dt, p1[c], m1[c] * dt, u);
+                    }
+                    if rotation {
+                        let q = quat_normalize([out[0], out[1], out[2], out[3]]);
+                        out[..4].copy_from_slice(&q);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Cubic Hermite spline between `p0` and `p1` with tangents already scaled by the key
+    /// interval, as glTF's CUBICSPLINE interpolation defines it.
+    pub fn hermite(p0: f32, m0: f32, p1: f32, m1: f32, t: f32) -> f32 {
+        let t2 = t * t;
+        let t3 = t2 * t;
+        (2.0 * t3 - 3.0 * t2 + 1.0) * p0
+            + (t3 - 2.0 * t2 + t) * m0
+            + (-2.0 * t3 + 3.0 * t2) * p1
+            + (t3 - t2) * m1
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Channel {
+        /// Index into `Scene::nodes`.
+        pub node: usize,
+        pub property: Property,
+        pub sampler: Sampler,
+    }
+
+    impl Channel {
+        pub fn apply(&self, time: f32, pose: &mut Pose) {
+            let local = &mut pose.transforms[self.node];
+            match self.property {
+                Property::Translation => self.sampler.sample(time, false, &mut local.translation),
+                Property::Rotation => self.sampler.sample(time, true, &mut local.rotation),
+                Property::Scale => self.sampler.sample(time, false, &mut local.scale),
+                Property::Weights => {
+                    let weights = &mut pose.weights[self.node];
+                    if weights.len() < self.sampler.components {
+                        weights.resize(self.sampler.components, 0.0);
+                    }
+                    self.sampler.sample(time, false, weights);
+                }
+            }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct AnimationClip {
+        pub name: Option<String>,
+        pub channels: Vec<Channel>,
+        /// Time of the last keyframe of any channel, in seconds.
+        pub duration: f32,
+    }
+
+    impl AnimationClip {
+        /// Overwrites the animated properties of `pose`. Untouched nodes keep whatever
+        /// pose they had.
+        pub fn apply(&self, time: f32, pose: &mut Pose) {
+            for channel in &self.channels {
+                channel.apply(time, pose);
+            }
+        }
+
+        pub fn display_name(&self, index: usize) -> String {
+            match &self.name {
+                Some(name) => name.clone(),
+                None => format!("animation {}", index),
+            }
+        }
+    }
+
+    /// Playback state of one clip.
+    #[derive(Clone, Copy, Debug)]
+    pub struct AnimationPlayer {
+        /// Index into the clip list.
+        pub clip: usize,
+        pub time: f32,
+        pub speed: f32,
+        pub playing: bool,
+        pub looping: bool,
+    }
+
+    impl Default for AnimationPlayer {
+        fn default() -> Self {
+            AnimationPlayer {
+                clip: 0,
+                time: 0.0,
+                speed: 1.0,
+                playing: true,
+                looping: true,
+            }
+        }
+    }
+
+    impl AnimationPlayer {
+        pub const MIN_SPEED: f32 = 0.125;
+        pub const MAX_SPEED: f32 = 8.0;
+
+        /// Advances by `dt` seconds of wall time. Without looping, playback stops on the
+        /// last frame.
+        pub fn advance(&mut self, dt: f32, duration: f32) {
+            if !self.playing {
+                return;
+            }
+            self.time += dt * self.speed;
+            if duration <= 0.0 {
+                self.time = 0.0;
+            } else if self.looping {
+                self.time = self.time.rem_euclid(duration);
+            } else if self.time >= duration {
+                self.time = duration;
+                self.playing = false;
+            }
+        }
+
+        /// Resumes from the start if a non-looping clip already finished.
+        pub fn toggle_pause(&mut self, duration: f32) {
+            if !self.playing && self.time >= duration {
+                self.time = 0.0;
+            }
+            self.playing = !self.playing;
+        }
+
+        pub fn scale_speed(&mut self, factor: f32) {
+            self.speed = (self.speed * factor).clamp(Self::MIN_SPEED, Self::MAX_SPEED);
+        }
+
+        /// Switches to the next of `count` clips and restarts it.
+        pub fn next_clip(&mut self, count: usize) {
+            if count > 0 {
+                self.clip = (self.clip + 1) % count;
+            }
+            self.time = 0.0;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::scene::scene::NodeTransform;
+
+        fn sampler(interpolation: Interpolation, times: &[f32], values: &[f32]) -> Sampler {
+            let stride = if interpolation == Interpolation::CubicSpline {
+                3
+            } else {
+                1
+            };
+            Sampler {
+                times: times.to_vec(),
+                values: values.to_vec(),
+                components: values.len() / (times.len() * stride),
+                interpolation,
+            }
+        }
+
+        fn sample(sampler: &Sampler, time: f32, rotation: bool) -> Vec<f32> {
+            let mut out = vec![0.0; sampler.components];
+            sampler.sample(time, rotation, &mut out);
+            out
+        }
+
+        fn assert_near(actual: &[f32], expected: &[f32]) {
+            for (a, e) in actual.iter().zip(expected) {
+                assert!((a - e).abs() < 1e-5, "{:?} vs {:?}", actual, expected);
+            }
+        }
+
+        #[test]
+        fn step_holds_the_previous_key() {
+            let s = sampler(Interpolation::Step, &[0.0, 1.0, 2.0], &[10.0, 20.0, 30.0]);
+            assert_eq!(sample(&s, 0.99, false), [10.0]);
+            assert_eq!(sample(&s, 1.0, false), [20.0]);
+            assert_eq!(sample(&s, 1.5, false), [20.0]);
+        }
+
+        #[test]
+        fn linear_interpolates_and_clamps_outside_the_keys() {
+            let s = sampler(Interpolation::Linear, &[1.0, 3.0], &[0.0, 2.0, 4.0, 6.0]);
+            assert_eq!(sample(&s, 2.0, false), [2.0, 4.0]);
+            assert_eq!(sample(&s, 2.5, false), [3.0, 5.0]);
+            assert_eq!(sample(&s, 0.0, false), [0.0, 2.0]);
+            assert_eq!(sample(&s, 9.0, false), [4.0, 6.0]);
+        }
+
+        #[test]
+        fn repeated_key_times_jump_to_the_later_key() {
+            let s = sampler(
+                Interpolation::Linear,
+                &[0.0, 1.0, 1.0, 2.0],
+                &[0.0, 1.0, 5.0, 6.0],
+            );
+            assert_eq!(sample(&s, 0.5, false), [0.5]);
+            assert_eq!(sample(&s, 1.0, false), [5.0]);
+            assert_eq!(sample(&s, 1.5, false), [5.5]);
+        }
+
+        #[test]
+        fn linear_rotations_are_slerped() {
+            let half = std::f32::consts::FRAC_1_SQRT_2;
+            // Identity to 90 degrees about z.
+            let values = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, half, half];
+            let s = sampler(Interpolation::Linear, &[0.0, 1.0], &values);
+            let (sin, cos) = (
+                std::f32::consts::FRAC_PI_8.sin(),
+                std::f32::consts::FRAC_PI_8.cos(),
+            );
+            assert_near(&sample(&s, 0.5, true), &[0.0, 0.0, sin, cos]);
+        }
+
+        #[test]
+        fn cubic_splines_scale_tangents_by_the_interval() {
+            // Keys at 0 and 2 s: in-tangent, value, out-tangent each.
+            let values = [0.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+            let s = sampler(Interpolation::CubicSpline, &[0.0, 2.0], &values);
+            assert_eq!(s.components, 1);
+            // h00 p0 + h10 m0 dt + h01 p1 + h11 m1 dt at u = 0.5.
+            assert_near(&sample(&s, 1.0, false), &[0.125 * 2.0 + 0.5]);
+            assert_near(&sample(&s, 0.0, false), &[0.0]);
+            assert_near(&sample(&s, 2.0, false), &[1.0]);
+            assert_eq!(hermite(3.0, 5.0, 7.0, 11.0, 0.0), 3.0);
+            assert_eq!(hermite(3.0, 5.0, 7.0, 11.0, 1.0), 7.0);
+        }
+
+        #[test]
+        fn cubic_rotations_are_normalized() {
+            let half = std::f32::consts::FRAC_1_SQRT_2;
+            let zero = [0.0; 4];
+            let mut values = Vec::new();
+            for key in [[0.0, 0.0, 0.0, 1.0], [0.0, 0.0, half, half]] {
+                values.extend(zero.iter().chain(&key).chain(&zero));
+            }
+            let s = sampler(Interpolation::CubicSpline, &[0.0, 1.0], &values);
+            let q = sample(&s, 0.5, true);
+            let length = q.iter().map(|c| c * c).sum::<f32>().sqrt();
+            assert!((length - 1.0).abs() < 1e-5);
+            assert!(q[2] > 0.0 && q[3] > q[2]);
+        }
+
+        #[test]
+        fn channels_overwrite_only_their_property() {
+            let channel = Channel {
+                node: 1,
+                property: Property::Translation,
+                sampler: sampler(
+                    Interpolation::Linear,
+                    &[0.0, 1.0],
+                    &[0.0, 0.0, 0.0, 4.0, 8.0, 12.0],
+                ),
+            };
+            let scaled = NodeTransform {
+                scale: [2.0, 2.0, 2.0],
+                ..Default::default()
+            };
+            let mut pose = Pose {
+                transforms: vec![NodeTransform::default(), scaled],
+                weights: vec![Vec::new(); 2],
+            };
+            channel.apply(0.25, &mut pose);
+            assert_eq!(pose.transforms[0], NodeTransform::default());
+            assert_eq!(
+                pose.transforms[1],
+                NodeTransform {
+                    translation: [1.0, 2.0, 3.0],
+                    ..scaled
+                }
+            );
+        }
+
+        #[test]
+        fn weight_channels_grow_the_node_weights() {
+            let channel = Channel {
+                node: 0,
+                property: Property::Weights,
+                sampler: sampler(Interpolation::Linear, &[0.0, 1.0], &[0.0, 1.0, 1.0, 0.0]),
+            };
+            let mut pose = Pose {
+                transforms: vec![NodeTransform::default()],
+                weights: vec![Vec::new()],
+            };
+            channel.apply(0.25, &mut pose);
+            assert_eq!(pose.weights[0], [0.25, 0.75]);
+            assert_eq!(pose.transforms[0], NodeTransform::default());
+        }
+
+        #[test]
+        fn players_loop_or_stop_at_the_end() {
+            let mut player = AnimationPlayer::default();
+            player.advance(2.5, 2.0);
+            assert_eq!(player.time, 0.5);
+
+            player.looping = false;
+            player.advance(2.0, 2.0);
+            assert_eq!((player.time, player.playing), (2.0, false));
+            player.toggle_pause(2.0);
+            assert_eq!((player.time, player.playing), (0.0, true));
+
+            player.scale_speed(100.0);
+            assert_eq!(player.speed, AnimationPlayer::MAX_SPEED);
+            player.next_clip(2);
+            player.next_clip(2);
+            assert_eq!(player.clip, 0);
+        }
+    }
+}
@@ -0,0 +1,245 @@
+pub mod blending {
+
+    //! Pose blending for cross-fades and animation layers. Everything works on local node
+    //! transforms, before `world_transforms`.
+
+    use crate::math::math::{quat_conjugate, quat_mul, quat_normalize, quat_slerp};
+    use crate::scene::scene::{Node, NodeTransform, Pose};
+
+    const IDENTITY_ROTATION: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+    /// Per-node blend factors in [0, 1], indexed like `Scene::nodes`.
+    #[derive(Clone, Debug)]
+    pub struct NodeMask {
+        pub weights: Vec<f32>,
+    }
+
+    impl NodeMask {
+        /// `root` and all of its descendants get `weight`, every other node zero.
+        pub fn subtree(nodes: &[Node], root: usize, weight: f32) -> Self {
+            let mut weights = vec![0.0; nodes.len()];
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                weights[node] = weight;
+                stack.extend(&nodes[node].children);
+            }
+            NodeMask { weights }
+        }
+
+        pub fn weight(&self, node: usize) -> f32 {
+            self.weights.get(node).copied().unwrap_or(0.0)
+        }
+    }
+
+    fn lerp3(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+        [
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+        ]
+    }
+
+    fn node_factor(t: f32, mask: Option<&NodeMask>, node: usize) -> f32 {
+        mask.map_or(t, |mask| t * mask.weight(node))
+    }
+
+    /// Moves `a` towards `b` by `t`, scaled per node by `mask`. Rotations are slerped,
+    /// everything else is interpolated linearly.
+    pub fn blend(a: &Pose, b: &Pose, t: f32, mask: Option<&NodeMask>) -> Pose {
+        let mut out = a.clone();
+        for (node, (local, target)) in out.transforms.iter_mut().zip(&b.transforms).enumerate() {
+            let t = node_factor(t, mask, node);
+            if t == 0.0 {
+                continue;
+            }
+            *local = NodeTransform {
+                translation: lerp3(local.translation, target.translation, t),
+                rotation: quat_slerp(local.rotation, target.rotation, t),
+                scale: lerp3(local.scale, target.scale, t),
+            };
+        }
+        for (node, (weights, target)) in out.weights.iter_mut().zip(&b.weights).enumerate() {
+            let t = node_factor(t, mask, node);
+            for (w, target) in weights.iter_mut().zip(target) {
+                *w += (target - *w) * t;
+            }
+        }
+        out
+    }
+
+    /// Adds how far `pose` moved away from `reference` onto `base`, scaled by `weight` and
+    /// `mask`. Translations and morph weights add, rotations compose in local space and
+    /// scales multiply.
+    pub fn add(
+        base: &Pose,
+        pose: &Pose,
+        reference: &Pose,
+        weight: f32,
+        mask: Option<&NodeMask>,
+    ) -> Pose {
+        let mut out = base.clone();
+        let deltas = pose.transforms.iter().zip(&reference.transforms);
+        for (node, (local, (p, r))) in out.transforms.iter_mut().zip(deltas).enumerate() {
+            let t = node_factor(weight, mask, node);
+            if t == 0.0 {
+                continue;
+            }
+
+            let delta_rotation = quat_mul(quat_conjugate(r.rotation), p.rotation);
+            let rotation = quat_slerp(IDENTITY_ROTATION, delta_rotation, t);
+            let mut scale = local.scale;
+            for (c, s) in scale.iter_mut().enumerate() {
+                let ratio = if r.scale[c] != 0.0 {
+                    p.scale[c] / r.scale[c]
+                } else {
+                    1.0
+                };
+                *s *= 1.0 + (ratio - 1.0) * t;
+            }
+
+            *local = NodeTransform {
+                translation: [
+                    local.translation[0] + (p.translation[0] - r.translation[0]) * t,
+                    local.translation[1] + (p.translation[1] - r.translation[1]) * t,
+                    local.translation[2] + (p.translation[2] - r.translation[2]) * t,
+                ],
+                rotation: quat_normalize(quat_mul(local.rotation, rotation)),
+                scale,
+            };
+        }
+
+        let deltas = pose.weights.iter().zip(&reference.weights);
+        for (node, (weights, (p, r))) in out.weights.iter_mut().zip(deltas).enumerate() {
+            let t = node_factor(weight, mask, node);
+            for (w, (p, r)) in weights.iter_mut().zip(p.iter().zip(r)) {
+                *w += (p - r) * t;
+            }
+        }
+        out
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use serde_json::Value;
+
+        use super::*;
+
+        fn node(parent: Option<usize>, children: Vec<usize>) -> Node {
+            Node {
+                name: None,
+                parent,
+                children,
+                transform: NodeTransform::default(),
+                weights: Vec::new(),
+                extras: Value::Null,
+            }
+        }
+
+        /// A root with one child, and an unrelated second root.
+        fn nodes() -> Vec<Node> {
+            vec![
+                node(None, vec![1]),
+                node(Some(0), vec![]),
+                node(None, vec![]),
+            ]
+        }
+
+        fn pose(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3], weight: f32) -> Pose {
+            let transform = NodeTransform {
+                translation,
+                rotation,
+                scale,
+            };
+            Pose {
+                transforms: vec![transform; 3],
+                weights: vec![vec![weight]; 3],
+            }
+        }
+
+        fn assert_near(actual: &[f32], expected: &[f32]) {
+            for (a, e) in actual.iter().zip(expected) {
+                assert!((a - e).abs() < 1e-5, "{:?} vs {:?}", actual, expected);
+            }
+        }
+
+        fn z_rotation(angle: f32) -> [f32; 4] {
+            let (sin, cos) = (angle * 0.5).sin_cos();
+            [0.0, 0.0, sin, cos]
+        }
+
+        #[test]
+        fn subtree_masks_cover_the_descendants_only() {
+            let mask = NodeMask::subtree(&nodes(), 0, 0.5);
+            assert_eq!(mask.weights, [0.5, 0.5, 0.0]);
+            assert_eq!(mask.weight(7), 0.0);
+        }
+
+        #[test]
+        fn blend_interpolates_between_the_poses() {
+            let a = pose([0.0; 3], z_rotation(0.0), [1.0; 3], 0.0);
+            let b = pose([2.0, 4.0, 6.0], z_rotation(1.0), [3.0; 3], 1.0);
+
+            let start = blend(&a, &b, 0.0, None);
+            assert_eq!(start.transforms, a.transforms);
+            let end = blend(&a, &b, 1.0, None);
+            assert_near(&end.transforms[0].translation, &b.transforms[0].translation);
+            assert_near(&end.transforms[0].rotation, &b.transforms[0].rotation);
+
+            let half = blend(&a, &b, 0.5, None);
+            let local = &half.transforms[2];
+            assert_near(&local.translation, &[1.0, 2.0, 3.0]);
+            assert_near(&local.rotation, &z_rotation(0.5));
+            assert_near(&local.scale, &[2.0; 3]);
+            assert_eq!(half.weights[2], [0.5]);
+        }
+
+        #[test]
+        fn masked_nodes_blend_by_their_weight() {
+            let a = pose([0.0; 3], z_rotation(0.0), [1.0; 3], 0.0);
+            let b = pose([4.0; 3], z_rotation(0.0), [1.0; 3], 1.0);
+            let mask = NodeMask::subtree(&nodes(), 0, 0.5);
+
+            let out = blend(&a, &b, 1.0, Some(&mask));
+            assert_near(&out.transforms[0].translation, &[2.0; 3]);
+            assert_near(&out.transforms[1].translation, &[2.0; 3]);
+            assert_eq!(out.transforms[2], a.transforms[2]);
+            assert_eq!(out.weights, [vec![0.5], vec![0.5], vec![0.0]]);
+        }
+
+        #[test]
+        fn adding_the_reference_pose_changes_nothing() {
+            let base = pose([1.0, 2.0, 3.0], z_rotation(0.3), [2.0; 3], 0.25);
+            let reference = pose([5.0; 3], z_rotation(-1.0), [0.5; 3], 0.75);
+            let out = add(&base, &reference, &reference, 1.0, None);
+            for (a, b) in out.transforms.iter().zip(&base.transforms) {
+                assert_near(&a.translation, &b.translation);
+                assert_near(&a.rotation, &b.rotation);
+                assert_near(&a.scale, &b.scale);
+            }
+            assert_eq!(out.weights, base.weights);
+        }
+
+        #[test]
+        fn additive_layers_apply_the_difference_to_the_reference() {
+            let base = pose([1.0, 2.0, 3.0], z_rotation(0.25), [2.0; 3], 0.25);
+            let reference = pose([0.0; 3], z_rotation(0.5), [0.5; 3], 0.5);
+            let moved = pose([1.0, 0.0, -1.0], z_rotation(1.5), [1.0; 3], 1.0);
+            let mask = NodeMask::subtree(&nodes(), 2, 1.0);
+
+            let full = add(&base, &moved, &reference, 1.0, Some(&mask));
+            let local = &full.transforms[2];
+            assert_near(&local.translation, &[2.0, 2.0, 2.0]);
+            assert_near(&local.rotation, &z_rotation(1.25));
+            assert_near(&local.scale, &[4.0; 3]);
+            assert_eq!(full.weights[2], [0.75]);
+            assert_eq!(full.transforms[0], base.transforms[0]);
+
+            let half = add(&base, &moved, &reference, 0.5, None);
+            let local = &half.transforms[0];
+            assert_near(&local.translation, &[1.5, 2.0, 2.5]);
+            assert_near(&local.rotation, &z_rotation(0.75));
+            assert_near(&local.scale, &[3.0; 3]);
+            assert_eq!(half.weights[0], [0.5]);
+        }
+    }
+}
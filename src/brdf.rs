@@ -0,0 +1,482 @@
+pub mod brdf {
+
+    //! CPU reference of the shading model in brdf.hlsli. Every lobe follows the glTF 2.0
+    //! and KHR_materials_* specifications so it can be checked against known values.
+
+    use std::f32::consts::PI;
+
+    use glm::Vec3;
+
+    use crate::scene::scene::Material;
+
+    /// Material inputs at a single shading point, after texture lookups.
+    #[derive(Clone, Copy, Debug)]
+    pub struct ShadingParams {
+        pub base_color: Vec3,
+        pub metallic: f32,
+        pub roughness: f32,
+        pub ior: f32,
+        pub specular: f32,
+        pub specular_color: Vec3,
+        pub transmission: f32,
+        pub clearcoat: f32,
+        pub clearcoat_roughness: f32,
+        pub sheen_color: Vec3,
+        pub sheen_roughness: f32,
+        pub attenuation_color: Vec3,
+        pub attenuation_distance: f32,
+        pub thickness: f32,
+    }
+
+    impl ShadingParams {
+        /// Uses the constant factors only, as if every texture were white.
+        pub fn from_material(material: &Material) -> Self {
+            let c = material.base_color_factor;
+            let specular = material.specular.as_ref();
+            let clearcoat = material.clearcoat.as_ref();
+            let sheen = material.sheen.as_ref();
+            let volume = material.volume.as_ref();
+
+            ShadingParams {
+                base_color: glm::vec3(c[0], c[1], c[2]),
+                metallic: material.metallic_factor,
+                roughness: material.roughness_factor,
+                ior: material.ior,
+                specular: specular.map_or(1.0, |s| s.factor),
+                specular_color: to_vec3(specular.map_or([1.0; 3], |s| s.color_factor)),
+                transmission: material.transmission.as_ref().map_or(0.0, |t| t.factor),
+                clearcoat: clearcoat.map_or(0.0, |c| c.factor),
+                clearcoat_roughness: clearcoat.map_or(0.0, |c| c.roughness_factor),
+                sheen_color: to_vec3(sheen.map_or([0.0; 3], |s| s.color_factor)),
+                sheen_roughness: sheen.map_or(0.0, |s| s.roughness_factor),
+                attenuation_color: to_vec3(volume.map_or([1.0; 3], |v| v.attenuation_color)),
+                attenuation_distance: volume.map_or(f32::INFINITY, |v| v.attenuation_distance),
+                thickness: volume.map_or(0.0, |v| v.thickness_factor),
+            }
+        }
+    }
+
+    fn to_vec3(v: [f32; 3]) -> Vec3 {
+        glm::vec3(v[0], v[1], v[2])
+    }
+
+    fn splat(v: f32) -> Vec3 {
+        glm::vec3(v, v, v)
+    }
+
+    fn lerp(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+        a * (1.0 - t) + b * t
+    }
+
+    fn max3(v: Vec3) -> f32 {
+        v.x.max(v.y).max(v.z)
+    }
+
+    /// Reflectance at normal incidence of a dielectric, 0.04 for the default IOR of 1.5.
+    pub fn f0_from_ior(ior: f32) -> f32 {
+        let r = (ior - 1.0) / (ior + 1.0);
+        r * r
+    }
+
+    pub fn fresnel_schlick(f0: Vec3, f90: Vec3, v_dot_h: f32) -> Vec3 {
+        let k = (1.0 - v_dot_h.clamp(0.0, 1.0)).powi(5);
+        f0 + (f90 - f0) * k
+    }
+
+    /// Trowbridge-Reitz normal distribution with `alpha = roughness^2`.
+    pub fn d_ggx(n_dot_h: f32, alpha: f32) -> f32 {
+        let a2 = alpha * alpha;
+        let f = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+        a2 / (PI * f * f)
+    }
+
+    /// Height-correlated Smith visibility, already divided by `4 * n_dot_l * n_dot_v`.
+    pub fn v_smith_ggx_correlated(n_dot_l: f32, n_dot_v: f32, alpha: f32) -> f32 {
+        let a2 = alpha * alpha;
+        let ggx_v = n_dot_l * (n_dot_v * n_dot_v * (1.0 - a2) + a2).sqrt();
+        let ggx_l = n_dot_v * (n_dot_l * n_dot_l * (1.0 - a2) + a2).sqrt();
+        let denom = ggx_v + ggx_l;
+        if denom > 0.0 {
+            0.5 / denom
+        } else {
+            0.0
+        }
+    }
+
+    /// Charlie sheen distribution (Estevez and Kulla).
+    pub fn d_charlie(n_dot_h: f32, sheen_roughness: f32) -> f32 {
+        let alpha = (sheen_roughness * sheen_roughness).max(1e-4);
+        let inv_alpha = 1.0 / alpha;
+        let sin2 = 1.0 - n_dot_h * n_dot_h;
+        (2.0 + inv_alpha) * sin2.powf(inv_alpha * 0.5) / (2.0 * PI)
+    }
+
+    /// Ashikhmin visibility used by the sheen lobe.
+    pub fn v_sheen(n_dot_l: f32, n_dot_v: f32) -> f32 {
+        let denom = 4.0 * (n_dot_l + n_dot_v - n_dot_l * n_dot_v);
+        if denom > 0.0 {
+            (1.0 / denom).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn diffuse_lobe(color: Vec3) -> Vec3 {
+        color * (1.0 / PI)
+    }
+
+    /// Microfacet reflection without Fresnel.
+    pub fn specular_lobe(n_dot_l: f32, n_dot_v: f32, n_dot_h: f32, roughness: f32) -> f32 {
+        let alpha = (roughness * roughness).max(1e-4);
+        d_ggx(n_dot_h, alpha) * v_smith_ggx_correlated(n_dot_l, n_dot_v, alpha)
+    }
+
+    /// Thin-walled transmission: the light direction is mirrored through the surface and
+    /// the transmitted lobe is tinted by the base color and the volume attenuation.
+    pub fn transmission_lobe(p: &ShadingParams, n: Vec3, v: Vec3, l: Vec3) -> Vec3 {
+        let l_mirror = l - n * (2.0 * glm::dot(l, n));
+        let h = glm::normalize(l_mirror + v);
+        let n_dot_l = glm::dot(n, l_mirror).clamp(0.0, 1.0);
+        let n_dot_v = glm::dot(n, v).clamp(0.0, 1.0);
+        let n_dot_h = glm::dot(n, h).clamp(0.0, 1.0);
+        let lobe = specular_lobe(n_dot_l, n_dot_v, n_dot_h, p.roughness);
+        p.base_color * lobe * volume_attenuation(p)
+    }
+
+    /// Beer-Lambert attenuation through `thickness` of the volume.
+    pub fn volume_attenuation(p: &ShadingParams) -> Vec3 {
+        if p.attenuation_distance.is_infinite() || p.thickness == 0.0 {
+            return splat(1.0);
+        }
+        let t = p.thickness / p.attenuation_distance;
+        glm::vec3(
+            p.attenuation_color.x.powf(t),
+            p.attenuation_color.y.powf(t),
+            p.attenuation_color.z.powf(t),
+        )
+    }
+
+    pub fn sheen_lobe(p: &ShadingParams, n_dot_l: f32, n_dot_v: f32, n_dot_h: f32) -> Vec3 {
+        p.sheen_color * (d_charlie(n_dot_h, p.sheen_roughness) * v_sheen(n_dot_l, n_dot_v))
+    }
+
+    /// Side of `SHEEN_ALBEDO`.
+    pub const SHEEN_ALBEDO_SIZE: usize = 16;
+
+    /// Directional albedo of `sheen_lobe()` for a white sheen, integrated over the
+    /// hemisphere. Rows step the sheen roughness and columns `n_dot_v`, both from 0 to 1.
+    /// The same table is `sheen_albedo_table` in brdf.hlsli.
+    #[rustfmt::skip]
+    pub const SHEEN_ALBEDO: [[f32; SHEEN_ALBEDO_SIZE]; SHEEN_ALBEDO_SIZE] = [
+        [1.9842, 0.0002, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000],
+        [1.9521, 0.8158, 0.2861, 0.0800, 0.0164, 0.0026, 0.0003, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000],
+        [1.6450, 1.0565, 0.6370, 0.3546, 0.1811, 0.0877, 0.0401, 0.0170, 0.0066, 0.0023, 0.0007, 0.0002, 0.0000, 0.0000, 0.0000, 0.0000],
+        [1.3462, 0.9901, 0.7014, 0.4756, 0.3100, 0.1979, 0.1233, 0.0744, 0.0430, 0.0236, 0.0120, 0.0056, 0.0022, 0.0007, 0.0002, 0.0000],
+        [1.1329, 0.8931, 0.6860, 0.5122, 0.3738, 0.2702, 0.1929, 0.1352, 0.0925, 0.0613, 0.0389, 0.0233, 0.0129, 0.0062, 0.0024, 0.0004],
+        [0.9827, 0.8085, 0.6524, 0.5159, 0.4020, 0.3119, 0.2402, 0.1829, 0.1371, 0.1005, 0.0717, 0.0491, 0.0319, 0.0190, 0.0097, 0.0034],
+        [0.8743, 0.7406, 0.6177, 0.5074, 0.4126, 0.3349, 0.2707, 0.2172, 0.1723, 0.1346, 0.1030, 0.0767, 0.0548, 0.0370, 0.0225, 0.0112],
+        [0.7940, 0.6869, 0.5866, 0.4949, 0.4146, 0.3473, 0.2903, 0.2414, 0.1991, 0.1623, 0.1303, 0.1025, 0.0782, 0.0572, 0.0392, 0.0237],
+        [0.7331, 0.6443, 0.5600, 0.4819, 0.4125, 0.3536, 0.3028, 0.2584, 0.2192, 0.1843, 0.1531, 0.1252, 0.1001, 0.0776, 0.0574, 0.0393],
+        [0.6860, 0.6103, 0.5377, 0.4697, 0.4088, 0.3564, 0.3109, 0.2705, 0.2343, 0.2016, 0.1719, 0.1448, 0.1198, 0.0969, 0.0758, 0.0563],
+        [0.6489, 0.5828, 0.5189, 0.4587, 0.4044, 0.3574, 0.3161, 0.2793, 0.2459, 0.2154, 0.1874, 0.1614, 0.1372, 0.1145, 0.0933, 0.0734],
+        [0.6192, 0.5604, 0.5033, 0.4491, 0.3999, 0.3572, 0.3196, 0.2857, 0.2548, 0.2264, 0.2001, 0.1754, 0.1522, 0.1303, 0.1094, 0.0896],
+        [0.5951, 0.5420, 0.4901, 0.4407, 0.3957, 0.3565, 0.3218, 0.2905, 0.2619, 0.2354, 0.2106, 0.1873, 0.1652, 0.1441, 0.1240, 0.1046],
+        [0.5754, 0.5268, 0.4791, 0.4334, 0.3918, 0.3555, 0.3233, 0.2942, 0.2675, 0.2426, 0.2194, 0.1973, 0.1764, 0.1563, 0.1370, 0.1182],
+        [0.5590, 0.5140, 0.4697, 0.4272, 0.3883, 0.3544, 0.3243, 0.2971, 0.2720, 0.2487, 0.2267, 0.2059, 0.1860, 0.1669, 0.1485, 0.1305],
+        [0.5453, 0.5032, 0.4617, 0.4217, 0.3852, 0.3533, 0.3250, 0.2993, 0.2757, 0.2537, 0.2330, 0.2133, 0.1944, 0.1762, 0.1586, 0.1414],
+    ];
+
+    /// Bilinear lookup into `SHEEN_ALBEDO`.
+    pub fn sheen_directional_albedo(n_dot_v: f32, sheen_roughness: f32) -> f32 {
+        let last = (SHEEN_ALBEDO_SIZE - 1) as f32;
+        let x = n_dot_v.clamp(0.0, 1.0) * last;
+        let y = sheen_roughness.clamp(0.0, 1.0) * last;
+        let x0 = (x as usize).min(SHEEN_ALBEDO_SIZE - 2);
+        let y0 = (y as usize).min(SHEEN_ALBEDO_SIZE - 2);
+        let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+        let row = |y: usize| {
+            let r = &SHEEN_ALBEDO[y];
+            r[x0] + (r[x0 + 1] - r[x0]) * tx
+        };
+        row(y0) + (row(y0 + 1) - row(y0)) * ty
+    }
+
+    /// Energy left for the layers below the sheen, the albedo scaling of
+    /// KHR_materials_sheen.
+    pub fn sheen_albedo_scaling(p: &ShadingParams, n_dot_l: f32, n_dot_v: f32) -> f32 {
+        let albedo = sheen_directional_albedo(n_dot_v, p.sheen_roughness)
+            .max(sheen_directional_albedo(n_dot_l, p.sheen_roughness));
+        (1.0 - max3(p.sheen_color) * albedo).max(0.0)
+    }
+
+    pub fn clearcoat_lobe(p: &ShadingParams, n_dot_l: f32, n_dot_v: f32, n_dot_h: f32) -> f32 {
+        specular_lobe(n_dot_l, n_dot_v, n_dot_h, p.clearcoat_roughness)
+    }
+
+    /// KHR_lights_punctual range falloff on top of inverse-square attenuation.
+    pub fn range_attenuation(distance: f32, range: Option<f32>) -> f32 {
+        let inv_square = 1.0 / (distance * distance).max(1e-4);
+        match range {
+            Some(range) if range > 0.0 => {
+                let ratio = distance / range;
+                (1.0 - ratio.powi(4)).clamp(0.0, 1.0) * inv_square
+            }
+            _ => inv_square,
+        }
+    }
+
+    /// Scale and offset that map the cosine of the angle to the spot axis onto the
+    /// [outer, inner] cone, as suggested by KHR_lights_punctual.
+    pub fn spot_scale_offset(inner_cone_angle: f32, outer_cone_angle: f32) -> (f32, f32) {
+        let cos_outer = outer_cone_angle.cos();
+        let scale = 1.0 / (inner_cone_angle.cos() - cos_outer).max(0.001);
+        (scale, -cos_outer * scale)
+    }
+
+    pub fn spot_attenuation(cos_angle: f32, scale: f32, offset: f32) -> f32 {
+        let t = (cos_angle * scale + offset).clamp(0.0, 1.0);
+        t * t
+    }
+
+    /// Full layered BRDF times the cosine of each layer for a light arriving from `l`.
+    /// `coat_n` is the clearcoat normal, which has its own normal map. All vectors point
+    /// away from the surface and are normalized.
+    pub fn evaluate(p: &ShadingParams, n: Vec3, coat_n: Vec3, v: Vec3, l: Vec3) -> Vec3 {
+        let h = glm::normalize(l + v);
+        let n_dot_l = glm::dot(n, l);
+        let n_dot_v = glm::dot(n, v).clamp(1e-4, 1.0);
+        let n_dot_h = glm::dot(n, h).clamp(0.0, 1.0);
+        let v_dot_h = glm::dot(v, h).clamp(0.0, 1.0);
+
+        let transmitted = transmission_lobe(p, n, v, l) * p.transmission;
+        if n_dot_l <= 0.0 {
+            let back_n_dot_l = -n_dot_l;
+            return transmitted * (1.0 - p.metallic) * back_n_dot_l;
+        }
+
+        let f0 = p.specular_color * f0_from_ior(p.ior);
+        let dielectric_f0 = glm::vec3(f0.x.min(1.0), f0.y.min(1.0), f0.z.min(1.0)) * p.specular;
+        let dielectric_f90 = splat(p.specular);
+        let f_dielectric = fresnel_schlick(dielectric_f0, dielectric_f90, v_dot_h);
+        let f_metal = fresnel_schlick(p.base_color, splat(1.0), v_dot_h);
+
+        let specular = specular_lobe(n_dot_l, n_dot_v, n_dot_h, p.roughness);
+        let diffuse = diffuse_lobe(p.base_color) * (1.0 - p.transmission);
+
+        // fresnel_mix(): the specular layer takes F, the base keeps the rest.
+        let dielectric = diffuse * (splat(1.0) - f_dielectric) + f_dielectric * specular;
+        let metal = f_metal * specular;
+        let material = lerp(dielectric, metal, p.metallic);
+
+        let sheen = sheen_lobe(p, n_dot_l, n_dot_v, n_dot_h);
+        let with_sheen = sheen + material * sheen_albedo_scaling(p, n_dot_l, n_dot_v);
+
+        let coat_n_dot_l = glm::dot(coat_n, l).clamp(0.0, 1.0);
+        let coat_n_dot_v = glm::dot(coat_n, v).clamp(1e-4, 1.0);
+        let coat_n_dot_h = glm::dot(coat_n, h).clamp(0.0, 1.0);
+        let coat_fresnel = fresnel_schlick(splat(0.04), splat(1.0), coat_n_dot_v).x * p.clearcoat;
+        let coat = clearcoat_lobe(p, coat_n_dot_l, coat_n_dot_v, coat_n_dot_h);
+
+        with_sheen * (n_dot_l * (1.0 - coat_fresnel)) + splat(coat * coat_fresnel * coat_n_dot_l)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Midpoint rule over the hemisphere around +z, `f` already includes any cosine.
+        fn hemisphere_integral(steps: usize, f: impl Fn(Vec3) -> f32) -> f32 {
+            let (d_theta, d_phi) = (0.5 * PI / steps as f32, 2.0 * PI / (2 * steps) as f32);
+            let mut sum = 0.0f64;
+            for i in 0..steps {
+                let theta = (i as f32 + 0.5) * d_theta;
+                for j in 0..2 * steps {
+                    let phi = (j as f32 + 0.5) * d_phi;
+                    let dir = glm::vec3(
+                        theta.sin() * phi.cos(),
+                        theta.sin() * phi.sin(),
+                        theta.cos(),
+                    );
+                    sum += (f(dir) * theta.sin()) as f64;
+                }
+            }
+            sum as f32 * d_theta * d_phi
+        }
+
+        fn from_polar(theta: f32) -> Vec3 {
+            glm::vec3(theta.sin(), 0.0, theta.cos())
+        }
+
+        fn rough_dielectric() -> ShadingParams {
+            ShadingParams {
+                metallic: 0.0,
+                ..ShadingParams::from_material(&Material::default())
+            }
+        }
+
+        fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+            assert!(
+                (actual - expected).abs() <= tolerance,
+                "{actual} is not within {tolerance} of {expected}"
+            );
+        }
+
+        #[test]
+        fn fresnel_known_values() {
+            assert_near(f0_from_ior(1.5), 0.04, 1e-6);
+            assert_eq!(f0_from_ior(1.0), 0.0);
+            let (f0, f90) = (splat(0.04), splat(1.0));
+            assert_eq!(fresnel_schlick(f0, f90, 1.0), f0);
+            assert_eq!(fresnel_schlick(f0, f90, 0.0), f90);
+            assert_near(fresnel_schlick(f0, f90, 0.5).x, 0.04 + 0.96 / 32.0, 1e-6);
+        }
+
+        #[test]
+        fn ggx_known_values() {
+            // alpha = 1 is the uniform distribution.
+            for n_dot_h in [0.0, 0.3, 1.0] {
+                assert_near(d_ggx(n_dot_h, 1.0), 1.0 / PI, 1e-6);
+            }
+            assert_near(d_ggx(1.0, 0.5), 1.0 / (PI * 0.25), 1e-5);
+            // Projected microfacet area integrates to one.
+            for alpha in [0.2, 0.5, 1.0] {
+                let area = hemisphere_integral(256, |h| d_ggx(h.z, alpha) * h.z);
+                assert_near(area, 1.0, 2e-3);
+            }
+        }
+
+        #[test]
+        fn smith_visibility_known_values() {
+            for alpha in [0.1, 0.5, 1.0] {
+                assert_near(v_smith_ggx_correlated(1.0, 1.0, alpha), 0.25, 1e-6);
+            }
+            // alpha = 1 reduces to 1 / (2 (n_dot_l + n_dot_v)).
+            assert_near(v_smith_ggx_correlated(0.3, 0.8, 1.0), 0.5 / 1.1, 1e-6);
+            assert_eq!(v_smith_ggx_correlated(0.0, 0.0, 0.5), 0.0);
+        }
+
+        #[test]
+        fn sheen_known_values() {
+            for roughness in [0.3, 0.6, 1.0] {
+                let area = hemisphere_integral(256, |h| d_charlie(h.z, roughness) * h.z);
+                assert_near(area, 1.0, 2e-3);
+            }
+            // sin^2 / (2 pi) times 3 at roughness 1.
+            assert_near(d_charlie(0.0, 1.0), 3.0 / (2.0 * PI), 1e-6);
+            assert_eq!(d_charlie(1.0, 0.5), 0.0);
+            assert_near(v_sheen(1.0, 1.0), 0.25, 1e-6);
+            assert_near(v_sheen(0.5, 0.5), 1.0 / 3.0, 1e-6);
+            assert_eq!(v_sheen(0.0, 0.0), 0.0);
+        }
+
+        #[test]
+        fn lambert_conserves_energy() {
+            assert_near(diffuse_lobe(splat(1.0)).x, 1.0 / PI, 1e-7);
+            let albedo = hemisphere_integral(128, |l| diffuse_lobe(splat(1.0)).x * l.z);
+            assert_near(albedo, 1.0, 1e-3);
+        }
+
+        #[test]
+        fn sheen_albedo_table_matches_integral() {
+            // The grazing, nearly smooth corner is too sharp for a coarse integral.
+            let last = (SHEEN_ALBEDO_SIZE - 1) as f32;
+            for y in (3..SHEEN_ALBEDO_SIZE).step_by(4) {
+                for x in (1..SHEEN_ALBEDO_SIZE).step_by(4) {
+                    let (n_dot_v, roughness) = (x as f32 / last, y as f32 / last);
+                    let v = glm::vec3((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+                    let albedo = hemisphere_integral(192, |l| {
+                        let n_dot_h = glm::normalize(l + v).z;
+                        d_charlie(n_dot_h, roughness) * v_sheen(l.z, n_dot_v) * l.z
+                    });
+                    assert_near(SHEEN_ALBEDO[y][x], albedo, 2e-3);
+                }
+            }
+        }
+
+        #[test]
+        fn sheen_albedo_table_matches_shader() {
+            let shader = include_str!("shaders/brdf.hlsli");
+            let start = shader.find("sheen_albedo_table[").unwrap();
+            let body = &shader[start..];
+            let body = &body[body.find('{').unwrap() + 1..body.find('}').unwrap()];
+            let values: Vec<f32> = body.split(',').map(|v| v.trim().parse().unwrap()).collect();
+            assert_eq!(values, SHEEN_ALBEDO.concat());
+        }
+
+        #[test]
+        fn sheen_albedo_lookup_interpolates() {
+            assert_eq!(sheen_directional_albedo(1.0, 1.0), SHEEN_ALBEDO[15][15]);
+            assert_eq!(sheen_directional_albedo(0.0, 0.0), SHEEN_ALBEDO[0][0]);
+            let between = sheen_directional_albedo(7.5 / 15.0, 1.0);
+            assert_near(
+                between,
+                (SHEEN_ALBEDO[15][7] + SHEEN_ALBEDO[15][8]) / 2.0,
+                1e-6,
+            );
+
+            let mut p = rough_dielectric();
+            assert_eq!(sheen_albedo_scaling(&p, 0.5, 0.5), 1.0);
+            p.sheen_color = splat(1.0);
+            p.sheen_roughness = 1.0;
+            let expected = 1.0 - SHEEN_ALBEDO[15][0].max(SHEEN_ALBEDO[15][15]);
+            assert_near(sheen_albedo_scaling(&p, 0.0, 1.0), expected, 1e-6);
+        }
+
+        #[test]
+        fn rough_dielectric_does_not_create_energy() {
+            let p = rough_dielectric();
+            let n = glm::vec3(0.0, 0.0, 1.0);
+            for theta in [0.0f32, 0.6, 1.2] {
+                let v = from_polar(theta);
+                let albedo = hemisphere_integral(128, |l| evaluate(&p, n, n, v, l).x);
+                assert!(albedo > 0.8 && albedo <= 1.0, "{albedo} at {theta}");
+            }
+        }
+
+        #[test]
+        fn clearcoat_uses_its_own_normal() {
+            let mut p = rough_dielectric();
+            p.clearcoat = 1.0;
+            p.clearcoat_roughness = 0.1;
+            let n = glm::vec3(0.0, 0.0, 1.0);
+            let coat_n = from_polar(0.3);
+            // Mirror direction about the coat normal only.
+            let v = n;
+            let l = coat_n * (2.0 * glm::dot(coat_n, v)) - v;
+            let tilted = evaluate(&p, n, coat_n, v, l);
+            let flat = evaluate(&p, n, n, v, l);
+            assert!(tilted.x > 10.0 * flat.x, "{} vs {}", tilted.x, flat.x);
+
+            p.clearcoat = 0.0;
+            assert_eq!(evaluate(&p, n, coat_n, v, l), evaluate(&p, n, n, v, l));
+        }
+
+        #[test]
+        fn light_attenuation_known_values() {
+            assert_near(range_attenuation(2.0, None), 0.25, 1e-6);
+            assert_eq!(range_attenuation(4.0, Some(4.0)), 0.0);
+            assert_near(
+                range_attenuation(2.0, Some(4.0)),
+                (1.0 - 1.0 / 16.0) / 4.0,
+                1e-6,
+            );
+
+            let (scale, offset) = spot_scale_offset(0.2, 0.5);
+            assert_near(spot_attenuation(0.2f32.cos(), scale, offset), 1.0, 1e-5);
+            assert_near(spot_attenuation(0.5f32.cos(), scale, offset), 0.0, 1e-5);
+            assert_eq!(spot_attenuation(1.0, scale, offset), 1.0);
+        }
+
+        #[test]
+        fn volume_attenuation_follows_beer_lambert() {
+            let mut p = rough_dielectric();
+            p.attenuation_color = glm::vec3(0.5, 0.25, 1.0);
+            p.attenuation_distance = 2.0;
+            assert_eq!(volume_attenuation(&p), splat(1.0));
+            p.thickness = 2.0;
+            assert_eq!(volume_attenuation(&p), p.attenuation_color);
+            p.thickness = 4.0;
+            assert_near(volume_attenuation(&p).y, 0.0625, 1e-6);
+        }
+    }
+}
@@ -0,0 +1,687 @@
+pub mod bvh {
+
+    //! Bounding volume hierarchy over triangles, built with the binned surface area
+    //! heuristic (SAH), for ray, box and sphere queries.
+
+    use crate::math::math::{vec3_add, vec3_cross, vec3_dot, vec3_scale, vec3_sub};
+
+    /// Centroid bins per axis when looking for a split.
+    const BINS: usize = 12;
+    /// Ranges at most this long become leaves when splitting doesn't pay off.
+    const MAX_LEAF_TRIANGLES: usize = 8;
+
+    const EMPTY: ([f32; 3], [f32; 3]) = ([f32::MAX; 3], [f32::MIN; 3]);
+
+    fn union(a: ([f32; 3], [f32; 3]), b: ([f32; 3], [f32; 3])) -> ([f32; 3], [f32; 3]) {
+        (
+            [0, 1, 2].map(|c| a.0[c].min(b.0[c])),
+            [0, 1, 2].map(|c| a.1[c].max(b.1[c])),
+        )
+    }
+
+    fn surface_area(aabb: ([f32; 3], [f32; 3])) -> f32 {
+        let [x, y, z] = [0, 1, 2].map(|c| (aabb.1[c] - aabb.0[c]).max(0.0));
+        2.0 * (x * y + y * z + z * x)
+    }
+
+    fn triangle_aabb(triangle: &[[f32; 3]; 3]) -> ([f32; 3], [f32; 3]) {
+        triangle.iter().fold(EMPTY, |aabb, &p| union(aabb, (p, p)))
+    }
+
+    /// Centroid bin along an axis whose centroids start at `low`.
+    fn bin(centroid: f32, low: f32, scale: f32) -> usize {
+        (((centroid - low) * scale) as usize).min(BINS - 1)
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct Node {
+        aabb: ([f32; 3], [f32; 3]),
+        /// First of `count` entries of `Bvh::order` for leaves, index of the left child,
+        /// followed by the right one, for interior nodes.
+        start: u32,
+        /// Zero for interior nodes.
+        count: u32,
+    }
+
+    /// Split of a range: triangles whose centroid falls in a bin below `bin` go left.
+    struct Split {
+        axis: usize,
+        low: f32,
+        scale: f32,
+        bin: usize,
+    }
+
+    /// Closest triangle along a ray. `u` and `v` are the barycentric weights of the second
+    /// and third corners.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct RayHit {
+        pub triangle: usize,
+        pub t: f32,
+        pub u: f32,
+        pub v: f32,
+    }
+
+    /// Triangles are identified by their index in the list given to `build`.
+    #[derive(Clone, Debug, Default)]
+    pub struct Bvh {
+        nodes: Vec<Node>,
+        /// Triangle indices, each leaf owning a contiguous range.
+        order: Vec<u32>,
+        triangles: Vec<[[f32; 3]; 3]>,
+    }
+
+    impl Bvh {
+        pub fn build(triangles: Vec<[[f32; 3]; 3]>) -> Self {
+            if triangles.is_empty() {
+                return Bvh::default();
+            }
+            let boxes: Vec<([f32; 3], [f32; 3])> = triangles.iter().map(triangle_aabb).collect();
+            let centroids: Vec<[f32; 3]> = boxes
+                .iter()
+                .map(|(min, max)| [0, 1, 2].map(|c| (min[c] + max[c]) * 0.5))
+                .collect();
+            let mut order: Vec<u32> = (0..triangles.len() as u32).collect();
+            let mut nodes = vec![Node {
+                aabb: EMPTY,
+                start: 0,
+                count: triangles.len() as u32,
+            }];
+
+            let mut stack = vec![0];
+            while let Some(n) = stack.pop() {
+                let start = nodes[n].start as usize;
+                let range = &mut order[start..start + nodes[n].count as usize];
+                let aabb = range
+                    .iter()
+                    .fold(EMPTY, |aabb, &t| union(aabb, boxes[t as usize]));
+                nodes[n].aabb = aabb;
+                let split = match best_split(range, &boxes, &centroids, surface_area(aabb)) {
+                    Some(split) => split,
+                    None => continue,
+                };
+
+                let mut mid = 0;
+                for i in 0..range.len() {
+                    let centroid = centroids[range[i] as usize][split.axis];
+                    if bin(centroid, split.low, split.scale) < split.bin {
+                        range.swap(i, mid);
+                        mid += 1;
+                    }
+                }
+                let left = nodes.len();
+                nodes.push(Node {
+                    aabb: EMPTY,
+                    start: start as u32,
+                    count: mid as u32,
+                });
+                nodes.push(Node {
+                    aabb: EMPTY,
+                    start: (start + mid) as u32,
+                    count: (range.len() - mid) as u32,
+                });
+                nodes[n].start = left as u32;
+                nodes[n].count = 0;
+                stack.push(left);
+                stack.push(left + 1);
+            }
+            Bvh {
+                nodes,
+                order,
+                triangles,
+            }
+        }
+
+        /// Moves the triangles to `triangles`, given in the same order as to `build`, and
+        /// refits the node boxes around them. The tree keeps its shape, so queries slow
+        /// down as the triangles drift from where they were built.
+        pub fn refit(&mut self, triangles: Vec<[[f32; 3]; 3]>) {
+            debug_assert_eq!(triangles.len(), self.triangles.len());
+            self.triangles = triangles;
+            // Children always come after their parent.
+            for n in (0..self.nodes.len()).rev() {
+                let node = self.nodes[n];
+                let start = node.start as usize;
+                self.nodes[n].aabb = if node.count > 0 {
+                    self.order[start..start + node.count as usize]
+                        .iter()
+                        .fold(EMPTY, |aabb, &t| {
+                            union(aabb, triangle_aabb(&self.triangles[t as usize]))
+                        })
+                } else {
+                    union(self.nodes[start].aabb, self.nodes[start + 1].aabb)
+                };
+            }
+        }
+
+        /// Expected cost of a random ray query relative to testing one triangle, with a
+        /// node visit costing as much as a triangle test. Lower is better.
+        pub fn sah_cost(&self) -> f32 {
+            let root = match self.nodes.first() {
+                Some(root) => surface_area(root.aabb),
+                None => return 0.0,
+            };
+            let cost: f32 = self
+                .nodes
+                .iter()
+                .map(|node| surface_area(node.aabb) * (1 + node.count) as f32)
+                .sum();
+            if root > 0.0 {
+                cost / root
+            } else {
+                self.triangles.len() as f32
+            }
+        }
+
+        /// Calls `leaf` with every triangle of the leaves whose box passes `visit`, which
+        /// also prunes interior nodes.
+        fn leaves(
+            &self,
+            mut visit: impl FnMut(([f32; 3], [f32; 3])) -> bool,
+            mut leaf: impl FnMut(usize),
+        ) {
+            let mut stack = if self.nodes.is_empty() {
+                Vec::new()
+            } else {
+                vec![0]
+            };
+            while let Some(n) = stack.pop() {
+                let node = self.nodes[n];
+                if !visit(node.aabb) {
+                    continue;
+                }
+                let start = node.start as usize;
+                if node.count > 0 {
+                    for &t in &self.order[start..start + node.count as usize] {
+                        leaf(t as usize);
+                    }
+                } else {
+                    stack.push(start);
+                    stack.push(start + 1);
+                }
+            }
+        }
+
+        /// Nearest triangle hit from either side by the ray from `origin` along
+        /// `direction` within `max_t` direction lengths.
+        pub fn intersect_ray(
+            &self,
+            origin: [f32; 3],
+            direction: [f32; 3],
+            max_t: f32,
+        ) -> Option<RayHit> {
+            let inverse = direction.map(|d| 1.0 / d);
+            let mut nearest: Option<RayHit> = None;
+            let mut max_t = max_t;
+            let mut stack = if self.nodes.is_empty() {
+                Vec::new()
+            } else {
+                vec![0]
+            };
+            while let Some(n) = stack.pop() {
+                let node = self.nodes[n];
+                if ray_aabb(node.aabb, origin, inverse, max_t).is_none() {
+                    continue;
+                }
+                let start = node.start as usize;
+                if node.count > 0 {
+                    for &t in &self.order[start..start + node.count as usize] {
+                        let triangle = &self.triangles[t as usize];
+                        if let Some((hit_t, u, v)) = ray_triangle(origin, direction, triangle) {
+                            if hit_t < max_t {
+                                max_t = hit_t;
+                                nearest = Some(RayHit {
+                                    triangle: t as usize,
+                                    t: hit_t,
+                                    u,
+                                    v,
+                                });
+                            }
+                        }
+                    }
+                    continue;
+                }
+                // Visit the nearer child first so it can shorten the ray for the other.
+                let entry = |child: usize| {
+                    ray_aabb(self.nodes[child].aabb, origin, inverse, max_t).unwrap_or(f32::MAX)
+                };
+                if entry(start) < entry(start + 1) {
+                    stack.push(start + 1);
+                    stack.push(start);
+                } else {
+                    stack.push(start);
+                    stack.push(start + 1);
+                }
+            }
+            nearest
+        }
+
+        /// Triangles touching the `(min, max)` box, in no particular order.
+        pub fn query_aabb(&self, aabb: ([f32; 3], [f32; 3])) -> Vec<usize> {
+            let mut found = Vec::new();
+            self.leaves(
+                |node| (0..3).all(|c| node.0[c] <= aabb.1[c] && node.1[c] >= aabb.0[c]),
+                |t| {
+                    if triangle_overlaps_aabb(&self.triangles[t], aabb) {
+                        found.push(t);
+                    }
+                },
+            );
+            found
+        }
+
+        /// Triangles within `radius` of `center`, in no particular order.
+        pub fn query_sphere(&self, center: [f32; 3], radius: f32) -> Vec<usize> {
+            let mut found = Vec::new();
+            let radius2 = radius * radius;
+            self.leaves(
+                |(min, max)| {
+                    let d = [0, 1, 2].map(|c| center[c].clamp(min[c], max[c]) - center[c]);
+                    vec3_dot(d, d) <= radius2
+                },
+                |t| {
+                    let d = vec3_sub(closest_point(center, &self.triangles[t]), center);
+                    if vec3_dot(d, d) <= radius2 {
+                        found.push(t);
+                    }
+                },
+            );
+            found
+        }
+    }
+
+    /// Cheapest binned SAH split of `range`, `None` when a leaf costs less.
+    fn best_split(
+        range: &[u32],
+        boxes: &[([f32; 3], [f32; 3])],
+        centroids: &[[f32; 3]],
+        area: f32,
+    ) -> Option<Split> {
+        if range.len() < 2 {
+            return None;
+        }
+        let bounds = range.iter().fold(EMPTY, |aabb, &t| {
+            let c = centroids[t as usize];
+            union(aabb, (c, c))
+        });
+
+        let mut best: Option<(f32, Split)> = None;
+        for (axis, (&low, &high)) in bounds.0.iter().zip(&bounds.1).enumerate() {
+            if high <= low {
+                continue;
+            }
+            let scale = BINS as f32 / (high - low);
+            let mut counts = [0usize; BINS];
+            let mut bin_boxes = [EMPTY; BINS];
+            for &t in range {
+                let b = bin(centroids[t as usize][axis], low, scale);
+                counts[b] += 1;
+                bin_boxes[b] = union(bin_boxes[b], boxes[t as usize]);
+            }
+
+            // Area and count left of every split between bins.
+            let mut left = [(0.0f32, 0usize); BINS];
+            let (mut aabb, mut count) = (EMPTY, 0);
+            for (i, (n, b)) in counts.iter().zip(&bin_boxes).enumerate() {
+                aabb = union(aabb, *b);
+                count += n;
+                left[i] = (surface_area(aabb), count);
+            }
+            let (mut aabb, mut count) = (EMPTY, 0);
+            for (i, (n, b)) in counts.iter().zip(&bin_boxes).enumerate().skip(1).rev() {
+                aabb = union(aabb, *b);
+                count += n;
+                let (left_area, left_count) = left[i - 1];
+                if left_count == 0 || count == 0 {
+                    continue;
+                }
+                let cost = left_area * left_count as f32 + surface_area(aabb) * count as f32;
+                let better = match &best {
+                    Some((best, _)) => cost < *best,
+                    None => true,
+                };
+                if better {
+                    let split = Split {
+                        axis,
+                        low,
+                        scale,
+                        bin: i,
+                    };
+                    best = Some((cost, split));
+                }
+            }
+        }
+
+        let (cost, split) = best?;
+        // A node visit costs as much as one triangle test.
+        let split_cost = if area > 0.0 {
+            1.0 + cost / area
+        } else {
+            f32::MAX
+        };
+        (split_cost < range.len() as f32 || range.len() > MAX_LEAF_TRIANGLES).then_some(split)
+    }
+
+    /// Distance along the ray to where it enters the box, if it does within `max_t`.
+    fn ray_aabb(
+        aabb: ([f32; 3], [f32; 3]),
+        origin: [f32; 3],
+        inverse: [f32; 3],
+        max_t: f32,
+    ) -> Option<f32> {
+        let (mut near, mut far) = (0.0f32, max_t);
+        let slabs = aabb.0.iter().zip(&aabb.1).zip(origin.iter().zip(&inverse));
+        for ((min, max), (o, inverse)) in slabs {
+            let a = (min - o) * inverse;
+            let b = (max - o) * inverse;
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+        }
+        (near <= far).then_some(near)
+    }
+
+    /// Möller-Trumbore, hitting either side.
+    fn ray_triangle(
+        origin: [f32; 3],
+        direction: [f32; 3],
+        triangle: &[[f32; 3]; 3],
+    ) -> Option<(f32, f32, f32)> {
+        let [a, b, c] = *triangle;
+        let e1 = vec3_sub(b, a);
+        let e2 = vec3_sub(c, a);
+        let p = vec3_cross(direction, e2);
+        let det = vec3_dot(e1, p);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let inverse = 1.0 / det;
+        let s = vec3_sub(origin, a);
+        let u = vec3_dot(s, p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = vec3_cross(s, e1);
+        let v = vec3_dot(direction, q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = vec3_dot(e2, q) * inverse;
+        (t >= 0.0).then_some((t, u, v))
+    }
+
+    /// Separating axis test of a triangle against a `(min, max)` box (Akenine-Möller).
+    fn triangle_overlaps_aabb(triangle: &[[f32; 3]; 3], aabb: ([f32; 3], [f32; 3])) -> bool {
+        let (min, max) = aabb;
+        let center = [0, 1, 2].map(|c| (min[c] + max[c]) * 0.5);
+        let half = [0, 1, 2].map(|c| (max[c] - min[c]) * 0.5);
+        let v = triangle.map(|p| vec3_sub(p, center));
+        let separates = |axis: [f32; 3]| {
+            let p = v.map(|v| vec3_dot(v, axis));
+            let r = (0..3).map(|c| half[c] * axis[c].abs()).sum::<f32>();
+            p[0].min(p[1]).min(p[2]) > r || p[0].max(p[1]).max(p[2]) < -r
+        };
+
+        let units = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let edges = [
+            vec3_sub(v[1], v[0]),
+            vec3_sub(v[2], v[1]),
+            vec3_sub(v[0], v[2]),
+        ];
+        let normal = vec3_cross(edges[0], edges[1]);
+        let mut axes = units.to_vec();
+        axes.push(normal);
+        for unit in units {
+            axes.extend(edges.map(|edge| vec3_cross(unit, edge)));
+        }
+        !axes.into_iter().any(separates)
+    }
+
+    /// Point of `triangle` nearest to `p` (Ericson, Real-Time Collision Detection 5.1.5).
+    pub fn closest_point(p: [f32; 3], triangle: &[[f32; 3]; 3]) -> [f32; 3] {
+        let [a, b, c] = *triangle;
+        let ab = vec3_sub(b, a);
+        let ac = vec3_sub(c, a);
+        let ap = vec3_sub(p, a);
+        let d1 = vec3_dot(ab, ap);
+        let d2 = vec3_dot(ac, ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+        let bp = vec3_sub(p, b);
+        let d3 = vec3_dot(ab, bp);
+        let d4 = vec3_dot(ac, bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return vec3_add(a, vec3_scale(ab, d1 / (d1 - d3)));
+        }
+        let cp = vec3_sub(p, c);
+        let d5 = vec3_dot(ab, cp);
+        let d6 = vec3_dot(ac, cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return vec3_add(a, vec3_scale(ac, d2 / (d2 - d6)));
+        }
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return vec3_add(b, vec3_scale(vec3_sub(c, b), w));
+        }
+        let denom = 1.0 / (va + vb + vc);
+        vec3_add(
+            a,
+            vec3_add(vec3_scale(ab, vb * denom), vec3_scale(ac, vc * denom)),
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::f32::consts::TAU;
+
+        use super::*;
+
+        /// Deterministic values in [-1, 1).
+        struct Random(u32);
+
+        impl Random {
+            fn next(&mut self) -> f32 {
+                self.0 = self.0.wrapping_mul(1664525).wrapping_add(1013904223);
+                (self.0 >> 8) as f32 / (1 << 23) as f32 - 1.0
+            }
+
+            fn vec3(&mut self, scale: f32) -> [f32; 3] {
+                [self.next(), self.next(), self.next()].map(|v| v * scale)
+            }
+        }
+
+        /// Triangles of a torus around the y axis, two per quad of the grid.
+        fn torus(major: f32, minor: f32, segments: u32, sides: u32) -> Vec<[[f32; 3]; 3]> {
+            let point = |segment: u32, side: u32| {
+                let (sin, cos) = (TAU * segment as f32 / segments as f32).sin_cos();
+                let (sin_side, cos_side) = (TAU * side as f32 / sides as f32).sin_cos();
+                let radius = major + minor * cos_side;
+                [radius * sin, minor * sin_side, radius * cos]
+            };
+            let mut triangles = Vec::new();
+            for segment in 0..segments {
+                for side in 0..sides {
+                    let a = point(segment, side);
+                    let b = point(segment + 1, side);
+                    let c = point(segment + 1, side + 1);
+                    let d = point(segment, side + 1);
+                    triangles.extend([[a, b, c], [a, c, d]]);
+                }
+            }
+            triangles
+        }
+
+        /// A torus plus a soup of small triangles around it.
+        fn triangles(random: &mut Random) -> Vec<[[f32; 3]; 3]> {
+            let mut triangles = torus(2.0, 0.5, 32, 16);
+            for _ in 0..500 {
+                let center = random.vec3(3.0);
+                triangles.push([0, 1, 2].map(|_| vec3_add(center, random.vec3(0.3))));
+            }
+            triangles
+        }
+
+        fn brute_force_ray(
+            triangles: &[[[f32; 3]; 3]],
+            origin: [f32; 3],
+            direction: [f32; 3],
+            max_t: f32,
+        ) -> Option<f32> {
+            triangles
+                .iter()
+                .filter_map(|t| ray_triangle(origin, direction, t))
+                .map(|(t, _, _)| t)
+                .filter(|&t| t < max_t)
+                .min_by(f32::total_cmp)
+        }
+
+        /// Compares every query against testing each triangle.
+        fn check_queries(bvh: &Bvh, random: &mut Random) {
+            let triangles = &bvh.triangles;
+            let mut hits = 0;
+            for i in 0..300 {
+                let origin = random.vec3(5.0);
+                let direction = vec3_sub(random.vec3(1.0), vec3_scale(origin, 0.2));
+                let max_t = if i % 3 == 0 { 2.0 } else { f32::MAX };
+                let hit = bvh.intersect_ray(origin, direction, max_t);
+                let expected = brute_force_ray(triangles, origin, direction, max_t);
+                assert_eq!(hit.map(|hit| hit.t), expected);
+                if let Some(hit) = hit {
+                    hits += 1;
+                    let (t, u, v) =
+                        ray_triangle(origin, direction, &triangles[hit.triangle]).unwrap();
+                    assert_eq!((t, u, v), (hit.t, hit.u, hit.v));
+                }
+            }
+            assert!(hits > 50, "{}", hits);
+
+            for _ in 0..100 {
+                let center = random.vec3(3.0);
+                let half = random.vec3(0.5).map(|h| h.abs() + 0.05);
+                let aabb = (vec3_sub(center, half), vec3_add(center, half));
+                let mut found = bvh.query_aabb(aabb);
+                found.sort();
+                let expected: Vec<usize> = (0..triangles.len())
+                    .filter(|&t| triangle_overlaps_aabb(&triangles[t], aabb))
+                    .collect();
+                assert_eq!(found, expected);
+
+                let radius = half[0];
+                let mut found = bvh.query_sphere(center, radius);
+                found.sort();
+                let expected: Vec<usize> = (0..triangles.len())
+                    .filter(|&t| {
+                        let d = vec3_sub(closest_point(center, &triangles[t]), center);
+                        vec3_dot(d, d) <= radius * radius
+                    })
+                    .collect();
+                assert_eq!(found, expected);
+            }
+        }
+
+        /// Every node box holds its children or triangles, and every triangle is in
+        /// exactly one leaf.
+        fn check_structure(bvh: &Bvh) {
+            let contains = |outer: ([f32; 3], [f32; 3]), inner: ([f32; 3], [f32; 3])| {
+                (0..3).all(|c| outer.0[c] <= inner.0[c] && inner.1[c] <= outer.1[c])
+            };
+            let mut seen = vec![0; bvh.triangles.len()];
+            for node in &bvh.nodes {
+                let start = node.start as usize;
+                if node.count > 0 {
+                    for &t in &bvh.order[start..start + node.count as usize] {
+                        seen[t as usize] += 1;
+                        assert!(contains(
+                            node.aabb,
+                            triangle_aabb(&bvh.triangles[t as usize])
+                        ));
+                    }
+                } else {
+                    assert!(contains(node.aabb, bvh.nodes[start].aabb));
+                    assert!(contains(node.aabb, bvh.nodes[start + 1].aabb));
+                }
+            }
+            assert!(seen.iter().all(|&n| n == 1));
+        }
+
+        #[test]
+        fn queries_match_brute_force() {
+            let mut random = Random(7);
+            let bvh = Bvh::build(triangles(&mut random));
+            check_structure(&bvh);
+            check_queries(&bvh, &mut random);
+        }
+
+        #[test]
+        fn refitted_trees_match_brute_force() {
+            let mut random = Random(11);
+            let triangles = triangles(&mut random);
+            let mut bvh = Bvh::build(triangles.clone());
+            let moved = triangles
+                .iter()
+                .enumerate()
+                .map(|(i, t)| t.map(|[x, y, z]| [y + i as f32 * 1e-3, -x, z * 1.5]))
+                .collect();
+            bvh.refit(moved);
+            check_structure(&bvh);
+            check_queries(&bvh, &mut random);
+        }
+
+        #[test]
+        fn the_sah_beats_a_single_leaf() {
+            let mut random = Random(3);
+            let triangles = triangles(&mut random);
+            let count = triangles.len() as f32;
+            let bvh = Bvh::build(triangles);
+            assert!(bvh.sah_cost() > 1.0);
+            assert!(bvh.sah_cost() < count / 10.0, "{}", bvh.sah_cost());
+            for node in &bvh.nodes {
+                assert!(node.count as usize <= MAX_LEAF_TRIANGLES);
+            }
+        }
+
+        #[test]
+        fn empty_trees_find_nothing() {
+            let bvh = Bvh::build(Vec::new());
+            assert_eq!(bvh.sah_cost(), 0.0);
+            assert_eq!(bvh.intersect_ray([0.0; 3], [0.0, 0.0, 1.0], f32::MAX), None);
+            assert!(bvh.query_aabb(([-1.0; 3], [1.0; 3])).is_empty());
+            assert!(bvh.query_sphere([0.0; 3], 1.0).is_empty());
+        }
+
+        #[test]
+        fn rays_hit_either_side_with_barycentrics() {
+            let triangle = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+            let bvh = Bvh::build(vec![triangle]);
+            let hit = bvh
+                .intersect_ray([0.25, 0.5, 2.0], [0.0, 0.0, -0.5], f32::MAX)
+                .unwrap();
+            assert_eq!(
+                hit,
+                RayHit {
+                    triangle: 0,
+                    t: 4.0,
+                    u: 0.25,
+                    v: 0.5
+                }
+            );
+            assert!(bvh
+                .intersect_ray([0.25, 0.5, -2.0], [0.0, 0.0, 1.0], f32::MAX)
+                .is_some());
+            assert!(bvh
+                .intersect_ray([0.25, 0.5, -2.0], [0.0, 0.0, 1.0], 1.5)
+                .is_none());
+            assert!(bvh
+                .intersect_ray([0.75, 0.5, 2.0], [0.0, 0.0, -1.0], f32::MAX)
+                .is_none());
+        }
+    }
+}
@@ -0,0 +1,406 @@
+pub mod camera {
+
+    use glm::{GenSquareMat, Mat4, Vec3};
+
+    use crate::math::math::{identity, transform_point};
+
+    /// glTF camera projections. Angles are in radians; a missing `zfar` means an infinite
+    /// perspective projection.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Projection {
+        Perspective {
+            yfov: f32,
+            aspect_ratio: Option<f32>,
+            znear: f32,
+            zfar: Option<f32>,
+        },
+        Orthographic {
+            xmag: f32,
+            ymag: f32,
+            znear: f32,
+            zfar: f32,
+        },
+    }
+
+    /// How an authored camera adapts when the viewport aspect differs from its own.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum AspectFit {
+        /// Keep the authored vertical extent; the horizontal one follows the viewport.
+        Vertical,
+        /// Keep the whole authored frame visible, widening whichever axis is short.
+        Contain,
+    }
+
+    /// Right-handed perspective looking down -Z, mapping depth to [0, 1].
+    pub fn perspective_rh(tan_half_y: f32, aspect: f32, znear: f32, zfar: Option<f32>) -> Mat4 {
+        let f = 1.0 / tan_half_y;
+        let (c2z, c3z) = match zfar {
+            Some(zfar) => (zfar / (znear - zfar), znear * zfar / (znear - zfar)),
+            None => (-1.0, -znear),
+        };
+        glm::mat4(
+            f / aspect, 0.0, 0.0, 0.0, 0.0, f, 0.0, 0.0, 0.0, 0.0, c2z, -1.0, 0.0, 0.0, c3z, 0.0,
+        )
+    }
+
+    /// Right-handed orthographic projection of a `2 * half_w` by `2 * half_h` box.
+    pub fn orthographic_rh(half_w: f32, half_h: f32, znear: f32, zfar: f32) -> Mat4 {
+        let d = znear - zfar;
+        glm::mat4(
+            1.0 / half_w,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0 / half_h,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0 / d,
+            0.0,
+            0.0,
+            0.0,
+            znear / d,
+            1.0,
+        )
+    }
+
+    impl Projection {
+        pub fn matrix(&self, viewport_aspect: f32, fit: AspectFit) -> Mat4 {
+            match *self {
+                Projection::Perspective {
+                    yfov,
+                    aspect_ratio,
+                    znear,
+                    zfar,
+                } => {
+                    let mut tan_half_y = (yfov * 0.5).tan();
+                    if let (AspectFit::Contain, Some(authored)) = (fit, aspect_ratio) {
+                        if viewport_aspect < authored {
+                            tan_half_y *= authored / viewport_aspect;
+                        }
+                    }
+                    perspective_rh(tan_half_y, viewport_aspect, znear, zfar)
+                }
+                Projection::Orthographic {
+                    xmag,
+                    ymag,
+                    znear,
+                    zfar,
+                } => {
+                    let mut half_h = ymag;
+                    if fit == AspectFit::Contain && viewport_aspect < xmag / ymag {
+                        half_h = xmag / viewport_aspect;
+                    }
+                    orthographic_rh(half_h * viewport_aspect, half_h, znear, zfar)
+                }
+            }
+        }
+    }
+
+    /// A camera placed by a glTF node.
+    #[derive(Clone, Debug)]
+    pub struct Camera {
+        pub name: Option<String>,
+        pub node: usize,
+        pub projection: Projection,
+        /// Camera-to-world transform of the node.
+        pub world: Mat4,
+    }
+
+    impl Camera {
+        pub fn position(&self) -> Vec3 {
+            transform_point(&self.world, glm::vec3(0.0, 0.0, 0.0))
+        }
+
+        pub fn view_matrix(&self) -> Mat4 {
+            self.world.inverse().unwrap_or_else(identity)
+        }
+
+        pub fn view_projection(&self, viewport_aspect: f32, fit: AspectFit) -> Mat4 {
+            self.projection.matrix(viewport_aspect, fit) * self.view_matrix()
+        }
+    }
+
+    /// Fly camera. Yaw 0 and pitch 0 look down -Z.
+    #[derive(Clone, Copy, Debug)]
+    pub struct FreeCamera {
+        pub position: Vec3,
+        pub yaw: f32,
+        pub pitch: f32,
+        pub yfov: f32,
+        pub znear: f32,
+    }
+
+    impl FreeCamera {
+        /// Looks down -Z at the box from far enough away to see all of it.
+        pub fn framing(min: [f32; 3], max: [f32; 3]) -> Self {
+            let yfov = 60f32.to_radians();
+            let center = glm::vec3(
+                (min[0] + max[0]) * 0.5,
+                (min[1] + max[1]) * 0.5,
+                (min[2] + max[2]) * 0.5,
+            );
+            let extent = glm::vec3(max[0] - min[0], max[1] - min[1], max[2] - min[2]);
+            let radius = (glm::length(extent) * 0.5).max(1e-3);
+            let distance = radius / (yfov * 0.5).sin();
+
+            FreeCamera {
+                position: center + glm::vec3(0.0, 0.0, distance),
+                yaw: 0.0,
+                pitch: 0.0,
+                yfov,
+                znear: (distance - radius).max(distance * 0.01),
+            }
+        }
+
+        pub fn forward(&self) -> Vec3 {
+            let (sy, cy) = self.yaw.sin_cos();
+            let (sp, cp) = self.pitch.sin_cos();
+            glm::vec3(-sy * cp, sp, -cy * cp)
+        }
+
+        pub fn right(&self) -> Vec3 {
+            glm::normalize(glm::cross(self.forward(), glm::vec3(0.0, 1.0, 0.0)))
+        }
+
+        pub fn move_by(&mut self, forward: f32, right: f32, up: f32) {
+            self.position = self.position
+                + self.forward() * forward
+                + self.right() * right
+                + glm::vec3(0.0, up, 0.0);
+        }
+
+        pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+            let limit = 89f32.to_radians();
+            self.yaw += yaw;
+            self.pitch = (self.pitch + pitch).clamp(-limit, limit);
+        }
+
+        /// Camera-to-world transform.
+        pub fn world(&self) -> Mat4 {
+            let f = self.forward();
+            let r = self.right();
+            let u = glm::cross(r, f);
+            let p = self.position;
+            glm::mat4(
+                r.x, r.y, r.z, 0.0, u.x, u.y, u.z, 0.0, -f.x, -f.y, -f.z, 0.0, p.x, p.y, p.z, 1.0,
+            )
+        }
+
+        pub fn projection(&self, viewport_aspect: f32) -> Mat4 {
+            perspective_rh((self.yfov * 0.5).tan(), viewport_aspect, self.znear, None)
+        }
+
+        pub fn view_projection(&self, viewport_aspect: f32) -> Mat4 {
+            self.projection(viewport_aspect) * self.world().inverse().unwrap_or_else(identity)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ActiveCamera {
+        Free,
+        Authored(usize),
+    }
+
+    /// The authored cameras plus the free camera, and which one is looking.
+    pub struct CameraRig {
+        pub cameras: Vec<Camera>,
+        pub free: FreeCamera,
+        pub active: ActiveCamera,
+        pub fit: AspectFit,
+    }
+
+    impl CameraRig {
+        /// Starts on the first authored camera, or the free camera when there is none.
+        pub fn new(cameras: Vec<Camera>, free: FreeCamera) -> Self {
+            let active = if cameras.is_empty() {
+                ActiveCamera::Free
+            } else {
+                ActiveCamera::Authored(0)
+            };
+            CameraRig {
+                cameras,
+                free,
+                active,
+                fit: AspectFit::Contain,
+            }
+        }
+
+        /// Steps through the authored cameras in order, then the free camera.
+        pub fn cycle(&mut self) {
+            self.active = match self.active {
+                ActiveCamera::Authored(i) if i + 1 < self.cameras.len() => {
+                    ActiveCamera::Authored(i + 1)
+                }
+                ActiveCamera::Authored(_) => ActiveCamera::Free,
+                ActiveCamera::Free if !self.cameras.is_empty() => ActiveCamera::Authored(0),
+                ActiveCamera::Free => ActiveCamera::Free,
+            };
+        }
+
+        pub fn name(&self) -> String {
+            match self.active {
+                ActiveCamera::Free => "free camera".to_string(),
+                ActiveCamera::Authored(i) => match &self.cameras[i].name {
+                    Some(name) => name.clone(),
+                    None => format!("camera {}", i),
+                },
+            }
+        }
+
+        pub fn position(&self) -> Vec3 {
+            match self.active {
+                ActiveCamera::Free => self.free.position,
+                ActiveCamera::Authored(i) => self.cameras[i].position(),
+            }
+        }
+
+        pub fn projection(&self, viewport_aspect: f32) -> Mat4 {
+            match self.active {
+                ActiveCamera::Free => self.free.projection(viewport_aspect),
+                ActiveCamera::Authored(i) => {
+                    self.cameras[i].projection.matrix(viewport_aspect, self.fit)
+                }
+            }
+        }
+
+        pub fn view_projection(&self, viewport_aspect: f32) -> Mat4 {
+            match self.active {
+                ActiveCamera::Free => self.free.view_projection(viewport_aspect),
+                ActiveCamera::Authored(i) => {
+                    self.cameras[i].view_projection(viewport_aspect, self.fit)
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Normalized device coordinates of `p`.
+        fn ndc(m: &Mat4, p: [f32; 3]) -> [f32; 3] {
+            let c = *m * glm::vec4(p[0], p[1], p[2], 1.0);
+            [c.x / c.w, c.y / c.w, c.z / c.w]
+        }
+
+        fn assert_ndc(m: &Mat4, p: [f32; 3], expected: [f32; 3]) {
+            let actual = ndc(m, p);
+            assert!(
+                (0..3).all(|i| (actual[i] - expected[i]).abs() < 1e-5),
+                "{:?} maps to {:?}, expected {:?}",
+                p,
+                actual,
+                expected
+            );
+        }
+
+        fn camera(name: Option<&str>) -> Camera {
+            Camera {
+                name: name.map(str::to_string),
+                node: 0,
+                projection: Projection::Orthographic {
+                    xmag: 1.0,
+                    ymag: 1.0,
+                    znear: 0.1,
+                    zfar: 10.0,
+                },
+                world: identity(),
+            }
+        }
+
+        #[test]
+        fn perspective_keeps_the_authored_frame_in_view() {
+            let projection = Projection::Perspective {
+                yfov: std::f32::consts::FRAC_PI_2,
+                aspect_ratio: Some(1.5),
+                znear: 1.0,
+                zfar: Some(10.0),
+            };
+            // The top right corner of the authored frame, two units in front of the camera.
+            let corner = [3.0, 2.0, -2.0];
+
+            for fit in [AspectFit::Vertical, AspectFit::Contain] {
+                let wide = projection.matrix(2.0, fit);
+                assert_ndc(&wide, corner, [0.75, 1.0, 5.0 / 9.0]);
+                assert_ndc(&wide, [0.0, 0.0, -1.0], [0.0, 0.0, 0.0]);
+                assert_ndc(&wide, [0.0, 0.0, -10.0], [0.0, 0.0, 1.0]);
+            }
+
+            // A viewport narrower than the authored aspect crops the sides, unless the
+            // whole frame has to stay visible.
+            let cropped = projection.matrix(1.0, AspectFit::Vertical);
+            assert_ndc(&cropped, corner, [1.5, 1.0, 5.0 / 9.0]);
+            let contained = projection.matrix(1.0, AspectFit::Contain);
+            assert_ndc(&contained, corner, [1.0, 2.0 / 3.0, 5.0 / 9.0]);
+        }
+
+        #[test]
+        fn perspective_without_zfar_is_infinite() {
+            let projection = Projection::Perspective {
+                yfov: std::f32::consts::FRAC_PI_2,
+                aspect_ratio: None,
+                znear: 0.5,
+                zfar: None,
+            };
+            // Without an authored aspect `Contain` has nothing to keep.
+            let m = projection.matrix(0.5, AspectFit::Contain);
+            assert_eq!(m, projection.matrix(0.5, AspectFit::Vertical));
+            assert_ndc(&m, [1.0, 1.0, -1.0], [2.0, 1.0, 0.5]);
+            assert_ndc(&m, [0.0, 0.0, -0.5], [0.0, 0.0, 0.0]);
+            assert!((ndc(&m, [0.0, 0.0, -1e6])[2] - 1.0).abs() < 1e-5);
+        }
+
+        #[test]
+        fn orthographic_fits_the_magnification() {
+            let projection = Projection::Orthographic {
+                xmag: 3.0,
+                ymag: 2.0,
+                znear: 0.5,
+                zfar: 4.5,
+            };
+            let corner = [3.0, 2.0, -0.5];
+
+            for fit in [AspectFit::Vertical, AspectFit::Contain] {
+                let m = projection.matrix(1.5, fit);
+                assert_ndc(&m, corner, [1.0, 1.0, 0.0]);
+                assert_ndc(&m, [0.0, 0.0, -4.5], [0.0, 0.0, 1.0]);
+                let wide = projection.matrix(3.0, fit);
+                assert_ndc(&wide, corner, [0.5, 1.0, 0.0]);
+            }
+
+            let cropped = projection.matrix(1.0, AspectFit::Vertical);
+            assert_ndc(&cropped, corner, [1.5, 1.0, 0.0]);
+            let contained = projection.matrix(1.0, AspectFit::Contain);
+            assert_ndc(&contained, corner, [1.0, 2.0 / 3.0, 0.0]);
+        }
+
+        #[test]
+        fn cycle_wraps_through_the_free_camera() {
+            let free = FreeCamera::framing([-1.0; 3], [1.0; 3]);
+            let mut rig = CameraRig::new(vec![camera(Some("front")), camera(None)], free);
+            assert_eq!(rig.active, ActiveCamera::Authored(0));
+            assert_eq!(rig.name(), "front");
+
+            rig.cycle();
+            assert_eq!(rig.active, ActiveCamera::Authored(1));
+            assert_eq!(rig.name(), "camera 1");
+            rig.cycle();
+            assert_eq!(rig.active, ActiveCamera::Free);
+            assert_eq!(rig.name(), "free camera");
+            assert_eq!(rig.position(), free.position);
+            rig.cycle();
+            assert_eq!(rig.active, ActiveCamera::Authored(0));
+        }
+
+        #[test]
+        fn cycle_without_authored_cameras_stays_free() {
+            let mut rig = CameraRig::new(Vec::new(), FreeCamera::framing([0.0; 3], [1.0; 3]));
+            assert_eq!(rig.active, ActiveCamera::Free);
+            rig.cycle();
+            assert_eq!(rig.active, ActiveCamera::Free);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+pub mod cooker {
+
+    //! Offline BCn encoding. The cooked glTF keeps each PNG or JPEG as the core image
+    //! source and points its textures at the DDS copies through MSFT_texture_dds, so
+    //! viewers without the extension still load it.
+
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::io::{Error, ErrorKind};
+    use std::path::{Path, PathBuf};
+
+    use serde_json::{json, Value};
+
+    use crate::dds::dds::write_dds;
+    use crate::gltf_loader::gltf_loader::{FileResolver, UriResolver};
+    use crate::texture_compressor::texture_compressor::{
+        compress, decompress, psnr, BcFormat, RgbaImage,
+    };
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum TextureUsage {
+        BaseColor,
+        Normal,
+        MetallicRoughness,
+        Occlusion,
+        Emissive,
+        Other,
+    }
+
+    impl TextureUsage {
+        /// Returns the block format and whether it is sampled as sRGB.
+        pub fn encoding(self, has_alpha: bool) -> (BcFormat, bool) {
+            match self {
+                TextureUsage::BaseColor => (BcFormat::Bc7, true),
+                TextureUsage::Normal => (BcFormat::Bc5, false),
+                TextureUsage::MetallicRoughness => (BcFormat::Bc7, false),
+                TextureUsage::Occlusion => (BcFormat::Bc4, false),
+                TextureUsage::Emissive => (BcFormat::Bc1, true),
+                TextureUsage::Other if has_alpha => (BcFormat::Bc3, true),
+                TextureUsage::Other => (BcFormat::Bc1, true),
+            }
+        }
+    }
+
+    pub struct CookedTexture {
+        pub image_index: usize,
+        pub usage: TextureUsage,
+        pub format: BcFormat,
+        pub srgb: bool,
+        pub width: u32,
+        pub height: u32,
+        pub mip_count: usize,
+        pub path: PathBuf,
+        /// Quality of each mip against the source downsampled to its size, top first, in
+        /// dB.
+        pub mip_psnr: Vec<f64>,
+    }
+
+    impl CookedTexture {
+        /// PSNR of the least faithful mip.
+        pub fn worst_psnr(&self) -> f64 {
+            self.mip_psnr.iter().copied().fold(f64::INFINITY, f64::min)
+        }
+    }
+
+    pub struct CookReport {
+        pub gltf_path: PathBuf,
+        pub textures: Vec<CookedTexture>,
+    }
+
+    impl fmt::Display for CookReport {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "Cooked {}", self.gltf_path.display())?;
+            for t in &self.textures {
+                writeln!(
+                    f,
+                    "  image {:>3} {:<18} {:?}{} {}x{} ({} mips) worst PSNR {:.2} dB -> {}",
+                    t.image_index,
+                    format!("{:?}", t.usage),
+                    t.format,
+                    if t.srgb { " sRGB" } else { "" },
+                    t.width,
+                    t.height,
+                    t.mip_count,
+                    t.worst_psnr(),
+                    t.path.display()
+                )?;
+            }
+            Ok(())
+        }
+    }
+
+    fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(err: E) -> Error {
+        Error::new(ErrorKind::InvalidData, err)
+    }
+
+    /// Image index to usage. When an image serves several slots the first match in
+    /// normal, base color, emissive, metallic-roughness, occlusion order wins.
+    fn classify_images(doc: &gltf::Document) -> HashMap<usize, TextureUsage> {
+        let mut usages = HashMap::new();
+
+        for material in doc.materials() {
+            if let Some(info) = material.normal_texture() {
+                usages
+                    .entry(info.texture().source().index())
+                    .or_insert(TextureUsage::Normal);
+            }
+        }
+        for material in doc.materials() {
+            let pbr = material.pbr_metallic_roughness();
+            if let Some(info) = pbr.base_color_texture() {
+                usages
+                    .entry(info.texture().source().index())
+                    .or_insert(TextureUsage::BaseColor);
+            }
+        }
+        for material in doc.materials() {
+            if let Some(info) = material.emissive_texture() {
+                usages
+                    .entry(info.texture().source().index())
+                    .or_insert(TextureUsage::Emissive);
+            }
+        }
+        for material in doc.materials() {
+            let pbr = material.pbr_metallic_roughness();
+            if let Some(info) = pbr.metallic_roughness_texture() {
+                usages
+                    .entry(info.texture().source().index())
+                    .or_insert(TextureUsage::MetallicRoughness);
+            }
+        }
+        for material in doc.materials() {
+            if let Some(info) = material.occlusion_texture() {
+                usages
+                    .entry(info.texture().source().index())
+                    .or_insert(TextureUsage::Occlusion);
+            }
+        }
+
+        usages
+    }
+
+    fn image_stem(image: &gltf::Image) -> String {
+        if let gltf::image::Source::Uri { uri, .. } = image.source() {
+            if !uri.starts_with("data:") {
+                if let Some(stem) = Path::new(uri).file_stem() {
+                    return stem.to_string_lossy().into_owned();
+                }
+            }
+        }
+        match image.name() {
+            Some(name) => name.to_string(),
+            None => format!("image_{}", image.index()),
+        }
+    }
+
+    /// Compresses `source` and its mip chain down to 1x1, with the PSNR of each level
+    /// against the downsampled source.
+    pub fn encode_mips(source: &RgbaImage, format: BcFormat) -> (Vec<Vec<u8>>, Vec<f64>) {
+        let mut mips = Vec::new();
+        let mut mip_psnr = Vec::new();
+        let mut level = source.clone();
+        loop {
+            let data = compress(&level, format);
+            let decoded = decompress(&data, level.width, level.height, format);
+            mip_psnr.push(psnr(&level, &decoded, format));
+            mips.push(data);
+            if level.width == 1 && level.height == 1 {
+                return (mips, mip_psnr);
+            }
+            level = level.downsample();
+        }
+    }
+
+    /// Appends an image per DDS file and gives every texture an MSFT_texture_dds source
+    /// pointing at the DDS copy of its core image. `dds_uris` is indexed like the images.
+    fn add_dds_sources(root: &mut Value, dds_uris: Vec<String>) {
+        if dds_uris.is_empty() {
+            return;
+        }
+        let images = root["images"].as_array_mut().expect("cooked images");
+        let first = images.len();
+        images.extend(dds_uris.into_iter().map(|uri| json!({ "uri": uri })));
+        if let Some(textures) = root["textures"].as_array_mut() {
+            for texture in textures {
+                if let Some(source) = texture["source"].as_u64() {
+                    let dds = json!({ "source": first + source as usize });
+                    texture["extensions"]["MSFT_texture_dds"] = dds;
+                }
+            }
+        }
+        let used = &mut root["extensionsUsed"];
+        if !used.is_array() {
+            *used = json!([]);
+        }
+        let used = used.as_array_mut().unwrap();
+        if !used.iter().any(|name| name == "MSFT_texture_dds") {
+            used.push("MSFT_texture_dds".into());
+        }
+    }
+
+    /// Encodes every image referenced by `input` to BCn and writes the DDS files, copies
+    /// of external images, the buffers and a glTF pointing at them into `out_dir`.
+    pub fn cook(input: &Path, out_dir: &Path) -> std::io::Result<CookReport> {
+        let (doc, buffers, images) = gltf::import(input).map_err(invalid_data)?;
+        std::fs::create_dir_all(out_dir)?;
+
+        let usages = classify_images(&doc);
+        let resolver = FileResolver::for_file(input);
+        let mut textures = Vec::new();
+        let mut dds_uris = Vec::new();
+        // External core images are copied next to the cooked glTF under a unique name.
+        let mut core_uris = HashMap::new();
+
+        for image in doc.images() {
+            let index = image.index();
+            let source = RgbaImage::from_gltf(&images[index]).ok_or_else(|| {
+                invalid_data(format!("image {} has an unsupported pixel format", index))
+            })?;
+            let usage = *usages.get(&index).unwrap_or(&TextureUsage::Other);
+            let (format, srgb) = usage.encoding(source.has_alpha());
+            let (mips, mip_psnr) = encode_mips(&source, format);
+
+            // Images from different folders may share a stem.
+            let stem = format!("{}_{}", image_stem(&image), index);
+            if let gltf::image::Source::Uri { uri, .. } = image.source() {
+                if !uri.starts_with("data:") {
+                    let extension = Path::new(uri).extension().unwrap_or_default();
+                    let file_name = format!("{}.{}", stem, extension.to_string_lossy());
+                    std::fs::write(out_dir.join(&file_name), resolver.resolve(uri)?)?;
+                    core_uris.insert(index, file_name);
+                }
+            }
+
+            let file_name = format!("{}.dds", stem);
+            let path = out_dir.join(&file_name);
+            write_dds(
+                &path,
+                source.width,
+                source.height,
+                format.dxgi_format(srgb),
+                &mips,
+            )?;
+
+            dds_uris.push(file_name);
+            textures.push(CookedTexture {
+                image_index: index,
+                usage,
+                format,
+                srgb,
+                width: source.width,
+                height: source.height,
+                mip_count: mips.len(),
+                path,
+                mip_psnr,
+            });
+        }
+
+        let stem = input
+            .file_stem()
+            .map_or("scene".into(), |s| s.to_string_lossy().into_owned());
+
+        let mut root = doc.into_json();
+        for (i, buffer) in root.buffers.iter_mut().enumerate() {
+            let file_name = format!("{}_{}.bin", stem, i);
+            std::fs::write(out_dir.join(&file_name), &buffers[i].0)?;
+            buffer.uri = Some(file_name);
+        }
+        for (index, image) in root.images.iter_mut().enumerate() {
+            if let Some(uri) = core_uris.remove(&index) {
+                image.uri = Some(uri);
+            }
+        }
+
+        let mut root = serde_json::to_value(&root).map_err(invalid_data)?;
+        add_dds_sources(&mut root, dds_uris);
+
+        let gltf_path = out_dir.join(format!("{}.gltf", stem));
+        let json = serde_json::to_string_pretty(&root).map_err(invalid_data)?;
+        std::fs::write(&gltf_path, json)?;
+
+        Ok(CookReport {
+            gltf_path,
+            textures,
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::gltf_loader::gltf_loader::{load, ImportSettings};
+
+        /// Smooth gradients with a little deterministic noise and a soft alpha ramp, like
+        /// a photographed texture.
+        fn fixture(size: u32) -> RgbaImage {
+            let mut seed = 1u32;
+            let mut pixels = Vec::new();
+            for y in 0..size {
+                for x in 0..size {
+                    seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                    let noise = (seed >> 28) as i32 - 8;
+                    let wave = ((x as f32 * 0.3).sin() * (y as f32 * 0.2).cos() * 40.0) as i32;
+                    let channel = |v: i32| (v + noise).clamp(0, 255) as u8;
+                    pixels.extend_from_slice(&[
+                        channel((x * 255 / size) as i32),
+                        channel((y * 255 / size) as i32 + wave / 2),
+                        channel(128 + wave),
+                        (255 - x * 128 / size) as u8,
+                    ]);
+                }
+            }
+            RgbaImage {
+                width: size,
+                height: size,
+                pixels,
+            }
+        }
+
+        #[test]
+        fn every_mip_meets_the_format_threshold() {
+            // (top mip, worst mip) in dB. Small mips squeeze both gradients into one block,
+            // which a single endpoint line follows poorly.
+            let thresholds = [
+                (BcFormat::Bc1, 34.0, 14.0),
+                (BcFormat::Bc3, 35.0, 15.0),
+                (BcFormat::Bc4, 47.0, 30.0),
+                (BcFormat::Bc5, 46.0, 30.0),
+                (BcFormat::Bc7, 37.0, 15.0),
+            ];
+            let source = fixture(64);
+            for (format, top, worst) in thresholds {
+                let (mips, mip_psnr) = encode_mips(&source, format);
+                assert_eq!((mips.len(), mip_psnr.len()), (7, 7));
+                assert_eq!(mips[6].len(), format.block_size());
+                assert!(mip_psnr[0] >= top, "{:?} top mip {}", format, mip_psnr[0]);
+                let lowest = mip_psnr.iter().copied().fold(f64::INFINITY, f64::min);
+                assert!(lowest >= worst, "{:?} worst mip {}", format, lowest);
+            }
+        }
+
+        #[test]
+        fn cooked_textures_keep_the_png_source() {
+            let dir = std::env::temp_dir().join(format!("cooker-{}", std::process::id()));
+            for folder in ["a", "b"] {
+                std::fs::create_dir_all(dir.join(folder)).unwrap();
+                let tex = fixture(16);
+                let path = dir.join(folder).join("tex.png");
+                image::save_buffer(path, &tex.pixels, 16, 16, image::ColorType::Rgba8).unwrap();
+            }
+            let input = dir.join("scene.gltf");
+            let json = r#"{
+                "asset": { "version": "2.0" },
+                "images": [{ "uri": "a/tex.png" }, { "uri": "b/tex.png" }],
+                "textures": [{ "source": 0 }, { "source": 1 }],
+                "materials": [{
+                    "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } },
+                    "normalTexture": { "index": 1 }
+                }]
+            }"#;
+            std::fs::write(&input, json).unwrap();
+
+            let out = dir.join("cooked");
+            let report = cook(&input, &out).unwrap();
+            let formats: Vec<_> = report.textures.iter().map(|t| t.format).collect();
+            assert_eq!(formats, [BcFormat::Bc7, BcFormat::Bc5]);
+            assert!(report.textures.iter().all(|t| t.mip_psnr.len() == 5));
+
+            let root: Value =
+                serde_json::from_slice(&std::fs::read(&report.gltf_path).unwrap()).unwrap();
+            let uris: Vec<_> = (0..4).map(|i| root["images"][i]["uri"].clone()).collect();
+            assert_eq!(uris, ["tex_0.png", "tex_1.png", "tex_0.dds", "tex_1.dds"]);
+            for uri in &uris {
+                assert!(out.join(uri.as_str().unwrap()).exists());
+            }
+            for texture in 0..2 {
+                let extension = &root["textures"][texture]["extensions"]["MSFT_texture_dds"];
+                assert_eq!(root["textures"][texture]["source"], texture);
+                assert_eq!(extension["source"], texture + 2);
+            }
+            assert_eq!(root["extensionsUsed"], json!(["MSFT_texture_dds"]));
+            assert!(root.get("extensionsRequired").is_none());
+
+            // Loaders without the extension still find the PNGs.
+            let path = report.gltf_path.to_str().unwrap();
+            let scene = load(path, &ImportSettings::default()).unwrap();
+            assert_eq!(scene.materials[0].base_color_texture.unwrap().image, 0);
+            assert_eq!(scene.images[0].pixels, fixture(16).pixels);
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
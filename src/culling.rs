@@ -0,0 +1,277 @@
+pub mod culling {
+
+    //! View frustum culling against world-space bounding boxes and spheres.
+
+    use std::fmt;
+
+    use glm::Mat4;
+
+    use crate::math::math::{max_axis_scale, transform_point};
+    use crate::mesh::mesh::MeshData;
+
+    /// Mesh-space `(min, max)` box around `mesh` and every shape its morph targets blend it
+    /// into with weights between 0 and 1.
+    pub fn mesh_bounds(mesh: &MeshData) -> ([f32; 3], [f32; 3]) {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for (i, p) in mesh.positions.iter().enumerate() {
+            let mut low = *p;
+            let mut high = *p;
+            for target in &mesh.morph_targets {
+                if let Some(delta) = target.positions.get(i) {
+                    for (c, d) in delta.iter().enumerate() {
+                        low[c] += d.min(0.0);
+                        high[c] += d.max(0.0);
+                    }
+                }
+            }
+            for (c, (low, high)) in low.iter().zip(&high).enumerate() {
+                min[c] = min[c].min(*low);
+                max[c] = max[c].max(*high);
+            }
+        }
+        if mesh.positions.is_empty() {
+            return ([0.0; 3], [0.0; 3]);
+        }
+        (min, max)
+    }
+
+    /// World-space volumes of one instance.
+    #[derive(Clone, Copy, Debug)]
+    pub struct WorldBounds {
+        /// `(min, max)` corners.
+        pub aabb: ([f32; 3], [f32; 3]),
+        /// `(center, radius)`.
+        pub sphere: ([f32; 3], f32),
+    }
+
+    impl WorldBounds {
+        /// The mesh-space box `aabb` placed by `world`: the box around the transformed
+        /// box (Arvo's method) and the sphere around the transformed box.
+        pub fn new(aabb: ([f32; 3], [f32; 3]), world: &Mat4) -> Self {
+            let (min, max) = aabb;
+            let center = [0, 1, 2].map(|c| (min[c] + max[c]) * 0.5);
+            let half = [0, 1, 2].map(|c| (max[c] - min[c]) * 0.5);
+            let radius = (half[0] * half[0] + half[1] * half[1] + half[2] * half[2]).sqrt();
+
+            let w = transform_point(world, glm::vec3(center[0], center[1], center[2]));
+            let center = [w.x, w.y, w.z];
+            // Column-major: world[c][r] maps mesh axis c onto world axis r.
+            let extent =
+                [0, 1, 2].map(|r| (0..3).map(|c| world[c][r].abs() * half[c]).sum::<f32>());
+            WorldBounds {
+                aabb: (
+                    [0, 1, 2].map(|r| center[r] - extent[r]),
+                    [0, 1, 2].map(|r| center[r] + extent[r]),
+                ),
+                sphere: (center, radius * max_axis_scale(world)),
+            }
+        }
+    }
+
+    /// `WorldBounds` of the mesh-space box `aabb` at every one of `worlds`.
+    pub fn world_bounds(aabb: ([f32; 3], [f32; 3]), worlds: &[Mat4]) -> Vec<WorldBounds> {
+        worlds
+            .iter()
+            .map(|world| WorldBounds::new(aabb, world))
+            .collect()
+    }
+
+    /// Planes `[a, b, c, d]` whose normals point inside: left, right, bottom, top, near and
+    /// far.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Frustum {
+        pub planes: [[f32; 4]; 6],
+    }
+
+    impl Frustum {
+        /// Extracts the planes from the rows of a view-projection matrix with clip depth in
+        /// [0, w] (Gribb and Hartmann). The far plane of an infinite projection has a zero
+        /// normal and a positive distance, so nothing is ever behind it.
+        pub fn from_view_projection(m: &Mat4) -> Self {
+            let row = |r: usize| [m[0][r], m[1][r], m[2][r], m[3][r]];
+            let add = |a: [f32; 4], b: [f32; 4]| [0, 1, 2, 3].map(|i| a[i] + b[i]);
+            let sub = |a: [f32; 4], b: [f32; 4]| [0, 1, 2, 3].map(|i| a[i] - b[i]);
+            let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+            let planes = [add(w, x), sub(w, x), add(w, y), sub(w, y), z, sub(w, z)].map(|p| {
+                let length = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+                if length > 0.0 {
+                    p.map(|v| v / length)
+                } else {
+                    p
+                }
+            });
+            Frustum { planes }
+        }
+
+        /// Whether the sphere isn't entirely behind any plane.
+        pub fn intersects_sphere(&self, sphere: ([f32; 3], f32)) -> bool {
+            let (c, radius) = sphere;
+            self.planes
+                .iter()
+                .all(|p| p[0] * c[0] + p[1] * c[1] + p[2] * c[2] + p[3] >= -radius)
+        }
+
+        /// Whether the box isn't entirely behind any plane, testing the corner furthest
+        /// along each plane normal.
+        pub fn intersects_aabb(&self, aabb: ([f32; 3], [f32; 3])) -> bool {
+            let (min, max) = aabb;
+            self.planes.iter().all(|p| {
+                let corner = [0, 1, 2].map(|c| if p[c] >= 0.0 { max[c] } else { min[c] });
+                p[0] * corner[0] + p[1] * corner[1] + p[2] * corner[2] + p[3] >= 0.0
+            })
+        }
+
+        /// Cheap sphere test first, then the tighter box.
+        pub fn is_visible(&self, bounds: &WorldBounds) -> bool {
+            self.intersects_sphere(bounds.sphere) && self.intersects_aabb(bounds.aabb)
+        }
+    }
+
+    /// Draws of the last frame.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct CullStats {
+        pub visible: usize,
+        pub culled: usize,
+    }
+
+    impl fmt::Display for CullStats {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{} visible, {} culled", self.visible, self.culled)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::camera::camera::perspective_rh;
+        use crate::math::math::{identity, trs_matrix};
+        use crate::mesh::mesh::MorphTarget;
+
+        /// Looking down -z from the origin, 20 units wide and 10 high at z = -10.
+        fn frustum(zfar: Option<f32>) -> Frustum {
+            Frustum::from_view_projection(&perspective_rh(0.5, 2.0, 1.0, zfar))
+        }
+
+        fn cube(center: [f32; 3], half: f32) -> ([f32; 3], [f32; 3]) {
+            (center.map(|c| c - half), center.map(|c| c + half))
+        }
+
+        fn is_in_clip_volume(m: &Mat4, p: [f32; 3]) -> bool {
+            let clip = *m * glm::vec4(p[0], p[1], p[2], 1.0);
+            let w = clip.w;
+            clip.x.abs() <= w && clip.y.abs() <= w && clip.z >= 0.0 && clip.z <= w
+        }
+
+        #[test]
+        fn planes_are_normalized_and_face_inside() {
+            let frustum = frustum(Some(100.0));
+            for p in frustum.planes {
+                assert!((p[0] * p[0] + p[1] * p[1] + p[2] * p[2] - 1.0).abs() < 1e-5);
+                assert!(p[0] * 0.0 + p[1] * 0.0 + p[2] * -10.0 + p[3] > 0.0);
+            }
+            // Near at z = -1, far at z = -100.
+            assert!((frustum.planes[4][3] - -1.0).abs() < 1e-5);
+            assert!((frustum.planes[5][3] - 100.0).abs() < 1e-3);
+        }
+
+        #[test]
+        fn boxes_outside_any_plane_are_culled() {
+            let frustum = frustum(Some(100.0));
+            let visible = |center, half| {
+                let bounds = WorldBounds::new(cube(center, half), &identity());
+                frustum.is_visible(&bounds)
+            };
+            assert!(visible([0.0, 0.0, -10.0], 1.0));
+            assert!(!visible([0.0, 0.0, 5.0], 1.0));
+            assert!(!visible([0.0, 0.0, -0.25], 0.5));
+            assert!(!visible([0.0, 0.0, -150.0], 1.0));
+            assert!(!visible([-13.0, 0.0, -10.0], 1.0));
+            assert!(!visible([0.0, 7.0, -10.0], 1.0));
+            // Straddling a side or the far plane.
+            assert!(visible([-10.5, 0.0, -10.0], 1.0));
+            assert!(visible([0.0, 0.0, -100.5], 1.0));
+
+            let infinite = self::frustum(None);
+            let far = WorldBounds::new(cube([0.0, 0.0, -1e6], 1.0), &identity());
+            assert!(infinite.is_visible(&far));
+        }
+
+        #[test]
+        fn culling_never_drops_a_box_with_a_point_inside() {
+            let projection = perspective_rh(0.5, 2.0, 1.0, Some(100.0));
+            let view = trs_matrix(
+                [1.0, -2.0, 3.0],
+                [0.0, 0.38268343, 0.0, 0.9238795],
+                [1.0; 3],
+            );
+            let m = projection * view;
+            let frustum = Frustum::from_view_projection(&m);
+            let mut visible = 0;
+            for x in -30..30 {
+                for z in -60..10 {
+                    let center = [x as f32 * 2.0, 1.5, z as f32 * 2.0];
+                    let aabb = cube(center, 0.75);
+                    let inside = (0..27).any(|k| {
+                        let offset = [k % 3, k / 3 % 3, k / 9].map(|o| (o as f32 - 1.0) * 0.75);
+                        is_in_clip_volume(&m, [0, 1, 2].map(|c| center[c] + offset[c]))
+                    });
+                    let bounds = WorldBounds::new(aabb, &identity());
+                    if inside {
+                        assert!(frustum.is_visible(&bounds), "{:?}", center);
+                        visible += 1;
+                    }
+                }
+            }
+            assert!(visible > 100);
+        }
+
+        #[test]
+        fn world_bounds_contain_the_placed_box() {
+            let aabb = ([-1.0, -2.0, -0.5], [1.0, 2.0, 0.5]);
+            let (sin, cos) = (0.3f32.sin(), 0.3f32.cos());
+            let world = trs_matrix([5.0, 0.0, -1.0], [0.0, 0.0, sin, cos], [2.0, 1.0, 3.0]);
+            let bounds = WorldBounds::new(aabb, &world);
+            let (min, max) = bounds.aabb;
+            for k in 0..8 {
+                let corner = [0, 1, 2].map(|c| {
+                    if k >> c & 1 == 0 {
+                        aabb.0[c]
+                    } else {
+                        aabb.1[c]
+                    }
+                });
+                let p = transform_point(&world, glm::vec3(corner[0], corner[1], corner[2]));
+                let p = [p.x, p.y, p.z];
+                for c in 0..3 {
+                    assert!(p[c] >= min[c] - 1e-5 && p[c] <= max[c] + 1e-5);
+                }
+                let (center, radius) = bounds.sphere;
+                let d = [0, 1, 2].map(|c| p[c] - center[c]);
+                assert!((d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt() <= radius + 1e-5);
+            }
+            // The z axis stays put, so its extent is exact.
+            assert!((max[2] - min[2] - 3.0).abs() < 1e-5);
+        }
+
+        #[test]
+        fn mesh_bounds_include_morph_targets() {
+            let mesh = MeshData {
+                positions: vec![[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]],
+                morph_targets: vec![
+                    MorphTarget {
+                        positions: vec![[0.0, -2.0, 0.0], [0.0; 3]],
+                        ..Default::default()
+                    },
+                    MorphTarget {
+                        positions: vec![[0.0, -1.0, 0.0], [0.5, 0.0, 0.0]],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            };
+            assert_eq!(mesh_bounds(&mesh), ([0.0, -3.0, 0.0], [1.5, 1.0, 1.0]));
+            assert_eq!(mesh_bounds(&MeshData::default()), ([0.0; 3], [0.0; 3]));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+pub mod dds {
+
+    use std::io::Write;
+    use std::path::Path;
+
+    const DDS_MAGIC: &[u8; 4] = b"DDS ";
+    const DDS_HEADER_SIZE: u32 = 124;
+    const DDS_PIXELFORMAT_SIZE: u32 = 32;
+
+    const DDSD_CAPS: u32 = 0x1;
+    const DDSD_HEIGHT: u32 = 0x2;
+    const DDSD_WIDTH: u32 = 0x4;
+    const DDSD_PIXELFORMAT: u32 = 0x1000;
+    const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+    const DDSD_LINEARSIZE: u32 = 0x80000;
+
+    const DDPF_FOURCC: u32 = 0x4;
+
+    const DDSCAPS_COMPLEX: u32 = 0x8;
+    const DDSCAPS_TEXTURE: u32 = 0x1000;
+    const DDSCAPS_MIPMAP: u32 = 0x400000;
+
+    const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+    /// Writes a 2D texture with a DX10 header. `format` is a DXGI_FORMAT value and `mips`
+    /// holds the compressed data of each level, largest first.
+    pub fn write_dds(
+        path: &Path,
+        width: u32,
+        height: u32,
+        format: u32,
+        mips: &[Vec<u8>],
+    ) -> std::io::Result<()> {
+        let mut out = Vec::new();
+        out.extend_from_slice(DDS_MAGIC);
+
+        let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_LINEARSIZE;
+        let mut caps = DDSCAPS_TEXTURE;
+        if mips.len() > 1 {
+            flags |= DDSD_MIPMAPCOUNT;
+            caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+        }
+
+        let mut header = vec![
+            DDS_HEADER_SIZE,
+            flags,
+            height,
+            width,
+            mips.first().map_or(0, |m| m.len() as u32),
+            0,
+            mips.len() as u32,
+        ];
+        header.extend_from_slice(&[0; 11]);
+        header.extend_from_slice(&[
+            DDS_PIXELFORMAT_SIZE,
+            DDPF_FOURCC,
+            u32::from_le_bytes(*b"DX10"),
+            0,
+            0,
+            0,
+            0,
+            0,
+        ]);
+        header.extend_from_slice(&[caps, 0, 0, 0, 0]);
+
+        // DDS_HEADER_DXT10
+        header.extend_from_slice(&[format, D3D10_RESOURCE_DIMENSION_TEXTURE2D, 0, 1, 0]);
+
+        for value in header {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        for mip in mips {
+            out.extend_from_slice(mip);
+        }
+
+        std::fs::File::create(path)?.write_all(&out)
+    }
+}
@@ -5,26 +5,39 @@ pub mod device_resources {
         Win32::{
             Foundation::{BOOL, HWND},
             Graphics::{
-                Direct3D::D3D_DRIVER_TYPE_HARDWARE,
+                Direct3D::{D3D11_SRV_DIMENSION_BUFFER, D3D_DRIVER_TYPE_HARDWARE},
                 Direct3D11::{
                     D3D11CreateDevice, ID3D11Buffer, ID3D11DepthStencilView, ID3D11Device,
                     ID3D11DeviceContext, ID3D11InputLayout, ID3D11PixelShader,
-                    ID3D11RenderTargetView, ID3D11Texture2D, ID3D11VertexShader,
-                    D3D11_BIND_DEPTH_STENCIL, D3D11_BIND_FLAG, D3D11_BUFFER_DESC,
-                    D3D11_CPU_ACCESS_FLAG, D3D11_CREATE_DEVICE_BGRA_SUPPORT,
-                    D3D11_CREATE_DEVICE_DEBUG, D3D11_DEPTH_STENCIL_VIEW_DESC,
-                    D3D11_DEPTH_STENCIL_VIEW_DESC_0, D3D11_DSV_DIMENSION_TEXTURE2D,
-                    D3D11_INPUT_ELEMENT_DESC, D3D11_INPUT_PER_VERTEX_DATA,
-                    D3D11_RENDER_TARGET_VIEW_DESC, D3D11_RENDER_TARGET_VIEW_DESC_0,
-                    D3D11_RESOURCE_MISC_FLAG, D3D11_RTV_DIMENSION_TEXTURE2D, D3D11_SDK_VERSION,
-                    D3D11_SUBRESOURCE_DATA, D3D11_TEXTURE2D_DESC, D3D11_USAGE_DEFAULT,
-                    D3D11_USAGE_IMMUTABLE, D3D11_VIEWPORT,
+                    ID3D11RenderTargetView, ID3D11SamplerState, ID3D11ShaderResourceView,
+                    ID3D11Texture2D, ID3D11VertexShader, D3D11_BIND_CONSTANT_BUFFER,
+                    D3D11_BIND_DEPTH_STENCIL, D3D11_BIND_FLAG, D3D11_BIND_SHADER_RESOURCE,
+                    D3D11_BUFFER_DESC, D3D11_BUFFER_SRV, D3D11_BUFFER_SRV_0, D3D11_BUFFER_SRV_1,
+                    D3D11_COMPARISON_NEVER, D3D11_CPU_ACCESS_FLAG, D3D11_CPU_ACCESS_READ,
+                    D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_CREATE_DEVICE_DEBUG,
+                    D3D11_DEPTH_STENCIL_VIEW_DESC, D3D11_DEPTH_STENCIL_VIEW_DESC_0,
+                    D3D11_DSV_DIMENSION_TEXTURE2D, D3D11_FILTER_MIN_MAG_MIP_LINEAR,
+                    D3D11_INPUT_CLASSIFICATION, D3D11_INPUT_ELEMENT_DESC,
+                    D3D11_INPUT_PER_INSTANCE_DATA, D3D11_INPUT_PER_VERTEX_DATA,
+                    D3D11_MAPPED_SUBRESOURCE, D3D11_MAP_READ, D3D11_RENDER_TARGET_VIEW_DESC,
+                    D3D11_RENDER_TARGET_VIEW_DESC_0, D3D11_RESOURCE_MISC_FLAG,
+                    D3D11_RTV_DIMENSION_TEXTURE2D, D3D11_SAMPLER_DESC, D3D11_SDK_VERSION,
+                    D3D11_SHADER_RESOURCE_VIEW_DESC, D3D11_SHADER_RESOURCE_VIEW_DESC_0,
+                    D3D11_SUBRESOURCE_DATA, D3D11_TEXTURE2D_DESC, D3D11_TEXTURE_ADDRESS_WRAP,
+                    D3D11_USAGE_DEFAULT, D3D11_USAGE_IMMUTABLE, D3D11_USAGE_STAGING,
+                    D3D11_VIEWPORT,
                 },
                 Dxgi::{
                     Common::{
-                        DXGI_ALPHA_MODE_IGNORE, DXGI_FORMAT_B8G8R8A8_UNORM,
-                        DXGI_FORMAT_D24_UNORM_S8_UINT, DXGI_FORMAT_R32G32B32_FLOAT,
-                        DXGI_SAMPLE_DESC,
+                        DXGI_ALPHA_MODE_IGNORE, DXGI_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM,
+                        DXGI_FORMAT_D24_UNORM_S8_UINT, DXGI_FORMAT_R16G16B16A16_SNORM,
+                        DXGI_FORMAT_R16G16B16A16_UINT, DXGI_FORMAT_R16G16B16A16_UNORM,
+                        DXGI_FORMAT_R16G16_FLOAT, DXGI_FORMAT_R16G16_SNORM,
+                        DXGI_FORMAT_R16G16_UNORM, DXGI_FORMAT_R32G32B32A32_FLOAT,
+                        DXGI_FORMAT_R32G32B32_FLOAT, DXGI_FORMAT_R32G32_FLOAT,
+                        DXGI_FORMAT_R8G8B8A8_SNORM, DXGI_FORMAT_R8G8B8A8_UNORM,
+                        DXGI_FORMAT_R8G8B8A8_UNORM_SRGB, DXGI_FORMAT_R8G8_SNORM,
+                        DXGI_FORMAT_R8G8_UNORM, DXGI_SAMPLE_DESC,
                     },
                     CreateDXGIFactory2, IDXGIFactory7, IDXGISwapChain1, DXGI_CREATE_FACTORY_DEBUG,
                     DXGI_SCALING_NONE, DXGI_SWAP_CHAIN_DESC1, DXGI_SWAP_EFFECT_FLIP_DISCARD,
@@ -34,7 +47,10 @@ pub mod device_resources {
         },
     };
 
-    use windows::core::Result as WinResult;
+    use windows::core::{Result as WinResult, PCSTR};
+
+    use hello_window::mesh::mesh::{ElementFormat, VertexLayout};
+    use hello_window::texture_compressor::texture_compressor::RgbaImage;
 
     pub const DEFAULT_WIDTH: u32 = 1280;
     pub const DEFAULT_HEIGHT: u32 = 720;
@@ -50,7 +66,121 @@ pub mod device_resources {
         pub rtv: Vec<ID3D11RenderTargetView>,
         pub vs: ID3D11VertexShader,
         pub ps: ID3D11PixelShader,
+        /// `Vertex` stream in slot 0 and `InstanceData` stream in slot 2.
         pub il: ID3D11InputLayout,
+        pub skinned_vs: ID3D11VertexShader,
+        /// `Vertex` stream in slot 0 and `SkinVertex` stream in slot 1.
+        pub skinned_il: ID3D11InputLayout,
+        pub sampler: ID3D11SamplerState,
+        /// Kept to create input layouts for packed vertex formats.
+        vs_bytecode: Vec<u8>,
+        skinned_vs_bytecode: Vec<u8>,
+    }
+
+    fn element_format(format: ElementFormat) -> DXGI_FORMAT {
+        match format {
+            ElementFormat::Half2 => DXGI_FORMAT_R16G16_FLOAT,
+            ElementFormat::Float2 => DXGI_FORMAT_R32G32_FLOAT,
+            ElementFormat::Float3 => DXGI_FORMAT_R32G32B32_FLOAT,
+            ElementFormat::Float4 => DXGI_FORMAT_R32G32B32A32_FLOAT,
+            ElementFormat::Snorm8x2 => DXGI_FORMAT_R8G8_SNORM,
+            ElementFormat::Unorm8x2 => DXGI_FORMAT_R8G8_UNORM,
+            ElementFormat::Snorm8x4 => DXGI_FORMAT_R8G8B8A8_SNORM,
+            ElementFormat::Unorm8x4 => DXGI_FORMAT_R8G8B8A8_UNORM,
+            ElementFormat::Snorm16x2 => DXGI_FORMAT_R16G16_SNORM,
+            ElementFormat::Unorm16x2 => DXGI_FORMAT_R16G16_UNORM,
+            ElementFormat::Snorm16x4 => DXGI_FORMAT_R16G16B16A16_SNORM,
+            ElementFormat::Unorm16x4 => DXGI_FORMAT_R16G16B16A16_UNORM,
+        }
+    }
+
+    fn element(
+        name: PCSTR,
+        index: u32,
+        format: DXGI_FORMAT,
+        slot: u32,
+        offset: u32,
+    ) -> D3D11_INPUT_ELEMENT_DESC {
+        classified_element(
+            name,
+            index,
+            format,
+            slot,
+            offset,
+            D3D11_INPUT_PER_VERTEX_DATA,
+        )
+    }
+
+    fn classified_element(
+        name: PCSTR,
+        index: u32,
+        format: DXGI_FORMAT,
+        slot: u32,
+        offset: u32,
+        class: D3D11_INPUT_CLASSIFICATION,
+    ) -> D3D11_INPUT_ELEMENT_DESC {
+        D3D11_INPUT_ELEMENT_DESC {
+            SemanticName: name,
+            SemanticIndex: index,
+            Format: format,
+            InputSlot: slot,
+            AlignedByteOffset: offset,
+            InputSlotClass: class,
+            InstanceDataStepRate: if class == D3D11_INPUT_PER_INSTANCE_DATA {
+                1
+            } else {
+                0
+            },
+        }
+    }
+
+    /// The slot 0 stream, `formats` and `offsets` in `Vertex` field order.
+    fn vertex_elements(
+        formats: [DXGI_FORMAT; 5],
+        offsets: [u32; 5],
+    ) -> Vec<D3D11_INPUT_ELEMENT_DESC> {
+        vec![
+            element(s!("POSITION"), 0, formats[0], 0, offsets[0]),
+            element(s!("NORMAL"), 0, formats[1], 0, offsets[1]),
+            element(s!("TEXCOORD"), 0, formats[2], 0, offsets[2]),
+            element(s!("TEXCOORD"), 1, formats[3], 0, offsets[3]),
+            element(s!("TANGENT"), 0, formats[4], 0, offsets[4]),
+        ]
+    }
+
+    /// The `SkinVertex` stream in slot 1.
+    fn skin_elements() -> [D3D11_INPUT_ELEMENT_DESC; 4] {
+        [
+            element(s!("BLENDINDICES"), 0, DXGI_FORMAT_R16G16B16A16_UINT, 1, 0),
+            element(s!("BLENDINDICES"), 1, DXGI_FORMAT_R16G16B16A16_UINT, 1, 8),
+            element(s!("BLENDWEIGHT"), 0, DXGI_FORMAT_R32G32B32A32_FLOAT, 1, 16),
+            element(s!("BLENDWEIGHT"), 1, DXGI_FORMAT_R32G32B32A32_FLOAT, 1, 32),
+        ]
+    }
+
+    /// The `InstanceData` stream in slot 2: world matrix columns, then normal matrix columns.
+    fn instance_elements() -> Vec<D3D11_INPUT_ELEMENT_DESC> {
+        let world = (0..4).map(|i| {
+            classified_element(
+                s!("INSTANCE_WORLD"),
+                i,
+                DXGI_FORMAT_R32G32B32A32_FLOAT,
+                2,
+                i * 16,
+                D3D11_INPUT_PER_INSTANCE_DATA,
+            )
+        });
+        let normal = (0..3).map(|i| {
+            classified_element(
+                s!("INSTANCE_NORMAL"),
+                i,
+                DXGI_FORMAT_R32G32B32_FLOAT,
+                2,
+                64 + i * 12,
+                D3D11_INPUT_PER_INSTANCE_DATA,
+            )
+        });
+        world.chain(normal).collect()
     }
 
     impl DeviceResources {
@@ -156,27 +286,47 @@ pub mod device_resources {
 
             let mut vs = None;
             let mut il = None;
+            let mut skinned_vs = None;
+            let mut skinned_il = None;
 
             let cwd = std::env::current_dir()
                 .unwrap()
                 .join("target")
                 .join("debug");
 
+            let vs_bytecode = std::fs::read(cwd.join("vs.cso")).unwrap();
+            let skinned_vs_bytecode = std::fs::read(cwd.join("skinned_vs.cso")).unwrap();
             unsafe {
-                let bytes = std::fs::read(cwd.join("vs.cso")).unwrap();
-                device.CreateVertexShader(&bytes as _, None, Some(&mut vs))?;
-
-                let input_desc = vec![D3D11_INPUT_ELEMENT_DESC {
-                    SemanticName: s!("POSITION"),
-                    SemanticIndex: 0,
-                    Format: DXGI_FORMAT_R32G32B32_FLOAT,
-                    InputSlot: 0,
-                    AlignedByteOffset: 0,
-                    InputSlotClass: D3D11_INPUT_PER_VERTEX_DATA,
-                    InstanceDataStepRate: 0,
-                }];
-
-                device.CreateInputLayout(&input_desc, &bytes, Some(&mut il))?;
+                device.CreateVertexShader(&vs_bytecode as _, None, Some(&mut vs))?;
+
+                let input_desc = vertex_elements(
+                    [
+                        DXGI_FORMAT_R32G32B32_FLOAT,
+                        DXGI_FORMAT_R32G32B32_FLOAT,
+                        DXGI_FORMAT_R32G32_FLOAT,
+                        DXGI_FORMAT_R32G32_FLOAT,
+                        DXGI_FORMAT_R32G32B32A32_FLOAT,
+                    ],
+                    [0, 12, 24, 32, 40],
+                );
+
+                let mut instanced_desc = input_desc.clone();
+                instanced_desc.extend(instance_elements());
+                device.CreateInputLayout(&instanced_desc, &vs_bytecode, Some(&mut il))?;
+
+                device.CreateVertexShader(
+                    &skinned_vs_bytecode as _,
+                    None,
+                    Some(&mut skinned_vs),
+                )?;
+
+                let mut skinned_desc = input_desc.clone();
+                skinned_desc.extend(skin_elements());
+                device.CreateInputLayout(
+                    &skinned_desc,
+                    &skinned_vs_bytecode,
+                    Some(&mut skinned_il),
+                )?;
             }
             let mut ps = None;
             unsafe {
@@ -184,27 +334,74 @@ pub mod device_resources {
                 device.CreatePixelShader(&bytes as _, None, Some(&mut ps))?;
             }
 
-            return Ok(DeviceResources {
-                device: device,
-                context: context,
-                swapchain: swapchain,
-                factory: factory,
-                viewport: viewport,
+            let mut sampler = None;
+            unsafe {
+                let desc = D3D11_SAMPLER_DESC {
+                    Filter: D3D11_FILTER_MIN_MAG_MIP_LINEAR,
+                    AddressU: D3D11_TEXTURE_ADDRESS_WRAP,
+                    AddressV: D3D11_TEXTURE_ADDRESS_WRAP,
+                    AddressW: D3D11_TEXTURE_ADDRESS_WRAP,
+                    MipLODBias: 0f32,
+                    MaxAnisotropy: 1,
+                    ComparisonFunc: D3D11_COMPARISON_NEVER,
+                    BorderColor: [0f32; 4],
+                    MinLOD: 0f32,
+                    MaxLOD: f32::MAX,
+                };
+                device.CreateSamplerState(&desc, Some(&mut sampler))?;
+            }
+
+            Ok(DeviceResources {
+                device,
+                context,
+                swapchain,
+                factory,
+                viewport,
                 dsv: dsv.unwrap(),
                 rtv: vec![rtv.unwrap()],
                 vs: vs.unwrap(),
                 ps: ps.unwrap(),
                 il: il.unwrap(),
-            });
+                skinned_vs: skinned_vs.unwrap(),
+                skinned_il: skinned_il.unwrap(),
+                sampler: sampler.unwrap(),
+                vs_bytecode,
+                skinned_vs_bytecode,
+            })
+        }
+
+        /// Input layout reading a `PackedVertices` buffer in slot 0, followed by the
+        /// `SkinVertex` stream in slot 1 when `skinned` or the instance stream in slot 2.
+        pub fn create_packed_input_layout(
+            &self,
+            layout: &VertexLayout,
+            skinned: bool,
+        ) -> WinResult<ID3D11InputLayout> {
+            let (offsets, _) = layout.offsets();
+            let mut desc = vertex_elements(layout.elements().map(element_format), offsets);
+            let bytecode = if skinned {
+                desc.extend(skin_elements());
+                &self.skinned_vs_bytecode
+            } else {
+                desc.extend(instance_elements());
+                &self.vs_bytecode
+            };
+
+            let mut il = None;
+            unsafe {
+                self.device
+                    .CreateInputLayout(&desc, bytecode, Some(&mut il))?;
+            }
+            Ok(il.unwrap())
         }
 
         pub fn create_buffer<T>(
             device: &ID3D11Device,
-            data: &Vec<T>,
+            data: &[T],
             bind_flag: D3D11_BIND_FLAG,
         ) -> WinResult<ID3D11Buffer> {
             let desc = D3D11_BUFFER_DESC {
-                ByteWidth: (data.len() * std::mem::size_of::<T>()) as u32,
+                ByteWidth: std::mem::size_of_val(data) as u32,
                 Usage: D3D11_USAGE_IMMUTABLE,
                 BindFlags: bind_flag,
                 CPUAccessFlags: D3D11_CPU_ACCESS_FLAG(0),
@@ -223,5 +420,194 @@ pub mod device_resources {
             };
             Ok(buffer.unwrap())
         }
+
+        /// Like `create_buffer`, but the contents can be replaced with `update_buffer_slice`.
+        pub fn create_default_buffer<T>(
+            device: &ID3D11Device,
+            data: &[T],
+            bind_flag: D3D11_BIND_FLAG,
+        ) -> WinResult<ID3D11Buffer> {
+            let desc = D3D11_BUFFER_DESC {
+                ByteWidth: std::mem::size_of_val(data) as u32,
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: bind_flag,
+                CPUAccessFlags: D3D11_CPU_ACCESS_FLAG(0),
+                MiscFlags: D3D11_RESOURCE_MISC_FLAG(0),
+                StructureByteStride: 0,
+            };
+
+            let init_data = D3D11_SUBRESOURCE_DATA {
+                pSysMem: data.as_ptr() as *const core::ffi::c_void,
+                SysMemPitch: 0,
+                SysMemSlicePitch: 0,
+            };
+            let mut buffer = None;
+            unsafe {
+                device.CreateBuffer(&desc, Some(&init_data), Some(&mut buffer))?;
+            };
+            Ok(buffer.unwrap())
+        }
+
+        /// Immutable `Buffer<float4>` shader input.
+        pub fn create_buffer_srv(
+            device: &ID3D11Device,
+            data: &[[f32; 4]],
+        ) -> WinResult<ID3D11ShaderResourceView> {
+            let buffer = Self::create_buffer(device, data, D3D11_BIND_SHADER_RESOURCE)?;
+            let desc = D3D11_SHADER_RESOURCE_VIEW_DESC {
+                Format: DXGI_FORMAT_R32G32B32A32_FLOAT,
+                ViewDimension: D3D11_SRV_DIMENSION_BUFFER,
+                Anonymous: D3D11_SHADER_RESOURCE_VIEW_DESC_0 {
+                    Buffer: D3D11_BUFFER_SRV {
+                        Anonymous1: D3D11_BUFFER_SRV_0 { FirstElement: 0 },
+                        Anonymous2: D3D11_BUFFER_SRV_1 {
+                            NumElements: data.len() as u32,
+                        },
+                    },
+                },
+            };
+
+            let mut srv = None;
+            unsafe {
+                device.CreateShaderResourceView(&buffer, Some(&desc), Some(&mut srv))?;
+            };
+            Ok(srv.unwrap())
+        }
+
+        /// Constant buffer for data that changes every frame, see `update_buffer`.
+        pub fn create_constant_buffer<T>(device: &ID3D11Device) -> WinResult<ID3D11Buffer> {
+            let desc = D3D11_BUFFER_DESC {
+                ByteWidth: ((std::mem::size_of::<T>() + 15) & !15) as u32,
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: D3D11_BIND_CONSTANT_BUFFER,
+                CPUAccessFlags: D3D11_CPU_ACCESS_FLAG(0),
+                MiscFlags: D3D11_RESOURCE_MISC_FLAG(0),
+                StructureByteStride: 0,
+            };
+
+            let mut buffer = None;
+            unsafe {
+                device.CreateBuffer(&desc, None, Some(&mut buffer))?;
+            };
+            Ok(buffer.unwrap())
+        }
+
+        pub fn update_buffer<T>(&self, buffer: &ID3D11Buffer, data: &T) {
+            unsafe {
+                self.context.UpdateSubresource(
+                    buffer,
+                    0,
+                    None,
+                    data as *const T as *const core::ffi::c_void,
+                    0,
+                    0,
+                );
+            }
+        }
+
+        /// Replaces the whole contents of a buffer made by `create_default_buffer`.
+        pub fn update_buffer_slice<T>(&self, buffer: &ID3D11Buffer, data: &[T]) {
+            unsafe {
+                self.context.UpdateSubresource(
+                    buffer,
+                    0,
+                    None,
+                    data.as_ptr() as *const core::ffi::c_void,
+                    0,
+                    0,
+                );
+            }
+        }
+
+        /// Copies the back buffer to memory, for frames rendered without presenting them.
+        pub fn read_back(&self) -> WinResult<RgbaImage> {
+            let desc = D3D11_TEXTURE2D_DESC {
+                Width: DEFAULT_WIDTH,
+                Height: DEFAULT_HEIGHT,
+                MipLevels: 1,
+                ArraySize: 1,
+                Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0,
+                },
+                Usage: D3D11_USAGE_STAGING,
+                BindFlags: D3D11_BIND_FLAG::default(),
+                CPUAccessFlags: D3D11_CPU_ACCESS_READ,
+                MiscFlags: D3D11_RESOURCE_MISC_FLAG::default(),
+            };
+
+            let mut staging = None;
+            let mut pixels = Vec::with_capacity((DEFAULT_WIDTH * DEFAULT_HEIGHT * 4) as usize);
+            unsafe {
+                let backbuffer = self.swapchain.GetBuffer::<ID3D11Texture2D>(0)?;
+                self.device
+                    .CreateTexture2D(&desc, None, Some(&mut staging))?;
+                let staging = staging.unwrap();
+                self.context.CopyResource(&staging, &backbuffer);
+
+                let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+                self.context
+                    .Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))?;
+                let row_bytes = DEFAULT_WIDTH as usize * 4;
+                for y in 0..DEFAULT_HEIGHT as usize {
+                    let row = std::slice::from_raw_parts(
+                        (mapped.pData as *const u8).add(y * mapped.RowPitch as usize),
+                        row_bytes,
+                    );
+                    // BGRA to RGBA, the back buffer has no meaningful alpha.
+                    for bgra in row.chunks_exact(4) {
+                        pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], 255]);
+                    }
+                }
+                self.context.Unmap(&staging, 0);
+            }
+
+            Ok(RgbaImage {
+                width: DEFAULT_WIDTH,
+                height: DEFAULT_HEIGHT,
+                pixels,
+            })
+        }
+
+        pub fn create_texture(
+            device: &ID3D11Device,
+            image: &RgbaImage,
+            srgb: bool,
+        ) -> WinResult<ID3D11ShaderResourceView> {
+            let desc = D3D11_TEXTURE2D_DESC {
+                Width: image.width,
+                Height: image.height,
+                MipLevels: 1,
+                ArraySize: 1,
+                Format: if srgb {
+                    DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+                } else {
+                    DXGI_FORMAT_R8G8B8A8_UNORM
+                },
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0,
+                },
+                Usage: D3D11_USAGE_IMMUTABLE,
+                BindFlags: D3D11_BIND_SHADER_RESOURCE,
+                CPUAccessFlags: D3D11_CPU_ACCESS_FLAG::default(),
+                MiscFlags: D3D11_RESOURCE_MISC_FLAG::default(),
+            };
+
+            let init_data = D3D11_SUBRESOURCE_DATA {
+                pSysMem: image.pixels.as_ptr() as *const core::ffi::c_void,
+                SysMemPitch: image.width * 4,
+                SysMemSlicePitch: 0,
+            };
+
+            let mut texture = None;
+            let mut srv = None;
+            unsafe {
+                device.CreateTexture2D(&desc, Some(&init_data), Some(&mut texture))?;
+                device.CreateShaderResourceView(&texture.unwrap(), None, Some(&mut srv))?;
+            }
+            Ok(srv.unwrap())
+        }
     }
 }
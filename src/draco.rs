@@ -0,0 +1,948 @@
+pub mod draco {
+
+    //! Decoder for KHR_draco_mesh_compression: Draco 2.2 triangle meshes with sequential
+    //! connectivity, compressed or not, and their generic, integer, quantized and octahedral
+    //! normal attributes. Edgebreaker connectivity, and the mesh prediction schemes that only
+    //! come with it, are not decoded.
+
+    use std::fmt;
+
+    const TRIANGULAR_MESH: u8 = 1;
+    const SEQUENTIAL_ENCODING: u8 = 0;
+    const EDGEBREAKER_ENCODING: u8 = 1;
+    const METADATA_FLAG: u16 = 0x8000;
+
+    const COMPRESSED_INDICES: u8 = 0;
+    const UNCOMPRESSED_INDICES: u8 = 1;
+
+    const TAGGED_SYMBOLS: u8 = 0;
+    const RAW_SYMBOLS: u8 = 1;
+
+    const PREDICTION_NONE: i8 = -2;
+    /// The last prediction method, geometric normals.
+    const PREDICTION_MAX: i8 = 6;
+    const TRANSFORM_NONE: i8 = -1;
+    const TRANSFORM_WRAP: i8 = 1;
+    const TRANSFORM_OCTAHEDRON: i8 = 2;
+    const TRANSFORM_OCTAHEDRON_CANONICALIZED: i8 = 3;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum DataType {
+        I8,
+        U8,
+        I16,
+        U16,
+        I32,
+        U32,
+        I64,
+        U64,
+        F32,
+        F64,
+        Bool,
+    }
+
+    impl DataType {
+        fn parse(value: u8) -> Option<Self> {
+            let types = [
+                DataType::I8,
+                DataType::U8,
+                DataType::I16,
+                DataType::U16,
+                DataType::I32,
+                DataType::U32,
+                DataType::I64,
+                DataType::U64,
+                DataType::F32,
+                DataType::F64,
+                DataType::Bool,
+            ];
+            types.get((value as usize).checked_sub(1)?).copied()
+        }
+
+        pub fn size(self) -> usize {
+            match self {
+                DataType::I8 | DataType::U8 | DataType::Bool => 1,
+                DataType::I16 | DataType::U16 => 2,
+                DataType::I32 | DataType::U32 | DataType::F32 => 4,
+                DataType::I64 | DataType::U64 | DataType::F64 => 8,
+            }
+        }
+    }
+
+    /// One value per point, tightly packed and little-endian.
+    #[derive(Clone, Debug)]
+    pub struct Attribute {
+        /// What glTF's `attributes` map refers to.
+        pub unique_id: u32,
+        pub data_type: DataType,
+        pub components: usize,
+        pub normalized: bool,
+        pub bytes: Vec<u8>,
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Mesh {
+        pub points: usize,
+        /// Three per triangle.
+        pub indices: Vec<u32>,
+        pub attributes: Vec<Attribute>,
+    }
+
+    impl Mesh {
+        pub fn attribute(&self, unique_id: u32) -> Option<&Attribute> {
+            self.attributes
+                .iter()
+                .find(|attribute| attribute.unique_id == unique_id)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum DecodeError {
+        /// Truncated or inconsistent data.
+        Invalid,
+        /// Valid data using a part of Draco this decoder leaves out.
+        Unsupported(&'static str),
+    }
+
+    impl fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                DecodeError::Invalid => write!(f, "the Draco data is invalid"),
+                DecodeError::Unsupported(what) => write!(f, "Draco {} is not supported", what),
+            }
+        }
+    }
+
+    use DecodeError::{Invalid, Unsupported};
+
+    /// Decodes a Draco mesh, such as the buffer view of a KHR_draco_mesh_compression
+    /// primitive.
+    pub fn decode(data: &[u8]) -> Result<Mesh, DecodeError> {
+        let mut r = Reader { data, pos: 0 };
+        if r.bytes(5)? != b"DRACO" {
+            return Err(Invalid);
+        }
+        if (r.u8()?, r.u8()?) != (2, 2) {
+            return Err(Unsupported("bitstreams older or newer than 2.2"));
+        }
+        if r.u8()? != TRIANGULAR_MESH {
+            return Err(Unsupported("point clouds"));
+        }
+        match r.u8()? {
+            SEQUENTIAL_ENCODING => {}
+            EDGEBREAKER_ENCODING => return Err(Unsupported("Edgebreaker connectivity")),
+            _ => return Err(Invalid),
+        }
+        if r.u16()? & METADATA_FLAG != 0 {
+            skip_metadata(&mut r)?;
+        }
+
+        let (points, indices) = decode_connectivity(&mut r)?;
+        let attributes = decode_attributes(&mut r, points)?;
+        Ok(Mesh {
+            points,
+            indices,
+            attributes,
+        })
+    }
+
+    /// Little-endian reads that fail with `Invalid` past the end.
+    struct Reader<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+            let end = self.pos.checked_add(len).ok_or(Invalid)?;
+            let bytes = self.data.get(self.pos..end).ok_or(Invalid)?;
+            self.pos = end;
+            Ok(bytes)
+        }
+
+        fn array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+            let mut array = [0; N];
+            array.copy_from_slice(self.bytes(N)?);
+            Ok(array)
+        }
+
+        fn u8(&mut self) -> Result<u8, DecodeError> {
+            Ok(self.array::<1>()?[0])
+        }
+
+        fn i8(&mut self) -> Result<i8, DecodeError> {
+            Ok(self.u8()? as i8)
+        }
+
+        fn u16(&mut self) -> Result<u16, DecodeError> {
+            Ok(u16::from_le_bytes(self.array()?))
+        }
+
+        fn u32(&mut self) -> Result<u32, DecodeError> {
+            Ok(u32::from_le_bytes(self.array()?))
+        }
+
+        fn i32(&mut self) -> Result<i32, DecodeError> {
+            Ok(i32::from_le_bytes(self.array()?))
+        }
+
+        fn f32(&mut self) -> Result<f32, DecodeError> {
+            Ok(f32::from_le_bytes(self.array()?))
+        }
+
+        /// LEB128: seven bits per byte, least significant first, the high bit set on all
+        /// but the last byte.
+        fn varint(&mut self) -> Result<u64, DecodeError> {
+            let mut value = 0u64;
+            for shift in (0..64).step_by(7) {
+                let byte = self.u8()?;
+                value |= ((byte & 127) as u64) << shift;
+                if byte < 128 {
+                    return Ok(value);
+                }
+            }
+            Err(Invalid)
+        }
+
+        fn varint_usize(&mut self) -> Result<usize, DecodeError> {
+            u32::try_from(self.varint()?)
+                .map(|value| value as usize)
+                .map_err(|_| Invalid)
+        }
+    }
+
+    /// Skips the metadata of the attributes and the mesh, which glTF has no use for.
+    fn skip_metadata(r: &mut Reader) -> Result<(), DecodeError> {
+        for _ in 0..r.varint_usize()? {
+            // The attribute's unique id.
+            r.varint()?;
+            skip_metadata_block(r)?;
+        }
+        skip_metadata_block(r)
+    }
+
+    /// Skips a block of named entries followed by named child blocks, each laid out the
+    /// same way.
+    fn skip_metadata_block(r: &mut Reader) -> Result<(), DecodeError> {
+        let mut children = skip_metadata_entries(r)?;
+        while children > 0 {
+            let name = r.u8()? as usize;
+            r.bytes(name)?;
+            children = children - 1 + skip_metadata_entries(r)?;
+        }
+        Ok(())
+    }
+
+    /// Skips the entries of a metadata block, returning how many child blocks follow.
+    fn skip_metadata_entries(r: &mut Reader) -> Result<usize, DecodeError> {
+        for _ in 0..r.varint_usize()? {
+            let name = r.u8()? as usize;
+            r.bytes(name)?;
+            let size = r.varint_usize()?;
+            r.bytes(size)?;
+        }
+        r.varint_usize()
+    }
+
+    /// Draco's `RAnsSymbolDecoder`: a table of symbol probabilities out of `1 << precision`,
+    /// then the coded bytes, which are consumed from the end.
+    struct Rans<'a> {
+        data: &'a [u8],
+        offset: usize,
+        state: u32,
+        precision: u32,
+        /// `(probability, cumulative probability)` of every symbol.
+        symbols: Vec<(u32, u32)>,
+        /// The symbol owning each slot of the precision range.
+        slots: Vec<u32>,
+    }
+
+    impl<'a> Rans<'a> {
+        /// Reads the probability table and the coded bytes of a coder whose symbols need up
+        /// to `bit_length` bits.
+        fn new(r: &mut Reader<'a>, bit_length: u32) -> Result<Self, DecodeError> {
+            let precision = (3 * bit_length / 2).clamp(12, 20);
+            let count = r.varint_usize()?;
+            let mut probabilities = Vec::new();
+            while probabilities.len() < count {
+                let byte = r.u8()?;
+                if byte & 3 == 3 {
+                    // A run of symbols that never occur.
+                    let run = (byte >> 2) as usize + 1;
+                    if probabilities.len() + run > count {
+                        return Err(Invalid);
+                    }
+                    probabilities.resize(probabilities.len() + run, 0);
+                } else {
+                    // Six bits, then up to two more bytes.
+                    let mut probability = (byte >> 2) as u32;
+                    for extra in 0..(byte & 3) as u32 {
+                        probability |= (r.u8()? as u32) << (8 * (extra + 1) - 2);
+                    }
+                    probabilities.push(probability);
+                }
+            }
+
+            let mut symbols = Vec::with_capacity(count);
+            let mut slots = Vec::with_capacity(1 << precision);
+            for (symbol, &probability) in probabilities.iter().enumerate() {
+                symbols.push((probability, slots.len() as u32));
+                if slots.len() + probability as usize > 1 << precision {
+                    return Err(Invalid);
+                }
+                slots.resize(slots.len() + probability as usize, symbol as u32);
+            }
+            if slots.len() != 1 << precision {
+                return Err(Invalid);
+            }
+
+            let len = usize::try_from(r.varint()?).map_err(|_| Invalid)?;
+            let data = r.bytes(len)?;
+            // The final state, in one to four bytes counted by the top two bits of the last.
+            let last = *data.last().ok_or(Invalid)?;
+            let size = (last >> 6) as usize + 1;
+            let offset = data.len().checked_sub(size).ok_or(Invalid)?;
+            let mut state = 0u32;
+            for &byte in data[offset..].iter().rev() {
+                state = state << 8 | byte as u32;
+            }
+            state &= (1 << (8 * size - 2)) - 1;
+
+            let lower_bound = 4 << precision;
+            state += lower_bound;
+            if state >= lower_bound * 256 {
+                return Err(Invalid);
+            }
+            Ok(Rans {
+                data,
+                offset,
+                state,
+                precision,
+                symbols,
+                slots,
+            })
+        }
+
+        fn read(&mut self) -> u32 {
+            let lower_bound = 4 << self.precision;
+            while self.state < lower_bound && self.offset > 0 {
+                self.offset -= 1;
+                self.state = self.state * 256 + self.data[self.offset] as u32;
+            }
+            let slot = self.state & ((1 << self.precision) - 1);
+            let symbol = self.slots[slot as usize];
+            let (probability, cumulative) = self.symbols[symbol as usize];
+            self.state = (self.state >> self.precision) * probability + slot - cumulative;
+            symbol
+        }
+    }
+
+    /// Bits from the least significant up, as Draco's `DecoderBuffer::BitDecoder` reads
+    /// them.
+    struct Bits<'a> {
+        data: &'a [u8],
+        bit: usize,
+    }
+
+    impl Bits<'_> {
+        fn read(&mut self, count: u32) -> Result<u32, DecodeError> {
+            let mut value = 0;
+            for i in 0..count {
+                let byte = *self.data.get(self.bit / 8).ok_or(Invalid)?;
+                value |= (((byte >> (self.bit % 8)) & 1) as u32) << i;
+                self.bit += 1;
+            }
+            Ok(value)
+        }
+    }
+
+    /// `count` unsigned values in groups of `components`, coded with rANS either directly
+    /// or as a bit length per group that tags the raw bits of its values.
+    fn decode_symbols(
+        r: &mut Reader,
+        count: usize,
+        components: usize,
+    ) -> Result<Vec<u32>, DecodeError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let mut values = Vec::with_capacity(count);
+        match r.u8()? {
+            TAGGED_SYMBOLS => {
+                let mut tags = Rans::new(r, 5)?;
+                let mut bits = Bits {
+                    data: &r.data[r.pos..],
+                    bit: 0,
+                };
+                while values.len() < count {
+                    let bit_length = tags.read();
+                    if bit_length > 32 {
+                        return Err(Invalid);
+                    }
+                    for _ in 0..components {
+                        values.push(bits.read(bit_length)?);
+                    }
+                }
+                r.bytes(bits.bit.div_ceil(8))?;
+            }
+            RAW_SYMBOLS => {
+                let bit_length = r.u8()? as u32;
+                if !(1..=18).contains(&bit_length) {
+                    return Err(Invalid);
+                }
+                let mut symbols = Rans::new(r, bit_length)?;
+                values.extend((0..count).map(|_| symbols.read()));
+            }
+            _ => return Err(Invalid),
+        }
+        values.truncate(count);
+        Ok(values)
+    }
+
+    /// Unsigned values with the sign in the lowest bit.
+    fn to_signed(value: u32) -> i32 {
+        let magnitude = (value >> 1) as i32;
+        if value & 1 == 0 {
+            magnitude
+        } else {
+            -magnitude - 1
+        }
+    }
+
+    /// The point count and triangle list. Sequential connectivity lists the triangles as
+    /// they are, either as plain indices or as compressed deltas between them.
+    fn decode_connectivity(r: &mut Reader) -> Result<(usize, Vec<u32>), DecodeError> {
+        let faces = r.varint_usize()?;
+        let points = r.varint_usize()?;
+        let count = faces.checked_mul(3).ok_or(Invalid)?;
+        let indices = match r.u8()? {
+            COMPRESSED_INDICES => {
+                // Differences from the previous index, the sign in the lowest bit.
+                let mut last = 0i64;
+                let mut indices = Vec::with_capacity(count);
+                for value in decode_symbols(r, count, 1)? {
+                    let difference = (value >> 1) as i64;
+                    last += if value & 1 == 0 {
+                        difference
+                    } else {
+                        -difference
+                    };
+                    indices.push(u32::try_from(last).map_err(|_| Invalid)?);
+                }
+                indices
+            }
+            UNCOMPRESSED_INDICES => {
+                if count > r.data.len() {
+                    return Err(Invalid);
+                }
+                let mut indices = Vec::with_capacity(count);
+                for _ in 0..count {
+                    indices.push(match points {
+                        p if p < 1 << 8 => r.u8()? as u32,
+                        p if p < 1 << 16 => r.u16()? as u32,
+                        p if p < 1 << 21 => r.varint_usize()? as u32,
+                        _ => r.u32()?,
+                    });
+                }
+                indices
+            }
+            _ => return Err(Invalid),
+        };
+        if indices.iter().any(|&index| index as usize >= points) {
+            return Err(Invalid);
+        }
+        Ok((points, indices))
+    }
+
+    /// How a sequential attribute decoder stores values (`SequentialAttributeEncoderType`).
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Coding {
+        /// The values as they are.
+        Generic,
+        /// Integers, usually predicted from the previous point.
+        Integer,
+        /// Floats quantized to integers.
+        Quantization,
+        /// Unit vectors quantized to two octahedral coordinates.
+        Normals,
+    }
+
+    struct Header {
+        data_type: DataType,
+        components: usize,
+        normalized: bool,
+        unique_id: u32,
+        coding: Coding,
+    }
+
+    /// Attribute decoders each hold a group of attributes. Every value of a group comes
+    /// before any of the parameters that dequantize them.
+    fn decode_attributes(r: &mut Reader, points: usize) -> Result<Vec<Attribute>, DecodeError> {
+        let mut groups = Vec::new();
+        for _ in 0..r.u8()? {
+            let count = r.varint_usize()?;
+            if count == 0 {
+                return Err(Invalid);
+            }
+            let mut group = Vec::new();
+            for _ in 0..count {
+                // The semantic; glTF names attributes by unique id instead.
+                r.u8()?;
+                let data_type = DataType::parse(r.u8()?).ok_or(Invalid)?;
+                let components = r.u8()? as usize;
+                let normalized = r.u8()? != 0;
+                let unique_id = u32::try_from(r.varint()?).map_err(|_| Invalid)?;
+                if components == 0 {
+                    return Err(Invalid);
+                }
+                group.push(Header {
+                    data_type,
+                    components,
+                    normalized,
+                    unique_id,
+                    coding: Coding::Generic,
+                });
+            }
+            for header in &mut group {
+                header.coding = match r.u8()? {
+                    0 => Coding::Generic,
+                    1 => Coding::Integer,
+                    2 => Coding::Quantization,
+                    3 => Coding::Normals,
+                    _ => return Err(Invalid),
+                };
+                let float = header.data_type == DataType::F32;
+                let valid = match header.coding {
+                    Coding::Generic | Coding::Integer => true,
+                    Coding::Quantization => float,
+                    Coding::Normals => float && header.components == 3,
+                };
+                if !valid {
+                    return Err(Invalid);
+                }
+            }
+            groups.push(group);
+        }
+
+        let mut attributes = Vec::new();
+        for group in groups {
+            let mut values = Vec::new();
+            for header in &group {
+                values.push(match header.coding {
+                    Coding::Generic => {
+                        let size = header.components * header.data_type.size();
+                        let len = points.checked_mul(size).ok_or(Invalid)?;
+                        Stored::Raw(r.bytes(len)?.to_vec())
+                    }
+                    _ => Stored::Integers(decode_integers(r, header, points)?),
+                });
+            }
+            for (header, values) in group.into_iter().zip(values) {
+                attributes.push(Attribute {
+                    bytes: transform_values(r, &header, values)?,
+                    unique_id: header.unique_id,
+                    data_type: header.data_type,
+                    components: header.components,
+                    normalized: header.normalized,
+                });
+            }
+        }
+        Ok(attributes)
+    }
+
+    /// Values before dequantization: raw bytes for generic attributes, integers for the
+    /// rest.
+    enum Stored {
+        Raw(Vec<u8>),
+        Integers(Vec<i32>),
+    }
+
+    /// Reads the prediction scheme, the corrections it left, and its transform's parameters,
+    /// then undoes the prediction.
+    fn decode_integers(
+        r: &mut Reader,
+        header: &Header,
+        points: usize,
+    ) -> Result<Vec<i32>, DecodeError> {
+        let components = match header.coding {
+            Coding::Normals => 2,
+            _ => header.components,
+        };
+        let count = points.checked_mul(components).ok_or(Invalid)?;
+
+        let method = r.i8()?;
+        let transform = if method == PREDICTION_NONE {
+            TRANSFORM_NONE
+        } else {
+            if !(-1..=PREDICTION_MAX).contains(&method) {
+                return Err(Invalid);
+            }
+            r.i8()?
+        };
+        // Sequential connectivity leaves the mesh prediction methods nothing to work with, so
+        // every method predicts from the previous point. Draco skips prediction altogether for
+        // a transform the coding doesn't support.
+        let predicted = match (header.coding, transform) {
+            (_, TRANSFORM_NONE) => false,
+            (Coding::Integer | Coding::Quantization, TRANSFORM_WRAP) => true,
+            (Coding::Normals, TRANSFORM_OCTAHEDRON | TRANSFORM_OCTAHEDRON_CANONICALIZED) => true,
+            (_, -1..=3) => false,
+            _ => return Err(Invalid),
+        };
+
+        let mut values: Vec<i32> = if r.u8()? != 0 {
+            let symbols = decode_symbols(r, count, components)?;
+            symbols.into_iter().map(|symbol| symbol as i32).collect()
+        } else {
+            let width = r.u8()? as usize;
+            if !(1..=4).contains(&width) {
+                return Err(Invalid);
+            }
+            let mut values = Vec::with_capacity(count);
+            for chunk in r
+                .bytes(count.checked_mul(width).ok_or(Invalid)?)?
+                .chunks_exact(width)
+            {
+                let mut bytes = [0; 4];
+                bytes[..width].copy_from_slice(chunk);
+                values.push(i32::from_le_bytes(bytes));
+            }
+            values
+        };
+
+        let transform = match (predicted, transform) {
+            (false, _) => None,
+            (true, TRANSFORM_WRAP) => {
+                let (min, max) = (r.i32()?, r.i32()?);
+                let range = (max as i64 - min as i64) + 1;
+                if range <= 0 || range > i32::MAX as i64 {
+                    return Err(Invalid);
+                }
+                Some(Transform::Wrap { min, max })
+            }
+            (true, TRANSFORM_OCTAHEDRON) => {
+                let max_quantized = r.i32()?;
+                Some(Transform::Octahedron(Octahedron::for_max(max_quantized)?))
+            }
+            (true, _) => {
+                let max_quantized = r.i32()?;
+                // The center, which follows from the maximum.
+                r.i32()?;
+                let octahedron = Octahedron::for_max(max_quantized)?;
+                Some(Transform::OctahedronCanonicalized(octahedron))
+            }
+        };
+
+        // Octahedral corrections are stored modulo the coordinate range; the others carry a
+        // sign.
+        if !matches!(
+            transform,
+            Some(Transform::Octahedron(_) | Transform::OctahedronCanonicalized(_))
+        ) {
+            for value in &mut values {
+                *value = to_signed(*value as u32);
+            }
+        }
+        if let Some(transform) = transform {
+            let mut previous = vec![0; components];
+            for element in values.chunks_exact_mut(components) {
+                transform.restore(&previous, element);
+                previous.copy_from_slice(element);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Dequantizes the decoded integers, reading the parameters that takes.
+    fn transform_values(
+        r: &mut Reader,
+        header: &Header,
+        values: Stored,
+    ) -> Result<Vec<u8>, DecodeError> {
+        let values = match values {
+            Stored::Raw(bytes) => return Ok(bytes),
+            Stored::Integers(values) => values,
+        };
+        let mut bytes = Vec::with_capacity(values.len() * header.data_type.size());
+        match header.coding {
+            Coding::Generic => return Err(Invalid),
+            Coding::Integer => {
+                for value in values {
+                    match header.data_type {
+                        DataType::I8 | DataType::U8 => bytes.push(value as u8),
+                        DataType::I16 | DataType::U16 => {
+                            bytes.extend_from_slice(&(value as u16).to_le_bytes())
+                        }
+                        DataType::I32 | DataType::U32 => {
+                            bytes.extend_from_slice(&value.to_le_bytes())
+                        }
+                        _ => return Err(Invalid),
+                    }
+                }
+            }
+            Coding::Quantization => {
+                let mut min = Vec::with_capacity(header.components);
+                for _ in 0..header.components {
+                    min.push(r.f32()?);
+                }
+                let range = r.f32()?;
+                let bits = r.u8()?;
+                if !(1..=30).contains(&bits) {
+                    return Err(Invalid);
+                }
+                let step = range / ((1u32 << bits) - 1) as f32;
+                for point in values.chunks_exact(header.components) {
+                    for (&value, &min) in point.iter().zip(&min) {
+                        bytes.extend_from_slice(&(value as f32 * step + min).to_le_bytes());
+                    }
+                }
+            }
+            Coding::Normals => {
+                let octahedron = Octahedron::new(r.u8()? as u32)?;
+                for st in values.chunks_exact(2) {
+                    for value in octahedron.unit_vector(st[0], st[1]) {
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Turns a prediction and a correction back into the value.
+    #[derive(Clone, Copy, Debug)]
+    enum Transform {
+        /// Corrections wrap around the range of the values.
+        Wrap { min: i32, max: i32 },
+        /// Octahedral coordinates, with the prediction mirrored into the inner diamond.
+        Octahedron(Octahedron),
+        /// Like `Octahedron`, with the prediction also rotated into the bottom left quadrant.
+        OctahedronCanonicalized(Octahedron),
+    }
+
+    impl Transform {
+        /// Replaces the corrections in `values` by the values they correct `predicted` to.
+        fn restore(self, predicted: &[i32], values: &mut [i32]) {
+            match self {
+                Transform::Wrap { min, max } => {
+                    let range = max.wrapping_sub(min).wrapping_add(1);
+                    for (value, &predicted) in values.iter_mut().zip(predicted) {
+                        let restored = predicted.clamp(min, max).wrapping_add(*value);
+                        *value = if restored > max {
+                            restored.wrapping_sub(range)
+                        } else if restored < min {
+                            restored.wrapping_add(range)
+                        } else {
+                            restored
+                        };
+                    }
+                }
+                Transform::Octahedron(o) => {
+                    let center = o.center;
+                    let (mut s, mut t) = (predicted[0] - center, predicted[1] - center);
+                    let in_diamond = o.is_in_diamond(s, t);
+                    if !in_diamond {
+                        (s, t) = o.invert_diamond(s, t);
+                    }
+                    let (mut s, mut t) = (o.mod_max(s + values[0]), o.mod_max(t + values[1]));
+                    if !in_diamond {
+                        (s, t) = o.invert_diamond(s, t);
+                    }
+                    values[0] = s + center;
+                    values[1] = t + center;
+                }
+                Transform::OctahedronCanonicalized(o) => {
+                    let center = o.center;
+                    let mut p = (predicted[0] - center, predicted[1] - center);
+                    let in_diamond = o.is_in_diamond(p.0, p.1);
+                    if !in_diamond {
+                        p = o.invert_diamond(p.0, p.1);
+                    }
+                    let bottom_left = p == (0, 0) || (p.0 < 0 && p.1 <= 0);
+                    let rotations = match (p.0.signum(), p.1.signum()) {
+                        (0, 0) => 0,
+                        (0, 1) | (-1, 1) => 3,
+                        (0, _) | (1, -1) => 1,
+                        (1, _) => 2,
+                        _ => 0,
+                    };
+                    if !bottom_left {
+                        p = rotate(p, rotations);
+                    }
+                    let mut v = (o.mod_max(p.0 + values[0]), o.mod_max(p.1 + values[1]));
+                    if !bottom_left {
+                        v = rotate(v, (4 - rotations) % 4);
+                    }
+                    if !in_diamond {
+                        v = o.invert_diamond(v.0, v.1);
+                    }
+                    values[0] = v.0 + center;
+                    values[1] = v.1 + center;
+                }
+            }
+        }
+    }
+
+    /// Quarter turns clockwise.
+    fn rotate((s, t): (i32, i32), count: i32) -> (i32, i32) {
+        match count {
+            1 => (t, -s),
+            2 => (-s, -t),
+            3 => (-t, s),
+            _ => (s, t),
+        }
+    }
+
+    /// Draco's `OctahedronToolBox`: unit vectors as two coordinates in `0..=max`, the
+    /// octahedron's upper half in the diamond around the center and the lower half folded
+    /// into the corners.
+    #[derive(Clone, Copy, Debug)]
+    struct Octahedron {
+        /// `(1 << bits) - 1`, the modulus of corrections.
+        max_quantized: i32,
+        max: i32,
+        center: i32,
+    }
+
+    impl Octahedron {
+        fn new(bits: u32) -> Result<Self, DecodeError> {
+            if !(2..=30).contains(&bits) {
+                return Err(Invalid);
+            }
+            let max_quantized = (1 << bits) - 1;
+            let max = max_quantized - 1;
+            Ok(Octahedron {
+                max_quantized,
+                max,
+                center: max / 2,
+            })
+        }
+
+        /// From the `(1 << bits) - 1` that prediction transforms store.
+        fn for_max(max_quantized: i32) -> Result<Self, DecodeError> {
+            if max_quantized <= 0 || max_quantized % 2 == 0 {
+                return Err(Invalid);
+            }
+            Octahedron::new(32 - max_quantized.leading_zeros())
+        }
+
+        fn is_in_diamond(self, s: i32, t: i32) -> bool {
+            s.abs() + t.abs() <= self.center
+        }
+
+        /// Mirrors a point relative to the center across the diamond's nearest edge.
+        fn invert_diamond(self, s: i32, t: i32) -> (i32, i32) {
+            let (sign_s, sign_t) = if s >= 0 && t >= 0 {
+                (1, 1)
+            } else if s <= 0 && t <= 0 {
+                (-1, -1)
+            } else {
+                (if s > 0 { 1 } else { -1 }, if t > 0 { 1 } else { -1 })
+            };
+            let corner_s = sign_s * self.center;
+            let corner_t = sign_t * self.center;
+            let (us, ut) = (2 * s - corner_s, 2 * t - corner_t);
+            let (us, ut) = if sign_s * sign_t >= 0 {
+                (-ut, -us)
+            } else {
+                (ut, us)
+            };
+            ((us + corner_s) / 2, (ut + corner_t) / 2)
+        }
+
+        fn mod_max(self, x: i32) -> i32 {
+            if x > self.center {
+                x - self.max_quantized
+            } else if x < -self.center {
+                x + self.max_quantized
+            } else {
+                x
+            }
+        }
+
+        /// The normalized vector at `(s, t)`. Draco's octahedron points its tips along x.
+        fn unit_vector(self, s: i32, t: i32) -> [f32; 3] {
+            let scale = 2.0 / self.max as f32;
+            let mut y = s as f32 * scale - 1.0;
+            let mut z = t as f32 * scale - 1.0;
+            let x = 1.0 - y.abs() - z.abs();
+            // Unfold the lower half.
+            let offset = (-x).max(0.0);
+            y += if y < 0.0 { offset } else { -offset };
+            z += if z < 0.0 { offset } else { -offset };
+            let length_squared = x * x + y * y + z * z;
+            if length_squared < 1e-6 {
+                return [0.0; 3];
+            }
+            let d = 1.0 / length_squared.sqrt();
+            [x * d, y * d, z * d]
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// One triangle with uncompressed connectivity, a generic `u8` attribute with unique
+        /// id 5, and a `u16` attribute with id 7 whose values 10, 12 and 11 are predicted
+        /// from the previous point and wrapped into 10..=12.
+        fn triangle(flags: [u8; 2], metadata: &[u8]) -> Vec<u8> {
+            let mut data = b"DRACO\x02\x02\x01\x00".to_vec();
+            data.extend(flags);
+            data.extend(metadata);
+            data.extend([1, 3, UNCOMPRESSED_INDICES, 0, 1, 2]);
+            // One attribute decoder with two attributes, generic then integer coded.
+            data.extend([1, 2, 0, 2, 1, 0, 5, 4, 4, 1, 0, 7, 0, 1]);
+            data.extend([9, 8, 7]);
+            // Difference prediction, wrapped, stored as one byte each without rANS. The
+            // corrections 0, -1 and -1 carry their sign in the lowest bit.
+            data.extend([0, TRANSFORM_WRAP as u8, 0, 1, 0, 1, 1]);
+            data.extend(10i32.to_le_bytes());
+            data.extend(12i32.to_le_bytes());
+            data
+        }
+
+        fn assert_triangle(mesh: &Mesh) {
+            assert_eq!(mesh.points, 3);
+            assert_eq!(mesh.indices, [0, 1, 2]);
+            let generic = mesh.attribute(5).unwrap();
+            assert_eq!(generic.data_type, DataType::U8);
+            assert_eq!(generic.bytes, [9, 8, 7]);
+            let integer = mesh.attribute(7).unwrap();
+            assert_eq!((integer.data_type, integer.components), (DataType::U16, 1));
+            assert_eq!(integer.bytes, [10, 0, 12, 0, 11, 0]);
+        }
+
+        #[test]
+        fn sequential_meshes_decode() {
+            assert_triangle(&decode(&triangle([0, 0], &[])).unwrap());
+        }
+
+        #[test]
+        fn metadata_is_skipped() {
+            // Attribute 5 has the entry "a" and a child block "c"; the mesh has none.
+            let metadata = [1, 5, 1, 1, b'a', 1, 0, 1, 1, b'c', 0, 0, 0, 0];
+            assert_triangle(&decode(&triangle([0, 0x80], &metadata)).unwrap());
+        }
+
+        #[test]
+        fn truncated_data_is_invalid() {
+            let data = triangle([0, 0], &[]);
+            for len in 0..data.len() {
+                assert_eq!(decode(&data[..len]).unwrap_err(), Invalid, "{} bytes", len);
+            }
+        }
+
+        #[test]
+        fn edgebreaker_and_other_versions_are_unsupported() {
+            let mut data = triangle([0, 0], &[]);
+            data[8] = EDGEBREAKER_ENCODING;
+            assert_eq!(
+                decode(&data).unwrap_err(),
+                Unsupported("Edgebreaker connectivity")
+            );
+            data[6] = 3;
+            assert!(matches!(decode(&data), Err(Unsupported(_))));
+        }
+    }
+}
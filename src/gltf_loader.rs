@@ -0,0 +1,1964 @@
+pub mod gltf_loader {
+
+    use std::any::Any;
+    use std::collections::{HashMap, HashSet};
+    use std::fmt;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use glm::Mat4;
+    use serde_json::Value;
+
+    use crate::accessor::accessor::Accessor;
+    use crate::animation::animation::{AnimationClip, Channel, Interpolation, Property, Sampler};
+    use crate::camera::camera::{Camera, Projection};
+    use crate::draco::draco::{self, DecodeError};
+    use crate::lod::lod::{generate_lods, Lod, LodSettings};
+    use crate::math::math::{from_cols_array, transform_point, transform_vector};
+    use crate::mesh::mesh::{MeshData, MorphTarget};
+    use crate::mesh_optimizer::mesh_optimizer::optimize_mesh;
+    use crate::meshlets::meshlets::{
+        build_meshlets, MeshletStats, MAX_MESHLET_TRIANGLES, MAX_MESHLET_VERTICES,
+    };
+    use crate::meshopt::meshopt::{self, Filter, Mode};
+    use crate::mikktspace::mikktspace::generate_tangents;
+    use crate::normals::normals::{generate_normals, NormalSettings};
+    use crate::scene::scene::{
+        world_transforms, AlphaMode, Clearcoat, Component, ExtensionTarget, Light, LightKind,
+        Material, Mesh, Node, NodeTransform, Primitive, Scene, Sheen, Skin, Specular, TextureSlot,
+        TextureTransform, Transmission, Volume,
+    };
+    use crate::texture_compressor::texture_compressor::RgbaImage;
+
+    /// Extensions a file may list in `extensionsRequired`.
+    pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+        "KHR_lights_punctual",
+        "KHR_materials_clearcoat",
+        "KHR_materials_emissive_strength",
+        "KHR_materials_ior",
+        "KHR_materials_sheen",
+        "KHR_materials_specular",
+        "KHR_materials_transmission",
+        "KHR_materials_unlit",
+        "KHR_materials_variants",
+        "KHR_materials_volume",
+        "KHR_mesh_quantization",
+        "KHR_texture_transform",
+        "KHR_draco_mesh_compression",
+        "EXT_meshopt_compression",
+        "EXT_mesh_gpu_instancing",
+    ];
+
+    #[derive(Debug)]
+    pub enum LoadError {
+        Gltf(gltf::Error),
+        UnsupportedExtension(String),
+        UnsupportedImage(usize),
+        /// A URI could not be resolved or a data URI did not decode.
+        Uri(String, io::Error),
+        /// A buffer holds fewer bytes than its `byteLength`.
+        BufferLength(usize),
+        /// A buffer refers to the GLB binary chunk but the file has none.
+        MissingBinChunk,
+        /// A buffer view's EXT_meshopt_compression data did not decode.
+        Meshopt(usize),
+        /// A registered `ExtensionHandler` rejected its extension's JSON.
+        Extension(String, String),
+    }
+
+    impl fmt::Display for LoadError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                LoadError::Gltf(err) => write!(f, "{}", err),
+                LoadError::UnsupportedExtension(name) => {
+                    write!(f, "required extension {} is not supported", name)
+                }
+                LoadError::UnsupportedImage(index) => {
+                    write!(f, "image {} has an unsupported pixel format", index)
+                }
+                LoadError::Uri(uri, err) => write!(f, "could not load {}: {}", uri, err),
+                LoadError::BufferLength(index) => {
+                    write!(f, "buffer {} is shorter than its byteLength", index)
+                }
+                LoadError::MissingBinChunk => write!(f, "the GLB file has no BIN chunk"),
+                LoadError::Meshopt(view) => {
+                    write!(f, "buffer view {} has invalid meshopt data", view)
+                }
+                LoadError::Extension(name, message) => write!(f, "{}: {}", name, message),
+            }
+        }
+    }
+
+    impl From<gltf::Error> for LoadError {
+        fn from(err: gltf::Error) -> Self {
+            LoadError::Gltf(err)
+        }
+    }
+
+    /// The document as untyped JSON, for extensions the gltf crate drops while parsing.
+    fn raw_json(slice: &[u8]) -> Result<Value, LoadError> {
+        let value = if slice.starts_with(b"glTF") {
+            let glb = gltf::binary::Glb::from_slice(slice)?;
+            serde_json::from_slice(&glb.json)
+        } else {
+            serde_json::from_slice(slice)
+        };
+        value.map_err(|err| LoadError::Gltf(gltf::Error::Deserialize(err)))
+    }
+
+    /// Parses a vendor extension into an application type, stored in `Scene::components`.
+    pub trait ExtensionHandler {
+        /// The extension's name, as it appears in `extensions` objects.
+        fn name(&self) -> &str;
+
+        /// `value` is the extension object found on `target`. Errors fail the load.
+        fn parse(&self, target: ExtensionTarget, value: &Value) -> Result<Box<dyn Any>, String>;
+    }
+
+    /// Handlers run while loading. Their extensions may also appear in `extensionsRequired`.
+    #[derive(Default)]
+    pub struct ExtensionRegistry {
+        handlers: Vec<Box<dyn ExtensionHandler>>,
+    }
+
+    impl ExtensionRegistry {
+        pub fn register(&mut self, handler: impl ExtensionHandler + 'static) {
+            self.handlers.push(Box::new(handler));
+        }
+
+        fn handles(&self, name: &str) -> bool {
+            self.handlers.iter().any(|handler| handler.name() == name)
+        }
+    }
+
+    /// Runs the registered handlers over the extensions of scenes, nodes, meshes and
+    /// materials.
+    fn parse_components(
+        raw: &Value,
+        extensions: &ExtensionRegistry,
+    ) -> Result<Vec<Component>, LoadError> {
+        type Target = fn(usize) -> ExtensionTarget;
+        let kinds: [(&str, Target); 4] = [
+            ("scenes", ExtensionTarget::Scene),
+            ("nodes", ExtensionTarget::Node),
+            ("meshes", ExtensionTarget::Mesh),
+            ("materials", ExtensionTarget::Material),
+        ];
+        let mut components = Vec::new();
+        for (key, target) in kinds {
+            let objects = raw[key].as_array().into_iter().flatten();
+            for (index, object) in objects.enumerate() {
+                for handler in &extensions.handlers {
+                    let value = &object["extensions"][handler.name()];
+                    if value.is_null() {
+                        continue;
+                    }
+                    let data = handler
+                        .parse(target(index), value)
+                        .map_err(|err| LoadError::Extension(handler.name().to_string(), err))?;
+                    components.push(Component {
+                        target: target(index),
+                        data,
+                    });
+                }
+            }
+        }
+        Ok(components)
+    }
+
+    /// Checks `extensionsRequired` before validation turns an unknown extension into a
+    /// generic error.
+    fn check_required_extensions(
+        root: &Value,
+        extensions: &ExtensionRegistry,
+    ) -> Result<(), LoadError> {
+        let required = root["extensionsRequired"].as_array().into_iter().flatten();
+        for name in required.filter_map(Value::as_str) {
+            if !SUPPORTED_EXTENSIONS.contains(&name) && !extensions.handles(name) {
+                return Err(LoadError::UnsupportedExtension(name.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn raw_f32(value: Option<&Value>, default: f32) -> f32 {
+        value.and_then(Value::as_f64).map_or(default, |v| v as f32)
+    }
+
+    fn raw_f32_array<const N: usize>(value: Option<&Value>) -> Option<[f32; N]> {
+        let values = value?.as_array()?;
+        if values.len() != N {
+            return None;
+        }
+        let mut out = [0f32; N];
+        for (o, v) in out.iter_mut().zip(values) {
+            *o = v.as_f64()? as f32;
+        }
+        Some(out)
+    }
+
+    fn raw_texture_transform(info: &Value) -> TextureTransform {
+        let t = &info["extensions"]["KHR_texture_transform"];
+        TextureTransform {
+            offset: raw_f32_array(t.get("offset")).unwrap_or([0.0, 0.0]),
+            rotation: raw_f32(t.get("rotation"), 0.0),
+            scale: raw_f32_array(t.get("scale")).unwrap_or([1.0, 1.0]),
+            tex_coord: t.get("texCoord").and_then(Value::as_u64).map(|v| v as u32),
+        }
+    }
+
+    /// Texture info (`index`, `texCoord`, extensions) read from raw JSON.
+    fn raw_texture_slot(doc: &gltf::Document, info: Option<&Value>) -> Option<TextureSlot> {
+        let info = info?;
+        let texture = doc.textures().nth(info.get("index")?.as_u64()? as usize)?;
+        Some(TextureSlot {
+            image: texture.source().index(),
+            tex_coord: info.get("texCoord").and_then(Value::as_u64).unwrap_or(0) as u32,
+            transform: raw_texture_transform(info),
+        })
+    }
+
+    fn texture_slot(info: &gltf::texture::Info) -> TextureSlot {
+        TextureSlot {
+            image: info.texture().source().index(),
+            tex_coord: info.tex_coord(),
+            transform: info
+                .texture_transform()
+                .map_or(TextureTransform::default(), |t| TextureTransform {
+                    offset: t.offset(),
+                    rotation: t.rotation(),
+                    scale: t.scale(),
+                    tex_coord: t.tex_coord(),
+                }),
+        }
+    }
+
+    fn load_clearcoat(doc: &gltf::Document, ext: &Value) -> Option<Clearcoat> {
+        if !ext.is_object() {
+            return None;
+        }
+        Some(Clearcoat {
+            factor: raw_f32(ext.get("clearcoatFactor"), 0.0),
+            texture: raw_texture_slot(doc, ext.get("clearcoatTexture")),
+            roughness_factor: raw_f32(ext.get("clearcoatRoughnessFactor"), 0.0),
+            roughness_texture: raw_texture_slot(doc, ext.get("clearcoatRoughnessTexture")),
+            normal_texture: raw_texture_slot(doc, ext.get("clearcoatNormalTexture")),
+            normal_scale: raw_f32(ext["clearcoatNormalTexture"].get("scale"), 1.0),
+        })
+    }
+
+    fn load_sheen(doc: &gltf::Document, ext: &Value) -> Option<Sheen> {
+        if !ext.is_object() {
+            return None;
+        }
+        Some(Sheen {
+            color_factor: raw_f32_array(ext.get("sheenColorFactor")).unwrap_or([0.0; 3]),
+            color_texture: raw_texture_slot(doc, ext.get("sheenColorTexture")),
+            roughness_factor: raw_f32(ext.get("sheenRoughnessFactor"), 0.0),
+            roughness_texture: raw_texture_slot(doc, ext.get("sheenRoughnessTexture")),
+        })
+    }
+
+    /// `raw` is the material's JSON object. The gltf crate neither parses clearcoat and
+    /// sheen nor exposes KHR_texture_transform on normal and occlusion textures, so those
+    /// come from the raw JSON.
+    pub fn load_material(
+        doc: &gltf::Document,
+        material: &gltf::Material,
+        raw: &Value,
+    ) -> Material {
+        let pbr = material.pbr_metallic_roughness();
+        let extensions = &raw["extensions"];
+
+        Material {
+            name: material.name().map(str::to_string),
+            base_color_factor: pbr.base_color_factor(),
+            base_color_texture: pbr.base_color_texture().map(|info| texture_slot(&info)),
+            metallic_factor: pbr.metallic_factor(),
+            roughness_factor: pbr.roughness_factor(),
+            metallic_roughness_texture: pbr
+                .metallic_roughness_texture()
+                .map(|info| texture_slot(&info)),
+            normal_texture: raw_texture_slot(doc, raw.get("normalTexture")),
+            normal_scale: material.normal_texture().map_or(1.0, |t| t.scale()),
+            occlusion_texture: raw_texture_slot(doc, raw.get("occlusionTexture")),
+            occlusion_strength: material.occlusion_texture().map_or(1.0, |t| t.strength()),
+            emissive_factor: material.emissive_factor(),
+            emissive_texture: material.emissive_texture().map(|info| texture_slot(&info)),
+            emissive_strength: material.emissive_strength().unwrap_or(1.0),
+            unlit: material.unlit(),
+            alpha_mode: match material.alpha_mode() {
+                gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+                gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+                gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+            },
+            alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            double_sided: material.double_sided(),
+            ior: material.ior().unwrap_or(1.5),
+            clearcoat: load_clearcoat(doc, &extensions["KHR_materials_clearcoat"]),
+            sheen: load_sheen(doc, &extensions["KHR_materials_sheen"]),
+            transmission: material.transmission().map(|t| Transmission {
+                factor: t.transmission_factor(),
+                texture: t.transmission_texture().map(|info| texture_slot(&info)),
+            }),
+            volume: material.volume().map(|v| Volume {
+                thickness_factor: v.thickness_factor(),
+                thickness_texture: v.thickness_texture().map(|info| texture_slot(&info)),
+                attenuation_distance: v.attenuation_distance(),
+                attenuation_color: v.attenuation_color(),
+            }),
+            specular: material.specular().map(|s| Specular {
+                factor: s.specular_factor(),
+                texture: s.specular_texture().map(|info| texture_slot(&info)),
+                color_factor: s.specular_color_factor(),
+                color_texture: s.specular_color_texture().map(|info| texture_slot(&info)),
+            }),
+            extras: raw["extras"].clone(),
+        }
+    }
+
+    fn read(accessor: Option<gltf::Accessor>, buffers: &[gltf::buffer::Data]) -> Option<Accessor> {
+        Accessor::read(&accessor?, buffers)
+    }
+
+    /// Attributes decoded from KHR_draco_mesh_compression take the place of their accessors'
+    /// data.
+    fn load_mesh_data(
+        prim: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        mut draco: Option<DracoPrimitive>,
+    ) -> MeshData {
+        let mut mesh = MeshData::default();
+        let quantized = |data: Accessor| Some(data).filter(Accessor::is_quantized);
+
+        for (sem, accessor) in prim.attributes() {
+            let decoded = draco
+                .as_mut()
+                .and_then(|draco| draco.attributes.remove(&sem.to_string()));
+            let data = match decoded.or_else(|| Accessor::read(&accessor, buffers)) {
+                Some(data) => data,
+                None => continue,
+            };
+            match sem {
+                gltf::Semantic::Positions => {
+                    mesh.positions = data.to_f32();
+                    mesh.quantized.position = quantized(data);
+                }
+                gltf::Semantic::Normals => {
+                    mesh.normals = data.to_f32();
+                    mesh.quantized.normal = quantized(data);
+                }
+                gltf::Semantic::TexCoords(0) => {
+                    mesh.tex_coords0 = data.to_f32();
+                    mesh.quantized.tex_coord0 = quantized(data);
+                }
+                gltf::Semantic::TexCoords(1) => {
+                    mesh.tex_coords1 = data.to_f32();
+                    mesh.quantized.tex_coord1 = quantized(data);
+                }
+                gltf::Semantic::TexCoords(_) => {}
+                gltf::Semantic::Tangents => mesh.tangents = data.to_f32(),
+                gltf::Semantic::Colors(_) => {}
+                gltf::Semantic::Joints(0) => mesh.joints0 = data.to_u16(),
+                gltf::Semantic::Joints(1) => mesh.joints1 = data.to_u16(),
+                gltf::Semantic::Weights(0) => mesh.weights0 = data.to_f32(),
+                gltf::Semantic::Weights(1) => mesh.weights1 = data.to_f32(),
+                // Only eight influences per vertex are supported.
+                gltf::Semantic::Joints(_) => {}
+                gltf::Semantic::Weights(_) => {}
+            }
+        }
+
+        // A stream that doesn't cover every vertex can't be uploaded as stored.
+        let count = mesh.vertex_count();
+        let q = &mut mesh.quantized;
+        for stream in [
+            &mut q.position,
+            &mut q.normal,
+            &mut q.tex_coord0,
+            &mut q.tex_coord1,
+        ] {
+            if stream.as_ref().is_some_and(|data| data.count != count) {
+                *stream = None;
+            }
+        }
+
+        let deltas = |accessor| read(accessor, buffers).map_or(Vec::new(), |data| data.to_f32());
+        mesh.morph_targets = prim
+            .morph_targets()
+            .map(|target| MorphTarget {
+                positions: deltas(target.positions()),
+                normals: deltas(target.normals()),
+                tangents: deltas(target.tangents()),
+            })
+            .collect();
+
+        mesh.indices = match (draco, read(prim.indices(), buffers)) {
+            (Some(draco), _) => draco.indices,
+            (None, Some(indices)) => indices.to_u32(),
+            (None, None) => (0..mesh.vertex_count() as u32).collect(),
+        };
+        mesh
+    }
+
+    fn load_light(light: &gltf::khr_lights_punctual::Light, world: &Mat4) -> Light {
+        use gltf::khr_lights_punctual::Kind;
+
+        let position = transform_point(world, glm::vec3(0.0, 0.0, 0.0));
+        let direction = glm::normalize(transform_vector(world, glm::vec3(0.0, 0.0, -1.0)));
+
+        Light {
+            name: light.name().map(str::to_string),
+            kind: match light.kind() {
+                Kind::Directional => LightKind::Directional,
+                Kind::Point => LightKind::Point,
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => LightKind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                },
+            },
+            color: light.color(),
+            intensity: light.intensity(),
+            range: light.range(),
+            position: [position.x, position.y, position.z],
+            direction: [direction.x, direction.y, direction.z],
+        }
+    }
+
+    fn load_camera(camera: &gltf::Camera, node: usize, world: &Mat4) -> Camera {
+        let projection = match camera.projection() {
+            gltf::camera::Projection::Perspective(p) => Projection::Perspective {
+                yfov: p.yfov(),
+                aspect_ratio: p.aspect_ratio(),
+                znear: p.znear(),
+                zfar: p.zfar(),
+            },
+            gltf::camera::Projection::Orthographic(o) => Projection::Orthographic {
+                xmag: o.xmag(),
+                ymag: o.ymag(),
+                znear: o.znear(),
+                zfar: o.zfar(),
+            },
+        };
+
+        Camera {
+            name: camera.name().map(str::to_string),
+            node,
+            projection,
+            world: *world,
+        }
+    }
+
+    fn load_nodes(doc: &gltf::Document, raw: &Value) -> Vec<Node> {
+        let mut nodes: Vec<Node> = doc
+            .nodes()
+            .map(|node| {
+                let (translation, rotation, scale) = node.transform().decomposed();
+                // Primitives of a mesh should agree on the target count, but a weight per
+                // target of the largest one never leaves a target without a weight.
+                let target_count = node.mesh().map_or(0, |mesh| {
+                    let counts = mesh.primitives().map(|prim| prim.morph_targets().count());
+                    counts.max().unwrap_or(0)
+                });
+                let default_weights = node.mesh().and_then(|mesh| mesh.weights());
+                let mut weights = node.weights().or(default_weights).unwrap_or(&[]).to_vec();
+                weights.resize(target_count.max(weights.len()), 0.0);
+                Node {
+                    name: node.name().map(str::to_string),
+                    parent: None,
+                    children: node.children().map(|child| child.index()).collect(),
+                    transform: NodeTransform {
+                        translation,
+                        rotation,
+                        scale,
+                    },
+                    weights,
+                    extras: raw["nodes"][node.index()]["extras"].clone(),
+                }
+            })
+            .collect();
+        for parent in doc.nodes() {
+            for child in parent.children() {
+                nodes[child.index()].parent = Some(parent.index());
+            }
+        }
+        nodes
+    }
+
+    fn load_skin(skin: &gltf::Skin, buffers: &[gltf::buffer::Data]) -> Skin {
+        let inverse_bind_matrices = match read(skin.inverse_bind_matrices(), buffers) {
+            Some(matrices) => matrices
+                .to_f32::<16>()
+                .iter()
+                .map(|m| {
+                    let mut columns = [[0f32; 4]; 4];
+                    for (k, &v) in m.iter().enumerate() {
+                        columns[k / 4][k % 4] = v;
+                    }
+                    from_cols_array(&columns)
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Skin {
+            name: skin.name().map(str::to_string),
+            joints: skin.joints().map(|joint| joint.index()).collect(),
+            inverse_bind_matrices,
+        }
+    }
+
+    fn load_animation(
+        animation: &gltf::Animation,
+        buffers: &[gltf::buffer::Data],
+    ) -> AnimationClip {
+        let mut channels = Vec::new();
+
+        for channel in animation.channels() {
+            let property = match channel.target().property() {
+                gltf::animation::Property::Translation => Property::Translation,
+                gltf::animation::Property::Rotation => Property::Rotation,
+                gltf::animation::Property::Scale => Property::Scale,
+                gltf::animation::Property::MorphTargetWeights => Property::Weights,
+            };
+            // Quantized rotations and weights are normalized integers, which decode to
+            // floats like any other accessor.
+            let sampler = channel.sampler();
+            let (times, values) = match (
+                Accessor::read(&sampler.input(), buffers),
+                Accessor::read(&sampler.output(), buffers),
+            ) {
+                (Some(times), Some(values)) => (times.to_f32_flat(), values.to_f32_flat()),
+                _ => continue,
+            };
+            let interpolation = match channel.sampler().interpolation() {
+                gltf::animation::Interpolation::Step => Interpolation::Step,
+                gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+            };
+
+            let keys = match interpolation {
+                Interpolation::CubicSpline => times.len() * 3,
+                _ => times.len(),
+            };
+            if keys == 0 {
+                continue;
+            }
+
+            channels.push(Channel {
+                node: channel.target().node().index(),
+                property,
+                sampler: Sampler {
+                    components: values.len() / keys,
+                    times,
+                    values,
+                    interpolation,
+                },
+            });
+        }
+
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.sampler.times.last().copied())
+            .fold(0.0, f32::max);
+
+        AnimationClip {
+            name: animation.name().map(str::to_string),
+            channels,
+            duration,
+        }
+    }
+
+    /// A KHR_draco_mesh_compression primitive's triangles and the attributes it maps, by
+    /// semantic.
+    struct DracoPrimitive {
+        attributes: HashMap<String, Accessor>,
+        indices: Vec<u32>,
+    }
+
+    /// The bytes of a buffer view, read from the JSON for views that the gltf crate's
+    /// document doesn't hand out by index.
+    fn view_bytes<'a>(
+        raw: &Value,
+        view: usize,
+        buffers: &'a [gltf::buffer::Data],
+    ) -> Option<&'a [u8]> {
+        let view = &raw["bufferViews"][view];
+        let field = |name: &str| view[name].as_u64().map(|v| v as usize);
+        let buffer = buffers.get(field("buffer")?)?;
+        let offset = field("byteOffset").unwrap_or(0);
+        buffer.get(offset..offset.checked_add(field("byteLength")?)?)
+    }
+
+    /// Decodes the Draco mesh in `ext` into the types and counts its accessors declare.
+    fn decode_draco(
+        prim: &gltf::Primitive,
+        ext: &Value,
+        raw: &Value,
+        buffers: &[gltf::buffer::Data],
+    ) -> Result<DracoPrimitive, DecodeError> {
+        use gltf::accessor::DataType;
+
+        let view = ext["bufferView"].as_u64().ok_or(DecodeError::Invalid)?;
+        let data = view_bytes(raw, view as usize, buffers).ok_or(DecodeError::Invalid)?;
+        let mesh = draco::decode(data)?;
+
+        let mut attributes = HashMap::new();
+        for (sem, accessor) in prim.attributes() {
+            let name = sem.to_string();
+            let id = match ext["attributes"][&name].as_u64() {
+                Some(id) => id,
+                None => continue,
+            };
+            let attribute = u32::try_from(id)
+                .ok()
+                .and_then(|id| mesh.attribute(id))
+                .ok_or(DecodeError::Invalid)?;
+            let data_type = match attribute.data_type {
+                draco::DataType::I8 => DataType::I8,
+                draco::DataType::U8 => DataType::U8,
+                draco::DataType::I16 => DataType::I16,
+                draco::DataType::U16 => DataType::U16,
+                draco::DataType::U32 => DataType::U32,
+                draco::DataType::F32 => DataType::F32,
+                _ => return Err(DecodeError::Invalid),
+            };
+            let components = accessor.dimensions().multiplicity();
+            if data_type != accessor.data_type()
+                || attribute.components != components
+                || mesh.points != accessor.count()
+            {
+                return Err(DecodeError::Invalid);
+            }
+            let data = Accessor {
+                data_type,
+                components,
+                normalized: accessor.normalized(),
+                count: mesh.points,
+                bytes: attribute.bytes.clone(),
+            };
+            attributes.insert(name, data);
+        }
+        Ok(DracoPrimitive {
+            attributes,
+            indices: mesh.indices,
+        })
+    }
+
+    /// Loads a primitive, decoding its KHR_draco_mesh_compression data if it has any. When
+    /// that fails, accessors with data of their own are the uncompressed fallback.
+    fn load_primitive(
+        prim: &gltf::Primitive,
+        raw_mesh: &Value,
+        raw: &Value,
+        buffers: &[gltf::buffer::Data],
+    ) -> Result<MeshData, DecodeError> {
+        let ext = &raw_mesh["primitives"][prim.index()]["extensions"]["KHR_draco_mesh_compression"];
+        if !ext.is_object() {
+            return Ok(load_mesh_data(prim, buffers, None));
+        }
+        match decode_draco(prim, ext, raw, buffers) {
+            Ok(draco) => Ok(load_mesh_data(prim, buffers, Some(draco))),
+            Err(_)
+                if prim
+                    .get(&gltf::Semantic::Positions)
+                    .is_some_and(|accessor| accessor.view().is_some()) =>
+            {
+                Ok(load_mesh_data(prim, buffers, None))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// KHR_materials_variants names declared by the document, in index order.
+    fn load_variants(raw: &Value) -> Vec<String> {
+        let variants = raw["extensions"]["KHR_materials_variants"]["variants"].as_array();
+        variants.map_or(Vec::new(), |variants| {
+            variants
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| match variant["name"].as_str() {
+                    Some(name) => name.to_string(),
+                    None => format!("variant {}", i),
+                })
+                .collect()
+        })
+    }
+
+    /// `(variant, material)` pairs of a primitive's KHR_materials_variants mappings,
+    /// without mappings to materials that do not exist.
+    fn load_variant_materials(raw_prim: &Value, material_count: usize) -> Vec<(usize, usize)> {
+        let mappings = raw_prim["extensions"]["KHR_materials_variants"]["mappings"].as_array();
+        let mut pairs = Vec::new();
+        for mapping in mappings.into_iter().flatten() {
+            let material = match mapping["material"].as_u64() {
+                Some(material) if (material as usize) < material_count => material as usize,
+                _ => continue,
+            };
+            let variants = mapping["variants"].as_array().into_iter().flatten();
+            pairs.extend(variants.filter_map(|v| Some((v.as_u64()? as usize, material))));
+        }
+        pairs
+    }
+
+    /// EXT_mesh_gpu_instancing transforms of a node, empty without the extension. Missing
+    /// attributes keep their default.
+    fn load_instances(
+        doc: &gltf::Document,
+        ext: &Value,
+        buffers: &[gltf::buffer::Data],
+    ) -> Vec<Mat4> {
+        let attribute = |name: &str| {
+            let index = ext["attributes"][name].as_u64()? as usize;
+            read(doc.accessors().nth(index), buffers)
+        };
+        let translations = attribute("TRANSLATION").map(|a| a.to_f32::<3>());
+        let rotations = attribute("ROTATION").map(|a| a.to_f32::<4>());
+        let scales = attribute("SCALE").map(|a| a.to_f32::<3>());
+
+        let count = translations
+            .as_ref()
+            .map_or(0, Vec::len)
+            .max(rotations.as_ref().map_or(0, Vec::len))
+            .max(scales.as_ref().map_or(0, Vec::len));
+        let rest = NodeTransform::default();
+        (0..count)
+            .map(|i| {
+                NodeTransform {
+                    translation: translations
+                        .as_ref()
+                        .and_then(|t| t.get(i).copied())
+                        .unwrap_or(rest.translation),
+                    rotation: rotations
+                        .as_ref()
+                        .and_then(|r| r.get(i).copied())
+                        .unwrap_or(rest.rotation),
+                    scale: scales
+                        .as_ref()
+                        .and_then(|s| s.get(i).copied())
+                        .unwrap_or(rest.scale),
+                }
+                .matrix()
+            })
+            .collect()
+    }
+
+    fn visit_node(
+        doc: &gltf::Document,
+        node: &gltf::Node,
+        worlds: &[Mat4],
+        buffers: &[gltf::buffer::Data],
+        raw: &Value,
+        scene: &mut Scene,
+    ) {
+        let world = worlds[node.index()];
+
+        if let Some(mesh) = node.mesh() {
+            let instances = load_instances(
+                doc,
+                &raw["nodes"][node.index()]["extensions"]["EXT_mesh_gpu_instancing"],
+                buffers,
+            );
+            for prim in mesh.primitives() {
+                let data = match load_primitive(&prim, &raw["meshes"][mesh.index()], raw, buffers) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        let source = (mesh.index(), prim.index());
+                        if !scene.report.skipped.iter().any(|(s, _)| *s == source) {
+                            scene.report.skipped.push((source, err.to_string()));
+                        }
+                        continue;
+                    }
+                };
+                scene.primitives.push(Primitive {
+                    mesh: data,
+                    material: prim.material().index(),
+                    node: Some(node.index()),
+                    skin: node.skin().map(|skin| skin.index()),
+                    world,
+                    source: Some((mesh.index(), prim.index())),
+                    instances: instances.clone(),
+                    variant_materials: load_variant_materials(
+                        &raw["meshes"][mesh.index()]["primitives"][prim.index()],
+                        doc.materials().len(),
+                    ),
+                });
+            }
+        }
+
+        if let Some(light) = node.light() {
+            scene.lights.push(load_light(&light, &world));
+        }
+
+        if let Some(camera) = node.camera() {
+            scene.cameras.push(load_camera(&camera, node.index(), &world));
+        }
+
+        for child in node.children() {
+            visit_node(doc, &child, worlds, buffers, raw, scene);
+        }
+    }
+
+    /// Supplies the bytes behind external buffer and image URIs. Data URIs and the GLB
+    /// binary chunk never reach the resolver.
+    pub trait UriResolver {
+        /// `uri` is passed as written in the file, still percent-encoded.
+        fn resolve(&self, uri: &str) -> io::Result<Vec<u8>>;
+    }
+
+    impl<F: Fn(&str) -> io::Result<Vec<u8>>> UriResolver for F {
+        fn resolve(&self, uri: &str) -> io::Result<Vec<u8>> {
+            self(uri)
+        }
+    }
+
+    /// Reads relative URIs from a directory.
+    pub struct FileResolver {
+        pub base: PathBuf,
+    }
+
+    impl FileResolver {
+        /// Resolves against the directory holding the glTF file at `path`.
+        pub fn for_file(path: &Path) -> Self {
+            FileResolver {
+                base: path.parent().map_or(PathBuf::new(), Path::to_path_buf),
+            }
+        }
+    }
+
+    impl UriResolver for FileResolver {
+        fn resolve(&self, uri: &str) -> io::Result<Vec<u8>> {
+            if uri.contains("://") {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "only relative file URIs are supported",
+                ));
+            }
+            std::fs::read(self.base.join(percent_decode(uri)))
+        }
+    }
+
+    fn percent_decode(uri: &str) -> String {
+        let bytes = uri.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3).unwrap_or_default());
+            match hex.ok().and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                Some(byte) if bytes[i] == b'%' => {
+                    out.push(byte);
+                    i += 3;
+                }
+                _ => {
+                    out.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    fn decode_base64(text: &str) -> Option<Vec<u8>> {
+        let mut out = Vec::with_capacity(text.len() / 4 * 3);
+        let mut bits = 0u32;
+        let mut count = 0;
+        for c in text.bytes().take_while(|&c| c != b'=') {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' | b'-' => 62,
+                b'/' | b'_' => 63,
+                _ => return None,
+            };
+            bits = bits << 6 | value as u32;
+            count += 6;
+            if count >= 8 {
+                count -= 8;
+                out.push((bits >> count) as u8);
+            }
+        }
+        Some(out)
+    }
+
+    /// Decodes `data:` URIs and hands everything else to `resolver`.
+    fn read_uri(uri: &str, resolver: &dyn UriResolver) -> Result<Vec<u8>, LoadError> {
+        let data = match uri.strip_prefix("data:") {
+            Some(data) => data,
+            None => {
+                let resolved = resolver.resolve(uri);
+                return resolved.map_err(|err| LoadError::Uri(uri.to_string(), err));
+            }
+        };
+        let invalid = |message: &str| {
+            let err = io::Error::new(io::ErrorKind::InvalidData, message);
+            LoadError::Uri(format!("data URI ({} bytes)", uri.len()), err)
+        };
+        match data.split_once(',') {
+            Some((header, payload)) if header.ends_with(";base64") => {
+                decode_base64(payload).ok_or_else(|| invalid("invalid base64"))
+            }
+            _ => Err(invalid("only base64 data URIs are supported")),
+        }
+    }
+
+    fn decode_meshopt_view(ext: &Value, buffers: &[gltf::buffer::Data]) -> Option<Vec<u8>> {
+        let field = |name: &str| ext[name].as_u64().map(|v| v as usize);
+        let source = buffers.get(field("buffer")?)?;
+        let offset = field("byteOffset").unwrap_or(0);
+        let compressed = source.get(offset..offset + field("byteLength")?)?;
+        let count = field("count")?;
+        let stride = field("byteStride")?;
+        let mode = Mode::parse(ext["mode"].as_str()?)?;
+        let filter = Filter::parse(ext["filter"].as_str().unwrap_or("NONE"))?;
+        meshopt::decode(compressed, count, stride, mode, filter)
+    }
+
+    /// Decodes EXT_meshopt_compression views into the buffers they belong to, usually a
+    /// fallback buffer with no data of its own.
+    fn decode_meshopt_views(
+        doc: &gltf::Document,
+        raw: &Value,
+        buffers: &mut [gltf::buffer::Data],
+    ) -> Result<(), LoadError> {
+        for view in doc.views() {
+            let ext = &raw["bufferViews"][view.index()]["extensions"]["EXT_meshopt_compression"];
+            if !ext.is_object() {
+                continue;
+            }
+
+            let invalid = LoadError::Meshopt(view.index());
+            let decoded = decode_meshopt_view(ext, buffers).ok_or(invalid)?;
+            let buffer = &mut buffers[view.buffer().index()].0;
+            match buffer.get_mut(view.offset()..view.offset() + decoded.len()) {
+                Some(target) => target.copy_from_slice(&decoded),
+                None => return Err(LoadError::Meshopt(view.index())),
+            }
+        }
+        Ok(())
+    }
+
+    fn load_buffers(
+        doc: &gltf::Document,
+        raw: &Value,
+        mut blob: Option<Vec<u8>>,
+        resolver: &dyn UriResolver,
+    ) -> Result<Vec<gltf::buffer::Data>, LoadError> {
+        let mut buffers = Vec::new();
+        for buffer in doc.buffers() {
+            let meshopt = &raw["buffers"][buffer.index()]["extensions"]["EXT_meshopt_compression"];
+            let fallback = meshopt["fallback"].as_bool() == Some(true);
+            let mut data = match buffer.source() {
+                // Only filled by decoding compressed views.
+                gltf::buffer::Source::Bin if fallback => vec![0; buffer.length()],
+                gltf::buffer::Source::Bin => blob.take().ok_or(LoadError::MissingBinChunk)?,
+                gltf::buffer::Source::Uri(uri) => read_uri(uri, resolver)?,
+            };
+            if data.len() < buffer.length() {
+                return Err(LoadError::BufferLength(buffer.index()));
+            }
+            // Accessors may read up to the 4-byte aligned end.
+            while data.len() % 4 != 0 {
+                data.push(0);
+            }
+            buffers.push(gltf::buffer::Data(data));
+        }
+        decode_meshopt_views(doc, raw, &mut buffers)?;
+        Ok(buffers)
+    }
+
+    fn is_dds(image: &gltf::Image) -> bool {
+        const DDS: &str = "image/vnd-ms.dds";
+        match image.source() {
+            gltf::image::Source::View { mime_type, .. } => mime_type == DDS,
+            gltf::image::Source::Uri { uri, mime_type } => {
+                mime_type == Some(DDS) || uri.to_ascii_lowercase().ends_with(".dds")
+            }
+        }
+    }
+
+    fn load_image(
+        image: &gltf::Image,
+        buffers: &[gltf::buffer::Data],
+        resolver: &dyn UriResolver,
+    ) -> Result<RgbaImage, LoadError> {
+        // MSFT_texture_dds lists DDS images next to a PNG or JPEG core source, which is what
+        // textures sample here. They keep their index as a single white texel.
+        if is_dds(image) {
+            return Ok(RgbaImage {
+                width: 1,
+                height: 1,
+                pixels: vec![255; 4],
+            });
+        }
+        let decoded = match image.source() {
+            gltf::image::Source::View { view, .. } => {
+                let buffer = &buffers[view.buffer().index()];
+                let bytes = buffer.get(view.offset()..view.offset() + view.length());
+                bytes.and_then(RgbaImage::decode)
+            }
+            gltf::image::Source::Uri { uri, .. } => RgbaImage::decode(&read_uri(uri, resolver)?),
+        };
+        decoded.ok_or(LoadError::UnsupportedImage(image.index()))
+    }
+
+    /// Parses and validates the document. The gltf crate rejects required extensions it
+    /// has no feature for, including ones this loader handles itself, so that verdict is
+    /// left to `check_required_extensions`. It also rejects accessors without a buffer view,
+    /// which the spec allows and Draco compressed primitives rely on.
+    fn parse(bytes: &[u8]) -> Result<gltf::Gltf, LoadError> {
+        use gltf::json::validation::{Error, Validate};
+
+        let gltf = gltf::Gltf::from_slice_without_validation(bytes)?;
+        let root = gltf.document.as_json();
+        let mut errors = Vec::new();
+        root.validate(root, gltf::json::Path::new, &mut |path, err| {
+            let path = path();
+            let name = path.as_str();
+            let required = name.starts_with("extensionsRequired");
+            let view = name.starts_with("accessors[") && name.ends_with("].bufferView");
+            let allowed = match err {
+                Error::Unsupported => required,
+                Error::Missing => view,
+                _ => false,
+            };
+            if !allowed {
+                errors.push((path, err));
+            }
+        });
+        if errors.is_empty() {
+            Ok(gltf)
+        } else {
+            Err(LoadError::Gltf(gltf::Error::Validation(errors)))
+        }
+    }
+
+    /// Processing applied to the geometry while importing.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct ImportSettings {
+        /// How normals are generated for primitives without them.
+        pub normals: NormalSettings,
+        /// Weld vertices and reorder them and the triangles for the GPU, reporting the vertex
+        /// cache statistics of every mesh primitive in `Scene::report`.
+        pub optimize: bool,
+        /// Simplified levels of detail to generate for every primitive.
+        pub lods: LodSettings,
+        /// Partition every primitive into meshlets, reporting how they turned out in
+        /// `Scene::report`.
+        pub meshlets: bool,
+    }
+
+    /// Fills in normals where they are missing, and MikkTSpace tangents where a normal map
+    /// is used without them.
+    fn generate_attributes(scene: &mut Scene, settings: &ImportSettings) {
+        for prim in &mut scene.primitives {
+            if prim.mesh.normals.is_empty() {
+                generate_normals(&mut prim.mesh, settings.normals);
+            }
+            let normal_texture = prim
+                .material
+                .and_then(|material| scene.materials.get(material))
+                .and_then(|material| material.normal_texture);
+            if let Some(texture) = normal_texture {
+                if prim.mesh.tangents.is_empty() {
+                    generate_tangents(&mut prim.mesh, texture.uv_set());
+                }
+            }
+        }
+    }
+
+    /// Runs `optimize_mesh` on every primitive. Nodes sharing a mesh primitive produce the
+    /// same result, which is reported once.
+    fn optimize_primitives(scene: &mut Scene) {
+        let mut reported = HashSet::new();
+        for prim in &mut scene.primitives {
+            let report = optimize_mesh(&mut prim.mesh);
+            if let Some(source) = prim.source.filter(|&source| reported.insert(source)) {
+                scene.report.optimized.push((source, report));
+            }
+        }
+    }
+
+    /// Runs `generate_lods` on every primitive, once per glTF mesh primitive.
+    fn generate_primitive_lods(scene: &mut Scene, settings: LodSettings) {
+        let mut generated: HashMap<(usize, usize), Vec<Lod>> = HashMap::new();
+        for prim in &mut scene.primitives {
+            match prim.source.and_then(|source| generated.get(&source)) {
+                Some(lods) => prim.mesh.lods = lods.clone(),
+                None => {
+                    generate_lods(&mut prim.mesh, settings);
+                    if let Some(source) = prim.source {
+                        generated.insert(source, prim.mesh.lods.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fills `MeshData::meshlets` of every primitive with the largest meshlets allowed.
+    fn build_primitive_meshlets(scene: &mut Scene) {
+        let mut reported = HashSet::new();
+        for prim in &mut scene.primitives {
+            let mesh = &mut prim.mesh;
+            mesh.meshlets = build_meshlets(mesh, MAX_MESHLET_VERTICES, MAX_MESHLET_TRIANGLES);
+            let count = mesh.meshlets.len();
+            if let Some(source) = prim
+                .source
+                .filter(|&source| count > 0 && reported.insert(source))
+            {
+                let stats = MeshletStats::new(&mesh.meshlets);
+                scene.report.meshlets.push((source, stats));
+            }
+        }
+    }
+
+    /// Loads the default scene from a `.gltf` or `.glb` file. External URIs resolve next to
+    /// the file.
+    pub fn load(path: &str, settings: &ImportSettings) -> Result<Scene, LoadError> {
+        load_with_extensions(path, &ExtensionRegistry::default(), settings)
+    }
+
+    /// `load` that also parses the vendor extensions in `extensions` into
+    /// `Scene::components`.
+    pub fn load_with_extensions(
+        path: &str,
+        extensions: &ExtensionRegistry,
+        settings: &ImportSettings,
+    ) -> Result<Scene, LoadError> {
+        let bytes = std::fs::read(path).map_err(gltf::Error::Io)?;
+        let resolver = FileResolver::for_file(Path::new(path));
+        load_slice_with_extensions(&bytes, &resolver, extensions, settings)
+    }
+
+    /// Loads the default scene from a glTF JSON or GLB document in memory. Files without
+    /// lights get `Light::key_light`.
+    pub fn load_slice(
+        bytes: &[u8],
+        resolver: &dyn UriResolver,
+        settings: &ImportSettings,
+    ) -> Result<Scene, LoadError> {
+        load_slice_with_extensions(bytes, resolver, &ExtensionRegistry::default(), settings)
+    }
+
+    /// `load_slice` that also parses the vendor extensions in `extensions` into
+    /// `Scene::components`.
+    pub fn load_slice_with_extensions(
+        bytes: &[u8],
+        resolver: &dyn UriResolver,
+        extensions: &ExtensionRegistry,
+        settings: &ImportSettings,
+    ) -> Result<Scene, LoadError> {
+        let raw = raw_json(bytes)?;
+        check_required_extensions(&raw, extensions)?;
+
+        let gltf = parse(bytes)?;
+        let doc = gltf.document;
+        let buffers = load_buffers(&doc, &raw, gltf.blob, resolver)?;
+        let mut scene = Scene {
+            materials: doc
+                .materials()
+                .map(|m| load_material(&doc, &m, &raw["materials"][m.index().unwrap_or(0)]))
+                .collect(),
+            ..Default::default()
+        };
+        for image in doc.images() {
+            scene.images.push(load_image(&image, &buffers, resolver)?);
+        }
+
+        scene.variants = load_variants(&raw);
+        scene.nodes = load_nodes(&doc, &raw);
+        scene.meshes = doc
+            .meshes()
+            .map(|mesh| Mesh {
+                name: mesh.name().map(str::to_string),
+                extras: raw["meshes"][mesh.index()]["extras"].clone(),
+            })
+            .collect();
+        scene.components = parse_components(&raw, extensions)?;
+        scene.skins = doc.skins().map(|skin| load_skin(&skin, &buffers)).collect();
+        scene.animations = doc
+            .animations()
+            .map(|animation| load_animation(&animation, &buffers))
+            .collect();
+
+        let worlds = world_transforms(&scene.nodes, &scene.rest_pose().transforms);
+        // Without a default scene the first one is as good as any.
+        if let Some(root) = doc.default_scene().or_else(|| doc.scenes().next()) {
+            scene.extras = raw["scenes"][root.index()]["extras"].clone();
+            for node in root.nodes() {
+                visit_node(&doc, &node, &worlds, &buffers, &raw, &mut scene);
+            }
+        }
+        generate_attributes(&mut scene, settings);
+        if settings.optimize {
+            optimize_primitives(&mut scene);
+        }
+        if settings.lods.count > 0 {
+            generate_primitive_lods(&mut scene, settings.lods);
+        }
+        if settings.meshlets {
+            build_primitive_meshlets(&mut scene);
+        }
+
+        if scene.lights.is_empty() {
+            scene.lights.push(Light::key_light());
+        }
+
+        Ok(scene)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::cell::RefCell;
+        use std::collections::HashMap;
+
+        use super::*;
+
+        /// A white 1x1 PNG.
+        const PIXEL: &str = "data:image/png;base64,\
+            iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAAC0lEQVR4nGP4DwQACfsD/fteaysAAAAASUVORK5CYII=";
+
+        fn no_files(uri: &str) -> io::Result<Vec<u8>> {
+            Err(io::Error::new(io::ErrorKind::NotFound, uri.to_string()))
+        }
+
+        fn load_bytes(bytes: &[u8]) -> Result<Scene, LoadError> {
+            load_slice(bytes, &no_files, &ImportSettings::default())
+        }
+
+        fn load_json(json: &str) -> Result<Scene, LoadError> {
+            load_bytes(json.as_bytes())
+        }
+
+        /// A GLB container around `json` and an optional BIN chunk.
+        fn glb(json: &str, bin: Option<&[u8]>) -> Vec<u8> {
+            fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8], pad: u8) {
+                let padded = data.len().div_ceil(4) * 4;
+                out.extend_from_slice(&(padded as u32).to_le_bytes());
+                out.extend_from_slice(kind);
+                out.extend_from_slice(data);
+                out.resize(out.len() + padded - data.len(), pad);
+            }
+            let mut chunks = Vec::new();
+            chunk(&mut chunks, b"JSON", json.as_bytes(), b' ');
+            if let Some(bin) = bin {
+                chunk(&mut chunks, b"BIN\0", bin, 0);
+            }
+            let mut out = b"glTF".to_vec();
+            out.extend_from_slice(&2u32.to_le_bytes());
+            out.extend_from_slice(&(12 + chunks.len() as u32).to_le_bytes());
+            out.extend(chunks);
+            out
+        }
+
+        /// Positions and `u16` indices of one triangle.
+        fn triangle_bin() -> Vec<u8> {
+            let positions = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+            let mut bin: Vec<u8> = positions.iter().flat_map(|v| v.to_le_bytes()).collect();
+            bin.extend([0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()));
+            bin
+        }
+
+        /// A triangle read from `buffer`, the JSON of a 42 byte buffer in `triangle_bin`
+        /// layout.
+        fn triangle_json(buffer: &str) -> String {
+            format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "buffers": [{}],
+                    "bufferViews": [
+                        {{ "buffer": 0, "byteLength": 36 }},
+                        {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+                    ],
+                    "accessors": [
+                        {{
+                            "bufferView": 0,
+                            "componentType": 5126,
+                            "count": 3,
+                            "type": "VEC3",
+                            "min": [0, 0, 0],
+                            "max": [1, 1, 0]
+                        }},
+                        {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+                    ],
+                    "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1 }}] }}],
+                    "nodes": [{{ "mesh": 0 }}],
+                    "scenes": [{{ "nodes": [0] }}]
+                }}"#,
+                buffer
+            )
+        }
+
+        fn assert_triangle(scene: &Scene) {
+            assert_eq!(scene.primitives.len(), 1);
+            let mesh = &scene.primitives[0].mesh;
+            assert_eq!(
+                mesh.positions,
+                [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+            );
+            assert_eq!(mesh.indices, [0, 1, 2]);
+        }
+
+        /// Serves URIs from a map and records every request.
+        #[derive(Default)]
+        struct MockResolver {
+            files: HashMap<String, Vec<u8>>,
+            requests: RefCell<Vec<String>>,
+        }
+
+        impl UriResolver for MockResolver {
+            fn resolve(&self, uri: &str) -> io::Result<Vec<u8>> {
+                self.requests.borrow_mut().push(uri.to_string());
+                let file = self.files.get(uri).cloned();
+                file.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, uri.to_string()))
+            }
+        }
+
+        struct Tag;
+
+        impl ExtensionHandler for Tag {
+            fn name(&self) -> &str {
+                "VENDOR_tag"
+            }
+
+            fn parse(&self, _: ExtensionTarget, value: &Value) -> Result<Box<dyn Any>, String> {
+                let label = value["label"].as_str().ok_or("missing label")?;
+                Ok(Box::new(label.to_string()))
+            }
+        }
+
+        /// A node carrying `tag` as its VENDOR_tag extension.
+        fn tagged_document(required: bool, tag: &str) -> Vec<u8> {
+            let required = if required {
+                r#""extensionsRequired": ["VENDOR_tag"],"#
+            } else {
+                ""
+            };
+            format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "extensionsUsed": ["VENDOR_tag"],
+                    {}
+                    "nodes": [{{ "extensions": {{ "VENDOR_tag": {} }} }}],
+                    "scenes": [{{ "nodes": [0] }}],
+                    "scene": 0
+                }}"#,
+                required, tag
+            )
+            .into_bytes()
+        }
+
+        fn registry() -> ExtensionRegistry {
+            let mut registry = ExtensionRegistry::default();
+            registry.register(Tag);
+            registry
+        }
+
+        #[test]
+        fn base64_decodes_padded_unpadded_and_url_safe_text() {
+            assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+            assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+            assert_eq!(decode_base64("TQ==").unwrap(), b"M");
+            assert_eq!(decode_base64("TQ").unwrap(), b"M");
+            assert_eq!(decode_base64("-_8=").unwrap(), [0xfb, 0xff]);
+            assert_eq!(decode_base64("+/8=").unwrap(), [0xfb, 0xff]);
+            assert!(decode_base64("").unwrap().is_empty());
+            assert!(decode_base64("TW u").is_none());
+        }
+
+        #[test]
+        fn data_uris_decode_without_the_resolver() {
+            let resolver = MockResolver::default();
+            let uri = "data:application/octet-stream;base64,TWFu";
+            assert_eq!(read_uri(uri, &resolver).unwrap(), b"Man");
+            assert!(resolver.requests.borrow().is_empty());
+
+            for uri in ["data:text/plain,Man", "data:;base64,TW!u"] {
+                assert!(matches!(read_uri(uri, &resolver), Err(LoadError::Uri(..))));
+            }
+        }
+
+        #[test]
+        fn file_uris_are_percent_decoded() {
+            assert_eq!(percent_decode("my%20file%2Fa.bin"), "my file/a.bin");
+            // Escapes that do not decode stay as written.
+            assert_eq!(percent_decode("100%"), "100%");
+            assert_eq!(percent_decode("%zz%4"), "%zz%4");
+
+            let dir = std::env::temp_dir().join(format!("gltf-loader-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("my file.bin"), b"bytes").unwrap();
+            let resolver = FileResolver::for_file(&dir.join("scene.gltf"));
+            let read = read_uri("my%20file.bin", &resolver);
+            std::fs::remove_dir_all(&dir).unwrap();
+            assert_eq!(read.unwrap(), b"bytes");
+            assert!(FileResolver { base: dir }
+                .resolve("https://example.com/a.bin")
+                .is_err());
+        }
+
+        #[test]
+        fn external_uris_go_through_the_resolver_as_written() {
+            let mut resolver = MockResolver::default();
+            resolver
+                .files
+                .insert("tri%20angle.bin".to_string(), triangle_bin());
+            let pixel = decode_base64(PIXEL.split_once(',').unwrap().1).unwrap();
+            resolver.files.insert("white.png".to_string(), pixel);
+
+            let buffer = r#"{ "byteLength": 42, "uri": "tri%20angle.bin" }"#;
+            let images = format!(
+                r#""images": [{{ "uri": "white.png" }}, {{ "uri": "{}" }}], "nodes""#,
+                PIXEL
+            );
+            let json = triangle_json(buffer).replace(r#""nodes""#, &images);
+            let scene = load_slice(json.as_bytes(), &resolver, &ImportSettings::default()).unwrap();
+            assert_triangle(&scene);
+            assert_eq!(scene.images.len(), 2);
+            assert_eq!(scene.images[0].pixels, [255; 4]);
+            assert_eq!(
+                *resolver.requests.borrow(),
+                ["tri%20angle.bin", "white.png"]
+            );
+
+            let missing = triangle_json(r#"{ "byteLength": 42, "uri": "missing.bin" }"#);
+            match load_slice(missing.as_bytes(), &resolver, &ImportSettings::default()) {
+                Err(LoadError::Uri(uri, err)) => {
+                    assert_eq!(uri, "missing.bin");
+                    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+                }
+                other => panic!("expected a URI error, got {:?}", other.map(|_| ())),
+            }
+        }
+
+        #[test]
+        fn glb_buffers_read_the_binary_chunk() {
+            let json = triangle_json(r#"{ "byteLength": 42 }"#);
+            let scene = load_bytes(&glb(&json, Some(&triangle_bin()))).unwrap();
+            assert_triangle(&scene);
+
+            let result = load_bytes(&glb(&json, None));
+            assert!(matches!(result, Err(LoadError::MissingBinChunk)));
+        }
+
+        #[test]
+        fn buffers_must_hold_their_byte_length() {
+            let json = triangle_json(r#"{ "byteLength": 48 }"#);
+            let result = load_bytes(&glb(&json, Some(&triangle_bin())));
+            assert!(matches!(result, Err(LoadError::BufferLength(0))));
+
+            // Buffers that hold their length are zero padded to the 4-byte aligned end,
+            // which accessors may read up to.
+            let json = triangle_json(r#"{ "byteLength": 42 }"#);
+            let doc = gltf::Gltf::from_slice(json.as_bytes()).unwrap().document;
+            let raw = raw_json(json.as_bytes()).unwrap();
+            let buffers = load_buffers(&doc, &raw, Some(triangle_bin()), &no_files).unwrap();
+            assert_eq!(buffers[0].len(), 44);
+            assert_eq!(buffers[0][42..], [0, 0]);
+        }
+
+        #[test]
+        fn materials_read_texture_transforms_emissive_strength_and_unlit() {
+            let json = format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "extensionsUsed": [
+                        "KHR_materials_emissive_strength",
+                        "KHR_materials_unlit",
+                        "KHR_texture_transform"
+                    ],
+                    "images": [{{ "uri": "{}" }}],
+                    "textures": [{{ "source": 0 }}],
+                    "materials": [
+                        {{
+                            "pbrMetallicRoughness": {{
+                                "baseColorTexture": {{
+                                    "index": 0,
+                                    "extensions": {{
+                                        "KHR_texture_transform": {{
+                                            "offset": [0.5, 0.25],
+                                            "rotation": 0.5,
+                                            "scale": [2, 4],
+                                            "texCoord": 1
+                                        }}
+                                    }}
+                                }}
+                            }},
+                            "emissiveFactor": [1, 0.5, 0],
+                            "extensions": {{
+                                "KHR_materials_emissive_strength": {{ "emissiveStrength": 4 }}
+                            }}
+                        }},
+                        {{
+                            "emissiveTexture": {{ "index": 0, "texCoord": 1 }},
+                            "extensions": {{ "KHR_materials_unlit": {{}} }}
+                        }}
+                    ]
+                }}"#,
+                PIXEL
+            );
+            let scene = load_json(&json).unwrap();
+
+            let lit = &scene.materials[0];
+            let slot = lit.base_color_texture.unwrap();
+            assert_eq!((slot.image, slot.tex_coord, slot.uv_set()), (0, 0, 1));
+            let transform = TextureTransform {
+                offset: [0.5, 0.25],
+                rotation: 0.5,
+                scale: [2.0, 4.0],
+                tex_coord: Some(1),
+            };
+            assert_eq!(slot.transform, transform);
+            assert_eq!(lit.emissive_factor, [1.0, 0.5, 0.0]);
+            assert_eq!(lit.emissive_strength, 4.0);
+            assert!(!lit.unlit);
+
+            // Without the extensions the defaults hold, and texCoord alone picks the set.
+            let unlit = &scene.materials[1];
+            let slot = unlit.emissive_texture.unwrap();
+            assert_eq!((slot.tex_coord, slot.uv_set()), (1, 1));
+            assert_eq!(slot.transform, TextureTransform::default());
+            assert_eq!(unlit.emissive_strength, 1.0);
+            assert!(unlit.unlit);
+        }
+
+        #[test]
+        fn lights_come_from_the_default_scene_in_world_space() {
+            let json = r#"{
+                "asset": { "version": "2.0" },
+                "extensionsUsed": ["KHR_lights_punctual"],
+                "extensions": {
+                    "KHR_lights_punctual": {
+                        "lights": [
+                            { "type": "point", "intensity": 2 },
+                            {
+                                "type": "spot",
+                                "color": [1, 0.5, 0.25],
+                                "intensity": 40,
+                                "range": 10,
+                                "spot": { "innerConeAngle": 0.1, "outerConeAngle": 0.5 }
+                            }
+                        ]
+                    }
+                },
+                "nodes": [
+                    { "extensions": { "KHR_lights_punctual": { "light": 0 } } },
+                    { "translation": [1, 2, 3], "children": [2] },
+                    {
+                        "rotation": [0, 0.70710678, 0, 0.70710678],
+                        "extensions": { "KHR_lights_punctual": { "light": 1 } }
+                    }
+                ],
+                "scenes": [{ "nodes": [0] }, { "nodes": [1] }],
+                "scene": 1
+            }"#;
+            let scene = load_json(json).unwrap();
+            assert_eq!(scene.lights.len(), 1);
+            let spot = &scene.lights[0];
+            let cone = LightKind::Spot {
+                inner_cone_angle: 0.1,
+                outer_cone_angle: 0.5,
+            };
+            assert_eq!(spot.kind, cone);
+            assert_eq!((spot.color, spot.intensity), ([1.0, 0.5, 0.25], 40.0));
+            assert_eq!(spot.range, Some(10.0));
+            assert_eq!(spot.position, [1.0, 2.0, 3.0]);
+            // A quarter turn about +y points the node's -z axis down -x.
+            let expected = [-1.0, 0.0, 0.0];
+            assert!((0..3).all(|i| (spot.direction[i] - expected[i]).abs() < 1e-5));
+
+            let first = load_json(&json.replace(r#""scene": 1"#, r#""scene": 0"#)).unwrap();
+            assert_eq!(first.lights.len(), 1);
+            assert!(matches!(first.lights[0].kind, LightKind::Point));
+            assert_eq!(first.lights[0].range, None);
+        }
+
+        #[test]
+        fn files_without_lights_get_the_key_light() {
+            let json =
+                r#"{ "asset": { "version": "2.0" }, "nodes": [{}], "scenes": [{ "nodes": [0] }] }"#;
+            let scene = load_json(json).unwrap();
+            assert_eq!(scene.lights.len(), 1);
+            assert_eq!(scene.lights[0].name.as_deref(), Some("default key light"));
+        }
+
+        #[test]
+        fn cameras_keep_their_projection_and_world_transform() {
+            let json = r#"{
+                "asset": { "version": "2.0" },
+                "cameras": [
+                    {
+                        "name": "main",
+                        "type": "perspective",
+                        "perspective": { "yfov": 0.8, "aspectRatio": 1.5, "znear": 0.1, "zfar": 100 }
+                    },
+                    {
+                        "type": "orthographic",
+                        "orthographic": { "xmag": 2, "ymag": 1, "znear": 0.01, "zfar": 50 }
+                    },
+                    { "type": "perspective", "perspective": { "yfov": 1, "znear": 0.5 } }
+                ],
+                "nodes": [
+                    { "translation": [1, 2, 3], "children": [1] },
+                    { "rotation": [0, 0.70710678, 0, 0.70710678], "camera": 0 },
+                    { "translation": [0, 0, 5], "camera": 1 },
+                    { "camera": 2 }
+                ],
+                "scenes": [{ "nodes": [0, 2, 3] }]
+            }"#;
+            let scene = load_json(json).unwrap();
+            assert_eq!(scene.cameras.len(), 3);
+
+            let main = &scene.cameras[0];
+            assert_eq!((main.name.as_deref(), main.node), (Some("main"), 1));
+            assert_eq!(
+                main.projection,
+                Projection::Perspective {
+                    yfov: 0.8,
+                    aspect_ratio: Some(1.5),
+                    znear: 0.1,
+                    zfar: Some(100.0),
+                }
+            );
+            assert_eq!(main.position(), glm::vec3(1.0, 2.0, 3.0));
+            // A quarter turn about +y points the camera's -z view axis down -x.
+            let forward = transform_vector(&main.world, glm::vec3(0.0, 0.0, -1.0));
+            assert!(glm::length(forward - glm::vec3(-1.0, 0.0, 0.0)) < 1e-5);
+
+            let ortho = &scene.cameras[1];
+            assert_eq!((ortho.name.as_deref(), ortho.node), (None, 2));
+            assert_eq!(
+                ortho.projection,
+                Projection::Orthographic {
+                    xmag: 2.0,
+                    ymag: 1.0,
+                    znear: 0.01,
+                    zfar: 50.0,
+                }
+            );
+            assert_eq!(ortho.position(), glm::vec3(0.0, 0.0, 5.0));
+
+            assert_eq!(
+                scene.cameras[2].projection,
+                Projection::Perspective {
+                    yfov: 1.0,
+                    aspect_ratio: None,
+                    znear: 0.5,
+                    zfar: None,
+                }
+            );
+        }
+
+        #[test]
+        fn nodes_get_a_weight_per_target_of_any_primitive() {
+            // A primitive without targets comes first.
+            let json = r#"{
+                "asset": { "version": "2.0" },
+                "buffers": [{
+                    "byteLength": 36,
+                    "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+                }],
+                "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+                "accessors": [{
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0, 0, 0],
+                    "max": [1, 1, 0]
+                }],
+                "meshes": [{
+                    "primitives": [
+                        { "attributes": { "POSITION": 0 } },
+                        {
+                            "attributes": { "POSITION": 0 },
+                            "targets": [{ "POSITION": 0 }, { "POSITION": 0 }]
+                        }
+                    ]
+                }],
+                "nodes": [{ "mesh": 0 }],
+                "scenes": [{ "nodes": [0] }]
+            }"#;
+            let scene = load_json(json).unwrap();
+            assert_eq!(scene.nodes[0].weights, [0.0, 0.0]);
+        }
+
+        /// A 4x4 vertex grid of 18 triangles in `mesh.bin`, listed as mesh 0 primitive 0 and
+        /// instanced by two nodes.
+        fn grid() -> (Vec<u8>, Vec<u8>) {
+            let mut bin = Vec::new();
+            for y in 0..4 {
+                for x in 0..4 {
+                    for c in [x as f32, y as f32, 0.0] {
+                        bin.extend_from_slice(&c.to_le_bytes());
+                    }
+                }
+            }
+            for y in 0..3u16 {
+                for x in 0..3u16 {
+                    let v = y * 4 + x;
+                    for i in [v, v + 1, v + 5, v, v + 5, v + 4] {
+                        bin.extend_from_slice(&i.to_le_bytes());
+                    }
+                }
+            }
+            let json = format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "buffers": [{{ "uri": "mesh.bin", "byteLength": {} }}],
+                    "bufferViews": [
+                        {{ "buffer": 0, "byteLength": 192 }},
+                        {{ "buffer": 0, "byteOffset": 192, "byteLength": 108 }}
+                    ],
+                    "accessors": [
+                        {{ "bufferView": 0, "componentType": 5126, "count": 16, "type": "VEC3",
+                           "min": [0, 0, 0], "max": [3, 3, 0] }},
+                        {{ "bufferView": 1, "componentType": 5123, "count": 54, "type": "SCALAR" }}
+                    ],
+                    "meshes": [{{ "primitives": [{{
+                        "attributes": {{ "POSITION": 0 }}, "indices": 1
+                    }}] }}],
+                    "nodes": [{{ "mesh": 0 }}, {{ "mesh": 0 }}],
+                    "scenes": [{{ "nodes": [0, 1] }}],
+                    "scene": 0
+                }}"#,
+                bin.len()
+            );
+            (json.into_bytes(), bin)
+        }
+
+        #[test]
+        fn import_report_lists_each_mesh_primitive_once() {
+            let (json, bin) = grid();
+            let resolver = |_: &str| Ok(bin.clone());
+            let settings = ImportSettings {
+                optimize: true,
+                meshlets: true,
+                ..Default::default()
+            };
+            let scene = load_slice(&json, &resolver, &settings).unwrap();
+            assert_eq!(scene.primitives.len(), 2);
+            assert!(scene.report.skipped.is_empty());
+
+            assert_eq!(scene.report.optimized.len(), 1);
+            let (source, report) = scene.report.optimized[0];
+            assert_eq!(source, (0, 0));
+            assert_eq!((report.vertices_before, report.vertices_after), (16, 16));
+            assert!(report.after.acmr <= report.before.acmr);
+            assert!(report.after.acmr >= 0.5 && report.after.atvr >= 1.0);
+
+            assert_eq!(scene.report.meshlets.len(), 1);
+            let (source, stats) = scene.report.meshlets[0];
+            assert_eq!(source, (0, 0));
+            assert_eq!(
+                (stats.count, stats.vertices, stats.triangles),
+                (1, 16.0, 18.0)
+            );
+
+            let text = scene.report.to_string();
+            assert!(text.starts_with("Optimized primitive 0 of mesh 0: ACMR"));
+            assert!(text.ends_with(
+                "Primitive 0 of mesh 0: 1 meshlets of 16.0 vertices and 18.0 triangles\n"
+            ));
+        }
+
+        /// The 24 vertex, 12 triangle cube the compressed fixtures hold: four corners per
+        /// face, each face's normal, and UVs spanning the face.
+        fn cube() -> MeshData {
+            let faces = [
+                ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+                ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+                ([0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+                ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+                ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+                ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+            ];
+            let mut mesh = MeshData::default();
+            for (f, (n, u, v)) in faces.iter().enumerate() {
+                for (su, sv) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                    let p: [f32; 3] = std::array::from_fn(|i| n[i] + su * u[i] + sv * v[i]);
+                    mesh.positions.push(p);
+                    mesh.normals.push(*n);
+                    mesh.tex_coords0.push([(su + 1.0) / 2.0, (sv + 1.0) / 2.0]);
+                }
+                let base = 4 * f as u32;
+                mesh.indices.extend([0, 1, 2, 0, 2, 3].map(|i| base + i));
+            }
+            mesh
+        }
+
+        fn assert_close<const N: usize>(actual: &[[f32; N]], expected: &[[f32; N]], eps: f32) {
+            assert_eq!(actual.len(), expected.len());
+            for (a, e) in actual.iter().zip(expected) {
+                assert!(
+                    a.iter().zip(e).all(|(a, e)| (a - e).abs() <= eps),
+                    "{:?} != {:?}",
+                    a,
+                    e
+                );
+            }
+        }
+
+        #[test]
+        fn draco_primitives_decode_into_mesh_data() {
+            let bytes = include_bytes!("../tests/fixtures/draco_cube.glb");
+            let scene = load_bytes(bytes).unwrap();
+            assert!(scene.report.skipped.is_empty());
+            assert_eq!(scene.primitives.len(), 1);
+            let mesh = &scene.primitives[0].mesh;
+            let cube = cube();
+            // Positions are quantized to 11 bits, UVs to 10 and normals to 8 bit octahedral
+            // coordinates, which hold the axes exactly.
+            assert_close(&mesh.positions, &cube.positions, 2.0 / 2047.0);
+            assert_close(&mesh.normals, &cube.normals, 1e-6);
+            assert_close(&mesh.tex_coords0, &cube.tex_coords0, 1.0 / 1023.0);
+            assert_eq!(mesh.indices, cube.indices);
+        }
+
+        #[test]
+        fn meshopt_views_decode_from_the_compressed_buffer() {
+            // The uncompressed views point into a fallback buffer without data.
+            let bytes = include_bytes!("../tests/fixtures/meshopt_cube.glb");
+            let scene = load_bytes(bytes).unwrap();
+            assert_eq!(scene.primitives.len(), 1);
+            let mesh = &scene.primitives[0].mesh;
+            let cube = cube();
+            assert_eq!(mesh.positions, cube.positions);
+            assert_eq!(mesh.normals, cube.normals);
+            assert_eq!(mesh.tex_coords0, cube.tex_coords0);
+            assert_eq!(mesh.indices, cube.indices);
+        }
+
+        #[test]
+        fn undecodable_draco_primitives_fall_back_or_are_reported() {
+            // An Edgebreaker header after the triangle, at the 4-byte aligned offset 44.
+            let mut bin = triangle_bin();
+            bin.extend([0, 0]);
+            bin.extend(b"DRACO\x02\x02\x01\x01\x00\x00");
+            let json = |views: bool| {
+                let view = |i: usize| match views {
+                    true => format!(r#""bufferView": {}, "#, i),
+                    false => String::new(),
+                };
+                format!(
+                    r#"{{
+                        "asset": {{ "version": "2.0" }},
+                        "extensionsUsed": ["KHR_draco_mesh_compression"],
+                        "buffers": [{{ "byteLength": 55 }}],
+                        "bufferViews": [
+                            {{ "buffer": 0, "byteLength": 36 }},
+                            {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }},
+                            {{ "buffer": 0, "byteOffset": 44, "byteLength": 11 }}
+                        ],
+                        "accessors": [
+                            {{
+                                {}"componentType": 5126,
+                                "count": 3,
+                                "type": "VEC3",
+                                "min": [0, 0, 0],
+                                "max": [1, 1, 0]
+                            }},
+                            {{ {}"componentType": 5123, "count": 3, "type": "SCALAR" }}
+                        ],
+                        "meshes": [{{
+                            "primitives": [{{
+                                "attributes": {{ "POSITION": 0 }},
+                                "indices": 1,
+                                "extensions": {{
+                                    "KHR_draco_mesh_compression": {{
+                                        "bufferView": 2,
+                                        "attributes": {{ "POSITION": 0 }}
+                                    }}
+                                }}
+                            }}]
+                        }}],
+                        "nodes": [{{ "mesh": 0 }}],
+                        "scenes": [{{ "nodes": [0] }}]
+                    }}"#,
+                    view(0),
+                    view(1)
+                )
+            };
+
+            let scene = load_bytes(&glb(&json(true), Some(&bin))).unwrap();
+            assert_triangle(&scene);
+            assert!(scene.report.skipped.is_empty());
+
+            let scene = load_bytes(&glb(&json(false), Some(&bin))).unwrap();
+            assert!(scene.primitives.is_empty());
+            assert_eq!(
+                scene.report.skipped,
+                [(
+                    (0, 0),
+                    "Draco Edgebreaker connectivity is not supported".to_string()
+                )]
+            );
+        }
+
+        #[test]
+        fn variants_are_named_in_index_order() {
+            let raw = serde_json::json!({
+                "extensions": {
+                    "KHR_materials_variants": {
+                        "variants": [{ "name": "Red" }, {}, { "name": "Blue" }]
+                    }
+                }
+            });
+            assert_eq!(load_variants(&raw), ["Red", "variant 1", "Blue"]);
+            assert!(load_variants(&serde_json::json!({})).is_empty());
+        }
+
+        #[test]
+        fn variant_mappings_skip_missing_materials() {
+            let prim = serde_json::json!({
+                "extensions": {
+                    "KHR_materials_variants": {
+                        "mappings": [
+                            { "material": 1, "variants": [0, 2] },
+                            { "material": 2, "variants": [1] },
+                            { "variants": [3] },
+                            { "material": 0, "variants": [3] }
+                        ]
+                    }
+                }
+            });
+            assert_eq!(load_variant_materials(&prim, 2), [(0, 1), (2, 1), (3, 0)]);
+            assert!(load_variant_materials(&serde_json::json!({}), 2).is_empty());
+        }
+
+        #[test]
+        fn registered_handlers_fill_components() {
+            let bytes = tagged_document(false, r#"{ "label": "door" }"#);
+            let settings = ImportSettings::default();
+            let scene =
+                load_slice_with_extensions(&bytes, &no_files, &registry(), &settings).unwrap();
+            let label = scene.component::<String>(ExtensionTarget::Node(0));
+            assert_eq!(label.map(String::as_str), Some("door"));
+
+            let scene = load_bytes(&bytes).unwrap();
+            assert!(scene.components.is_empty());
+        }
+
+        #[test]
+        fn required_extensions_need_a_handler() {
+            let bytes = tagged_document(true, r#"{ "label": "door" }"#);
+            let err = load_bytes(&bytes).err();
+            assert!(
+                matches!(err, Some(LoadError::UnsupportedExtension(name)) if name == "VENDOR_tag")
+            );
+            let settings = ImportSettings::default();
+            assert!(load_slice_with_extensions(&bytes, &no_files, &registry(), &settings).is_ok());
+        }
+
+        #[test]
+        fn handler_errors_fail_the_load() {
+            let bytes = tagged_document(false, "{}");
+            let settings = ImportSettings::default();
+            let err = load_slice_with_extensions(&bytes, &no_files, &registry(), &settings).err();
+            assert!(matches!(err, Some(LoadError::Extension(name, _)) if name == "VENDOR_tag"));
+        }
+    }
+}
@@ -0,0 +1,171 @@
+pub mod indices {
+
+    //! Index buffer compaction: 16-bit indices for meshes small enough, and splitting of
+    //! larger meshes into parts that are.
+
+    use crate::mesh::mesh::MeshData;
+
+    /// Vertices addressable by 16-bit indices.
+    pub const MAX_16BIT_VERTICES: usize = 1 << 16;
+
+    /// `indices` as 16-bit values, `None` if any of them doesn't fit.
+    pub fn narrow_indices(indices: &[u32]) -> Option<Vec<u16>> {
+        indices.iter().map(|&i| u16::try_from(i).ok()).collect()
+    }
+
+    /// Splits `mesh` into parts of at most `max_vertices` vertices, drawing the same
+    /// triangles in the same order. Triangles go to the current part until one would
+    /// overflow it; vertices shared across parts are duplicated. A mesh that already fits
+    /// is returned whole, otherwise its LODs and meshlets are dropped.
+    pub fn split_mesh(mesh: &MeshData, max_vertices: usize) -> Vec<MeshData> {
+        let max_vertices = max_vertices.max(3);
+        if mesh.vertex_count() <= max_vertices {
+            return vec![mesh.clone()];
+        }
+
+        let mut parts = Vec::new();
+        // Index of every vertex in the current part.
+        let mut local: Vec<Option<u32>> = vec![None; mesh.vertex_count()];
+        let mut sources: Vec<usize> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        for triangle in mesh.indices.chunks_exact(3) {
+            let new_vertices = triangle
+                .iter()
+                .filter(|&&v| local[v as usize].is_none())
+                .count();
+            if sources.len() + new_vertices > max_vertices {
+                for &v in &sources {
+                    local[v] = None;
+                }
+                parts.push(mesh.subset(&sources, std::mem::take(&mut indices)));
+                sources.clear();
+            }
+            for &v in triangle {
+                let index = *local[v as usize].get_or_insert_with(|| {
+                    sources.push(v as usize);
+                    (sources.len() - 1) as u32
+                });
+                indices.push(index);
+            }
+        }
+        if !indices.is_empty() {
+            parts.push(mesh.subset(&sources, indices));
+        }
+        parts
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::lod::lod::Lod;
+        use crate::mesh::mesh::{MorphTarget, SkinVertex, Vertex};
+
+        /// Grid of `quads` by `quads` unit squares with a distinct texture coordinate at
+        /// every vertex.
+        fn grid(quads: u32) -> MeshData {
+            let mut mesh = MeshData::default();
+            for y in 0..=quads {
+                for x in 0..=quads {
+                    mesh.positions.push([x as f32, y as f32, 0.0]);
+                    mesh.normals.push([0.0, 0.0, 1.0]);
+                    mesh.tex_coords0
+                        .push([x as f32 / quads as f32, y as f32 / quads as f32]);
+                }
+            }
+            for y in 0..quads {
+                for x in 0..quads {
+                    let v = y * (quads + 1) + x;
+                    let up = v + quads + 1;
+                    mesh.indices
+                        .extend_from_slice(&[v, v + 1, up + 1, v, up + 1, up]);
+                }
+            }
+            mesh
+        }
+
+        /// The vertex drawn at every index, in draw order.
+        fn corners(mesh: &MeshData) -> Vec<Vertex> {
+            let vertices = mesh.vertices();
+            mesh.indices.iter().map(|&i| vertices[i as usize]).collect()
+        }
+
+        fn skin_corners(mesh: &MeshData) -> Vec<(SkinVertex, [f32; 3])> {
+            let vertices = mesh.skin_vertices().unwrap();
+            let deltas = &mesh.morph_targets[0].positions;
+            mesh.indices
+                .iter()
+                .map(|&i| (vertices[i as usize], deltas[i as usize]))
+                .collect()
+        }
+
+        #[test]
+        fn indices_narrow_only_when_they_fit() {
+            assert_eq!(narrow_indices(&[0, 1, 65535]), Some(vec![0, 1, 65535]));
+            assert_eq!(narrow_indices(&[0, 65536, 1]), None);
+        }
+
+        #[test]
+        fn large_meshes_split_into_16_bit_parts_with_the_same_triangles() {
+            let mesh = grid(260);
+            assert!(mesh.vertex_count() > MAX_16BIT_VERTICES);
+            let parts = split_mesh(&mesh, MAX_16BIT_VERTICES);
+            assert_eq!(parts.len(), 2);
+            let mut drawn = Vec::new();
+            for part in &parts {
+                assert!(part.vertex_count() <= MAX_16BIT_VERTICES);
+                assert!(narrow_indices(&part.indices).is_some());
+                drawn.extend(corners(part));
+            }
+            assert!(drawn == corners(&mesh));
+        }
+
+        #[test]
+        fn every_stream_follows_the_split() {
+            let mut mesh = grid(6);
+            let n = mesh.vertex_count();
+            mesh.joints0 = (0..n).map(|i| [i as u16, 1, 2, 3]).collect();
+            mesh.weights0 = (0..n)
+                .map(|i| [i as f32 / n as f32, 0.5, 0.0, 0.0])
+                .collect();
+            mesh.morph_targets = vec![MorphTarget {
+                positions: (0..n).map(|i| [0.0, i as f32, 0.0]).collect(),
+                ..Default::default()
+            }];
+            // Scatter the triangles so parts share vertices.
+            let triangles: Vec<&[u32]> = mesh.indices.chunks_exact(3).collect();
+            let (even, odd): (Vec<_>, Vec<_>) =
+                triangles.iter().enumerate().partition(|(t, _)| t % 2 == 0);
+            mesh.indices = even
+                .iter()
+                .chain(&odd)
+                .flat_map(|(_, t)| t.to_vec())
+                .collect();
+
+            let parts = split_mesh(&mesh, 10);
+            assert!(parts.len() > 7);
+            let mut drawn = Vec::new();
+            let mut skinned = Vec::new();
+            for part in &parts {
+                assert!(part.vertex_count() <= 10);
+                drawn.extend(corners(part));
+                skinned.extend(skin_corners(part));
+            }
+            assert!(drawn == corners(&mesh));
+            assert!(skinned == skin_corners(&mesh));
+        }
+
+        #[test]
+        fn meshes_that_fit_are_kept_whole() {
+            let mut mesh = grid(2);
+            mesh.lods = vec![Lod {
+                indices: vec![0, 1, 2],
+                error: 0.5,
+            }];
+            let parts = split_mesh(&mesh, mesh.vertex_count());
+            assert_eq!(parts.len(), 1);
+            assert_eq!(parts[0].indices, mesh.indices);
+            assert_eq!(parts[0].lods.len(), 1);
+            assert!(split_mesh(&mesh, 4).iter().all(|part| part.lods.is_empty()));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+pub mod instancing {
+
+    //! Groups scene primitives into instanced draws. Nodes drawing the same glTF mesh
+    //! primitive with the same material share one draw, and EXT_mesh_gpu_instancing
+    //! transforms add instances to it. Mirrored instances get a draw of their own, since
+    //! their triangles wind the other way.
+
+    use std::collections::HashMap;
+
+    use glm::Mat4;
+
+    use crate::math::math::{identity, is_mirrored};
+    use crate::scene::scene::Primitive;
+
+    /// One copy of a batch's mesh.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Instance {
+        /// Index into `Scene::nodes` placing the instance, `None` to keep `rest`.
+        pub node: Option<usize>,
+        /// Node-to-world transform in the rest pose.
+        pub rest: Mat4,
+        /// Applied before the node transform.
+        pub local: Mat4,
+    }
+
+    /// Instances drawing the mesh and material of `Scene::primitives[primitive]`.
+    #[derive(Clone, Debug)]
+    pub struct Batch {
+        pub primitive: usize,
+        /// Every instance has a negative determinant in the rest pose, so front faces are
+        /// clockwise.
+        pub mirrored: bool,
+        pub instances: Vec<Instance>,
+    }
+
+    impl Batch {
+        /// World transform of every instance, using `worlds` for posed nodes.
+        pub fn worlds(&self, worlds: &[Mat4]) -> Vec<Mat4> {
+            self.instances
+                .iter()
+                .map(|instance| {
+                    let world = instance.node.map_or(instance.rest, |node| worlds[node]);
+                    world * instance.local
+                })
+                .collect()
+        }
+    }
+
+    /// glTF mesh primitive, material and `Batch::mirrored` of a shared draw.
+    type BatchKey = ((usize, usize), Option<usize>, bool);
+
+    /// Skinned and morphed primitives depend on per-node state, so they keep their own draw.
+    fn is_shareable(prim: &Primitive) -> bool {
+        prim.source.is_some() && prim.skin.is_none() && prim.mesh.morph_targets.is_empty()
+    }
+
+    /// Batches in order of first appearance; every instance ends up in exactly one, and a
+    /// primitive in one per winding.
+    pub fn batch_primitives(primitives: &[Primitive]) -> Vec<Batch> {
+        let mut batches: Vec<Batch> = Vec::new();
+        let mut shared: HashMap<BatchKey, usize> = HashMap::new();
+        for (i, prim) in primitives.iter().enumerate() {
+            let single = [identity()];
+            let locals = if prim.instances.is_empty() {
+                &single[..]
+            } else {
+                &prim.instances[..]
+            };
+            let (mirrored, front): (Vec<Instance>, Vec<Instance>) = locals
+                .iter()
+                .map(|local| Instance {
+                    node: prim.node,
+                    rest: prim.world,
+                    local: *local,
+                })
+                .partition(|instance| is_mirrored(&(instance.rest * instance.local)));
+
+            for (mirrored, instances) in [(false, front), (true, mirrored)] {
+                if instances.is_empty() {
+                    continue;
+                }
+                let key = prim
+                    .source
+                    .filter(|_| is_shareable(prim))
+                    .map(|source| (source, prim.material, mirrored));
+                match key.and_then(|key| shared.get(&key)) {
+                    Some(&index) => batches[index].instances.extend(instances),
+                    None => {
+                        if let Some(key) = key {
+                            shared.insert(key, batches.len());
+                        }
+                        batches.push(Batch {
+                            primitive: i,
+                            mirrored,
+                            instances,
+                        });
+                    }
+                }
+            }
+        }
+        batches
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::math::math::trs_matrix;
+        use crate::mesh::mesh::MeshData;
+
+        fn scaled(scale: [f32; 3]) -> Mat4 {
+            trs_matrix([0.0; 3], [0.0, 0.0, 0.0, 1.0], scale)
+        }
+
+        fn primitive(node: usize, world: Mat4, instances: Vec<Mat4>) -> Primitive {
+            Primitive {
+                mesh: MeshData::default(),
+                material: None,
+                node: Some(node),
+                skin: None,
+                world,
+                source: Some((0, 0)),
+                instances,
+                variant_materials: Vec::new(),
+            }
+        }
+
+        #[test]
+        fn nodes_share_a_draw_per_winding() {
+            let mirror = scaled([-1.0, 1.0, 1.0]);
+            let primitives = [
+                primitive(0, identity(), Vec::new()),
+                primitive(1, mirror, Vec::new()),
+                primitive(2, scaled([2.0; 3]), Vec::new()),
+                // Mirrored twice winds the original way.
+                primitive(3, scaled([-1.0, -1.0, 1.0]), Vec::new()),
+            ];
+            let batches = batch_primitives(&primitives);
+            assert_eq!(batches.len(), 2);
+            assert_eq!((batches[0].primitive, batches[0].mirrored), (0, false));
+            let nodes: Vec<_> = batches[0].instances.iter().map(|i| i.node).collect();
+            assert_eq!(nodes, [Some(0), Some(2), Some(3)]);
+            assert_eq!((batches[1].primitive, batches[1].mirrored), (1, true));
+            assert_eq!(batches[1].instances.len(), 1);
+        }
+
+        #[test]
+        fn mirrored_gpu_instances_get_their_own_draw() {
+            let mirror = scaled([1.0, 1.0, -1.0]);
+            let locals = vec![identity(), mirror, identity()];
+            // The node mirrors too, so only the second instance keeps its winding.
+            let batches = batch_primitives(&[primitive(0, mirror, locals)]);
+            assert_eq!(batches.len(), 2);
+            assert!(!batches[0].mirrored && batches[1].mirrored);
+            assert_eq!(batches[0].instances[0].local, mirror);
+            assert_eq!(batches[1].instances.len(), 2);
+            assert!(batches.iter().all(|batch| batch.primitive == 0));
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! glTF loading, mesh processing, animation and shading math shared by the viewer, free of
+//! windowing and Direct3D so it builds and tests on any platform.
+
+// Every module file wraps its items in a module of the same name.
+#![allow(clippy::module_inception)]
+
+pub mod accessor;
+pub mod animation;
+pub mod blending;
+pub mod brdf;
+pub mod bvh;
+pub mod camera;
+pub mod cooker;
+pub mod culling;
+pub mod dds;
+pub mod draco;
+pub mod gltf_loader;
+pub mod indices;
+pub mod instancing;
+pub mod lod;
+pub mod math;
+pub mod mesh;
+pub mod mesh_optimizer;
+pub mod meshlets;
+pub mod meshopt;
+pub mod mikktspace;
+pub mod morph;
+pub mod normals;
+pub mod picking;
+pub mod scene;
+pub mod shapes;
+pub mod simplify;
+pub mod skinning;
+pub mod state_machine;
+pub mod texture_compressor;
+pub mod vertex_compression;
@@ -0,0 +1,249 @@
+pub mod lod {
+
+    //! Levels of detail: simplified index lists generated at import, and the choice between
+    //! them by how large their error would appear on screen.
+
+    use glm::{Mat4, Vec3};
+
+    use crate::math::math::{max_axis_scale, transform_point};
+    use crate::mesh::mesh::MeshData;
+    use crate::simplify::simplify::{simplify, AttributeWeights};
+
+    /// A simplified index list and how far, in mesh units, it strays from the original.
+    #[derive(Clone, Debug, Default)]
+    pub struct Lod {
+        pub indices: Vec<u32>,
+        pub error: f32,
+    }
+
+    /// How many levels to generate and how they shrink.
+    #[derive(Clone, Copy, Debug)]
+    pub struct LodSettings {
+        pub count: usize,
+        /// Fraction of the previous level's triangles each level aims for.
+        pub ratio: f32,
+        pub weights: AttributeWeights,
+    }
+
+    impl Default for LodSettings {
+        fn default() -> Self {
+            LodSettings {
+                count: 0,
+                ratio: 0.5,
+                weights: AttributeWeights::default(),
+            }
+        }
+    }
+
+    /// Fills `mesh.lods`, stopping early once simplification stalls. Skinned and morphed
+    /// meshes are left alone, their shape isn't known at import.
+    pub fn generate_lods(mesh: &mut MeshData, settings: LodSettings) {
+        mesh.lods.clear();
+        if mesh.is_skinned() || !mesh.morph_targets.is_empty() {
+            return;
+        }
+        let mut target = mesh.triangle_count() as f32;
+        let mut previous = mesh.triangle_count() * 3;
+        let mut max_error = 0.0f32;
+        for _ in 0..settings.count {
+            target *= settings.ratio;
+            let target_index_count = target as usize * 3;
+            if target_index_count < 3 {
+                break;
+            }
+            let (indices, error) = simplify(mesh, target_index_count, settings.weights);
+            if indices.len() >= previous {
+                break;
+            }
+            previous = indices.len();
+            // Levels generated from the full mesh; keep their errors ascending.
+            max_error = max_error.max(error);
+            mesh.lods.push(Lod {
+                indices,
+                error: max_error,
+            });
+        }
+    }
+
+    /// Center and radius of a sphere around `positions`.
+    pub fn bounding_sphere(positions: &[[f32; 3]]) -> ([f32; 3], f32) {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in positions {
+            for (c, v) in p.iter().enumerate() {
+                min[c] = min[c].min(*v);
+                max[c] = max[c].max(*v);
+            }
+        }
+        if positions.is_empty() {
+            return ([0.0; 3], 0.0);
+        }
+        let center = [0, 1, 2].map(|c| (min[c] + max[c]) * 0.5);
+        let radius = positions
+            .iter()
+            .map(|p| {
+                let d = [0, 1, 2].map(|c| p[c] - center[c]);
+                (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+            })
+            .fold(0.0, f32::max);
+        (center, radius)
+    }
+
+    /// The camera a level is chosen for.
+    pub struct LodView {
+        pub eye: Vec3,
+        pub projection: Mat4,
+        pub viewport_height: f32,
+        /// Largest acceptable error in pixels.
+        pub threshold: f32,
+    }
+
+    impl LodView {
+        /// Pixels covered by `error` mesh units at the nearest point of the bounding
+        /// `sphere` placed by `world`.
+        pub fn projected_error(&self, error: f32, sphere: ([f32; 3], f32), world: &Mat4) -> f32 {
+            let scale = max_axis_scale(world);
+            let (center, radius) = sphere;
+            let center = transform_point(world, glm::vec3(center[0], center[1], center[2]));
+            // Column-major: [1][1] scales y, and perspective projections zero [3][3].
+            let pixels_per_unit = self.projection[1][1] * self.viewport_height * 0.5;
+            let pixels_per_unit = if self.projection[3][3] == 0.0 {
+                let distance = glm::length(center - self.eye) - radius * scale;
+                if distance <= 0.0 {
+                    return f32::MAX;
+                }
+                pixels_per_unit / distance
+            } else {
+                pixels_per_unit
+            };
+            error * scale * pixels_per_unit
+        }
+
+        /// Index into `errors`, which ascend from the full mesh's zero, of the coarsest
+        /// level within the threshold at every one of `worlds`.
+        pub fn select(&self, errors: &[f32], sphere: ([f32; 3], f32), worlds: &[Mat4]) -> usize {
+            errors
+                .iter()
+                .rposition(|&error| {
+                    worlds
+                        .iter()
+                        .all(|world| self.projected_error(error, sphere, world) <= self.threshold)
+                })
+                .unwrap_or(0)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::camera::camera::{orthographic_rh, perspective_rh};
+        use crate::math::math::{identity, trs_matrix};
+
+        /// 1000 pixels per unit at distance one.
+        fn view(eye_z: f32) -> LodView {
+            LodView {
+                eye: glm::vec3(0.0, 0.0, eye_z),
+                projection: perspective_rh(0.5, 1.0, 0.1, None),
+                viewport_height: 1000.0,
+                threshold: 1.0,
+            }
+        }
+
+        /// Rolling terrain of 32x32 quads, curved everywhere so that every collapse costs
+        /// something.
+        fn terrain() -> MeshData {
+            let mut mesh = MeshData::default();
+            for z in 0..33 {
+                for x in 0..33 {
+                    let (x, z) = (x as f32 / 32.0, z as f32 / 32.0);
+                    let y = 0.1 * (6.0 * x).sin() * (5.0 * z).cos();
+                    mesh.positions.push([x, y, z]);
+                    mesh.normals.push([0.0, 1.0, 0.0]);
+                }
+            }
+            for z in 0..32 {
+                for x in 0..32 {
+                    let v = z * 33 + x;
+                    mesh.indices
+                        .extend_from_slice(&[v, v + 33, v + 34, v, v + 34, v + 1]);
+                }
+            }
+            mesh
+        }
+
+        #[test]
+        fn levels_shrink_and_their_errors_ascend() {
+            let mut mesh = terrain();
+            let settings = LodSettings {
+                count: 3,
+                ..Default::default()
+            };
+            generate_lods(&mut mesh, settings);
+            assert_eq!(mesh.lods.len(), 3);
+            let mut previous = (mesh.indices.len(), 0.0);
+            for lod in &mesh.lods {
+                assert!(lod.indices.len() <= previous.0 / 2);
+                assert!(lod.error > previous.1);
+                previous = (lod.indices.len(), lod.error);
+            }
+
+            mesh.joints0 = vec![[0; 4]; mesh.vertex_count()];
+            mesh.weights0 = vec![[1.0, 0.0, 0.0, 0.0]; mesh.vertex_count()];
+            generate_lods(&mut mesh, settings);
+            assert!(mesh.lods.is_empty());
+        }
+
+        #[test]
+        fn bounding_spheres_hold_every_position() {
+            let corners: Vec<[f32; 3]> = (0..8)
+                .map(|i| [0, 1, 2].map(|bit| if i >> bit & 1 == 1 { 1.0 } else { -1.0 }))
+                .collect();
+            let (center, radius) = bounding_sphere(&corners);
+            assert_eq!(center, [0.0; 3]);
+            assert!((radius - 3f32.sqrt()).abs() < 1e-6);
+            assert_eq!(bounding_sphere(&[]), ([0.0; 3], 0.0));
+        }
+
+        #[test]
+        fn errors_shrink_with_distance() {
+            let sphere = ([0.0; 3], 1.0);
+            // The nearest point of the sphere is 9 units away.
+            let pixels = view(10.0).projected_error(0.01, sphere, &identity());
+            assert!((pixels - 10.0 / 9.0).abs() < 1e-4, "{}", pixels);
+            let scaled = trs_matrix([0.0; 3], [0.0, 0.0, 0.0, 1.0], [2.0; 3]);
+            let pixels = view(10.0).projected_error(0.01, sphere, &scaled);
+            assert!((pixels - 20.0 / 8.0).abs() < 1e-4, "{}", pixels);
+            assert_eq!(
+                view(0.5).projected_error(0.01, sphere, &identity()),
+                f32::MAX
+            );
+
+            let orthographic = LodView {
+                projection: orthographic_rh(1.0, 2.0, 0.1, 100.0),
+                ..view(10.0)
+            };
+            for eye_z in [10.0, 50.0] {
+                let pixels = LodView {
+                    eye: glm::vec3(0.0, 0.0, eye_z),
+                    ..orthographic
+                }
+                .projected_error(0.01, sphere, &identity());
+                assert!((pixels - 2.5).abs() < 1e-4, "{}", pixels);
+            }
+        }
+
+        #[test]
+        fn selection_picks_the_coarsest_level_within_the_threshold() {
+            let errors = [0.0, 0.01, 0.1];
+            let sphere = ([0.0; 3], 1.0);
+            let worlds = [identity()];
+            assert_eq!(view(5.0).select(&errors, sphere, &worlds), 0);
+            assert_eq!(view(50.0).select(&errors, sphere, &worlds), 1);
+            assert_eq!(view(200.0).select(&errors, sphere, &worlds), 2);
+            // Every instance has to be far enough.
+            let near = trs_matrix([0.0, 0.0, 150.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3]);
+            assert_eq!(view(200.0).select(&errors, sphere, &[identity(), near]), 1);
+            assert_eq!(view(200.0).select(&[], sphere, &worlds), 0);
+        }
+    }
+}
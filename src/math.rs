@@ -0,0 +1,181 @@
+pub mod math {
+
+    use glm::{GenSquareMat, Mat4, Vec3};
+
+    pub fn identity() -> Mat4 {
+        glm::mat4(
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        )
+    }
+
+    /// Builds a matrix from glTF's column-major `[column][row]` layout.
+    pub fn from_cols_array(t: &[[f32; 4]; 4]) -> Mat4 {
+        glm::mat4(
+            t[0][0], t[0][1], t[0][2], t[0][3], t[1][0], t[1][1], t[1][2], t[1][3], t[2][0],
+            t[2][1], t[2][2], t[2][3], t[3][0], t[3][1], t[3][2], t[3][3],
+        )
+    }
+
+    /// Column-major layout, which is also what HLSL constant buffers expect by default.
+    pub fn to_cols_array(m: &Mat4) -> [[f32; 4]; 4] {
+        let mut out = [[0f32; 4]; 4];
+        for c in 0..4 {
+            for r in 0..4 {
+                out[c][r] = m[c][r];
+            }
+        }
+        out
+    }
+
+    pub fn transpose(m: &Mat4) -> Mat4 {
+        let t = to_cols_array(m);
+        from_cols_array(&[0, 1, 2, 3].map(|c| [0, 1, 2, 3].map(|r| t[r][c])))
+    }
+
+    /// Largest factor by which `m` stretches a length, for scaling radii and distances.
+    pub fn max_axis_scale(m: &Mat4) -> f32 {
+        (0..3)
+            .map(|c| glm::length(glm::vec3(m[c][0], m[c][1], m[c][2])))
+            .fold(0.0, f32::max)
+    }
+
+    /// Inverse transpose, for transforming normals. Singular matrices give the identity.
+    pub fn normal_matrix(m: &Mat4) -> Mat4 {
+        transpose(&m.inverse().unwrap_or_else(identity))
+    }
+
+    /// glTF triangles wind counter-clockwise; a transform with a negative determinant
+    /// mirrors them to clockwise.
+    pub fn is_mirrored(m: &Mat4) -> bool {
+        m.determinant() < 0.0
+    }
+
+    pub fn transform_point(m: &Mat4, p: Vec3) -> Vec3 {
+        let v = *m * glm::vec4(p.x, p.y, p.z, 1.0);
+        glm::vec3(v.x, v.y, v.z)
+    }
+
+    pub fn transform_vector(m: &Mat4, v: Vec3) -> Vec3 {
+        let v = *m * glm::vec4(v.x, v.y, v.z, 0.0);
+        glm::vec3(v.x, v.y, v.z)
+    }
+
+    // Quaternions are `[x, y, z, w]`, as stored by glTF.
+
+    pub fn quat_dot(a: [f32; 4], b: [f32; 4]) -> f32 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2] + a[3] * b[3]
+    }
+
+    pub fn quat_normalize(q: [f32; 4]) -> [f32; 4] {
+        let len = quat_dot(q, q).sqrt();
+        if len < 1e-8 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+        [q[0] / len, q[1] / len, q[2] / len, q[3] / len]
+    }
+
+    pub fn quat_conjugate(q: [f32; 4]) -> [f32; 4] {
+        [-q[0], -q[1], -q[2], q[3]]
+    }
+
+    /// Hamilton product; applying the result rotates by `b` first, then `a`.
+    pub fn quat_mul(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+        [
+            a[3] * b[0] + a[0] * b[3] + a[1] * b[2] - a[2] * b[1],
+            a[3] * b[1] - a[0] * b[2] + a[1] * b[3] + a[2] * b[0],
+            a[3] * b[2] + a[0] * b[1] - a[1] * b[0] + a[2] * b[3],
+            a[3] * b[3] - a[0] * b[0] - a[1] * b[1] - a[2] * b[2],
+        ]
+    }
+
+    /// Spherical interpolation along the shorter arc. Nearly parallel inputs fall back to a
+    /// normalized lerp.
+    pub fn quat_slerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+        let mut b = b;
+        let mut cos_theta = quat_dot(a, b);
+        if cos_theta < 0.0 {
+            b = [-b[0], -b[1], -b[2], -b[3]];
+            cos_theta = -cos_theta;
+        }
+
+        let (wa, wb) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+
+        quat_normalize([
+            a[0] * wa + b[0] * wb,
+            a[1] * wa + b[1] * wb,
+            a[2] * wa + b[2] * wb,
+            a[3] * wa + b[3] * wb,
+        ])
+    }
+
+    // Vectors on raw attribute data are `[f32; 3]`.
+
+    pub fn vec3_add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+    }
+
+    pub fn vec3_sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+    }
+
+    pub fn vec3_scale(v: [f32; 3], s: f32) -> [f32; 3] {
+        [v[0] * s, v[1] * s, v[2] * s]
+    }
+
+    pub fn vec3_dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+
+    pub fn vec3_cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    }
+
+    pub fn vec3_length(v: [f32; 3]) -> f32 {
+        vec3_dot(v, v).sqrt()
+    }
+
+    /// Zero vectors are returned unchanged.
+    pub fn vec3_normalize(v: [f32; 3]) -> [f32; 3] {
+        let len = vec3_length(v);
+        if len == 0.0 {
+            return v;
+        }
+        vec3_scale(v, 1.0 / len)
+    }
+
+    /// Translation * rotation * scale, the glTF node transform order.
+    pub fn trs_matrix(t: [f32; 3], r: [f32; 4], s: [f32; 3]) -> Mat4 {
+        let [x, y, z, w] = r;
+        glm::mat4(
+            (1.0 - 2.0 * (y * y + z * z)) * s[0],
+            2.0 * (x * y + z * w) * s[0],
+            2.0 * (x * z - y * w) * s[0],
+            0.0,
+            2.0 * (x * y - z * w) * s[1],
+            (1.0 - 2.0 * (x * x + z * z)) * s[1],
+            2.0 * (y * z + x * w) * s[1],
+            0.0,
+            2.0 * (x * z + y * w) * s[2],
+            2.0 * (y * z - x * w) * s[2],
+            (1.0 - 2.0 * (x * x + y * y)) * s[2],
+            0.0,
+            t[0],
+            t[1],
+            t[2],
+            1.0,
+        )
+    }
+}
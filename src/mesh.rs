@@ -0,0 +1,594 @@
+pub mod mesh {
+
+    use gltf::accessor::DataType;
+
+    use crate::accessor::accessor::Accessor;
+    use crate::lod::lod::Lod;
+    use crate::meshlets::meshlets::Meshlet;
+
+    /// Interleaved layout uploaded to the vertex buffer. Must match the input layout in
+    /// `DeviceResources::bind_to_wnd`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    #[repr(C)]
+    pub struct Vertex {
+        pub position: [f32; 3],
+        pub normal: [f32; 3],
+        pub tex_coord0: [f32; 2],
+        pub tex_coord1: [f32; 2],
+        /// Zero for meshes without tangents, which the pixel shader then doesn't perturb.
+        pub tangent: [f32; 4],
+    }
+
+    /// Second vertex stream of skinned primitives. Must match the skinned input layout in
+    /// `DeviceResources::bind_to_wnd`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    #[repr(C)]
+    pub struct SkinVertex {
+        pub joints0: [u16; 4],
+        pub joints1: [u16; 4],
+        pub weights0: [f32; 4],
+        pub weights1: [f32; 4],
+    }
+
+    /// How primitives are laid out in their vertex buffers.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct VertexSettings {
+        /// Expand quantized attributes to floats instead of uploading them as stored.
+        pub dequantize: bool,
+        /// Split primitives with more vertices than 16-bit indices address, so every index
+        /// buffer can be 16-bit. Split primitives lose their LODs.
+        pub split_16bit: bool,
+        /// Upload compact vertices, see `vertex_compression`. Takes precedence over
+        /// `dequantize`.
+        pub compress: bool,
+    }
+
+    /// Storage of one attribute in a packed vertex. Three component integers are padded to
+    /// four, which the shader ignores.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum ElementFormat {
+        Half2,
+        Float2,
+        Float3,
+        Float4,
+        Snorm8x2,
+        Unorm8x2,
+        Snorm8x4,
+        Unorm8x4,
+        Snorm16x2,
+        Unorm16x2,
+        Snorm16x4,
+        Unorm16x4,
+    }
+
+    impl ElementFormat {
+        /// Format a quantized accessor keeps on the GPU. `None` for floats and integers
+        /// that aren't normalized, which the shader reads as floats.
+        fn quantized(accessor: &Accessor) -> Option<Self> {
+            if !accessor.is_quantized() {
+                return None;
+            }
+            let wide = accessor.components > 2;
+            Some(match (accessor.data_type, wide) {
+                (DataType::I8, false) => ElementFormat::Snorm8x2,
+                (DataType::U8, false) => ElementFormat::Unorm8x2,
+                (DataType::I8, true) => ElementFormat::Snorm8x4,
+                (DataType::U8, true) => ElementFormat::Unorm8x4,
+                (DataType::I16, false) => ElementFormat::Snorm16x2,
+                (DataType::U16, false) => ElementFormat::Unorm16x2,
+                (DataType::I16, true) => ElementFormat::Snorm16x4,
+                (DataType::U16, true) => ElementFormat::Unorm16x4,
+                _ => return None,
+            })
+        }
+
+        pub fn size(&self) -> usize {
+            match self {
+                ElementFormat::Half2 => 4,
+                ElementFormat::Float2 => 8,
+                ElementFormat::Float3 => 12,
+                ElementFormat::Float4 => 16,
+                ElementFormat::Snorm8x2 | ElementFormat::Unorm8x2 => 2,
+                ElementFormat::Snorm8x4 | ElementFormat::Unorm8x4 => 4,
+                ElementFormat::Snorm16x2 | ElementFormat::Unorm16x2 => 4,
+                ElementFormat::Snorm16x4 | ElementFormat::Unorm16x4 => 8,
+            }
+        }
+    }
+
+    /// Formats of the `Vertex` attributes in a packed vertex buffer. Attributes are stored
+    /// in `Vertex` order, each starting on a 4-byte boundary.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct VertexLayout {
+        pub position: ElementFormat,
+        pub normal: ElementFormat,
+        pub tex_coord0: ElementFormat,
+        pub tex_coord1: ElementFormat,
+        pub tangent: ElementFormat,
+    }
+
+    impl VertexLayout {
+        pub fn elements(&self) -> [ElementFormat; 5] {
+            [
+                self.position,
+                self.normal,
+                self.tex_coord0,
+                self.tex_coord1,
+                self.tangent,
+            ]
+        }
+
+        /// glTF normals have three components, so a two component normal is octahedral.
+        pub fn octahedral_normal(&self) -> bool {
+            self.normal == ElementFormat::Snorm16x2
+        }
+
+        /// Byte offset of every element, then the stride.
+        pub fn offsets(&self) -> ([u32; 5], u32) {
+            let mut offsets = [0u32; 5];
+            let mut offset = 0;
+            for (o, element) in offsets.iter_mut().zip(self.elements()) {
+                *o = offset as u32;
+                offset += (element.size() + 3) & !3;
+            }
+            (offsets, offset as u32)
+        }
+    }
+
+    /// Interleaved vertices in a `VertexLayout`.
+    #[derive(Clone, Debug)]
+    pub struct PackedVertices {
+        pub layout: VertexLayout,
+        pub bytes: Vec<u8>,
+        /// Stored positions are scaled by `position_scale`, then offset by
+        /// `position_offset`, before anything else in the vertex shader.
+        pub position_offset: [f32; 3],
+        pub position_scale: [f32; 3],
+    }
+
+    /// Normalized integer attributes as stored in the file, kept next to their dequantized
+    /// streams so they can be uploaded without conversion.
+    #[derive(Clone, Debug, Default)]
+    pub struct QuantizedAttributes {
+        pub position: Option<Accessor>,
+        pub normal: Option<Accessor>,
+        pub tex_coord0: Option<Accessor>,
+        pub tex_coord1: Option<Accessor>,
+    }
+
+    impl QuantizedAttributes {
+        pub fn is_empty(&self) -> bool {
+            self.position.is_none()
+                && self.normal.is_none()
+                && self.tex_coord0.is_none()
+                && self.tex_coord1.is_none()
+        }
+    }
+
+    /// Per-vertex deltas of one morph target. Streams are empty when the target leaves the
+    /// attribute alone.
+    #[derive(Clone, Debug, Default)]
+    pub struct MorphTarget {
+        pub positions: Vec<[f32; 3]>,
+        pub normals: Vec<[f32; 3]>,
+        pub tangents: Vec<[f32; 3]>,
+    }
+
+    /// CPU copy of a primitive's geometry. Attribute streams are either empty or as long
+    /// as `positions`.
+    #[derive(Clone, Debug, Default)]
+    pub struct MeshData {
+        pub positions: Vec<[f32; 3]>,
+        pub normals: Vec<[f32; 3]>,
+        /// xyz is the tangent, w the handedness of the bitangent.
+        pub tangents: Vec<[f32; 4]>,
+        pub tex_coords0: Vec<[f32; 2]>,
+        pub tex_coords1: Vec<[f32; 2]>,
+        /// JOINTS_0 and WEIGHTS_0, indices into the skin's joint list.
+        pub joints0: Vec<[u16; 4]>,
+        pub weights0: Vec<[f32; 4]>,
+        /// JOINTS_1 and WEIGHTS_1 for vertices with up to eight influences.
+        pub joints1: Vec<[u16; 4]>,
+        pub weights1: Vec<[f32; 4]>,
+        pub morph_targets: Vec<MorphTarget>,
+        pub indices: Vec<u32>,
+        pub quantized: QuantizedAttributes,
+        /// Simplified versions of `indices` over the same vertices, coarsest last. Passes
+        /// that renumber vertices run before these are generated.
+        pub lods: Vec<Lod>,
+        /// Clusters of `indices` for culling, when requested at import.
+        pub meshlets: Vec<Meshlet>,
+    }
+
+    impl MeshData {
+        pub fn vertex_count(&self) -> usize {
+            self.positions.len()
+        }
+
+        pub fn triangle_count(&self) -> usize {
+            self.indices.len() / 3
+        }
+
+        /// Interleaves the attribute streams, filling missing ones with zeros.
+        pub fn vertices(&self) -> Vec<Vertex> {
+            (0..self.vertex_count())
+                .map(|i| Vertex {
+                    position: self.positions[i],
+                    normal: self.normals.get(i).copied().unwrap_or_default(),
+                    tex_coord0: self.tex_coords0.get(i).copied().unwrap_or_default(),
+                    tex_coord1: self.tex_coords1.get(i).copied().unwrap_or_default(),
+                    tangent: self.tangents.get(i).copied().unwrap_or_default(),
+                })
+                .collect()
+        }
+
+        /// Interleaves quantized attributes in their stored formats and the rest as floats.
+        /// `None` when nothing is quantized and `vertices` fits.
+        pub fn packed_vertices(&self) -> Option<PackedVertices> {
+            if self.quantized.is_empty() {
+                return None;
+            }
+
+            let q = &self.quantized;
+            let pick = |accessor: &Option<Accessor>, float| {
+                let format = accessor.as_ref().and_then(ElementFormat::quantized);
+                format.unwrap_or(float)
+            };
+            let layout = VertexLayout {
+                position: pick(&q.position, ElementFormat::Float3),
+                normal: pick(&q.normal, ElementFormat::Float3),
+                tex_coord0: pick(&q.tex_coord0, ElementFormat::Float2),
+                tex_coord1: pick(&q.tex_coord1, ElementFormat::Float2),
+                tangent: ElementFormat::Float4,
+            };
+            let (offsets, stride) = layout.offsets();
+
+            let floats = self.vertices();
+            let mut bytes = vec![0u8; stride as usize * self.vertex_count()];
+            let vertices = bytes.chunks_exact_mut(stride as usize).zip(&floats);
+            for (i, (vertex, float)) in vertices.enumerate() {
+                let attributes: [(&Option<Accessor>, &[f32]); 5] = [
+                    (&q.position, &float.position),
+                    (&q.normal, &float.normal),
+                    (&q.tex_coord0, &float.tex_coord0),
+                    (&q.tex_coord1, &float.tex_coord1),
+                    (&None, &float.tangent),
+                ];
+                for ((accessor, values), (&offset, element)) in attributes
+                    .into_iter()
+                    .zip(offsets.iter().zip(layout.elements()))
+                {
+                    let out = &mut vertex[offset as usize..];
+                    match (element, accessor) {
+                        (ElementFormat::Float2 | ElementFormat::Float3, _) | (_, None) => {
+                            for (o, v) in out.chunks_exact_mut(4).zip(values) {
+                                o.copy_from_slice(&v.to_le_bytes());
+                            }
+                        }
+                        (_, Some(accessor)) => {
+                            let stored = accessor.element(i);
+                            out[..stored.len()].copy_from_slice(stored);
+                        }
+                    }
+                }
+            }
+            Some(PackedVertices {
+                layout,
+                bytes,
+                position_offset: [0.0; 3],
+                position_scale: [1.0; 3],
+            })
+        }
+
+        /// Rebuilds every vertex stream so that vertex `i` is the old vertex `sources[i]`.
+        /// Indices are left to the caller.
+        pub fn remap_vertices(&mut self, sources: &[usize]) {
+            let indices = std::mem::take(&mut self.indices);
+            let lods = std::mem::take(&mut self.lods);
+            let meshlets = std::mem::take(&mut self.meshlets);
+            *self = MeshData {
+                lods,
+                meshlets,
+                ..self.subset(sources, indices)
+            };
+        }
+
+        /// A mesh whose vertex `i` is vertex `sources[i]` of this one, drawn by `indices`.
+        /// LODs and meshlets, which index this mesh's vertices, are left out.
+        pub fn subset(&self, sources: &[usize], indices: Vec<u32>) -> MeshData {
+            fn gather<T: Copy>(stream: &[T], sources: &[usize]) -> Vec<T> {
+                if stream.is_empty() {
+                    Vec::new()
+                } else {
+                    sources.iter().map(|&i| stream[i]).collect()
+                }
+            }
+
+            let select = |stream: &Option<Accessor>| {
+                stream.as_ref().map(|accessor| accessor.select(sources))
+            };
+            MeshData {
+                positions: gather(&self.positions, sources),
+                normals: gather(&self.normals, sources),
+                tangents: gather(&self.tangents, sources),
+                tex_coords0: gather(&self.tex_coords0, sources),
+                tex_coords1: gather(&self.tex_coords1, sources),
+                joints0: gather(&self.joints0, sources),
+                weights0: gather(&self.weights0, sources),
+                joints1: gather(&self.joints1, sources),
+                weights1: gather(&self.weights1, sources),
+                morph_targets: self
+                    .morph_targets
+                    .iter()
+                    .map(|target| MorphTarget {
+                        positions: gather(&target.positions, sources),
+                        normals: gather(&target.normals, sources),
+                        tangents: gather(&target.tangents, sources),
+                    })
+                    .collect(),
+                indices,
+                quantized: QuantizedAttributes {
+                    position: select(&self.quantized.position),
+                    normal: select(&self.quantized.normal),
+                    tex_coord0: select(&self.quantized.tex_coord0),
+                    tex_coord1: select(&self.quantized.tex_coord1),
+                },
+                lods: Vec::new(),
+                meshlets: Vec::new(),
+            }
+        }
+
+        /// Turns one value per index into a vertex stream, duplicating vertices whose
+        /// corners disagree and pointing their indices at the copies.
+        pub fn split_corners<T: Copy + PartialEq>(&mut self, corner_values: &[T]) -> Vec<T> {
+            let vertex_count = self.vertex_count();
+            let mut values: Vec<Option<T>> = vec![None; vertex_count];
+            let mut sources: Vec<usize> = (0..vertex_count).collect();
+            // Copies made so far for every original vertex.
+            let mut copies: Vec<Vec<u32>> = vec![Vec::new(); vertex_count];
+            for (index, &value) in self.indices.iter_mut().zip(corner_values) {
+                let vertex = *index as usize;
+                match values[vertex] {
+                    None => values[vertex] = Some(value),
+                    Some(v) if v == value => {}
+                    Some(_) => {
+                        let existing = copies[vertex]
+                            .iter()
+                            .copied()
+                            .find(|&copy| values[copy as usize] == Some(value));
+                        *index = match existing {
+                            Some(copy) => copy,
+                            None => {
+                                let copy = sources.len() as u32;
+                                sources.push(vertex);
+                                values.push(Some(value));
+                                copies[vertex].push(copy);
+                                copy
+                            }
+                        };
+                    }
+                }
+            }
+
+            if sources.len() > vertex_count {
+                self.remap_vertices(&sources);
+            }
+            let fallback = corner_values.first().copied();
+            values
+                .into_iter()
+                .map(|v| v.or(fallback))
+                .collect::<Option<Vec<T>>>()
+                .unwrap_or_default()
+        }
+
+        pub fn is_skinned(&self) -> bool {
+            !self.joints0.is_empty() && !self.weights0.is_empty()
+        }
+
+        /// Joint and weight pairs of vertex `i`, including zero weights.
+        pub fn influences(&self, i: usize) -> impl Iterator<Item = (usize, f32)> + '_ {
+            let sets = [
+                (self.joints0.get(i), self.weights0.get(i)),
+                (self.joints1.get(i), self.weights1.get(i)),
+            ];
+            sets.into_iter()
+                .filter_map(|(joints, weights)| Some((joints?, weights?)))
+                .flat_map(|(joints, weights)| {
+                    joints
+                        .iter()
+                        .zip(weights)
+                        .map(|(&joint, &weight)| (joint as usize, weight))
+                })
+        }
+
+        /// Interleaves the joint and weight streams, `None` for meshes without a skin.
+        pub fn skin_vertices(&self) -> Option<Vec<SkinVertex>> {
+            if !self.is_skinned() {
+                return None;
+            }
+            let vertices = (0..self.vertex_count())
+                .map(|i| SkinVertex {
+                    joints0: self.joints0.get(i).copied().unwrap_or_default(),
+                    joints1: self.joints1.get(i).copied().unwrap_or_default(),
+                    weights0: self.weights0.get(i).copied().unwrap_or_default(),
+                    weights1: self.weights1.get(i).copied().unwrap_or_default(),
+                })
+                .collect();
+            Some(vertices)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::accessor::accessor::component_size;
+
+        fn accessor(
+            data_type: DataType,
+            components: usize,
+            normalized: bool,
+            bytes: &[u8],
+        ) -> Accessor {
+            Accessor {
+                data_type,
+                components,
+                normalized,
+                count: bytes.len() / (components * component_size(data_type)),
+                bytes: bytes.to_vec(),
+            }
+        }
+
+        fn float_bytes(values: &[f32]) -> Vec<u8> {
+            values.iter().flat_map(|v| v.to_le_bytes()).collect()
+        }
+
+        #[test]
+        fn quantized_formats_follow_component_type_and_width() {
+            use ElementFormat::*;
+            let cases = [
+                (DataType::I8, 2, Snorm8x2),
+                (DataType::I8, 3, Snorm8x4),
+                (DataType::U8, 2, Unorm8x2),
+                (DataType::U8, 4, Unorm8x4),
+                (DataType::I16, 2, Snorm16x2),
+                (DataType::I16, 3, Snorm16x4),
+                (DataType::U16, 2, Unorm16x2),
+                (DataType::U16, 4, Unorm16x4),
+            ];
+            for (data_type, components, format) in cases {
+                let bytes = vec![0; components * component_size(data_type)];
+                let normalized = accessor(data_type, components, true, &bytes);
+                assert_eq!(ElementFormat::quantized(&normalized), Some(format));
+                let integers = accessor(data_type, components, false, &bytes);
+                assert_eq!(ElementFormat::quantized(&integers), None);
+            }
+            for data_type in [DataType::U32, DataType::F32] {
+                for normalized in [false, true] {
+                    let wide = accessor(data_type, 3, normalized, &[0; 12]);
+                    assert_eq!(ElementFormat::quantized(&wide), None);
+                }
+            }
+        }
+
+        #[test]
+        fn packed_vertices_keep_normalized_integers_as_stored() {
+            let position = accessor(
+                DataType::I16,
+                3,
+                true,
+                &[1, 0, 2, 0, 3, 0, 0xff, 0x7f, 0, 0x80, 1, 0x80],
+            );
+            let normal = accessor(DataType::I8, 3, true, &[0, 0, 127, 0x81, 0, 0]);
+            let tex_coord0 = accessor(DataType::U16, 2, true, &[0, 0, 0xff, 0xff, 0, 0x80, 0, 0]);
+            let mesh = MeshData {
+                positions: position.to_f32(),
+                normals: normal.to_f32(),
+                tex_coords0: tex_coord0.to_f32(),
+                quantized: QuantizedAttributes {
+                    position: Some(position),
+                    normal: Some(normal),
+                    tex_coord0: Some(tex_coord0),
+                    tex_coord1: None,
+                },
+                ..Default::default()
+            };
+
+            let packed = mesh.packed_vertices().unwrap();
+            let layout = VertexLayout {
+                position: ElementFormat::Snorm16x4,
+                normal: ElementFormat::Snorm8x4,
+                tex_coord0: ElementFormat::Unorm16x2,
+                tex_coord1: ElementFormat::Float2,
+                tangent: ElementFormat::Float4,
+            };
+            assert_eq!(packed.layout, layout);
+            assert_eq!(layout.offsets(), ([0, 8, 12, 16, 24], 40));
+
+            // Three component integers get a zero fourth component; the missing second UV
+            // set and tangent are zeros.
+            let mut expected = Vec::new();
+            expected.extend([1, 0, 2, 0, 3, 0, 0, 0, 0, 0, 127, 0, 0, 0, 0xff, 0xff]);
+            expected.extend([0; 24]);
+            expected.extend([
+                0xff, 0x7f, 0, 0x80, 1, 0x80, 0, 0, 0x81, 0, 0, 0, 0, 0x80, 0, 0,
+            ]);
+            expected.extend([0; 24]);
+            assert_eq!(packed.bytes, expected);
+        }
+
+        #[test]
+        fn unquantized_attributes_fall_back_to_floats() {
+            let mut mesh = MeshData {
+                positions: vec![[1.0, 2.0, 3.0]],
+                normals: vec![[0.0, 0.0, 1.0]],
+                tex_coords1: vec![[0.25, 0.75]],
+                ..Default::default()
+            };
+            // Nothing quantized: `vertices` is uploaded instead.
+            assert!(mesh.packed_vertices().is_none());
+
+            let tex_coord0 = accessor(DataType::U8, 2, true, &[0, 255]);
+            mesh.tex_coords0 = tex_coord0.to_f32();
+            mesh.quantized.tex_coord0 = Some(tex_coord0);
+            let packed = mesh.packed_vertices().unwrap();
+            assert_eq!(packed.layout.position, ElementFormat::Float3);
+            assert_eq!(packed.layout.normal, ElementFormat::Float3);
+            assert_eq!(packed.layout.tex_coord1, ElementFormat::Float2);
+            let mut expected = float_bytes(&[1.0, 2.0, 3.0, 0.0, 0.0, 1.0]);
+            expected.extend([0, 255, 0, 0]);
+            expected.extend(float_bytes(&[0.25, 0.75, 0.0, 0.0, 0.0, 0.0]));
+            assert_eq!(packed.bytes, expected);
+        }
+
+        #[test]
+        fn every_component_type_packs_by_normalization() {
+            let types = [
+                DataType::I8,
+                DataType::U8,
+                DataType::I16,
+                DataType::U16,
+                DataType::U32,
+                DataType::F32,
+            ];
+            for data_type in types {
+                for normalized in [false, true] {
+                    // The components 1 and 2 in the accessor's own storage.
+                    let bytes: Vec<u8> = match data_type {
+                        DataType::I8 | DataType::U8 => vec![1, 2],
+                        DataType::I16 | DataType::U16 => vec![1, 0, 2, 0],
+                        DataType::U32 => vec![1, 0, 0, 0, 2, 0, 0, 0],
+                        DataType::F32 => float_bytes(&[1.0, 2.0]),
+                    };
+                    let tex_coord0 = accessor(data_type, 2, normalized, &bytes);
+                    let mesh = MeshData {
+                        positions: vec![[0.0; 3]],
+                        tex_coords0: tex_coord0.to_f32(),
+                        quantized: QuantizedAttributes {
+                            tex_coord0: Some(tex_coord0.clone()),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+
+                    let packed = mesh.packed_vertices().unwrap();
+                    let (offsets, _) = packed.layout.offsets();
+                    let start = offsets[2] as usize;
+                    let format = packed.layout.tex_coord0;
+                    let stored = &packed.bytes[start..start + format.size()];
+                    match ElementFormat::quantized(&tex_coord0) {
+                        Some(quantized) => {
+                            assert_eq!(format, quantized);
+                            assert_eq!(stored, bytes);
+                        }
+                        None => {
+                            assert_eq!(format, ElementFormat::Float2, "{:?}", data_type);
+                            assert_eq!(stored, float_bytes(&tex_coord0.to_f32_flat()));
+                        }
+                    }
+                    let quantizable = data_type != DataType::U32 && data_type != DataType::F32;
+                    assert_eq!(format != ElementFormat::Float2, normalized && quantizable);
+                }
+            }
+        }
+    }
+}
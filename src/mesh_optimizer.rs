@@ -0,0 +1,530 @@
+pub mod mesh_optimizer {
+
+    //! Reorders primitive geometry for the GPU: welds duplicate vertices, orders triangles
+    //! for the post-transform vertex cache (Tom Forsyth's linear-speed algorithm) and then
+    //! for less overdraw, and lays vertices out in the order they are fetched.
+
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use crate::math::math::{vec3_add, vec3_cross, vec3_dot, vec3_normalize, vec3_scale, vec3_sub};
+    use crate::mesh::mesh::MeshData;
+
+    /// Cache size assumed when reporting statistics and finding overdraw clusters; small
+    /// enough to hold on any GPU still in use.
+    pub const ANALYSIS_CACHE_SIZE: usize = 16;
+
+    /// Accesses to a FIFO vertex cache, as post-transform caches behave.
+    struct FifoCache {
+        /// When each vertex last entered the cache.
+        stamps: Vec<Option<usize>>,
+        time: usize,
+        size: usize,
+    }
+
+    impl FifoCache {
+        fn new(vertex_count: usize, size: usize) -> Self {
+            FifoCache {
+                stamps: vec![None; vertex_count],
+                time: 0,
+                size,
+            }
+        }
+
+        /// Whether `vertex` had to be transformed.
+        fn access(&mut self, vertex: u32) -> bool {
+            let stamp = &mut self.stamps[vertex as usize];
+            // Still cached while fewer than `size` vertices entered after it.
+            if stamp.is_some_and(|t| self.time - t <= self.size) {
+                return false;
+            }
+            *stamp = Some(self.time);
+            self.time += 1;
+            true
+        }
+
+        fn flush(&mut self) {
+            self.time += self.size;
+        }
+
+        fn triangle_misses(&mut self, triangle: &[u32]) -> usize {
+            triangle.iter().filter(|&&v| self.access(v)).count()
+        }
+    }
+
+    /// Post-transform vertex cache efficiency of a triangle list.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct VertexCacheStats {
+        /// Average cache miss ratio: transformed vertices per triangle, 0.5 at best and 3 at
+        /// worst.
+        pub acmr: f32,
+        /// Average transform to vertex ratio: transformed vertices per vertex, 1 at best.
+        pub atvr: f32,
+    }
+
+    pub fn analyze_vertex_cache(
+        indices: &[u32],
+        vertex_count: usize,
+        cache_size: usize,
+    ) -> VertexCacheStats {
+        let mut cache = FifoCache::new(vertex_count, cache_size);
+        let transformed = indices.iter().filter(|&&v| cache.access(v)).count() as f32;
+        let triangles = indices.len() / 3;
+        VertexCacheStats {
+            acmr: if triangles > 0 {
+                transformed / triangles as f32
+            } else {
+                0.0
+            },
+            atvr: if vertex_count > 0 {
+                transformed / vertex_count as f32
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// Statistics of a primitive before and after `optimize_mesh`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct OptimizationReport {
+        pub vertices_before: usize,
+        pub vertices_after: usize,
+        pub before: VertexCacheStats,
+        pub after: VertexCacheStats,
+    }
+
+    impl fmt::Display for OptimizationReport {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "ACMR {:.3} -> {:.3}, ATVR {:.3} -> {:.3}, {} -> {} vertices",
+                self.before.acmr,
+                self.after.acmr,
+                self.before.atvr,
+                self.after.atvr,
+                self.vertices_before,
+                self.vertices_after
+            )
+        }
+    }
+
+    /// Runs every pass in order and measures the result with `ANALYSIS_CACHE_SIZE`.
+    pub fn optimize_mesh(mesh: &mut MeshData) -> OptimizationReport {
+        let mut report = OptimizationReport {
+            vertices_before: mesh.vertex_count(),
+            before: analyze_vertex_cache(&mesh.indices, mesh.vertex_count(), ANALYSIS_CACHE_SIZE),
+            ..Default::default()
+        };
+        weld_vertices(mesh);
+        optimize_vertex_cache(mesh);
+        optimize_overdraw(mesh, DEFAULT_OVERDRAW_THRESHOLD);
+        optimize_vertex_fetch(mesh);
+        report.vertices_after = mesh.vertex_count();
+        report.after =
+            analyze_vertex_cache(&mesh.indices, mesh.vertex_count(), ANALYSIS_CACHE_SIZE);
+        report
+    }
+
+    fn push_floats<const N: usize>(key: &mut Vec<u32>, stream: &[[f32; N]], i: usize) {
+        if let Some(values) = stream.get(i) {
+            key.extend(values.iter().map(|v| v.to_bits()));
+        }
+    }
+
+    /// Every attribute of vertex `i`, bit for bit.
+    fn vertex_key(mesh: &MeshData, i: usize) -> Vec<u32> {
+        let mut key = Vec::new();
+        push_floats(&mut key, &mesh.positions, i);
+        push_floats(&mut key, &mesh.normals, i);
+        push_floats(&mut key, &mesh.tangents, i);
+        push_floats(&mut key, &mesh.tex_coords0, i);
+        push_floats(&mut key, &mesh.tex_coords1, i);
+        push_floats(&mut key, &mesh.weights0, i);
+        push_floats(&mut key, &mesh.weights1, i);
+        for joints in [&mesh.joints0, &mesh.joints1] {
+            if let Some(joints) = joints.get(i) {
+                key.extend(joints.iter().map(|&j| j as u32));
+            }
+        }
+        for target in &mesh.morph_targets {
+            push_floats(&mut key, &target.positions, i);
+            push_floats(&mut key, &target.normals, i);
+            push_floats(&mut key, &target.tangents, i);
+        }
+        let q = &mesh.quantized;
+        for accessor in [&q.position, &q.normal, &q.tex_coord0, &q.tex_coord1]
+            .into_iter()
+            .flatten()
+        {
+            key.extend(accessor.element(i).iter().map(|&b| b as u32));
+        }
+        key
+    }
+
+    /// Merges vertices whose attributes are identical, morph targets and quantized data
+    /// included, and drops vertices no index refers to.
+    pub fn weld_vertices(mesh: &mut MeshData) {
+        let mut unique: HashMap<Vec<u32>, u32> = HashMap::new();
+        let mut remap: Vec<Option<u32>> = vec![None; mesh.vertex_count()];
+        let mut sources = Vec::new();
+        let mut indices = std::mem::take(&mut mesh.indices);
+        for index in &mut indices {
+            let vertex = *index as usize;
+            *index = match remap[vertex] {
+                Some(welded) => welded,
+                None => {
+                    let welded = *unique.entry(vertex_key(mesh, vertex)).or_insert_with(|| {
+                        sources.push(vertex);
+                        sources.len() as u32 - 1
+                    });
+                    remap[vertex] = Some(welded);
+                    welded
+                }
+            };
+        }
+        mesh.indices = indices;
+        mesh.remap_vertices(&sources);
+    }
+
+    // Tuning of Forsyth's scoring, from "Linear-Speed Vertex Cache Optimisation".
+    const FORSYTH_CACHE_SIZE: usize = 32;
+    const CACHE_DECAY_POWER: f32 = 1.5;
+    const LAST_TRIANGLE_SCORE: f32 = 0.75;
+    const VALENCE_BOOST_SCALE: f32 = 2.0;
+    const VALENCE_BOOST_POWER: f32 = 0.5;
+
+    fn vertex_score(cache_position: Option<usize>, remaining: usize) -> f32 {
+        if remaining == 0 {
+            return -1.0;
+        }
+        let cache_score = match cache_position {
+            Some(p) if p < 3 => LAST_TRIANGLE_SCORE,
+            Some(p) => {
+                let scale = 1.0 / (FORSYTH_CACHE_SIZE - 3) as f32;
+                (1.0 - (p - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+            }
+            None => 0.0,
+        };
+        // Vertices with few triangles left are finished first so they can leave the cache.
+        cache_score + VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER)
+    }
+
+    /// Reorders triangles so that vertices are reused while still in the post-transform
+    /// cache. Each step emits the best scoring triangle next to the cache.
+    pub fn optimize_vertex_cache(mesh: &mut MeshData) {
+        let triangles: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+        let vertex_count = mesh.vertex_count();
+
+        // Triangles not yet emitted around every vertex.
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+        for (t, triangle) in triangles.iter().enumerate() {
+            for &v in triangle {
+                adjacency[v as usize].push(t);
+            }
+        }
+        let mut vertex_scores: Vec<f32> = adjacency
+            .iter()
+            .map(|triangles| vertex_score(None, triangles.len()))
+            .collect();
+        let mut emitted = vec![false; triangles.len()];
+
+        let mut indices = Vec::with_capacity(triangles.len() * 3);
+        let mut cache: Vec<u32> = Vec::with_capacity(FORSYTH_CACHE_SIZE + 3);
+        let mut next_unemitted = 0;
+        let mut best: Option<usize> = None;
+        for _ in 0..triangles.len() {
+            // Without a candidate next to the cache, continue with the next triangle in order.
+            let t = match best {
+                Some(t) => t,
+                None => {
+                    while emitted[next_unemitted] {
+                        next_unemitted += 1;
+                    }
+                    next_unemitted
+                }
+            };
+            emitted[t] = true;
+            indices.extend_from_slice(&triangles[t]);
+
+            let mut new_cache = triangles[t].to_vec();
+            for &v in &triangles[t] {
+                adjacency[v as usize].retain(|&other| other != t);
+            }
+            new_cache.extend(cache.iter().filter(|v| !triangles[t].contains(v)));
+            // Vertices pushed past the end leave the cache, but still get rescored.
+            for (p, &v) in new_cache.iter().enumerate() {
+                let position = Some(p).filter(|&p| p < FORSYTH_CACHE_SIZE);
+                vertex_scores[v as usize] = vertex_score(position, adjacency[v as usize].len());
+            }
+
+            best = None;
+            let mut best_score = f32::MIN;
+            for &v in &new_cache {
+                for &other in &adjacency[v as usize] {
+                    let score: f32 = triangles[other]
+                        .iter()
+                        .map(|&u| vertex_scores[u as usize])
+                        .sum();
+                    if score > best_score {
+                        best_score = score;
+                        best = Some(other);
+                    }
+                }
+            }
+            new_cache.truncate(FORSYTH_CACHE_SIZE);
+            cache = new_cache;
+        }
+
+        mesh.indices = indices;
+    }
+
+    /// How much worse the vertex cache ACMR of a cluster may get for finer overdraw
+    /// sorting, as in Sander et al. "Fast Triangle Reordering for Vertex Locality and
+    /// Reduced Overdraw".
+    pub const DEFAULT_OVERDRAW_THRESHOLD: f32 = 1.05;
+
+    /// Splits the cache ordered triangle list into clusters where the cache is flushed
+    /// anyway, or where splitting costs at most `threshold` times the cluster's ACMR, then
+    /// draws the clusters facing away from the mesh center first so they occlude the rest.
+    /// Run after `optimize_vertex_cache`.
+    pub fn optimize_overdraw(mesh: &mut MeshData, threshold: f32) {
+        let triangles: Vec<&[u32]> = mesh.indices.chunks_exact(3).collect();
+        if triangles.is_empty() {
+            return;
+        }
+        let mut cache = FifoCache::new(mesh.vertex_count(), ANALYSIS_CACHE_SIZE);
+
+        // Hard boundaries: triangles whose vertices all missed the cache.
+        let mut hard = Vec::new();
+        for (t, triangle) in triangles.iter().enumerate() {
+            if cache.triangle_misses(triangle) == 3 {
+                hard.push(t);
+            }
+        }
+        hard.push(triangles.len());
+
+        // Soft boundaries inside each, wherever the cluster so far is cheap enough.
+        let mut clusters: Vec<(usize, usize)> = Vec::new();
+        for bounds in hard.windows(2) {
+            let (start, end) = (bounds[0], bounds[1]);
+            cache.flush();
+            let misses: usize = triangles[start..end]
+                .iter()
+                .map(|t| cache.triangle_misses(t))
+                .sum();
+            let cluster_threshold = threshold * misses as f32 / (end - start) as f32;
+
+            cache.flush();
+            let mut cluster_start = start;
+            let mut cluster_misses = 0;
+            for (t, triangle) in triangles.iter().enumerate().take(end).skip(start) {
+                cluster_misses += cache.triangle_misses(triangle);
+                let count = (t + 1 - cluster_start) as f32;
+                if cluster_misses as f32 <= cluster_threshold * count || t + 1 == end {
+                    clusters.push((cluster_start, t + 1));
+                    cluster_start = t + 1;
+                    cluster_misses = 0;
+                    cache.flush();
+                }
+            }
+        }
+
+        // Area weighted centroid and normal of every cluster.
+        let positions = &mesh.positions;
+        let corners = |t: &[u32]| [0, 1, 2].map(|i| positions[t[i] as usize]);
+        let mut mesh_centroid = [0.0; 3];
+        let mut mesh_area = 0.0;
+        let mut keys: Vec<(f32, usize)> = Vec::with_capacity(clusters.len());
+        let mut cluster_shapes = Vec::with_capacity(clusters.len());
+        for &(start, end) in &clusters {
+            let mut centroid = [0.0; 3];
+            let mut normal = [0.0; 3];
+            let mut area = 0.0;
+            for triangle in &triangles[start..end] {
+                let [p0, p1, p2] = corners(triangle);
+                let cross = vec3_cross(vec3_sub(p1, p0), vec3_sub(p2, p0));
+                let triangle_area = vec3_dot(cross, cross).sqrt();
+                let center = vec3_scale(vec3_add(vec3_add(p0, p1), p2), 1.0 / 3.0);
+                centroid = vec3_add(centroid, vec3_scale(center, triangle_area));
+                normal = vec3_add(normal, cross);
+                area += triangle_area;
+            }
+            mesh_centroid = vec3_add(mesh_centroid, centroid);
+            mesh_area += area;
+            let centroid = if area > 0.0 {
+                vec3_scale(centroid, 1.0 / area)
+            } else {
+                centroid
+            };
+            cluster_shapes.push((centroid, vec3_normalize(normal)));
+        }
+        if mesh_area > 0.0 {
+            mesh_centroid = vec3_scale(mesh_centroid, 1.0 / mesh_area);
+        }
+        for (c, (centroid, normal)) in cluster_shapes.into_iter().enumerate() {
+            keys.push((vec3_dot(vec3_sub(centroid, mesh_centroid), normal), c));
+        }
+        keys.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let indices = keys
+            .iter()
+            .flat_map(|&(_, c)| {
+                let (start, end) = clusters[c];
+                triangles[start..end].iter().flat_map(|t| t.iter().copied())
+            })
+            .collect();
+        mesh.indices = indices;
+    }
+
+    /// Renumbers vertices in the order the indices first use them, so vertex fetch walks
+    /// the buffer forward. Unused vertices are dropped.
+    pub fn optimize_vertex_fetch(mesh: &mut MeshData) {
+        let mut remap: Vec<Option<u32>> = vec![None; mesh.vertex_count()];
+        let mut sources = Vec::new();
+        for index in &mut mesh.indices {
+            let vertex = *index as usize;
+            *index = *remap[vertex].get_or_insert_with(|| {
+                sources.push(vertex);
+                sources.len() as u32 - 1
+            });
+        }
+        mesh.remap_vertices(&sources);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A 32x32 quad grid with its triangles in a scrambled order.
+        fn scrambled_grid() -> MeshData {
+            let mut mesh = MeshData::default();
+            for y in 0..33 {
+                for x in 0..33 {
+                    mesh.positions.push([x as f32, y as f32, 0.0]);
+                    mesh.normals.push([0.0, 0.0, 1.0]);
+                }
+            }
+            for y in 0..32 {
+                for x in 0..32 {
+                    let v = y * 33 + x;
+                    mesh.indices
+                        .extend_from_slice(&[v, v + 1, v + 34, v, v + 34, v + 33]);
+                }
+            }
+            let mut triangles: Vec<&[u32]> = mesh.indices.chunks_exact(3).collect();
+            let mut seed = 1u32;
+            for i in (1..triangles.len()).rev() {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                triangles.swap(i, (seed >> 8) as usize % (i + 1));
+            }
+            mesh.indices = triangles.concat();
+            mesh
+        }
+
+        /// Triangles by corner positions, for comparing meshes with renumbered vertices.
+        fn triangles(mesh: &MeshData) -> Vec<[[u32; 3]; 3]> {
+            let mut triangles: Vec<[[u32; 3]; 3]> = mesh
+                .indices
+                .chunks_exact(3)
+                .map(|t| [0, 1, 2].map(|i| mesh.positions[t[i] as usize].map(f32::to_bits)))
+                .collect();
+            triangles.sort();
+            triangles
+        }
+
+        #[test]
+        fn cache_stats_count_fifo_misses() {
+            let quad = analyze_vertex_cache(&[0, 1, 2, 2, 1, 3], 4, 16);
+            assert_eq!(
+                quad,
+                VertexCacheStats {
+                    acmr: 2.0,
+                    atvr: 1.0
+                }
+            );
+            // With room for three vertices, the first triangle is gone when it repeats.
+            let repeated = analyze_vertex_cache(&[0, 1, 2, 3, 4, 5, 0, 1, 2], 6, 3);
+            assert_eq!(
+                repeated,
+                VertexCacheStats {
+                    acmr: 3.0,
+                    atvr: 1.5
+                }
+            );
+            let fits = analyze_vertex_cache(&[0, 1, 2, 3, 4, 5, 0, 1, 2], 6, 6);
+            assert_eq!(
+                fits,
+                VertexCacheStats {
+                    acmr: 2.0,
+                    atvr: 1.0
+                }
+            );
+            assert_eq!(
+                analyze_vertex_cache(&[], 0, 16),
+                VertexCacheStats::default()
+            );
+        }
+
+        #[test]
+        fn weld_merges_identical_vertices_and_drops_unused_ones() {
+            let mut mesh = MeshData {
+                positions: vec![
+                    [0.0, 0.0, 0.0],
+                    [1.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0],
+                    [9.0, 9.0, 9.0],
+                    [0.0, 1.0, 0.0],
+                    [1.0, 0.0, 0.0],
+                    [1.0, 1.0, 0.0],
+                    [1.0, 1.0, 0.0],
+                ],
+                normals: vec![[0.0, 0.0, 1.0]; 8],
+                indices: vec![0, 1, 2, 4, 5, 6, 2, 1, 7],
+                ..Default::default()
+            };
+            mesh.tex_coords0 = vec![[0.0; 2]; 8];
+            mesh.tex_coords0[7] = [1.0, 1.0];
+            let before = triangles(&mesh);
+            weld_vertices(&mut mesh);
+            assert_eq!(mesh.vertex_count(), 5);
+            assert_eq!(mesh.indices, [0, 1, 2, 2, 1, 3, 2, 1, 4]);
+            assert_eq!(triangles(&mesh), before);
+        }
+
+        #[test]
+        fn optimization_improves_the_cache_and_keeps_the_triangles() {
+            let mut mesh = scrambled_grid();
+            let before = triangles(&mesh);
+            let report = optimize_mesh(&mut mesh);
+
+            assert_eq!(triangles(&mesh), before);
+            assert_eq!(report.vertices_before, 33 * 33);
+            assert_eq!(report.vertices_after, 33 * 33);
+            assert!(report.before.acmr > 2.0, "{}", report);
+            // A regular grid can't get below 0.5 and a strip order gets about 1.
+            assert!(report.after.acmr < 0.8, "{}", report);
+            assert!(report.after.atvr < 1.6, "{}", report);
+            let measured =
+                analyze_vertex_cache(&mesh.indices, mesh.vertex_count(), ANALYSIS_CACHE_SIZE);
+            assert_eq!(report.after, measured);
+        }
+
+        #[test]
+        fn vertex_fetch_follows_first_use() {
+            let mut mesh = scrambled_grid();
+            optimize_vertex_fetch(&mut mesh);
+            let mut next = 0;
+            for &index in &mesh.indices {
+                assert!(index <= next);
+                if index == next {
+                    next += 1;
+                }
+            }
+            assert_eq!(next as usize, mesh.vertex_count());
+        }
+    }
+}
@@ -0,0 +1,376 @@
+pub mod meshlets {
+
+    //! Partitions triangle lists into meshlets: small clusters of triangles over a bounded
+    //! set of vertices, each with a bounding sphere and a normal cone for culling whole
+    //! clusters that are off screen or facing away.
+
+    use std::fmt;
+
+    use crate::lod::lod::bounding_sphere;
+    use crate::math::math::{vec3_add, vec3_cross, vec3_dot, vec3_normalize, vec3_scale, vec3_sub};
+    use crate::mesh::mesh::MeshData;
+
+    pub const MAX_MESHLET_VERTICES: usize = 64;
+    pub const MAX_MESHLET_TRIANGLES: usize = 124;
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Meshlet {
+        /// Indices into the mesh vertices.
+        pub vertices: Vec<u32>,
+        /// Indices into `vertices`.
+        pub triangles: Vec<[u8; 3]>,
+        pub center: [f32; 3],
+        pub radius: f32,
+        /// Every triangle faces away from a viewer inside the cone at `cone_apex` around
+        /// `cone_axis` whose half-angle has cosine `cone_cutoff`. A cutoff of 1 never culls.
+        pub cone_apex: [f32; 3],
+        pub cone_axis: [f32; 3],
+        pub cone_cutoff: f32,
+    }
+
+    impl Meshlet {
+        /// Whether the bounding sphere lies entirely on the negative side of `plane`, an
+        /// `[a, b, c, d]` plane in mesh space.
+        pub fn is_behind(&self, plane: [f32; 4]) -> bool {
+            let [a, b, c, d] = plane;
+            vec3_dot([a, b, c], self.center) + d < -self.radius
+        }
+
+        /// Whether every triangle faces away from `eye`, given in mesh space.
+        pub fn is_backfacing(&self, eye: [f32; 3]) -> bool {
+            let view = vec3_normalize(vec3_sub(self.cone_apex, eye));
+            vec3_dot(view, self.cone_axis) >= self.cone_cutoff
+        }
+    }
+
+    /// How a primitive was partitioned, averaged over its meshlets.
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct MeshletStats {
+        pub count: usize,
+        pub vertices: f32,
+        pub triangles: f32,
+    }
+
+    impl MeshletStats {
+        pub fn new(meshlets: &[Meshlet]) -> Self {
+            let count = meshlets.len();
+            if count == 0 {
+                return MeshletStats::default();
+            }
+            let vertices: usize = meshlets.iter().map(|m| m.vertices.len()).sum();
+            let triangles: usize = meshlets.iter().map(|m| m.triangles.len()).sum();
+            MeshletStats {
+                count,
+                vertices: vertices as f32 / count as f32,
+                triangles: triangles as f32 / count as f32,
+            }
+        }
+    }
+
+    impl fmt::Display for MeshletStats {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "{} meshlets of {:.1} vertices and {:.1} triangles",
+                self.count, self.vertices, self.triangles
+            )
+        }
+    }
+
+    /// Sphere and normal cone of the triangles of `meshlet`, following meshoptimizer's
+    /// `meshopt_computeMeshletBounds`.
+    fn compute_bounds(meshlet: &mut Meshlet, positions: &[[f32; 3]]) {
+        let corners: Vec<[f32; 3]> = meshlet
+            .vertices
+            .iter()
+            .map(|&v| positions[v as usize])
+            .collect();
+        let (center, radius) = bounding_sphere(&corners);
+        meshlet.center = center;
+        meshlet.radius = radius;
+
+        let triangles: Vec<([f32; 3], [f32; 3])> = meshlet
+            .triangles
+            .iter()
+            .filter_map(|t| {
+                let [p0, p1, p2] = t.map(|i| corners[i as usize]);
+                let cross = vec3_cross(vec3_sub(p1, p0), vec3_sub(p2, p0));
+                (cross != [0.0; 3]).then(|| (p0, vec3_normalize(cross)))
+            })
+            .collect();
+        let axis = vec3_normalize(
+            triangles
+                .iter()
+                .fold([0.0; 3], |sum, &(_, normal)| vec3_add(sum, normal)),
+        );
+        let min_dot = triangles
+            .iter()
+            .map(|&(_, normal)| vec3_dot(normal, axis))
+            .fold(1.0, f32::min);
+        meshlet.cone_axis = axis;
+        meshlet.cone_apex = center;
+        meshlet.cone_cutoff = 1.0;
+        if triangles.is_empty() || min_dot <= 0.1 {
+            // Normals spread over a hemisphere or more; no useful cone.
+            return;
+        }
+
+        // Move the apex back along the axis until it is behind every triangle's plane.
+        let max_t = triangles
+            .iter()
+            .map(|&(p0, normal)| vec3_dot(vec3_sub(center, p0), normal) / vec3_dot(axis, normal))
+            .fold(0.0, f32::max);
+        meshlet.cone_apex = vec3_sub(center, vec3_scale(axis, max_t));
+        meshlet.cone_cutoff = (1.0 - min_dot * min_dot).sqrt();
+    }
+
+    /// Splits the triangles of `mesh` into meshlets of at most `max_vertices` vertices and
+    /// `max_triangles` triangles, neither above the `MAX_MESHLET_*` limits. Each meshlet
+    /// grows by the adjacent triangle adding the fewest vertices, so it stays connected,
+    /// and is closed once no adjacent triangle fits.
+    pub fn build_meshlets(
+        mesh: &MeshData,
+        max_vertices: usize,
+        max_triangles: usize,
+    ) -> Vec<Meshlet> {
+        let max_vertices = max_vertices.clamp(3, MAX_MESHLET_VERTICES);
+        let max_triangles = max_triangles.clamp(1, MAX_MESHLET_TRIANGLES);
+        let triangles: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+
+        let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); mesh.vertex_count()];
+        for (t, triangle) in triangles.iter().enumerate() {
+            for &v in triangle {
+                adjacency[v as usize].push(t);
+            }
+        }
+
+        // Position of every vertex in the current meshlet.
+        let mut local: Vec<Option<u8>> = vec![None; mesh.vertex_count()];
+        let mut emitted = vec![false; triangles.len()];
+        let mut meshlets = Vec::new();
+        let mut meshlet = Meshlet::default();
+        let mut next_unemitted = 0;
+        let mut remaining = triangles.len();
+        while remaining > 0 {
+            let new_vertices = |t: usize| {
+                triangles[t]
+                    .iter()
+                    .filter(|&&v| local[v as usize].is_none())
+                    .count()
+            };
+            let fits = |t: usize| {
+                meshlet.triangles.len() < max_triangles
+                    && meshlet.vertices.len() + new_vertices(t) <= max_vertices
+            };
+            let best = meshlet
+                .vertices
+                .iter()
+                .flat_map(|&v| adjacency[v as usize].iter().copied())
+                .filter(|&t| !emitted[t] && fits(t))
+                .min_by_key(|&t| (new_vertices(t), t));
+
+            let t = match best {
+                Some(t) => t,
+                None if meshlet.triangles.is_empty() => {
+                    while emitted[next_unemitted] {
+                        next_unemitted += 1;
+                    }
+                    next_unemitted
+                }
+                None => {
+                    for &v in &meshlet.vertices {
+                        local[v as usize] = None;
+                    }
+                    compute_bounds(&mut meshlet, &mesh.positions);
+                    meshlets.push(std::mem::take(&mut meshlet));
+                    continue;
+                }
+            };
+
+            emitted[t] = true;
+            remaining -= 1;
+            let corners = triangles[t].map(|v| {
+                *local[v as usize].get_or_insert_with(|| {
+                    meshlet.vertices.push(v);
+                    (meshlet.vertices.len() - 1) as u8
+                })
+            });
+            meshlet.triangles.push(corners);
+        }
+        if !meshlet.triangles.is_empty() {
+            compute_bounds(&mut meshlet, &mesh.positions);
+            meshlets.push(meshlet);
+        }
+        meshlets
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::collections::HashMap;
+
+        use super::*;
+        use crate::math::math::vec3_length;
+
+        /// Unit sphere made from an octahedron with each face split into `n` by `n`
+        /// triangles, which keeps its triangles close to equal in size like an icosphere.
+        fn sphere(n: u32) -> MeshData {
+            let mut mesh = MeshData::default();
+            let mut vertices: HashMap<[i32; 3], u32> = HashMap::new();
+            for octant in 0..8 {
+                let sign = |bit: u32| if octant >> bit & 1 == 1 { -1.0 } else { 1.0 };
+                let (x, y, z) = (
+                    [sign(0), 0.0, 0.0],
+                    [0.0, sign(1), 0.0],
+                    [0.0, 0.0, sign(2)],
+                );
+                // Counter-clockwise seen from outside.
+                let [a, b, c] = if sign(0) * sign(1) * sign(2) > 0.0 {
+                    [x, y, z]
+                } else {
+                    [x, z, y]
+                };
+                let mut vertex = |i: u32, j: u32| {
+                    let k = (n - i - j) as f32;
+                    let p = [0, 1, 2].map(|d| a[d] * k + b[d] * i as f32 + c[d] * j as f32);
+                    let p = vec3_normalize(p);
+                    let key = p.map(|c| (c * 1e5).round() as i32);
+                    let next = mesh.positions.len() as u32;
+                    *vertices.entry(key).or_insert_with(|| {
+                        mesh.positions.push(p);
+                        next
+                    })
+                };
+                for i in 0..n {
+                    for j in 0..n - i {
+                        let triangle = [vertex(i, j), vertex(i + 1, j), vertex(i, j + 1)];
+                        mesh.indices.extend(triangle);
+                        if i + j + 1 < n {
+                            let triangle =
+                                [vertex(i + 1, j), vertex(i + 1, j + 1), vertex(i, j + 1)];
+                            mesh.indices.extend(triangle);
+                        }
+                    }
+                }
+            }
+            mesh
+        }
+
+        /// The mesh triangles the meshlets cover, in their original winding.
+        fn covered(meshlets: &[Meshlet]) -> Vec<[u32; 3]> {
+            let mut triangles: Vec<[u32; 3]> = meshlets
+                .iter()
+                .flat_map(|m| {
+                    m.triangles
+                        .iter()
+                        .map(move |t| t.map(|i| m.vertices[i as usize]))
+                })
+                .collect();
+            triangles.sort();
+            triangles
+        }
+
+        fn triangles(mesh: &MeshData) -> Vec<[u32; 3]> {
+            let mut triangles: Vec<[u32; 3]> = mesh
+                .indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect();
+            triangles.sort();
+            triangles
+        }
+
+        #[test]
+        fn meshlets_respect_the_limits_and_cover_every_triangle() {
+            for mesh in [sphere(16), sphere(5)] {
+                for (max_vertices, max_triangles) in [(64, 124), (32, 32), (3, 1), (1000, 1000)] {
+                    let meshlets = build_meshlets(&mesh, max_vertices, max_triangles);
+                    for m in &meshlets {
+                        assert!(m.vertices.len() <= max_vertices.min(MAX_MESHLET_VERTICES));
+                        assert!(m.triangles.len() <= max_triangles.min(MAX_MESHLET_TRIANGLES));
+                        let mut unique = m.vertices.clone();
+                        unique.sort();
+                        unique.dedup();
+                        assert_eq!(unique.len(), m.vertices.len());
+                        for t in &m.triangles {
+                            assert!(t.iter().all(|&i| (i as usize) < m.vertices.len()));
+                        }
+                    }
+                    assert_eq!(covered(&meshlets), triangles(&mesh));
+                }
+            }
+        }
+
+        #[test]
+        fn meshlets_are_filled() {
+            let mesh = sphere(16);
+            let stats = MeshletStats::new(&build_meshlets(&mesh, 64, 124));
+            assert_eq!(
+                stats.triangles * stats.count as f32,
+                mesh.triangle_count() as f32
+            );
+            // Compact patches of a closed mesh have more triangles than vertices.
+            assert!(stats.triangles > stats.vertices, "{}", stats);
+            assert_eq!(MeshletStats::new(&[]), MeshletStats::default());
+        }
+
+        #[test]
+        fn bounds_hold_the_vertices() {
+            let mesh = sphere(8);
+            for m in build_meshlets(&mesh, 64, 124) {
+                for &v in &m.vertices {
+                    let d = vec3_length(vec3_sub(mesh.positions[v as usize], m.center));
+                    assert!(d <= m.radius + 1e-5);
+                }
+                assert!(!m.is_behind([0.0, 1.0, 0.0, 2.0]));
+                assert!(m.is_behind([0.0, 1.0, 0.0, -2.0]));
+            }
+        }
+
+        #[test]
+        fn backfacing_meshlets_only_hold_backfacing_triangles() {
+            let mesh = sphere(16);
+            let meshlets = build_meshlets(&mesh, 64, 124);
+            assert!(meshlets.iter().all(|m| m.cone_cutoff < 1.0));
+            let mut culled = 0;
+            for m in &meshlets {
+                for eye in [[0.0, 0.0, 3.0], [2.0, -1.0, 0.5], [0.2, 0.3, 0.1]] {
+                    if !m.is_backfacing(eye) {
+                        continue;
+                    }
+                    culled += 1;
+                    for t in &m.triangles {
+                        let [p0, p1, p2] =
+                            t.map(|i| mesh.positions[m.vertices[i as usize] as usize]);
+                        let normal = vec3_cross(vec3_sub(p1, p0), vec3_sub(p2, p0));
+                        assert!(vec3_dot(vec3_sub(p0, eye), normal) >= 0.0);
+                    }
+                }
+            }
+            // From outside, about half of the sphere faces away.
+            assert!(
+                culled > meshlets.len() / 2,
+                "{} of {}",
+                culled,
+                meshlets.len()
+            );
+
+            let quad = MeshData {
+                positions: vec![
+                    [0.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0],
+                    [1.0, 0.0, 1.0],
+                    [1.0, 0.0, 0.0],
+                ],
+                indices: vec![0, 1, 2, 0, 2, 3],
+                ..Default::default()
+            };
+            let flat = &build_meshlets(&quad, 64, 124)[0];
+            assert!(flat.is_backfacing([0.3, -1.0, 0.2]));
+            assert!(!flat.is_backfacing([0.3, 1.0, 0.2]));
+        }
+    }
+}
@@ -0,0 +1,693 @@
+pub mod meshopt {
+
+    //! Decoders for EXT_meshopt_compression buffer views: the attribute codec (version 0),
+    //! the triangle and index sequence codecs (versions 0 and 1) and the three filters.
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Mode {
+        Attributes,
+        Triangles,
+        Indices,
+    }
+
+    impl Mode {
+        pub fn parse(name: &str) -> Option<Self> {
+            match name {
+                "ATTRIBUTES" => Some(Mode::Attributes),
+                "TRIANGLES" => Some(Mode::Triangles),
+                "INDICES" => Some(Mode::Indices),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Filter {
+        None,
+        Octahedral,
+        Quaternion,
+        Exponential,
+    }
+
+    impl Filter {
+        pub fn parse(name: &str) -> Option<Self> {
+            match name {
+                "NONE" => Some(Filter::None),
+                "OCTAHEDRAL" => Some(Filter::Octahedral),
+                "QUATERNION" => Some(Filter::Quaternion),
+                "EXPONENTIAL" => Some(Filter::Exponential),
+                _ => None,
+            }
+        }
+    }
+
+    /// Decodes `count` elements of `stride` bytes. `None` if the data is malformed or the
+    /// stride doesn't suit the mode or filter.
+    pub fn decode(
+        data: &[u8],
+        count: usize,
+        stride: usize,
+        mode: Mode,
+        filter: Filter,
+    ) -> Option<Vec<u8>> {
+        let mut out = match mode {
+            Mode::Attributes => decode_vertex_buffer(data, count, stride)?,
+            Mode::Triangles => decode_index_buffer(data, count, stride)?,
+            Mode::Indices => decode_index_sequence(data, count, stride)?,
+        };
+        match filter {
+            Filter::None => {}
+            Filter::Octahedral if stride == 4 => octahedral_filter::<1>(&mut out),
+            Filter::Octahedral if stride == 8 => octahedral_filter::<2>(&mut out),
+            Filter::Quaternion if stride == 8 => quaternion_filter(&mut out),
+            Filter::Exponential if stride.is_multiple_of(4) => exponential_filter(&mut out),
+            _ => return None,
+        }
+        Some(out)
+    }
+
+    const VERTEX_HEADER: u8 = 0xa0;
+    const BYTE_GROUP_SIZE: usize = 16;
+    const VERTEX_BLOCK_SIZE_BYTES: usize = 8192;
+    const VERTEX_BLOCK_MAX_SIZE: usize = 256;
+    const TAIL_MIN_SIZE: usize = 32;
+
+    fn unzigzag8(v: u8) -> u8 {
+        (v >> 1) ^ (v & 1).wrapping_neg()
+    }
+
+    fn unzigzag32(v: u32) -> u32 {
+        (v >> 1) ^ (v & 1).wrapping_neg()
+    }
+
+    /// One group of 16 bytes, stored as zeros, 2-bit or 4-bit values, or verbatim. Packed
+    /// values equal to the largest one are escapes for a full byte that follows the group.
+    fn decode_bytes_group(data: &[u8], pos: usize, group: &mut [u8], bits: u8) -> Option<usize> {
+        match bits {
+            0 => {
+                group.fill(0);
+                Some(pos)
+            }
+            3 => {
+                group.copy_from_slice(data.get(pos..pos + BYTE_GROUP_SIZE)?);
+                Some(pos + BYTE_GROUP_SIZE)
+            }
+            _ => {
+                let width = 1usize << bits;
+                let sentinel = (1u8 << width) - 1;
+                let mut extra = pos + BYTE_GROUP_SIZE * width / 8;
+                for (i, out) in group.iter_mut().enumerate() {
+                    let byte = *data.get(pos + i * width / 8)?;
+                    let value = (byte >> (8 - width - (i * width) % 8)) & sentinel;
+                    *out = if value == sentinel {
+                        extra += 1;
+                        *data.get(extra - 1)?
+                    } else {
+                        value
+                    };
+                }
+                Some(extra)
+            }
+        }
+    }
+
+    /// Fills `buffer`, a multiple of 16 bytes, from a header of 2-bit group modes followed
+    /// by the groups.
+    fn decode_bytes(data: &[u8], pos: usize, buffer: &mut [u8]) -> Option<usize> {
+        let groups = buffer.len() / BYTE_GROUP_SIZE;
+        let header = data.get(pos..pos + groups.div_ceil(4))?;
+        let mut pos = pos + header.len();
+        for (g, group) in buffer.chunks_exact_mut(BYTE_GROUP_SIZE).enumerate() {
+            let bits = (header[g / 4] >> ((g % 4) * 2)) & 3;
+            pos = decode_bytes_group(data, pos, group, bits)?;
+        }
+        Some(pos)
+    }
+
+    /// Vertices come in blocks, each storing byte `k` of every vertex as zigzag deltas from
+    /// the previous vertex. The tail holds the vertex the first deltas start from.
+    fn decode_vertex_buffer(data: &[u8], count: usize, stride: usize) -> Option<Vec<u8>> {
+        if stride == 0
+            || stride > 256
+            || !stride.is_multiple_of(4)
+            || data.first() != Some(&VERTEX_HEADER)
+        {
+            return None;
+        }
+        let tail = stride.max(TAIL_MIN_SIZE);
+        if data.len() < 1 + tail {
+            return None;
+        }
+        let body = &data[..data.len() - tail];
+        let mut last = data[data.len() - stride..].to_vec();
+
+        let block_size = ((VERTEX_BLOCK_SIZE_BYTES / stride) & !(BYTE_GROUP_SIZE - 1))
+            .min(VERTEX_BLOCK_MAX_SIZE);
+        let mut out = vec![0u8; count * stride];
+        let mut buffer = [0u8; VERTEX_BLOCK_MAX_SIZE];
+        let mut pos = 1;
+        for start in (0..count).step_by(block_size) {
+            let n = block_size.min(count - start);
+            let aligned = (n + BYTE_GROUP_SIZE - 1) & !(BYTE_GROUP_SIZE - 1);
+            for (k, last) in last.iter_mut().enumerate() {
+                pos = decode_bytes(body, pos, &mut buffer[..aligned])?;
+                let mut p = *last;
+                for (i, &delta) in buffer[..n].iter().enumerate() {
+                    p = p.wrapping_add(unzigzag8(delta));
+                    out[(start + i) * stride + k] = p;
+                }
+                *last = p;
+            }
+        }
+
+        if pos != body.len() {
+            return None;
+        }
+        Some(out)
+    }
+
+    fn read_varint(data: &[u8], pos: &mut usize) -> Option<u32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = *data.get(*pos)?;
+            *pos += 1;
+            value |= ((byte & 127) as u32) << shift;
+            if byte < 128 {
+                break;
+            }
+        }
+        Some(value)
+    }
+
+    fn write_index(out: &mut Vec<u8>, index: u32, index_size: usize) {
+        if index_size == 2 {
+            out.extend_from_slice(&(index as u16).to_le_bytes());
+        } else {
+            out.extend_from_slice(&index.to_le_bytes());
+        }
+    }
+
+    /// Recently seen edges and vertices the triangle codec refers back to.
+    struct Fifo {
+        edges: [(u32, u32); 16],
+        edge_offset: usize,
+        vertices: [u32; 16],
+        vertex_offset: usize,
+    }
+
+    impl Fifo {
+        /// The edge pushed `back + 1` pushes ago.
+        fn edge(&self, back: usize) -> (u32, u32) {
+            self.edges[(self.edge_offset + 15 - back) & 15]
+        }
+
+        /// The vertex at `back` slots behind the write position.
+        fn vertex(&self, back: usize) -> u32 {
+            self.vertices[(self.vertex_offset + 16 - back) & 15]
+        }
+
+        fn push_edge(&mut self, a: u32, b: u32) {
+            self.edges[self.edge_offset] = (a, b);
+            self.edge_offset = (self.edge_offset + 1) & 15;
+        }
+
+        fn push_vertex(&mut self, v: u32, advance: bool) {
+            self.vertices[self.vertex_offset] = v;
+            self.vertex_offset = (self.vertex_offset + advance as usize) & 15;
+        }
+    }
+
+    /// Triangle lists coded as one byte per triangle against edge and vertex FIFOs, with
+    /// new vertices numbered in order of first use.
+    fn decode_index_buffer(data: &[u8], count: usize, index_size: usize) -> Option<Vec<u8>> {
+        let header = *data.first()?;
+        let version = header & 0x0f;
+        if !count.is_multiple_of(3)
+            || (index_size != 2 && index_size != 4)
+            || header >> 4 != 0xe
+            || version > 1
+        {
+            return None;
+        }
+        let triangles = count / 3;
+        if data.len() < 1 + triangles + 16 {
+            return None;
+        }
+        let codes = &data[1..1 + triangles];
+        let aux_table = &data[data.len() - 16..];
+        let stream = &data[..data.len() - 16];
+        let mut pos = 1 + triangles;
+
+        let mut fifo = Fifo {
+            edges: [(u32::MAX, u32::MAX); 16],
+            edge_offset: 0,
+            vertices: [u32::MAX; 16],
+            vertex_offset: 0,
+        };
+        let mut next = 0u32;
+        let mut last = 0u32;
+        let fec_max = if version >= 1 { 13 } else { 15 };
+        let mut out = Vec::with_capacity(count * index_size);
+
+        for &code in codes {
+            let (a, b, c) = if code < 0xf0 {
+                // Edge from the FIFO plus one vertex: recent, new, or coded explicitly.
+                let (a, b) = fifo.edge((code >> 4) as usize);
+                let fec = (code & 15) as usize;
+                let c = if fec < fec_max {
+                    let c = if fec == 0 { next } else { fifo.vertex(1 + fec) };
+                    next += (fec == 0) as u32;
+                    fifo.push_vertex(c, fec == 0);
+                    c
+                } else {
+                    last = if fec != 15 {
+                        // 13 and 14 are -1 and +1 from the last explicit index.
+                        last.wrapping_add((fec as u32).wrapping_sub(fec as u32 ^ 3))
+                    } else {
+                        last.wrapping_add(unzigzag32(read_varint(stream, &mut pos)?))
+                    };
+                    fifo.push_vertex(last, true);
+                    last
+                };
+                fifo.push_edge(c, b);
+                fifo.push_edge(a, c);
+                (a, b, c)
+            } else {
+                let (aux, fea) = if code < 0xfe {
+                    (aux_table[(code & 15) as usize], 0)
+                } else {
+                    let aux = *stream.get(pos)?;
+                    pos += 1;
+                    if aux == 0 {
+                        next = 0;
+                    }
+                    (aux, if code == 0xfe { 0 } else { 15 })
+                };
+                let feb = (aux >> 4) as usize;
+                let fec = (aux & 15) as usize;
+
+                let (a, b, c) = if code < 0xfe {
+                    // New vertex plus two that are new or recent.
+                    let a = next;
+                    next += 1;
+                    let b = if feb == 0 { next } else { fifo.vertex(feb) };
+                    next += (feb == 0) as u32;
+                    let c = if fec == 0 { next } else { fifo.vertex(fec) };
+                    next += (fec == 0) as u32;
+                    (a, b, c)
+                } else {
+                    // New, recent or explicitly coded vertices. All new ones are numbered
+                    // first.
+                    let mut vertices = [0u32; 3];
+                    for (fe, v) in [fea, feb, fec].into_iter().zip(&mut vertices) {
+                        if fe == 0 {
+                            *v = next;
+                            next += 1;
+                        } else if fe != 15 {
+                            *v = fifo.vertex(fe);
+                        }
+                    }
+                    let [mut a, mut b, mut c] = vertices;
+                    for (fe, v) in [(fea, &mut a), (feb, &mut b), (fec, &mut c)] {
+                        if fe == 15 {
+                            last = last.wrapping_add(unzigzag32(read_varint(stream, &mut pos)?));
+                            *v = last;
+                        }
+                    }
+                    (a, b, c)
+                };
+
+                let slow = code >= 0xfe;
+                fifo.push_vertex(a, true);
+                fifo.push_vertex(b, feb == 0 || (slow && feb == 15));
+                fifo.push_vertex(c, fec == 0 || (slow && fec == 15));
+                fifo.push_edge(b, a);
+                fifo.push_edge(c, b);
+                fifo.push_edge(a, c);
+                (a, b, c)
+            };
+            for index in [a, b, c] {
+                write_index(&mut out, index, index_size);
+            }
+        }
+
+        if pos != stream.len() {
+            return None;
+        }
+        Some(out)
+    }
+
+    /// Arbitrary index lists as zigzag deltas from one of two previous indices.
+    fn decode_index_sequence(data: &[u8], count: usize, index_size: usize) -> Option<Vec<u8>> {
+        let header = *data.first()?;
+        if (index_size != 2 && index_size != 4) || header >> 4 != 0xd || header & 0x0f > 1 {
+            return None;
+        }
+        if data.len() < 1 + 4 {
+            return None;
+        }
+        let stream = &data[..data.len() - 4];
+        let mut pos = 1;
+        let mut last = [0u32; 2];
+        let mut out = Vec::with_capacity(count * index_size);
+        for _ in 0..count {
+            let v = read_varint(stream, &mut pos)?;
+            let baseline = (v & 1) as usize;
+            let index = last[baseline].wrapping_add(unzigzag32(v >> 1));
+            last[baseline] = index;
+            write_index(&mut out, index, index_size);
+        }
+
+        if pos != stream.len() {
+            return None;
+        }
+        Some(out)
+    }
+
+    fn read_signed<const N: usize>(bytes: &[u8]) -> i32 {
+        if N == 1 {
+            bytes[0] as i8 as i32
+        } else {
+            i16::from_le_bytes([bytes[0], bytes[1]]) as i32
+        }
+    }
+
+    fn write_signed<const N: usize>(bytes: &mut [u8], value: i32) {
+        if N == 1 {
+            bytes[0] = value as i8 as u8;
+        } else {
+            bytes[..2].copy_from_slice(&(value as i16).to_le_bytes());
+        }
+    }
+
+    fn round(v: f32) -> i32 {
+        (v + if v >= 0.0 { 0.5 } else { -0.5 }) as i32
+    }
+
+    /// Unit vectors stored as octahedral x and y plus the encoding's 1.0 in z, four
+    /// components of `N` bytes each. The fourth component passes through.
+    fn octahedral_filter<const N: usize>(data: &mut [u8]) {
+        let max = ((1 << (N * 8 - 1)) - 1) as f32;
+        for element in data.chunks_exact_mut(4 * N) {
+            let mut x = read_signed::<N>(&element[0..]) as f32;
+            let mut y = read_signed::<N>(&element[N..]) as f32;
+            let z = read_signed::<N>(&element[2 * N..]) as f32 - x.abs() - y.abs();
+
+            // Unfold the lower hemisphere.
+            let t = z.min(0.0);
+            x += if x >= 0.0 { t } else { -t };
+            y += if y >= 0.0 { t } else { -t };
+
+            let s = max / (x * x + y * y + z * z).sqrt();
+            write_signed::<N>(&mut element[0..], round(x * s));
+            write_signed::<N>(&mut element[N..], round(y * s));
+            write_signed::<N>(&mut element[2 * N..], round(z * s));
+        }
+    }
+
+    /// Quaternions stored as the three smallest 16-bit components; the fourth holds the
+    /// index of the dropped, largest component and the scale of the others.
+    fn quaternion_filter(data: &mut [u8]) {
+        let scale = 1.0 / 2f32.sqrt();
+        for element in data.chunks_exact_mut(8) {
+            let stored: Vec<i32> = element.chunks_exact(2).map(read_signed::<2>).collect();
+            let ss = scale / (stored[3] | 3) as f32;
+            let x = stored[0] as f32 * ss;
+            let y = stored[1] as f32 * ss;
+            let z = stored[2] as f32 * ss;
+            let w = (1.0 - x * x - y * y - z * z).max(0.0).sqrt();
+
+            let qc = (stored[3] & 3) as usize;
+            let values = [
+                (qc + 1, round(x * 32767.0)),
+                (qc + 2, round(y * 32767.0)),
+                (qc + 3, round(z * 32767.0)),
+                (qc, (w * 32767.0 + 0.5) as i32),
+            ];
+            for (component, value) in values {
+                let offset = (component & 3) * 2;
+                write_signed::<2>(&mut element[offset..], value);
+            }
+        }
+    }
+
+    /// 32-bit values holding an 8-bit exponent over a 24-bit signed mantissa, expanded to
+    /// floats.
+    fn exponential_filter(data: &mut [u8]) {
+        for value in data.chunks_exact_mut(4) {
+            let v = i32::from_le_bytes([value[0], value[1], value[2], value[3]]);
+            let exponent = v >> 24;
+            let mantissa = (v << 8) >> 8;
+            let f = mantissa as f32 * 2f32.powi(exponent);
+            value.copy_from_slice(&f.to_le_bytes());
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Encoded by meshoptimizer 0.12's `meshopt_encodeVertexBuffer` from `vertices()`.
+        const VERTICES_V0: [u8; 153] = [
+            0xa0, 0x05, 0x2a, 0xaa, 0xaa, 0xaa, 0xaa, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0x4a,
+            0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a, 0x4a,
+            0xff, 0x00, 0x00, 0x00, 0x4a, 0x4a, 0x4a, 0x4a, 0x06, 0x05, 0x55, 0x55, 0x55, 0x55,
+            0x55, 0x55, 0x55, 0xff, 0x00, 0x00, 0x00, 0x05, 0x05, 0x05, 0x05, 0x07, 0x00, 0x2f,
+            0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f, 0x2f,
+            0xff, 0x00, 0x00, 0x00, 0x2f, 0x2f, 0x2f, 0x2f, 0x06, 0x06, 0x88, 0x88, 0x88, 0x88,
+            0x86, 0x88, 0x88, 0xff, 0x00, 0x00, 0x00, 0x08, 0x08, 0x08, 0x08, 0x07, 0x00, 0x02,
+            0x06, 0x0a, 0x0e, 0x12, 0x16, 0x1a, 0x1e, 0x22, 0x26, 0x2a, 0x2e, 0x32, 0x36, 0x3a,
+            0xff, 0x00, 0x00, 0x00, 0x3e, 0x42, 0x46, 0x4a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07, 0x00, 0xff, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        /// 20 vertices of 8 bytes: ramps, a constant and a 16-bit value with large steps.
+        fn vertices() -> Vec<u8> {
+            let mut out = Vec::new();
+            for i in 0..20u32 {
+                let [w0, w1] = ((i * 1000) as u16).to_le_bytes();
+                out.extend_from_slice(&[
+                    i as u8,
+                    7,
+                    (i * 37) as u8,
+                    (255 - i * 3) as u8,
+                    w0,
+                    w1,
+                    (i * i) as u8,
+                    0,
+                ]);
+            }
+            out
+        }
+
+        /// Encoded by meshoptimizer 0.12's `meshopt_encodeIndexBuffer` from a 3x3 quad grid
+        /// followed by `[0, 15, 3, 12, 15, 0, 20, 21, 22, 21, 20, 1]`.
+        const TRIANGLES_V0: [u8; 61] = [
+            0xe0, 0xfe, 0x0f, 0xfe, 0x13, 0xfe, 0x12, 0xbf, 0x0f, 0xaf, 0x02, 0x9f, 0x01, 0x9f,
+            0x0f, 0x9f, 0x02, 0x9f, 0x01, 0xff, 0x23, 0xff, 0x2f, 0x0f, 0x0a, 0x01, 0xf3, 0x04,
+            0xf2, 0x02, 0x04, 0x01, 0x04, 0x02, 0x04, 0x01, 0x04, 0x02, 0x1a, 0x1d, 0xff, 0x28,
+            0x02, 0x02, 0x29, 0x00, 0x76, 0x87, 0x56, 0x67, 0x78, 0xa9, 0x86, 0x65, 0x89, 0x68,
+            0x98, 0x01, 0x69, 0x00, 0x00,
+        ];
+
+        /// What meshoptimizer decodes `TRIANGLES_V0` to: the source triangles, some rotated.
+        const TRIANGLES_V0_DECODED: [u32; 66] = [
+            0, 1, 5, 0, 5, 4, 2, 6, 1, 1, 6, 5, 3, 7, 2, 2, 7, 6, 4, 5, 9, 4, 9, 8, 5, 6, 10, 5,
+            10, 9, 6, 7, 11, 6, 11, 10, 8, 9, 13, 8, 13, 12, 9, 10, 14, 9, 14, 13, 10, 11, 15, 10,
+            15, 14, 0, 15, 3, 15, 0, 12, 20, 21, 22, 21, 20, 1,
+        ];
+
+        /// meshoptimizer's codeaux table, which every encoder writes.
+        const CODE_AUX: [u8; 16] = [
+            0x00, 0x76, 0x87, 0x56, 0x67, 0x78, 0xa9, 0x86, 0x65, 0x89, 0x68, 0x98, 0x01, 0x69,
+            0x00, 0x00,
+        ];
+
+        fn u32s(bytes: &[u8]) -> Vec<u32> {
+            let words = bytes.chunks_exact(4);
+            words
+                .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+                .collect()
+        }
+
+        fn u16s(bytes: &[u8]) -> Vec<u32> {
+            let words = bytes.chunks_exact(2);
+            words
+                .map(|w| u16::from_le_bytes([w[0], w[1]]) as u32)
+                .collect()
+        }
+
+        fn i16s(bytes: &[u8]) -> Vec<i32> {
+            bytes.chunks_exact(2).map(read_signed::<2>).collect()
+        }
+
+        #[test]
+        fn attributes() {
+            let decoded = decode(&VERTICES_V0, 20, 8, Mode::Attributes, Filter::None);
+            assert_eq!(decoded, Some(vertices()));
+        }
+
+        #[test]
+        fn attributes_reject_bad_input() {
+            let decode =
+                |data: &[u8], stride| decode(data, 20, stride, Mode::Attributes, Filter::None);
+            assert_eq!(decode(&VERTICES_V0, 6), None);
+            assert_eq!(decode(&VERTICES_V0[..100], 8), None);
+            let mut header = VERTICES_V0;
+            header[0] = 0xa1;
+            assert_eq!(decode(&header, 8), None);
+        }
+
+        #[test]
+        fn triangles_v0() {
+            let decoded = decode(&TRIANGLES_V0, 66, 4, Mode::Triangles, Filter::None);
+            assert_eq!(
+                decoded.as_deref().map(u32s),
+                Some(TRIANGLES_V0_DECODED.to_vec())
+            );
+            let decoded = decode(&TRIANGLES_V0, 66, 2, Mode::Triangles, Filter::None);
+            assert_eq!(
+                decoded.as_deref().map(u16s),
+                Some(TRIANGLES_V0_DECODED.to_vec())
+            );
+            assert_eq!(
+                decode(&TRIANGLES_V0, 63, 4, Mode::Triangles, Filter::None),
+                None
+            );
+            assert_eq!(
+                decode(&TRIANGLES_V0, 66, 1, Mode::Triangles, Filter::None),
+                None
+            );
+        }
+
+        #[test]
+        fn triangles_v1() {
+            // 0xf0 takes codeaux 0x00: three new vertices. 0x10 is the second newest edge and
+            // a new vertex. 0x0f codes the third vertex as zigzag 0x14 = +10 from the last
+            // explicit index, then v1's 0x0e and 0x0d step it by +1 and -1. 0xfe with a
+            // codeaux byte of 0 restarts the new vertex count.
+            let mut data = vec![0xe1, 0xf0, 0x10, 0x0f, 0x0e, 0x0d, 0xfe, 0x14, 0x00];
+            data.extend_from_slice(&CODE_AUX);
+            let expected = [0, 1, 2, 2, 1, 3, 2, 3, 10, 2, 10, 11, 2, 11, 10, 0, 1, 2];
+            let decoded = decode(&data, 18, 4, Mode::Triangles, Filter::None);
+            assert_eq!(decoded.as_deref().map(u32s), Some(expected.to_vec()));
+
+            // Version 0 reads 13 and 14 from the vertex FIFO instead.
+            data[0] = 0xe0;
+            let decoded = decode(&data, 18, 4, Mode::Triangles, Filter::None);
+            assert_ne!(decoded.as_deref().map(u32s), Some(expected.to_vec()));
+            data[0] = 0xe2;
+            assert_eq!(decode(&data, 18, 4, Mode::Triangles, Filter::None), None);
+        }
+
+        #[test]
+        fn indices() {
+            // Zigzag deltas shifted left by one, the low bit choosing the baseline: 100 and
+            // 101 run against the second baseline while the others step the first.
+            let data = [
+                0xd1, 0x00, 0x04, 0x04, 0x91, 0x03, 0x04, 0x05, 0x06, 0, 0, 0, 0,
+            ];
+            let expected = vec![0, 1, 2, 100, 3, 101, 1];
+            let decoded = decode(&data, 7, 4, Mode::Indices, Filter::None);
+            assert_eq!(decoded.as_deref().map(u32s), Some(expected.clone()));
+            let decoded = decode(&data, 7, 2, Mode::Indices, Filter::None);
+            assert_eq!(decoded.as_deref().map(u16s), Some(expected));
+            assert_eq!(decode(&data, 8, 4, Mode::Indices, Filter::None), None);
+            assert_eq!(decode(&data[..9], 7, 4, Mode::Indices, Filter::None), None);
+        }
+
+        /// Encodes up to 16 elements with the attribute codec, every byte group stored verbatim,
+        /// so the filter tests start from exact values.
+        fn verbatim_attributes(elements: &[Vec<u8>]) -> Vec<u8> {
+            let stride = elements[0].len();
+            let mut data = vec![VERTEX_HEADER];
+            let mut last = vec![0u8; stride];
+            let mut channels = vec![[0u8; BYTE_GROUP_SIZE]; stride];
+            for (i, element) in elements.iter().enumerate() {
+                for k in 0..stride {
+                    let delta = element[k].wrapping_sub(last[k]) as i8;
+                    channels[k][i] = ((delta as i32) << 1 ^ ((delta as i32) >> 7)) as u8;
+                    last[k] = element[k];
+                }
+            }
+            for channel in channels {
+                data.push(3);
+                data.extend_from_slice(&channel);
+            }
+            data.resize(data.len() + TAIL_MIN_SIZE.max(stride), 0);
+            data
+        }
+
+        #[test]
+        fn octahedral_filter_bytes() {
+            let elements = [
+                [0, 0, 127, 5],
+                [127, 0, 127, -3],
+                [127, 127, 127, 0],
+                [64, 64, 127, 0],
+            ];
+            let data = verbatim_attributes(&elements.map(|e| e.map(|v: i8| v as u8).to_vec()));
+            let decoded = decode(&data, 4, 4, Mode::Attributes, Filter::Octahedral).unwrap();
+            let decoded: Vec<i8> = decoded.iter().map(|&v| v as i8).collect();
+            // +Z and +X, -Z folded into the corner, and the W byte passed through.
+            assert_eq!(decoded[..12], [0, 0, 127, 5, 127, 0, 0, -3, 0, 0, -127, 0]);
+            // (1, 1, 0) comes back scaled to 127.
+            let diagonal = [decoded[12], decoded[13], decoded[14]].map(|v| v as f32);
+            let length = diagonal.iter().map(|v| v * v).sum::<f32>().sqrt();
+            assert!((length - 127.0).abs() < 1.0);
+            assert!(
+                diagonal[0] == diagonal[1] && (diagonal[0] / length - 0.5f32.sqrt()).abs() < 0.01
+            );
+        }
+
+        #[test]
+        fn octahedral_filter_shorts() {
+            let elements = [
+                [0, 0, 32767, 1234],
+                [-32767, 0, 32767, 0],
+                [16384, -16384, 32767, 0],
+            ];
+            let data = verbatim_attributes(
+                &elements.map(|e| e.iter().flat_map(|v: &i16| v.to_le_bytes()).collect()),
+            );
+            let decoded = decode(&data, 3, 8, Mode::Attributes, Filter::Octahedral).unwrap();
+            let decoded = i16s(&decoded);
+            assert_eq!(decoded[..8], [0, 0, 32767, 1234, -32767, 0, 0, 0]);
+            let expected = [23170, -23170, 0];
+            for (v, e) in decoded[8..11].iter().zip(expected) {
+                assert!((v - e).abs() <= 2, "{:?}", decoded);
+            }
+        }
+
+        #[test]
+        fn quaternion_filter() {
+            // Identity with w dropped, x with x dropped, and 90 degrees about y with y dropped:
+            // the kept components are scaled by sqrt(2) and the last short holds the index.
+            let elements = [[0, 0, 0, 32767], [0, 0, 0, 32764], [0, 32767, 0, 32765]];
+            let data = verbatim_attributes(
+                &elements.map(|e| e.iter().flat_map(|v: &i16| v.to_le_bytes()).collect()),
+            );
+            let decoded = decode(&data, 3, 8, Mode::Attributes, Filter::Quaternion).unwrap();
+            let decoded = i16s(&decoded);
+            let expected = [0, 0, 0, 32767, 32767, 0, 0, 0, 0, 23170, 0, 23170];
+            for (v, e) in decoded.iter().zip(expected) {
+                assert!((v - e).abs() <= 1, "{:?}", decoded);
+            }
+            assert_eq!(
+                decode(&data, 6, 4, Mode::Attributes, Filter::Quaternion),
+                None
+            );
+        }
+
+        #[test]
+        fn exponential_filter() {
+            // Exponent in the top byte over a signed 24-bit mantissa.
+            let values: [u32; 5] = [0xff000003, 0xfeffffff, 0x000003e8, 0, 0x14000003];
+            let data = verbatim_attributes(&values.map(|v| v.to_le_bytes().to_vec()));
+            let decoded = decode(&data, 5, 4, Mode::Attributes, Filter::Exponential).unwrap();
+            let floats: Vec<f32> = u32s(&decoded).into_iter().map(f32::from_bits).collect();
+            assert_eq!(floats, [1.5, -0.25, 1000.0, 0.0, 3.0 * 2f32.powi(20)]);
+        }
+    }
+}
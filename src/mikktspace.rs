@@ -0,0 +1,593 @@
+pub mod mikktspace {
+
+    //! Tangents matching the MikkTSpace reference implementation (mikktspace.c), so normal
+    //! maps baked against it shade without seams. The steps and their float operations follow
+    //! the reference; its quad handling is left out since primitives arrive as triangles.
+
+    use std::collections::HashMap;
+
+    use crate::math::math::{
+        vec3_add, vec3_dot, vec3_length, vec3_normalize, vec3_scale, vec3_sub,
+    };
+    use crate::mesh::mesh::MeshData;
+
+    /// Cosine of the reference's default 180 degree angular threshold.
+    const THRESHOLD_COS: f32 = -1.0;
+
+    /// `fabsf(x) > FLT_MIN`, the reference's test for a usable value.
+    fn not_zero(x: f32) -> bool {
+        x.abs() > f32::MIN_POSITIVE
+    }
+
+    fn normalize_not_zero(v: [f32; 3]) -> [f32; 3] {
+        if v.iter().any(|&c| not_zero(c)) {
+            vec3_normalize(v)
+        } else {
+            v
+        }
+    }
+
+    /// `v` projected onto the plane of the unit normal `n`, normalized.
+    fn project(n: [f32; 3], v: [f32; 3]) -> [f32; 3] {
+        normalize_not_zero(vec3_sub(v, vec3_scale(n, vec3_dot(n, v))))
+    }
+
+    struct Triangle {
+        /// Face in the primitive, which owns output corners `3 * face..3 * face + 3`.
+        face: usize,
+        /// Welded corner of each vertex.
+        verts: [usize; 3],
+        /// Triangle across the edge starting at each vertex.
+        neighbors: [Option<usize>; 3],
+        /// Group of each vertex.
+        groups: [Option<usize>; 3],
+        /// Unit tangent and bitangent directions of the face.
+        os: [f32; 3],
+        ot: [f32; 3],
+        orient_preserving: bool,
+        /// Set for faces with a degenerate UV mapping, which join any group.
+        group_with_any: bool,
+    }
+
+    /// Faces around one welded vertex that agree on orientation.
+    struct Group {
+        vertex: usize,
+        orient_preserving: bool,
+        faces: Vec<usize>,
+    }
+
+    /// Edge number of `(i0, i1)` in `verts` and its endpoints in winding order.
+    fn get_edge(verts: [usize; 3], i0: usize, i1: usize) -> (usize, usize, usize) {
+        if verts[0] == i0 || verts[0] == i1 {
+            if verts[1] == i0 || verts[1] == i1 {
+                (verts[0], verts[1], 0)
+            } else {
+                (verts[2], verts[0], 2)
+            }
+        } else {
+            (verts[1], verts[2], 1)
+        }
+    }
+
+    fn build_neighbors(triangles: &mut [Triangle]) {
+        let mut edges: Vec<(usize, usize, usize)> = triangles
+            .iter()
+            .enumerate()
+            .flat_map(|(f, t)| {
+                (0..3).map(move |i| {
+                    let (a, b) = (t.verts[i], t.verts[(i + 1) % 3]);
+                    (a.min(b), a.max(b), f)
+                })
+            })
+            .collect();
+        edges.sort_unstable();
+
+        for (i, &(i0, i1, f)) in edges.iter().enumerate() {
+            let (a0, a1, edge_a) = get_edge(triangles[f].verts, i0, i1);
+            if triangles[f].neighbors[edge_a].is_some() {
+                continue;
+            }
+            // The first unpaired face using the edge in the opposite direction.
+            let found = edges[i + 1..]
+                .iter()
+                .take_while(|e| e.0 == i0 && e.1 == i1)
+                .find_map(|&(_, _, t)| {
+                    let (b1, b0, edge_b) = get_edge(triangles[t].verts, i0, i1);
+                    let unpaired = triangles[t].neighbors[edge_b].is_none();
+                    (a0 == b0 && a1 == b1 && unpaired).then_some((t, edge_b))
+                });
+            if let Some((t, edge_b)) = found {
+                triangles[f].neighbors[edge_a] = Some(t);
+                triangles[t].neighbors[edge_b] = Some(f);
+            }
+        }
+    }
+
+    /// Adds `index` to group `g` if its corner at the group's vertex is free and the
+    /// orientation matches, then continues with the faces sharing that corner's edges.
+    fn assign(triangles: &mut [Triangle], index: usize, group: &mut Group, g: usize) {
+        let tri = &mut triangles[index];
+        let i = match tri.verts.iter().position(|&v| v == group.vertex) {
+            Some(i) => i,
+            None => return,
+        };
+        if tri.groups[i].is_some() {
+            return;
+        }
+        // The first group reaching a face without a UV orientation decides it.
+        if tri.group_with_any && tri.groups == [None; 3] {
+            tri.orient_preserving = group.orient_preserving;
+        }
+        if tri.orient_preserving != group.orient_preserving {
+            return;
+        }
+        group.faces.push(index);
+        tri.groups[i] = Some(g);
+        let neighbors = [tri.neighbors[i], tri.neighbors[(i + 2) % 3]];
+        for neighbor in neighbors.into_iter().flatten() {
+            assign(triangles, neighbor, group, g);
+        }
+    }
+
+    /// Angle weighted tangent of `members` at `vertex`.
+    fn eval_tspace(
+        members: &[usize],
+        triangles: &[Triangle],
+        vertex: usize,
+        positions: &[[f32; 3]],
+        normals: &[[f32; 3]],
+    ) -> [f32; 3] {
+        let mut os = [0.0; 3];
+        for tri in members.iter().map(|&f| &triangles[f]) {
+            if tri.group_with_any {
+                continue;
+            }
+            let i = match tri.verts.iter().position(|&v| v == vertex) {
+                Some(i) => i,
+                None => continue,
+            };
+            let n = normals[tri.verts[i]];
+            let p0 = positions[tri.verts[(i + 2) % 3]];
+            let p1 = positions[tri.verts[i]];
+            let p2 = positions[tri.verts[(i + 1) % 3]];
+            let v1 = project(n, vec3_sub(p0, p1));
+            let v2 = project(n, vec3_sub(p2, p1));
+            // The reference takes the arc cosine in double precision.
+            let angle = (vec3_dot(v1, v2).clamp(-1.0, 1.0) as f64).acos() as f32;
+            os = vec3_add(os, vec3_scale(project(n, tri.os), angle));
+        }
+        normalize_not_zero(os)
+    }
+
+    /// Fills `mesh.tangents` from the normals and texture coordinate set `uv_set`, splitting
+    /// vertices whose corners get different tangents. Does nothing without normals, without
+    /// the set or without a triangle of nonzero area.
+    pub fn generate_tangents(mesh: &mut MeshData, uv_set: u32) {
+        let uvs = match uv_set {
+            0 => &mesh.tex_coords0,
+            1 => &mesh.tex_coords1,
+            _ => return,
+        };
+        let vertex_count = mesh.vertex_count();
+        if mesh.normals.len() < vertex_count || uvs.len() < vertex_count {
+            return;
+        }
+
+        // Attributes per corner, and corners welded to the first with identical values.
+        let corner_count = mesh.triangle_count() * 3;
+        let corners = &mesh.indices[..corner_count];
+        let positions: Vec<[f32; 3]> = corners
+            .iter()
+            .map(|&v| mesh.positions[v as usize])
+            .collect();
+        let normals: Vec<[f32; 3]> = corners.iter().map(|&v| mesh.normals[v as usize]).collect();
+        let uvs: Vec<[f32; 2]> = corners.iter().map(|&v| uvs[v as usize]).collect();
+        let mut first: HashMap<[u32; 8], usize> = HashMap::new();
+        let welded: Vec<usize> = (0..corner_count)
+            .map(|c| {
+                let values = positions[c].iter().chain(&normals[c]).chain(&uvs[c]);
+                let mut key = [0u32; 8];
+                for (k, v) in key.iter_mut().zip(values) {
+                    // 0.0 and -0.0 compare equal in the reference.
+                    *k = (v + 0.0).to_bits();
+                }
+                *first.entry(key).or_insert(c)
+            })
+            .collect();
+
+        // Faces with coinciding positions take no part and copy their tangents at the end.
+        let (mut triangles, degenerate): (Vec<Triangle>, Vec<Triangle>) = (0..corner_count / 3)
+            .map(|face| Triangle {
+                face,
+                verts: [0, 1, 2].map(|i| welded[face * 3 + i]),
+                neighbors: [None; 3],
+                groups: [None; 3],
+                os: [0.0; 3],
+                ot: [0.0; 3],
+                orient_preserving: false,
+                group_with_any: true,
+            })
+            .partition(|t| {
+                let [a, b, c] = t.verts.map(|v| positions[v]);
+                a != b && a != c && b != c
+            });
+        if triangles.is_empty() {
+            return;
+        }
+
+        for t in &mut triangles {
+            let [v1, v2, v3] = t.verts.map(|v| positions[v]);
+            let [t1, t2, t3] = t.verts.map(|v| uvs[v]);
+            let (t21x, t21y) = (t2[0] - t1[0], t2[1] - t1[1]);
+            let (t31x, t31y) = (t3[0] - t1[0], t3[1] - t1[1]);
+            let d1 = vec3_sub(v2, v1);
+            let d2 = vec3_sub(v3, v1);
+            let signed_area = t21x * t31y - t21y * t31x;
+            let mut os = vec3_sub(vec3_scale(d1, t31y), vec3_scale(d2, t21y));
+            let mut ot = vec3_add(vec3_scale(d1, -t31x), vec3_scale(d2, t21x));
+            t.orient_preserving = signed_area > 0.0;
+            if not_zero(signed_area) {
+                let area = signed_area.abs();
+                let (len_os, len_ot) = (vec3_length(os), vec3_length(ot));
+                let sign = if t.orient_preserving { 1.0 } else { -1.0 };
+                if not_zero(len_os) {
+                    os = vec3_scale(os, sign / len_os);
+                }
+                if not_zero(len_ot) {
+                    ot = vec3_scale(ot, sign / len_ot);
+                }
+                t.group_with_any = !(not_zero(len_os / area) && not_zero(len_ot / area));
+            }
+            t.os = os;
+            t.ot = ot;
+        }
+
+        build_neighbors(&mut triangles);
+
+        let mut groups: Vec<Group> = Vec::new();
+        for f in 0..triangles.len() {
+            for i in 0..3 {
+                if triangles[f].group_with_any || triangles[f].groups[i].is_some() {
+                    continue;
+                }
+                let g = groups.len();
+                let mut group = Group {
+                    vertex: triangles[f].verts[i],
+                    orient_preserving: triangles[f].orient_preserving,
+                    faces: vec![f],
+                };
+                triangles[f].groups[i] = Some(g);
+                let neighbors = [
+                    triangles[f].neighbors[i],
+                    triangles[f].neighbors[(i + 2) % 3],
+                ];
+                for neighbor in neighbors.into_iter().flatten() {
+                    assign(&mut triangles, neighbor, &mut group, g);
+                }
+                groups.push(group);
+            }
+        }
+
+        // Within a group, faces whose tangent directions disagree by more than the threshold
+        // form separate subgroups; each distinct subgroup is evaluated once.
+        let mut tspaces = vec![([1.0, 0.0, 0.0], false); corner_count];
+        for (g, group) in groups.iter().enumerate() {
+            let mut subgroups: Vec<(Vec<usize>, [f32; 3])> = Vec::new();
+            for &f in &group.faces {
+                let tri = &triangles[f];
+                let index = match tri.groups.iter().position(|&x| x == Some(g)) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                let n = normals[tri.verts[index]];
+                let os = project(n, tri.os);
+                let ot = project(n, tri.ot);
+                let mut members: Vec<usize> = group
+                    .faces
+                    .iter()
+                    .copied()
+                    .filter(|&t| {
+                        let other = &triangles[t];
+                        tri.group_with_any
+                            || other.group_with_any
+                            || t == f
+                            || (vec3_dot(os, project(n, other.os)) > THRESHOLD_COS
+                                && vec3_dot(ot, project(n, other.ot)) > THRESHOLD_COS)
+                    })
+                    .collect();
+                members.sort_unstable();
+                let tangent = match subgroups.iter().find(|(m, _)| *m == members) {
+                    Some(&(_, tangent)) => tangent,
+                    None => {
+                        let tangent =
+                            eval_tspace(&members, &triangles, group.vertex, &positions, &normals);
+                        subgroups.push((members, tangent));
+                        tangent
+                    }
+                };
+                tspaces[tri.face * 3 + index] = (tangent, group.orient_preserving);
+            }
+        }
+
+        // Degenerate corners copy the first healthy corner welded to them.
+        let mut healthy: HashMap<usize, usize> = HashMap::new();
+        for t in &triangles {
+            for (i, &v) in t.verts.iter().enumerate() {
+                healthy.entry(v).or_insert(t.face * 3 + i);
+            }
+        }
+        for t in &degenerate {
+            for (i, v) in t.verts.iter().enumerate() {
+                if let Some(&c) = healthy.get(v) {
+                    tspaces[t.face * 3 + i] = tspaces[c];
+                }
+            }
+        }
+
+        let corner_tangents: Vec<[f32; 4]> = tspaces
+            .iter()
+            .map(|&([x, y, z], orient)| [x, y, z, if orient { 1.0 } else { -1.0 }])
+            .collect();
+        mesh.tangents = mesh.split_corners(&corner_tangents);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::f32::consts::{PI, TAU};
+
+        use super::*;
+
+        // Tangents of every corner, from bevy_mikktspace 0.15.3 (a port of mikktspace.c) run
+        // on the meshes below, rounded to five decimals.
+        const MIRRORED_GRID: [[f32; 4]; 36] = [
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.99506, 0.00245, -0.09926, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.99506, 0.00245, -0.09926, -1.0],
+            [-0.99504, 0.00000, -0.09950, -1.0],
+            [0.99999, 0.00490, 0.00049, 1.0],
+            [0.99999, 0.00469, 0.00094, 1.0],
+            [0.99504, -0.00007, 0.09949, 1.0],
+            [0.99999, 0.00490, 0.00049, 1.0],
+            [0.99504, -0.00007, 0.09949, 1.0],
+            [0.99501, 0.00240, 0.09974, 1.0],
+            [0.99999, 0.00469, 0.00094, 1.0],
+            [1.00000, 0.00000, 0.00000, 1.0],
+            [0.99521, -0.00664, 0.09753, 1.0],
+            [0.99999, 0.00469, 0.00094, 1.0],
+            [0.99521, -0.00664, 0.09753, 1.0],
+            [0.99504, -0.00007, 0.09949, 1.0],
+            [-0.99504, 0.00000, -0.09950, -1.0],
+            [-0.99506, 0.00245, -0.09926, -1.0],
+            [-0.98066, 0.00478, -0.19565, -1.0],
+            [-0.99504, 0.00000, -0.09950, -1.0],
+            [-0.98066, 0.00478, -0.19565, -1.0],
+            [-0.98058, 0.00000, -0.19612, -1.0],
+            [0.99501, 0.00240, 0.09974, 1.0],
+            [0.99504, -0.00007, 0.09949, 1.0],
+            [0.98074, -0.00451, 0.19525, 1.0],
+            [0.99501, 0.00240, 0.09974, 1.0],
+            [0.98074, -0.00451, 0.19525, 1.0],
+            [0.98058, 0.00000, 0.19612, 1.0],
+            [0.99504, -0.00007, 0.09949, 1.0],
+            [0.99521, -0.00664, 0.09753, 1.0],
+            [0.98125, -0.01315, 0.19230, 1.0],
+            [0.99504, -0.00007, 0.09949, 1.0],
+            [0.98125, -0.01315, 0.19230, 1.0],
+            [0.98074, -0.00451, 0.19525, 1.0],
+        ];
+        const DEGENERATE_UV_QUAD: [[f32; 4]; 6] = [
+            [1.00000, 0.00000, 0.00000, 1.0],
+            [1.00000, 0.00000, 0.00000, 1.0],
+            [1.00000, 0.00000, 0.00000, 1.0],
+            [1.00000, 0.00000, 0.00000, 1.0],
+            [1.00000, 0.00000, 0.00000, 1.0],
+            [1.00000, 0.00000, 0.00000, -1.0],
+        ];
+        const UV_SPHERE: [[f32; 4]; 72] = [
+            [0.86603, 0.00000, -0.50000, -1.0],
+            [0.96077, 0.24019, -0.13868, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.00000, 0.00000, -1.00000, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-0.86603, 0.00000, -0.50000, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.86603, 0.00000, 0.50000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [0.00000, 0.00000, 1.00000, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.86603, 0.00000, 0.50000, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.96077, -0.24019, 0.13868, -1.0],
+            [0.96077, 0.24019, -0.13868, -1.0],
+            [0.96077, -0.24019, -0.13868, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.96077, 0.24019, -0.13868, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.96077, 0.24019, 0.13868, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.96077, 0.24019, 0.13868, -1.0],
+            [0.96077, -0.24019, 0.13868, -1.0],
+            [0.96077, -0.24019, -0.13868, -1.0],
+            [0.86603, 0.00000, -0.50000, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.50000, 0.00000, -0.86603, -1.0],
+            [0.00000, 0.00000, -1.00000, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-0.50000, 0.00000, -0.86603, -1.0],
+            [-0.86603, 0.00000, -0.50000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-1.00000, 0.00000, 0.00000, -1.0],
+            [-0.86603, 0.00000, 0.50000, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [-0.50000, 0.00000, 0.86603, -1.0],
+            [0.00000, 0.00000, 1.00000, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.50000, 0.00000, 0.86603, -1.0],
+            [0.86603, 0.00000, 0.50000, -1.0],
+            [0.96077, 0.24019, 0.13868, -1.0],
+        ];
+
+        /// A bumpy grid whose u runs backwards left of x = 1, so the vertices there are
+        /// shared by faces of both orientations.
+        fn mirrored_grid() -> MeshData {
+            let mut mesh = MeshData::default();
+            for y in 0..3 {
+                for x in 0..4 {
+                    let (x, y) = (x as f32, y as f32);
+                    mesh.positions.push([x, y, 0.1 * x * y]);
+                    let n = [-0.1 * y, -0.1 * x, 1.0];
+                    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                    mesh.normals.push(n.map(|c| c / length));
+                    mesh.tex_coords0.push([(x - 1.0).abs(), 0.5 * y]);
+                }
+            }
+            for y in 0..2 {
+                for x in 0..3 {
+                    let v = y * 4 + x;
+                    mesh.indices
+                        .extend_from_slice(&[v, v + 1, v + 5, v, v + 5, v + 4]);
+                }
+            }
+            mesh
+        }
+
+        /// The second triangle has all of its UVs on a line.
+        fn degenerate_uv_quad() -> MeshData {
+            MeshData {
+                positions: vec![
+                    [0.0, 0.0, 0.0],
+                    [1.0, 0.0, 0.0],
+                    [1.0, 1.0, 0.0],
+                    [0.0, 1.0, 0.0],
+                ],
+                normals: vec![[0.0, 0.0, 1.0]; 4],
+                tex_coords0: vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [1.0, 1.0]],
+                indices: vec![0, 1, 2, 0, 2, 3],
+                ..Default::default()
+            }
+        }
+
+        /// Unit sphere of `segments` around by `rings` from pole to pole, u running around
+        /// from +z towards +x and v from the top. Pole vertices sit half a segment on and
+        /// the degenerate triangles touching the poles are left out.
+        fn uv_sphere(segments: u32, rings: u32) -> MeshData {
+            let mut mesh = MeshData::default();
+            for ring in 0..=rings {
+                let v = ring as f32 / rings as f32;
+                let pole = ring == 0 || ring == rings;
+                let (sin_polar, cos_polar) = if pole {
+                    (0.0, 1.0 - 2.0 * v)
+                } else {
+                    (PI * v).sin_cos()
+                };
+                for column in 0..=segments {
+                    let mut u = column as f32 / segments as f32;
+                    if pole {
+                        u += 0.5 / segments as f32;
+                    }
+                    let (sin, cos) = (TAU * u).sin_cos();
+                    let p = [sin_polar * sin, cos_polar, sin_polar * cos];
+                    mesh.positions.push(p);
+                    mesh.normals.push(p);
+                    mesh.tex_coords0.push([u, v]);
+                }
+            }
+            let index = |column: u32, row: u32| row * (segments + 1) + column;
+            for row in 0..rings {
+                for column in 0..segments {
+                    let top_left = index(column, row);
+                    let bottom_left = index(column, row + 1);
+                    let bottom_right = index(column + 1, row + 1);
+                    let top_right = index(column + 1, row);
+                    if row != rings - 1 {
+                        mesh.indices.extend([top_left, bottom_left, bottom_right]);
+                    }
+                    if row != 0 {
+                        mesh.indices.extend([top_left, bottom_right, top_right]);
+                    }
+                }
+            }
+            mesh
+        }
+
+        fn assert_matches_reference(mut mesh: MeshData, expected: &[[f32; 4]]) {
+            mesh.tangents.clear();
+            let vertex_count = mesh.vertex_count();
+            generate_tangents(&mut mesh, 0);
+            assert_eq!(mesh.tangents.len(), mesh.vertex_count());
+            assert!(mesh.vertex_count() >= vertex_count);
+            for (corner, (&v, e)) in mesh.indices.iter().zip(expected).enumerate() {
+                let t = mesh.tangents[v as usize];
+                let close = (0..3).all(|c| (t[c] - e[c]).abs() < 1e-4);
+                assert!(
+                    close && t[3] == e[3],
+                    "corner {}: {:?}, expected {:?}",
+                    corner,
+                    t,
+                    e
+                );
+            }
+        }
+
+        #[test]
+        fn mirrored_grid_matches_reference() {
+            assert_matches_reference(mirrored_grid(), &MIRRORED_GRID);
+        }
+
+        #[test]
+        fn degenerate_uvs_match_reference() {
+            assert_matches_reference(degenerate_uv_quad(), &DEGENERATE_UV_QUAD);
+        }
+
+        #[test]
+        fn uv_sphere_matches_reference() {
+            assert_matches_reference(uv_sphere(6, 3), &UV_SPHERE);
+        }
+
+        #[test]
+        fn mirrored_vertices_are_split() {
+            let source = mirrored_grid();
+            let mut mesh = mirrored_grid();
+            generate_tangents(&mut mesh, 0);
+            // The x = 1 column is shared by both orientations.
+            assert_eq!(mesh.vertex_count(), source.vertex_count() + 3);
+            for (&a, &b) in mesh.indices.iter().zip(&source.indices) {
+                assert_eq!(mesh.positions[a as usize], source.positions[b as usize]);
+                assert_eq!(mesh.tex_coords0[a as usize], source.tex_coords0[b as usize]);
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+pub mod morph {
+
+    //! Morph target blending. The vertex shader applies the same deltas in
+    //! `apply_morph_targets`, see morph.hlsli.
+
+    use crate::mesh::mesh::MeshData;
+
+    /// Where morph targets are blended and how many may be active per primitive.
+    #[derive(Clone, Copy, Debug)]
+    pub struct MorphSettings {
+        /// Blend on the CPU and re-upload vertices instead of reading deltas in the shader.
+        pub cpu: bool,
+        /// Only the targets with the largest weights are applied. The GPU path is further
+        /// limited by `MAX_MORPH_TARGETS` in morph.hlsli.
+        pub max_active_targets: usize,
+    }
+
+    impl Default for MorphSettings {
+        fn default() -> Self {
+            MorphSettings {
+                cpu: false,
+                max_active_targets: 8,
+            }
+        }
+    }
+
+    /// Up to `max` non-zero weights with the largest magnitude, as `(target, weight)`
+    /// pairs in target order.
+    pub fn active_targets(weights: &[f32], max: usize) -> Vec<(usize, f32)> {
+        let mut active: Vec<(usize, f32)> = weights
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(_, w)| w != 0.0)
+            .collect();
+        active.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()).then(a.0.cmp(&b.0)));
+        active.truncate(max);
+        active.sort_by_key(|&(target, _)| target);
+        active
+    }
+
+    fn add_scaled(v: &mut [f32], delta: &[f32; 3], weight: f32) {
+        for (v, d) in v.iter_mut().zip(delta) {
+            *v += d * weight;
+        }
+    }
+
+    fn normalize(v: &mut [f32]) {
+        let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+        if len > 0.0 {
+            for c in v.iter_mut().take(3) {
+                *c /= len;
+            }
+        }
+    }
+
+    /// Adds the weighted deltas of `active` targets to the base mesh. Blended normals and
+    /// tangents are renormalized; the tangent handedness is kept.
+    pub fn blend(mesh: &MeshData, active: &[(usize, f32)]) -> MeshData {
+        let mut blended = mesh.clone();
+        for &(index, weight) in active {
+            let target = match mesh.morph_targets.get(index) {
+                Some(target) => target,
+                None => continue,
+            };
+            for (p, d) in blended.positions.iter_mut().zip(&target.positions) {
+                add_scaled(p, d, weight);
+            }
+            for (n, d) in blended.normals.iter_mut().zip(&target.normals) {
+                add_scaled(n, d, weight);
+            }
+            for (t, d) in blended.tangents.iter_mut().zip(&target.tangents) {
+                add_scaled(&mut t[..3], d, weight);
+            }
+        }
+        for n in &mut blended.normals {
+            normalize(n);
+        }
+        for t in &mut blended.tangents {
+            normalize(t);
+        }
+        blended
+    }
+
+    /// `float4`s per vertex in `gpu_deltas()`.
+    pub const GPU_DELTAS_PER_VERTEX: usize = 3;
+
+    /// Position, normal and tangent delta of every vertex as `float4`s, one block of
+    /// `GPU_DELTAS_PER_VERTEX * vertex_count` per target, the layout `morph_deltas`
+    /// expects.
+    pub fn gpu_deltas(mesh: &MeshData) -> Vec<[f32; 4]> {
+        let count = mesh.vertex_count();
+        let mut deltas =
+            Vec::with_capacity(mesh.morph_targets.len() * count * GPU_DELTAS_PER_VERTEX);
+        for target in &mesh.morph_targets {
+            for i in 0..count {
+                let p = target.positions.get(i).copied().unwrap_or_default();
+                let n = target.normals.get(i).copied().unwrap_or_default();
+                let t = target.tangents.get(i).copied().unwrap_or_default();
+                deltas.push([p[0], p[1], p[2], 0.0]);
+                deltas.push([n[0], n[1], n[2], 0.0]);
+                deltas.push([t[0], t[1], t[2], 0.0]);
+            }
+        }
+        deltas
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::mesh::mesh::MorphTarget;
+
+        /// Two vertices; target 0 moves them up and tilts the normals, target 1 only moves
+        /// the second vertex and turns its tangent.
+        fn mesh() -> MeshData {
+            MeshData {
+                positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]],
+                normals: vec![[0.0, 0.0, 1.0]; 2],
+                tangents: vec![[1.0, 0.0, 0.0, -1.0]; 2],
+                morph_targets: vec![
+                    MorphTarget {
+                        positions: vec![[0.0, 1.0, 0.0]; 2],
+                        normals: vec![[1.0, 0.0, -1.0]; 2],
+                        tangents: Vec::new(),
+                    },
+                    MorphTarget {
+                        positions: vec![[0.0, 0.0, 0.0], [2.0, 0.0, 0.0]],
+                        normals: Vec::new(),
+                        tangents: vec![[0.0, 0.0, 0.0], [-1.0, 1.0, 0.0]],
+                    },
+                ],
+                ..Default::default()
+            }
+        }
+
+        fn assert_near(a: &[f32], b: &[f32]) {
+            for (x, y) in a.iter().zip(b) {
+                assert!((x - y).abs() < 1e-6, "{:?} vs {:?}", a, b);
+            }
+        }
+
+        #[test]
+        fn active_targets_keep_the_largest_weights_in_target_order() {
+            let weights = [0.1, 0.0, -0.9, 0.5, 0.3];
+            assert_eq!(active_targets(&weights, 2), [(2, -0.9), (3, 0.5)]);
+            assert_eq!(active_targets(&weights, 8).len(), 4);
+            assert!(active_targets(&[0.0; 3], 8).is_empty());
+            // Ties go to the lower target.
+            assert_eq!(active_targets(&[0.5, 0.5], 1), [(0, 0.5)]);
+        }
+
+        #[test]
+        fn blending_adds_weighted_deltas() {
+            let blended = blend(&mesh(), &[(0, 0.5), (1, 0.25)]);
+            assert_eq!(blended.positions, [[0.0, 0.5, 0.0], [1.5, 0.5, 0.0]]);
+            // (0.5, 0, 0.5) renormalized.
+            let half = std::f32::consts::FRAC_1_SQRT_2;
+            assert_near(&blended.normals[0], &[half, 0.0, half]);
+            // (0.75, 0.25, 0) renormalized, handedness kept.
+            let len = (0.75f32 * 0.75 + 0.25 * 0.25).sqrt();
+            assert_near(&blended.tangents[1], &[0.75 / len, 0.25 / len, 0.0, -1.0]);
+            assert_eq!(blended.tangents[0], [1.0, 0.0, 0.0, -1.0]);
+        }
+
+        #[test]
+        fn zero_and_missing_targets_leave_the_mesh_alone() {
+            let source = mesh();
+            for active in [&[][..], &[(0, 0.0)], &[(5, 1.0)]] {
+                let blended = blend(&source, active);
+                assert_eq!(blended.positions, source.positions);
+                assert_eq!(blended.normals, source.normals);
+                assert_eq!(blended.tangents, source.tangents);
+            }
+        }
+
+        #[test]
+        fn gpu_deltas_sum_like_the_cpu_blend() {
+            let source = mesh();
+            let deltas = gpu_deltas(&source);
+            assert_eq!(deltas.len(), 2 * 2 * GPU_DELTAS_PER_VERTEX);
+
+            // apply_morph_targets() in morph.hlsli, then renormalized like blend().
+            let active = [(0, 0.5), (1, 0.25)];
+            let blended = blend(&source, &active);
+            for vertex in 0..2 {
+                let mut position = source.positions[vertex];
+                let mut normal = source.normals[vertex];
+                let mut tangent = source.tangents[vertex];
+                for &(target, weight) in &active {
+                    let base = (target * 2 + vertex) * GPU_DELTAS_PER_VERTEX;
+                    for c in 0..3 {
+                        position[c] += deltas[base][c] * weight;
+                        normal[c] += deltas[base + 1][c] * weight;
+                        tangent[c] += deltas[base + 2][c] * weight;
+                    }
+                }
+                normalize(&mut normal);
+                normalize(&mut tangent);
+                assert_near(&position, &blended.positions[vertex]);
+                assert_near(&normal, &blended.normals[vertex]);
+                assert_near(&tangent, &blended.tangents[vertex]);
+            }
+        }
+    }
+}
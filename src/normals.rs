@@ -0,0 +1,191 @@
+pub mod normals {
+
+    //! Normals for primitives that come without them. The glTF spec asks for flat normals;
+    //! a crease angle smooths across edges whose faces meet at less than it.
+
+    use std::collections::HashMap;
+
+    use crate::math::math::{vec3_add, vec3_cross, vec3_dot, vec3_normalize, vec3_scale, vec3_sub};
+    use crate::mesh::mesh::MeshData;
+
+    /// How missing normals are generated.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct NormalSettings {
+        /// Faces whose normals differ by less than this, in radians, share smoothed normals
+        /// where they touch. Zero gives flat normals.
+        pub crease_angle: f32,
+    }
+
+    /// Used by degenerate faces with nothing to smooth with.
+    const FALLBACK: [f32; 3] = [0.0, 0.0, 1.0];
+
+    /// Equal for equal positions, treating 0.0 and -0.0 alike.
+    fn position_key(p: [f32; 3]) -> [u32; 3] {
+        p.map(|c| (c + 0.0).to_bits())
+    }
+
+    /// Interior angle of triangle `p` at corner `i`.
+    fn corner_angle(p: [[f32; 3]; 3], i: usize) -> f32 {
+        let a = vec3_normalize(vec3_sub(p[(i + 1) % 3], p[i]));
+        let b = vec3_normalize(vec3_sub(p[(i + 2) % 3], p[i]));
+        vec3_dot(a, b).clamp(-1.0, 1.0).acos()
+    }
+
+    /// Fills `mesh.normals`. Smoothed normals are angle weighted and shared by every corner
+    /// at the same position, so seams in other attributes don't show up in the shading.
+    /// Vertices that need different normals on different faces are duplicated.
+    pub fn generate_normals(mesh: &mut MeshData, settings: NormalSettings) {
+        let triangles: Vec<[[f32; 3]; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [0, 1, 2].map(|i| mesh.positions[t[i] as usize]))
+            .collect();
+        let face_normals: Vec<[f32; 3]> = triangles
+            .iter()
+            .map(|p| vec3_normalize(vec3_cross(vec3_sub(p[1], p[0]), vec3_sub(p[2], p[0]))))
+            .collect();
+
+        let corner_normals: Vec<[f32; 3]> = if settings.crease_angle <= 0.0 {
+            face_normals.iter().flat_map(|&n| [n; 3]).collect()
+        } else {
+            let mut shared: HashMap<[u32; 3], Vec<(usize, usize)>> = HashMap::new();
+            for (f, p) in triangles.iter().enumerate() {
+                for (i, &position) in p.iter().enumerate() {
+                    shared
+                        .entry(position_key(position))
+                        .or_default()
+                        .push((f, i));
+                }
+            }
+
+            let threshold = settings.crease_angle.cos();
+            let mut normals = Vec::with_capacity(triangles.len() * 3);
+            for (f, p) in triangles.iter().enumerate() {
+                for &position in p {
+                    let mut sum = [0.0; 3];
+                    for &(g, j) in &shared[&position_key(position)] {
+                        if vec3_dot(face_normals[f], face_normals[g]) >= threshold {
+                            let weight = corner_angle(triangles[g], j);
+                            sum = vec3_add(sum, vec3_scale(face_normals[g], weight));
+                        }
+                    }
+                    normals.push(sum);
+                }
+            }
+            normals
+        };
+
+        let corner_normals: Vec<[f32; 3]> = corner_normals
+            .into_iter()
+            .map(|n| {
+                if n == [0.0; 3] {
+                    FALLBACK
+                } else {
+                    vec3_normalize(n)
+                }
+            })
+            .collect();
+        mesh.normals = mesh.split_corners(&corner_normals);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Cube of side two around the origin sharing its eight corners between faces, so
+        /// that vertex `i` sits at -1 or +1 on x, y and z by bits 0, 1 and 2 of `i`.
+        fn shared_cube() -> MeshData {
+            let quads = [
+                [1, 3, 7, 5],
+                [0, 4, 6, 2],
+                [2, 6, 7, 3],
+                [0, 1, 5, 4],
+                [4, 5, 7, 6],
+                [0, 2, 3, 1],
+            ];
+            MeshData {
+                positions: (0..8)
+                    .map(|i| [0, 1, 2].map(|bit| if i >> bit & 1 == 1 { 1.0 } else { -1.0 }))
+                    .collect(),
+                indices: quads
+                    .iter()
+                    .flat_map(|&[a, b, c, d]| [a, b, c, a, c, d])
+                    .collect(),
+                ..Default::default()
+            }
+        }
+
+        fn smooth(degrees: f32) -> NormalSettings {
+            NormalSettings {
+                crease_angle: degrees.to_radians(),
+            }
+        }
+
+        fn assert_near(a: [f32; 3], b: [f32; 3]) {
+            let close = (0..3).all(|c| (a[c] - b[c]).abs() < 1e-5);
+            assert!(close, "{:?} != {:?}", a, b);
+        }
+
+        /// Checks that every corner has the normal of the cube face it belongs to.
+        fn assert_flat(mesh: &MeshData) {
+            for triangle in mesh.indices.chunks_exact(3) {
+                let p = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+                let axis = (0..3).find(|&c| p[0][c] == p[1][c] && p[1][c] == p[2][c]);
+                let axis = axis.unwrap();
+                let mut expected = [0.0; 3];
+                expected[axis] = p[0][axis];
+                for v in triangle {
+                    assert_near(mesh.normals[*v as usize], expected);
+                }
+            }
+        }
+
+        #[test]
+        fn flat_normals_split_every_corner_per_face() {
+            let mut mesh = shared_cube();
+            generate_normals(&mut mesh, NormalSettings::default());
+            assert_eq!(mesh.vertex_count(), 24);
+            assert_eq!(mesh.normals.len(), 24);
+            assert_flat(&mesh);
+        }
+
+        #[test]
+        fn smooth_normals_average_the_faces_around_a_corner() {
+            let mut mesh = shared_cube();
+            generate_normals(&mut mesh, smooth(120.0));
+            assert_eq!(mesh.vertex_count(), 8);
+            for (&p, &n) in mesh.positions.iter().zip(&mesh.normals) {
+                assert_near(n, vec3_normalize(p));
+            }
+        }
+
+        #[test]
+        fn edges_sharper_than_the_crease_angle_stay_hard() {
+            let mut hard = shared_cube();
+            generate_normals(&mut hard, smooth(89.0));
+            assert_eq!(hard.vertex_count(), 24);
+            assert_flat(&hard);
+
+            let mut soft = shared_cube();
+            generate_normals(&mut soft, smooth(91.0));
+            assert_eq!(soft.vertex_count(), 8);
+        }
+
+        #[test]
+        fn winding_decides_the_side_normals_face() {
+            for settings in [NormalSettings::default(), smooth(60.0)] {
+                let mut mesh = MeshData {
+                    positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                    indices: vec![0, 1, 2],
+                    ..Default::default()
+                };
+                generate_normals(&mut mesh, settings);
+                assert_eq!(mesh.normals, vec![[0.0, 0.0, 1.0]; 3]);
+
+                mesh.indices = vec![0, 2, 1];
+                generate_normals(&mut mesh, settings);
+                assert_eq!(mesh.normals, vec![[0.0, 0.0, -1.0]; 3]);
+            }
+        }
+    }
+}
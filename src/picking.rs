@@ -0,0 +1,165 @@
+pub mod picking {
+
+    //! Rays through the cursor and what they hit.
+
+    use glm::{GenSquareMat, Mat4};
+
+    use crate::math::math::{vec3_normalize, vec3_sub};
+
+    /// Origin and unit direction of the ray through `cursor`, in pixels from the top left
+    /// of a `[width, height]` viewport, for a camera with `view_projection` and depth in
+    /// [0, 1]. The origin lies on the near plane. `None` for a singular matrix.
+    pub fn unproject(
+        view_projection: &Mat4,
+        cursor: [f32; 2],
+        viewport: [f32; 2],
+    ) -> Option<([f32; 3], [f32; 3])> {
+        let inverse = view_projection.inverse()?;
+        let x = 2.0 * cursor[0] / viewport[0] - 1.0;
+        let y = 1.0 - 2.0 * cursor[1] / viewport[1];
+        let point = |z: f32| {
+            let p = inverse * glm::vec4(x, y, z, 1.0);
+            [p.x / p.w, p.y / p.w, p.z / p.w]
+        };
+        // Depth 0.5 stays finite for infinite projections, unlike the far plane.
+        let near = point(0.0);
+        Some((near, vec3_normalize(vec3_sub(point(0.5), near))))
+    }
+
+    /// The nearest triangle along a ray.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Pick {
+        /// Index into `Model::primitives`, and the instance of its batch.
+        pub primitive: usize,
+        pub instance: usize,
+        /// Node placing the instance.
+        pub node: Option<usize>,
+        /// glTF mesh and primitive index.
+        pub source: Option<(usize, usize)>,
+        /// Index of the triangle in the primitive's full index list.
+        pub triangle: usize,
+        /// Weights of the triangle's corners at `point`.
+        pub barycentrics: [f32; 3],
+        /// World-space hit point.
+        pub point: [f32; 3],
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::bvh::bvh::Bvh;
+        use crate::camera::camera::{orthographic_rh, perspective_rh};
+        use crate::math::math::trs_matrix;
+        use crate::mesh::mesh::MeshData;
+
+        const VIEWPORT: [f32; 2] = [800.0, 400.0];
+
+        /// Five units up +z looking down -z.
+        fn view() -> Mat4 {
+            trs_matrix([0.0, 0.0, -5.0], [0.0, 0.0, 0.0, 1.0], [1.0; 3])
+        }
+
+        /// Axis-aligned cube of edge `size` around the origin, corners shared between faces.
+        fn cube(size: f32) -> MeshData {
+            let half = size * 0.5;
+            let quads = [
+                [1, 3, 7, 5],
+                [0, 4, 6, 2],
+                [2, 6, 7, 3],
+                [0, 1, 5, 4],
+                [4, 5, 7, 6],
+                [0, 2, 3, 1],
+            ];
+            MeshData {
+                positions: (0..8)
+                    .map(|i| [0, 1, 2].map(|bit| if i >> bit & 1 == 1 { half } else { -half }))
+                    .collect(),
+                indices: quads
+                    .iter()
+                    .flat_map(|&[a, b, c, d]| [a, b, c, a, c, d])
+                    .collect(),
+                ..Default::default()
+            }
+        }
+
+        fn assert_near(a: [f32; 3], b: [f32; 3]) {
+            for c in 0..3 {
+                assert!((a[c] - b[c]).abs() < 1e-4, "{:?} vs {:?}", a, b);
+            }
+        }
+
+        #[test]
+        fn the_center_ray_runs_down_the_view_axis() {
+            for zfar in [Some(100.0), None] {
+                let m = perspective_rh(0.5, 2.0, 1.0, zfar) * view();
+                let (origin, direction) = unproject(&m, [400.0, 200.0], VIEWPORT).unwrap();
+                assert_near(origin, [0.0, 0.0, 4.0]);
+                assert_near(direction, [0.0, 0.0, -1.0]);
+            }
+        }
+
+        #[test]
+        fn corner_rays_follow_the_field_of_view() {
+            let m = perspective_rh(0.5, 2.0, 1.0, Some(100.0)) * view();
+            let (origin, direction) = unproject(&m, [0.0, 0.0], VIEWPORT).unwrap();
+            assert_near(origin, [-1.0, 0.5, 4.0]);
+            assert_near(direction, vec3_normalize([-1.0, 0.5, -1.0]));
+            let (origin, _) = unproject(&m, [800.0, 400.0], VIEWPORT).unwrap();
+            assert_near(origin, [1.0, -0.5, 4.0]);
+        }
+
+        #[test]
+        fn rays_pass_through_the_points_they_were_projected_from() {
+            let m = perspective_rh(0.7, 2.0, 0.1, None) * view();
+            for p in [[0.3, -0.2, 0.0], [-2.0, 1.0, -3.0], [4.0, 0.5, 1.0]] {
+                let clip = m * glm::vec4(p[0], p[1], p[2], 1.0);
+                let cursor = [
+                    (clip.x / clip.w + 1.0) * 0.5 * VIEWPORT[0],
+                    (1.0 - clip.y / clip.w) * 0.5 * VIEWPORT[1],
+                ];
+                let (origin, direction) = unproject(&m, cursor, VIEWPORT).unwrap();
+                let to_point = vec3_sub(p, origin);
+                let t = (0..3).map(|c| to_point[c] * direction[c]).sum::<f32>();
+                assert!(t > 0.0);
+                assert_near(p, [0, 1, 2].map(|c| origin[c] + direction[c] * t));
+            }
+        }
+
+        #[test]
+        fn orthographic_rays_are_parallel() {
+            let m = orthographic_rh(4.0, 2.0, 1.0, 10.0) * view();
+            let (origin, direction) = unproject(&m, [200.0, 100.0], VIEWPORT).unwrap();
+            assert_near(origin, [-2.0, 1.0, 4.0]);
+            assert_near(direction, [0.0, 0.0, -1.0]);
+        }
+
+        #[test]
+        fn singular_matrices_give_no_ray() {
+            let singular = trs_matrix([0.0; 3], [0.0, 0.0, 0.0, 1.0], [0.0; 3]);
+            assert!(unproject(&singular, [0.0; 2], VIEWPORT).is_none());
+        }
+
+        #[test]
+        fn cursor_rays_hit_the_front_face() {
+            let mesh = cube(2.0);
+            let triangles = mesh
+                .indices
+                .chunks_exact(3)
+                .map(|t| [0, 1, 2].map(|i| mesh.positions[t[i] as usize]))
+                .collect();
+            let bvh = Bvh::build(triangles);
+            let m = perspective_rh(0.5, 2.0, 1.0, None) * view();
+            // An eighth of the way from the center to the top left corner.
+            let (origin, direction) = unproject(&m, [350.0, 175.0], VIEWPORT).unwrap();
+            let hit = bvh.intersect_ray(origin, direction, f32::MAX).unwrap();
+            let point = [0, 1, 2].map(|c| origin[c] + direction[c] * hit.t);
+            assert_near(point, [-0.5, 0.25, 1.0]);
+            let t = hit.triangle * 3;
+            let corners = [0, 1, 2].map(|i| mesh.positions[mesh.indices[t + i] as usize]);
+            let weights = [1.0 - hit.u - hit.v, hit.u, hit.v];
+            let from_weights =
+                [0, 1, 2].map(|c| (0..3).map(|i| corners[i][c] * weights[i]).sum::<f32>());
+            assert_near(from_weights, point);
+        }
+    }
+}
@@ -0,0 +1,609 @@
+pub mod scene {
+
+    use std::any::Any;
+    use std::fmt;
+
+    use glm::Mat4;
+    use serde_json::Value;
+
+    use crate::animation::animation::AnimationClip;
+    use crate::camera::camera::Camera;
+    use crate::math::math::{identity, transform_point, trs_matrix};
+    use crate::mesh::mesh::MeshData;
+    use crate::mesh_optimizer::mesh_optimizer::OptimizationReport;
+    use crate::meshlets::meshlets::MeshletStats;
+    use crate::skinning::skinning::{joint_matrices, skin_mesh};
+    use crate::texture_compressor::texture_compressor::RgbaImage;
+
+    /// KHR_texture_transform. `tex_coord` overrides the set chosen by the texture info.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct TextureTransform {
+        pub offset: [f32; 2],
+        pub rotation: f32,
+        pub scale: [f32; 2],
+        pub tex_coord: Option<u32>,
+    }
+
+    impl Default for TextureTransform {
+        fn default() -> Self {
+            TextureTransform {
+                offset: [0.0, 0.0],
+                rotation: 0.0,
+                scale: [1.0, 1.0],
+                tex_coord: None,
+            }
+        }
+    }
+
+    impl TextureTransform {
+        /// Top two rows of `translation * rotation * scale` as defined by the extension.
+        pub fn rows(&self) -> [[f32; 3]; 2] {
+            let (s, c) = self.rotation.sin_cos();
+            [
+                [c * self.scale[0], s * self.scale[1], self.offset[0]],
+                [-s * self.scale[0], c * self.scale[1], self.offset[1]],
+            ]
+        }
+
+        pub fn apply(&self, uv: [f32; 2]) -> [f32; 2] {
+            let m = self.rows();
+            [
+                m[0][0] * uv[0] + m[0][1] * uv[1] + m[0][2],
+                m[1][0] * uv[0] + m[1][1] * uv[1] + m[1][2],
+            ]
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct TextureSlot {
+        /// Index into `Scene::images`.
+        pub image: usize,
+        pub tex_coord: u32,
+        pub transform: TextureTransform,
+    }
+
+    impl TextureSlot {
+        /// The UV set actually sampled once the transform override is applied.
+        pub fn uv_set(&self) -> u32 {
+            self.transform.tex_coord.unwrap_or(self.tex_coord)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum AlphaMode {
+        Opaque,
+        Mask,
+        Blend,
+    }
+
+    /// KHR_materials_clearcoat.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Clearcoat {
+        pub factor: f32,
+        pub texture: Option<TextureSlot>,
+        pub roughness_factor: f32,
+        pub roughness_texture: Option<TextureSlot>,
+        pub normal_texture: Option<TextureSlot>,
+        pub normal_scale: f32,
+    }
+
+    /// KHR_materials_sheen.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Sheen {
+        pub color_factor: [f32; 3],
+        pub color_texture: Option<TextureSlot>,
+        pub roughness_factor: f32,
+        pub roughness_texture: Option<TextureSlot>,
+    }
+
+    /// KHR_materials_transmission.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Transmission {
+        pub factor: f32,
+        pub texture: Option<TextureSlot>,
+    }
+
+    /// KHR_materials_volume. `attenuation_distance` is infinite when not authored.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Volume {
+        pub thickness_factor: f32,
+        pub thickness_texture: Option<TextureSlot>,
+        pub attenuation_distance: f32,
+        pub attenuation_color: [f32; 3],
+    }
+
+    /// KHR_materials_specular.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Specular {
+        pub factor: f32,
+        pub texture: Option<TextureSlot>,
+        pub color_factor: [f32; 3],
+        pub color_texture: Option<TextureSlot>,
+    }
+
+    pub const TEXTURE_SLOT_COUNT: usize = 14;
+
+    #[derive(Clone, Debug)]
+    pub struct Material {
+        pub name: Option<String>,
+        pub base_color_factor: [f32; 4],
+        pub base_color_texture: Option<TextureSlot>,
+        pub metallic_factor: f32,
+        pub roughness_factor: f32,
+        pub metallic_roughness_texture: Option<TextureSlot>,
+        pub normal_texture: Option<TextureSlot>,
+        pub normal_scale: f32,
+        pub occlusion_texture: Option<TextureSlot>,
+        pub occlusion_strength: f32,
+        pub emissive_factor: [f32; 3],
+        pub emissive_texture: Option<TextureSlot>,
+        /// KHR_materials_emissive_strength, multiplies `emissive_factor`.
+        pub emissive_strength: f32,
+        /// KHR_materials_unlit.
+        pub unlit: bool,
+        pub alpha_mode: AlphaMode,
+        pub alpha_cutoff: f32,
+        pub double_sided: bool,
+        /// KHR_materials_ior, 1.5 when not authored.
+        pub ior: f32,
+        pub clearcoat: Option<Clearcoat>,
+        pub sheen: Option<Sheen>,
+        pub transmission: Option<Transmission>,
+        pub volume: Option<Volume>,
+        pub specular: Option<Specular>,
+        /// Application data from the material's `extras`, `Null` when absent.
+        pub extras: Value,
+    }
+
+    impl Default for Material {
+        fn default() -> Self {
+            Material {
+                name: None,
+                base_color_factor: [1.0, 1.0, 1.0, 1.0],
+                base_color_texture: None,
+                metallic_factor: 1.0,
+                roughness_factor: 1.0,
+                metallic_roughness_texture: None,
+                normal_texture: None,
+                normal_scale: 1.0,
+                occlusion_texture: None,
+                occlusion_strength: 1.0,
+                emissive_factor: [0.0, 0.0, 0.0],
+                emissive_texture: None,
+                emissive_strength: 1.0,
+                unlit: false,
+                alpha_mode: AlphaMode::Opaque,
+                alpha_cutoff: 0.5,
+                double_sided: false,
+                ior: 1.5,
+                clearcoat: None,
+                sheen: None,
+                transmission: None,
+                volume: None,
+                specular: None,
+                extras: Value::Null,
+            }
+        }
+    }
+
+    impl Material {
+        /// Texture slots in shader register order (t0..t13).
+        pub fn texture_slots(&self) -> [Option<TextureSlot>; TEXTURE_SLOT_COUNT] {
+            let clearcoat = self.clearcoat.unwrap_or_default();
+            let sheen = self.sheen.unwrap_or_default();
+            [
+                self.base_color_texture,
+                self.metallic_roughness_texture,
+                self.normal_texture,
+                self.occlusion_texture,
+                self.emissive_texture,
+                clearcoat.texture,
+                clearcoat.roughness_texture,
+                clearcoat.normal_texture,
+                sheen.color_texture,
+                sheen.roughness_texture,
+                self.transmission.and_then(|t| t.texture),
+                self.volume.and_then(|v| v.thickness_texture),
+                self.specular.and_then(|s| s.texture),
+                self.specular.and_then(|s| s.color_texture),
+            ]
+        }
+    }
+
+    /// Local transform of a node. Rotation is a unit quaternion `[x, y, z, w]`.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct NodeTransform {
+        pub translation: [f32; 3],
+        pub rotation: [f32; 4],
+        pub scale: [f32; 3],
+    }
+
+    impl Default for NodeTransform {
+        fn default() -> Self {
+            NodeTransform {
+                translation: [0.0, 0.0, 0.0],
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                scale: [1.0, 1.0, 1.0],
+            }
+        }
+    }
+
+    impl NodeTransform {
+        pub fn matrix(&self) -> Mat4 {
+            trs_matrix(self.translation, self.rotation, self.scale)
+        }
+    }
+
+    /// A glTF node. Indices match the document's node indices.
+    #[derive(Clone, Debug)]
+    pub struct Node {
+        pub name: Option<String>,
+        pub parent: Option<usize>,
+        pub children: Vec<usize>,
+        /// Rest pose, before any animation.
+        pub transform: NodeTransform,
+        /// Morph target weights of the node's mesh, empty without morph targets.
+        pub weights: Vec<f32>,
+        /// Application data from the node's `extras`, `Null` when absent.
+        pub extras: Value,
+    }
+
+    /// A glTF mesh; its primitives are in `Scene::primitives` with a matching `source`.
+    #[derive(Clone, Debug)]
+    pub struct Mesh {
+        pub name: Option<String>,
+        /// Application data from the mesh's `extras`, `Null` when absent.
+        pub extras: Value,
+    }
+
+    /// The glTF object a vendor extension was attached to, by document index.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum ExtensionTarget {
+        Scene(usize),
+        Node(usize),
+        Mesh(usize),
+        Material(usize),
+    }
+
+    /// A vendor extension parsed by an `ExtensionHandler` into an application type.
+    pub struct Component {
+        pub target: ExtensionTarget,
+        pub data: Box<dyn Any>,
+    }
+
+    /// Animated state of every node, indexed like `Scene::nodes`.
+    #[derive(Clone, Debug, Default)]
+    pub struct Pose {
+        pub transforms: Vec<NodeTransform>,
+        pub weights: Vec<Vec<f32>>,
+    }
+
+    /// Node-to-world transforms for the local transforms `locals`, indexed like `nodes`.
+    pub fn world_transforms(nodes: &[Node], locals: &[NodeTransform]) -> Vec<Mat4> {
+        let mut worlds = vec![identity(); nodes.len()];
+        let mut stack: Vec<(usize, Mat4)> = (0..nodes.len())
+            .filter(|&i| nodes[i].parent.is_none())
+            .map(|i| (i, identity()))
+            .collect();
+        while let Some((i, parent)) = stack.pop() {
+            let world = parent * locals[i].matrix();
+            for &child in &nodes[i].children {
+                stack.push((child, world));
+            }
+            worlds[i] = world;
+        }
+        worlds
+    }
+
+    /// A glTF skin. `joints` index `Scene::nodes`; a missing inverse bind matrix is the
+    /// identity.
+    #[derive(Clone, Debug)]
+    pub struct Skin {
+        pub name: Option<String>,
+        pub joints: Vec<usize>,
+        pub inverse_bind_matrices: Vec<Mat4>,
+    }
+
+    pub struct Primitive {
+        pub mesh: MeshData,
+        /// Index into `Scene::materials`, `None` for the glTF default material.
+        pub material: Option<usize>,
+        /// Index into `Scene::nodes` of the node holding the mesh.
+        pub node: Option<usize>,
+        /// Index into `Scene::skins`. Skinned primitives ignore `world` and `instances`.
+        pub skin: Option<usize>,
+        /// Node-to-world transform in the rest pose.
+        pub world: Mat4,
+        /// glTF mesh and primitive index, equal for every node drawing the same mesh. `None`
+        /// for geometry that did not come from a glTF mesh.
+        pub source: Option<(usize, usize)>,
+        /// EXT_mesh_gpu_instancing transforms, applied before `world`. Empty draws the node
+        /// once.
+        pub instances: Vec<Mat4>,
+        /// KHR_materials_variants `(variant, material)` pairs, indices into
+        /// `Scene::variants` and `Scene::materials`.
+        pub variant_materials: Vec<(usize, usize)>,
+    }
+
+    impl Primitive {
+        /// World transform of every instance for the node transform `world`.
+        pub fn instance_worlds(&self, world: &Mat4) -> Vec<Mat4> {
+            if self.instances.is_empty() {
+                vec![*world]
+            } else {
+                self.instances.iter().map(|m| *world * *m).collect()
+            }
+        }
+
+        /// The material drawn while `variant` is active, `material` for `None` or a variant
+        /// without a mapping.
+        pub fn variant_material(&self, variant: Option<usize>) -> Option<usize> {
+            let mapped = variant.and_then(|variant| {
+                self.variant_materials
+                    .iter()
+                    .find(|&&(v, _)| v == variant)
+                    .map(|&(_, material)| material)
+            });
+            mapped.or(self.material)
+        }
+    }
+
+    /// KHR_lights_punctual light types. Cone angles are in radians.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum LightKind {
+        Directional,
+        Point,
+        Spot {
+            inner_cone_angle: f32,
+            outer_cone_angle: f32,
+        },
+    }
+
+    /// A punctual light placed in world space by its node.
+    #[derive(Clone, Debug)]
+    pub struct Light {
+        pub name: Option<String>,
+        pub kind: LightKind,
+        pub color: [f32; 3],
+        /// Lux for directional lights, candela for point and spot lights.
+        pub intensity: f32,
+        /// `None` means infinite range.
+        pub range: Option<f32>,
+        pub position: [f32; 3],
+        /// Direction the light travels in, the node's -Z axis.
+        pub direction: [f32; 3],
+    }
+
+    impl Light {
+        /// Stand-in for files without lights, shining down and away from the viewer.
+        pub fn key_light() -> Self {
+            let d = [-0.3f32, -0.8, 0.5];
+            let len = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+            Light {
+                name: Some("default key light".to_string()),
+                kind: LightKind::Directional,
+                color: [1.0, 1.0, 1.0],
+                intensity: 3.0,
+                range: None,
+                position: [0.0, 0.0, 0.0],
+                direction: [d[0] / len, d[1] / len, d[2] / len],
+            }
+        }
+    }
+
+    /// What importing did to each glTF mesh primitive, identified by `(mesh, primitive)`.
+    /// Nodes sharing a mesh primitive are reported once.
+    #[derive(Clone, Debug, Default)]
+    pub struct ImportReport {
+        /// Primitives left out of `Scene::primitives`, with the reason.
+        pub skipped: Vec<((usize, usize), String)>,
+        /// Filled when `ImportSettings::optimize` is set.
+        pub optimized: Vec<((usize, usize), OptimizationReport)>,
+        /// Filled when `ImportSettings::meshlets` is set.
+        pub meshlets: Vec<((usize, usize), MeshletStats)>,
+    }
+
+    impl fmt::Display for ImportReport {
+        /// One line per entry.
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for ((mesh, primitive), reason) in &self.skipped {
+                writeln!(
+                    f,
+                    "Skipped primitive {} of mesh {}: {}",
+                    primitive, mesh, reason
+                )?;
+            }
+            for ((mesh, primitive), report) in &self.optimized {
+                writeln!(
+                    f,
+                    "Optimized primitive {} of mesh {}: {}",
+                    primitive, mesh, report
+                )?;
+            }
+            for ((mesh, primitive), stats) in &self.meshlets {
+                writeln!(f, "Primitive {} of mesh {}: {}", primitive, mesh, stats)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Everything the renderer needs from a glTF file, without any GPU objects.
+    #[derive(Default)]
+    pub struct Scene {
+        pub primitives: Vec<Primitive>,
+        pub materials: Vec<Material>,
+        pub images: Vec<RgbaImage>,
+        pub lights: Vec<Light>,
+        pub cameras: Vec<Camera>,
+        pub nodes: Vec<Node>,
+        pub skins: Vec<Skin>,
+        pub animations: Vec<AnimationClip>,
+        /// KHR_materials_variants names.
+        pub variants: Vec<String>,
+        /// Indexed like the document's meshes.
+        pub meshes: Vec<Mesh>,
+        /// Application data from the loaded glTF scene's `extras`, `Null` when absent.
+        pub extras: Value,
+        /// Vendor extensions parsed by registered handlers.
+        pub components: Vec<Component>,
+        pub report: ImportReport,
+    }
+
+    impl Scene {
+        /// The first component of type `T` attached to `target`.
+        pub fn component<T: 'static>(&self, target: ExtensionTarget) -> Option<&T> {
+            self.components
+                .iter()
+                .filter(|component| component.target == target)
+                .find_map(|component| component.data.downcast_ref())
+        }
+
+        /// Every component of type `T` with the object it is attached to.
+        pub fn components<T: 'static>(&self) -> impl Iterator<Item = (ExtensionTarget, &T)> {
+            self.components.iter().filter_map(|component| {
+                let data = component.data.downcast_ref()?;
+                Some((component.target, data))
+            })
+        }
+
+        pub fn rest_pose(&self) -> Pose {
+            Pose {
+                transforms: self.nodes.iter().map(|node| node.transform).collect(),
+                weights: self.nodes.iter().map(|node| node.weights.clone()).collect(),
+            }
+        }
+
+        /// World-space bounding box of every primitive in the rest pose, `None` for an empty
+        /// scene.
+        pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+            let worlds = world_transforms(&self.nodes, &self.rest_pose().transforms);
+            let mut min = [f32::MAX; 3];
+            let mut max = [f32::MIN; 3];
+            for prim in &self.primitives {
+                let positions = match prim.skin {
+                    Some(skin) if prim.mesh.is_skinned() => {
+                        let palette = joint_matrices(&self.skins[skin], &worlds);
+                        skin_mesh(&prim.mesh, &palette).positions
+                    }
+                    _ => prim
+                        .instance_worlds(&prim.world)
+                        .iter()
+                        .flat_map(|world| {
+                            prim.mesh.positions.iter().map(move |p| {
+                                let w = transform_point(world, glm::vec3(p[0], p[1], p[2]));
+                                [w.x, w.y, w.z]
+                            })
+                        })
+                        .collect(),
+                };
+                for w in &positions {
+                    for (c, v) in w.iter().enumerate() {
+                        min[c] = min[c].min(*v);
+                        max[c] = max[c].max(*v);
+                    }
+                }
+            }
+            if min[0] > max[0] {
+                None
+            } else {
+                Some((min, max))
+            }
+        }
+
+        /// Whether `image` holds color data and should be sampled as sRGB.
+        pub fn is_srgb_image(&self, image: usize) -> bool {
+            self.materials.iter().any(|m| {
+                let sheen_color = m.sheen.and_then(|s| s.color_texture);
+                let specular_color = m.specular.and_then(|s| s.color_texture);
+                [
+                    m.base_color_texture,
+                    m.emissive_texture,
+                    sheen_color,
+                    specular_color,
+                ]
+                .iter()
+                .flatten()
+                .any(|slot| slot.image == image)
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn assert_uv(actual: [f32; 2], expected: [f32; 2]) {
+            let close = (0..2).all(|i| (actual[i] - expected[i]).abs() < 1e-6);
+            assert!(close, "{:?} != {:?}", actual, expected);
+        }
+
+        #[test]
+        fn default_transform_keeps_uvs() {
+            let identity = TextureTransform::default();
+            assert_uv(identity.apply([0.25, 0.75]), [0.25, 0.75]);
+        }
+
+        #[test]
+        fn offset_rotation_and_scale_apply_alone() {
+            let offset = TextureTransform {
+                offset: [0.5, -0.25],
+                ..Default::default()
+            };
+            assert_uv(offset.apply([0.25, 0.5]), [0.75, 0.25]);
+
+            let scale = TextureTransform {
+                scale: [2.0, 0.5],
+                ..Default::default()
+            };
+            assert_uv(scale.apply([0.25, 0.5]), [0.5, 0.25]);
+
+            // Positive rotations turn u towards -v, as the extension defines them.
+            let rotation = TextureTransform {
+                rotation: std::f32::consts::FRAC_PI_2,
+                ..Default::default()
+            };
+            assert_uv(rotation.apply([1.0, 0.0]), [0.0, -1.0]);
+            assert_uv(rotation.apply([0.0, 1.0]), [1.0, 0.0]);
+        }
+
+        #[test]
+        fn scale_applies_first_and_offset_last() {
+            let transform = TextureTransform {
+                offset: [0.5, 0.0],
+                rotation: std::f32::consts::FRAC_PI_2,
+                scale: [2.0, 3.0],
+                tex_coord: None,
+            };
+            // (1, 0) scales to (2, 0), rotates to (0, -2) and moves to (0.5, -2).
+            assert_uv(transform.apply([1.0, 0.0]), [0.5, -2.0]);
+            // (0, 1) scales to (0, 3), rotates to (3, 0) and moves to (3.5, 0).
+            assert_uv(transform.apply([0.0, 1.0]), [3.5, 0.0]);
+        }
+
+        #[test]
+        fn variants_pick_their_mapped_material() {
+            let prim = Primitive {
+                mesh: MeshData::default(),
+                material: Some(4),
+                node: None,
+                skin: None,
+                world: identity(),
+                source: None,
+                instances: Vec::new(),
+                variant_materials: vec![(0, 1), (2, 3)],
+            };
+            assert_eq!(prim.variant_material(Some(0)), Some(1));
+            assert_eq!(prim.variant_material(Some(2)), Some(3));
+            // Unmapped variants and switching back keep the default material.
+            assert_eq!(prim.variant_material(Some(1)), Some(4));
+            assert_eq!(prim.variant_material(None), Some(4));
+
+            let default = Primitive {
+                material: None,
+                ..prim
+            };
+            assert_eq!(default.variant_material(Some(0)), Some(1));
+            assert_eq!(default.variant_material(None), None);
+        }
+    }
+}
@@ -1,11 +1,129 @@
 struct vs_in {
     float3 position : POSITION;
+    float3 normal : NORMAL;
+    float2 uv0 : TEXCOORD0;
+    float2 uv1 : TEXCOORD1;
+    // xyz in mesh space, w the bitangent sign. Zero when the mesh has no tangents.
+    float4 tangent : TANGENT;
+    // Matches InstanceData in viewer.rs, read per instance from slot 2. Matrix columns.
+    float4 world0 : INSTANCE_WORLD0;
+    float4 world1 : INSTANCE_WORLD1;
+    float4 world2 : INSTANCE_WORLD2;
+    float4 world3 : INSTANCE_WORLD3;
+    float3 normal0 : INSTANCE_NORMAL0;
+    float3 normal1 : INSTANCE_NORMAL1;
+    float3 normal2 : INSTANCE_NORMAL2;
+    uint vertex_id : SV_VertexID;
+    uint instance_id : SV_InstanceID;
 };
 
 struct ps_in {
     float4 position : SV_POSITION;
+    float3 world_position : WORLD_POSITION;
+    float3 normal : NORMAL;
+    float2 uv0 : TEXCOORD0;
+    float2 uv1 : TEXCOORD1;
+    float4 tangent : TANGENT;
+    nointerpolation uint instance : INSTANCE;
 };
 
 struct ps_out {
     float4 color : SV_TARGET;
-};
\ No newline at end of file
+};
+
+#define SLOT_BASE_COLOR 0
+#define SLOT_METALLIC_ROUGHNESS 1
+#define SLOT_NORMAL 2
+#define SLOT_OCCLUSION 3
+#define SLOT_EMISSIVE 4
+#define SLOT_CLEARCOAT 5
+#define SLOT_CLEARCOAT_ROUGHNESS 6
+#define SLOT_CLEARCOAT_NORMAL 7
+#define SLOT_SHEEN_COLOR 8
+#define SLOT_SHEEN_ROUGHNESS 9
+#define SLOT_TRANSMISSION 10
+#define SLOT_THICKNESS 11
+#define SLOT_SPECULAR 12
+#define SLOT_SPECULAR_COLOR 13
+#define SLOT_COUNT 14
+
+#define MATERIAL_UNLIT (1 << 16)
+#define MATERIAL_ALPHA_MASK (1 << 17)
+
+// Matches FrameConstants in viewer.rs.
+cbuffer frame_constants : register(b3) {
+    float4x4 view_projection;
+    float3 camera_position;
+    float frame_padding;
+};
+
+// Matches MaterialConstants in viewer.rs.
+cbuffer material_constants : register(b0) {
+    float4 base_color_factor;
+    float3 emissive_factor;
+    float emissive_strength;
+    float metallic_factor;
+    float roughness_factor;
+    float alpha_cutoff;
+    uint flags;
+    float normal_scale;
+    float occlusion_strength;
+    float ior;
+    float specular_factor;
+    float3 specular_color_factor;
+    float transmission_factor;
+    float3 sheen_color_factor;
+    float sheen_roughness_factor;
+    float clearcoat_factor;
+    float clearcoat_roughness_factor;
+    float clearcoat_normal_scale;
+    float thickness_factor;
+    float3 attenuation_color;
+    // Zero when the volume has no attenuation.
+    float attenuation_distance;
+    // Two rows of the KHR_texture_transform matrix per slot, w of the first row is the UV set.
+    float4 uv_transform[SLOT_COUNT * 2];
+};
+
+// Matches VertexConstants in viewer.rs. Identity unless the vertices are compressed, see
+// vertex_compression.rs.
+cbuffer vertex_constants : register(b6) {
+    float3 position_offset;
+    uint octahedral_normal;
+    float3 position_scale;
+    float vertex_padding;
+};
+
+// Same as oct_decode() in vertex_compression.rs.
+float3 oct_decode(float2 e) {
+    float3 n = float3(e, 1.0 - abs(e.x) - abs(e.y));
+    float t = saturate(-n.z);
+    n.xy += n.xy >= 0.0 ? -t : t;
+    return normalize(n);
+}
+
+// Turns the stored attributes into mesh-space ones, before morphing and skinning.
+void decode_vertex(inout float3 position, inout float3 normal) {
+    position = position * position_scale + position_offset;
+    if (octahedral_normal != 0) {
+        normal = oct_decode(normal.xy);
+    }
+}
+
+// Matches HighlightConstants in viewer.rs. Alpha is zero unless drawing the selection.
+cbuffer highlight_constants : register(b7) {
+    float4 highlight_color;
+    uint highlight_instance;
+    float3 highlight_padding;
+};
+
+bool has_texture(uint slot) {
+    return (flags & (1u << slot)) != 0;
+}
+
+float2 transform_uv(uint slot, float2 uv0, float2 uv1) {
+    float4 row0 = uv_transform[slot * 2];
+    float4 row1 = uv_transform[slot * 2 + 1];
+    float3 uv = float3(row0.w > 0.5 ? uv1 : uv0, 1.0);
+    return float2(dot(row0.xyz, uv), dot(row1.xyz, uv));
+}
@@ -0,0 +1,155 @@
+// Layered glTF BRDF. brdf.rs holds the CPU reference of every function here.
+
+static const float PI = 3.14159265;
+
+struct shading_params {
+    float3 base_color;
+    float metallic;
+    float roughness;
+    float ior;
+    float specular;
+    float3 specular_color;
+    float transmission;
+    float clearcoat;
+    float clearcoat_roughness;
+    float3 sheen_color;
+    float sheen_roughness;
+    float3 attenuation_color;
+    float attenuation_distance;
+    float thickness;
+};
+
+float max3(float3 v) {
+    return max(v.x, max(v.y, v.z));
+}
+
+float f0_from_ior(float ior) {
+    float r = (ior - 1.0) / (ior + 1.0);
+    return r * r;
+}
+
+float3 fresnel_schlick(float3 f0, float3 f90, float v_dot_h) {
+    return f0 + (f90 - f0) * pow(1.0 - saturate(v_dot_h), 5.0);
+}
+
+float d_ggx(float n_dot_h, float alpha) {
+    float a2 = alpha * alpha;
+    float f = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * f * f);
+}
+
+float v_smith_ggx_correlated(float n_dot_l, float n_dot_v, float alpha) {
+    float a2 = alpha * alpha;
+    float ggx_v = n_dot_l * sqrt(n_dot_v * n_dot_v * (1.0 - a2) + a2);
+    float ggx_l = n_dot_v * sqrt(n_dot_l * n_dot_l * (1.0 - a2) + a2);
+    float denom = ggx_v + ggx_l;
+    return denom > 0.0 ? 0.5 / denom : 0.0;
+}
+
+float d_charlie(float n_dot_h, float sheen_roughness) {
+    float alpha = max(sheen_roughness * sheen_roughness, 1e-4);
+    float inv_alpha = 1.0 / alpha;
+    float sin2 = 1.0 - n_dot_h * n_dot_h;
+    return (2.0 + inv_alpha) * pow(sin2, inv_alpha * 0.5) / (2.0 * PI);
+}
+
+float v_sheen(float n_dot_l, float n_dot_v) {
+    float denom = 4.0 * (n_dot_l + n_dot_v - n_dot_l * n_dot_v);
+    return denom > 0.0 ? saturate(1.0 / denom) : 0.0;
+}
+
+float specular_lobe(float n_dot_l, float n_dot_v, float n_dot_h, float roughness) {
+    float alpha = max(roughness * roughness, 1e-4);
+    return d_ggx(n_dot_h, alpha) * v_smith_ggx_correlated(n_dot_l, n_dot_v, alpha);
+}
+
+// Same as SHEEN_ALBEDO in brdf.rs: directional albedo of the sheen lobe, rows by sheen
+// roughness and columns by n_dot_v.
+#define SHEEN_ALBEDO_SIZE 16
+static const float sheen_albedo_table[SHEEN_ALBEDO_SIZE * SHEEN_ALBEDO_SIZE] = {
+    1.9842, 0.0002, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000,
+    1.9521, 0.8158, 0.2861, 0.0800, 0.0164, 0.0026, 0.0003, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000, 0.0000,
+    1.6450, 1.0565, 0.6370, 0.3546, 0.1811, 0.0877, 0.0401, 0.0170, 0.0066, 0.0023, 0.0007, 0.0002, 0.0000, 0.0000, 0.0000, 0.0000,
+    1.3462, 0.9901, 0.7014, 0.4756, 0.3100, 0.1979, 0.1233, 0.0744, 0.0430, 0.0236, 0.0120, 0.0056, 0.0022, 0.0007, 0.0002, 0.0000,
+    1.1329, 0.8931, 0.6860, 0.5122, 0.3738, 0.2702, 0.1929, 0.1352, 0.0925, 0.0613, 0.0389, 0.0233, 0.0129, 0.0062, 0.0024, 0.0004,
+    0.9827, 0.8085, 0.6524, 0.5159, 0.4020, 0.3119, 0.2402, 0.1829, 0.1371, 0.1005, 0.0717, 0.0491, 0.0319, 0.0190, 0.0097, 0.0034,
+    0.8743, 0.7406, 0.6177, 0.5074, 0.4126, 0.3349, 0.2707, 0.2172, 0.1723, 0.1346, 0.1030, 0.0767, 0.0548, 0.0370, 0.0225, 0.0112,
+    0.7940, 0.6869, 0.5866, 0.4949, 0.4146, 0.3473, 0.2903, 0.2414, 0.1991, 0.1623, 0.1303, 0.1025, 0.0782, 0.0572, 0.0392, 0.0237,
+    0.7331, 0.6443, 0.5600, 0.4819, 0.4125, 0.3536, 0.3028, 0.2584, 0.2192, 0.1843, 0.1531, 0.1252, 0.1001, 0.0776, 0.0574, 0.0393,
+    0.6860, 0.6103, 0.5377, 0.4697, 0.4088, 0.3564, 0.3109, 0.2705, 0.2343, 0.2016, 0.1719, 0.1448, 0.1198, 0.0969, 0.0758, 0.0563,
+    0.6489, 0.5828, 0.5189, 0.4587, 0.4044, 0.3574, 0.3161, 0.2793, 0.2459, 0.2154, 0.1874, 0.1614, 0.1372, 0.1145, 0.0933, 0.0734,
+    0.6192, 0.5604, 0.5033, 0.4491, 0.3999, 0.3572, 0.3196, 0.2857, 0.2548, 0.2264, 0.2001, 0.1754, 0.1522, 0.1303, 0.1094, 0.0896,
+    0.5951, 0.5420, 0.4901, 0.4407, 0.3957, 0.3565, 0.3218, 0.2905, 0.2619, 0.2354, 0.2106, 0.1873, 0.1652, 0.1441, 0.1240, 0.1046,
+    0.5754, 0.5268, 0.4791, 0.4334, 0.3918, 0.3555, 0.3233, 0.2942, 0.2675, 0.2426, 0.2194, 0.1973, 0.1764, 0.1563, 0.1370, 0.1182,
+    0.5590, 0.5140, 0.4697, 0.4272, 0.3883, 0.3544, 0.3243, 0.2971, 0.2720, 0.2487, 0.2267, 0.2059, 0.1860, 0.1669, 0.1485, 0.1305,
+    0.5453, 0.5032, 0.4617, 0.4217, 0.3852, 0.3533, 0.3250, 0.2993, 0.2757, 0.2537, 0.2330, 0.2133, 0.1944, 0.1762, 0.1586, 0.1414
+};
+
+float sheen_directional_albedo(float n_dot_v, float sheen_roughness) {
+    float2 xy = saturate(float2(n_dot_v, sheen_roughness)) * (SHEEN_ALBEDO_SIZE - 1);
+    uint2 i0 = min((uint2)xy, SHEEN_ALBEDO_SIZE - 2);
+    float2 t = xy - i0;
+    uint base = i0.y * SHEEN_ALBEDO_SIZE + i0.x;
+    float row0 = lerp(sheen_albedo_table[base], sheen_albedo_table[base + 1], t.x);
+    float row1 = lerp(sheen_albedo_table[base + SHEEN_ALBEDO_SIZE],
+        sheen_albedo_table[base + SHEEN_ALBEDO_SIZE + 1], t.x);
+    return lerp(row0, row1, t.y);
+}
+
+float sheen_albedo_scaling(shading_params p, float n_dot_l, float n_dot_v) {
+    float albedo = max(sheen_directional_albedo(n_dot_v, p.sheen_roughness),
+        sheen_directional_albedo(n_dot_l, p.sheen_roughness));
+    return saturate(1.0 - max3(p.sheen_color) * albedo);
+}
+
+float3 volume_attenuation(shading_params p) {
+    if (p.attenuation_distance <= 0.0 || p.thickness == 0.0) {
+        return 1.0;
+    }
+    return pow(p.attenuation_color, p.thickness / p.attenuation_distance);
+}
+
+float3 transmission_lobe(shading_params p, float3 n, float3 v, float3 l) {
+    float3 l_mirror = l - n * (2.0 * dot(l, n));
+    float3 h = normalize(l_mirror + v);
+    float lobe = specular_lobe(saturate(dot(n, l_mirror)), saturate(dot(n, v)),
+        saturate(dot(n, h)), p.roughness);
+    return p.base_color * lobe * volume_attenuation(p);
+}
+
+// Returns the BRDF times the cosine of each layer for a light arriving from l. coat_n is
+// the clearcoat normal.
+float3 evaluate_brdf(shading_params p, float3 n, float3 coat_n, float3 v, float3 l) {
+    float3 h = normalize(l + v);
+    float n_dot_l = dot(n, l);
+    float n_dot_v = clamp(dot(n, v), 1e-4, 1.0);
+    float n_dot_h = saturate(dot(n, h));
+    float v_dot_h = saturate(dot(v, h));
+
+    float3 transmitted = transmission_lobe(p, n, v, l) * p.transmission;
+    if (n_dot_l <= 0.0) {
+        return transmitted * (1.0 - p.metallic) * -n_dot_l;
+    }
+
+    float3 dielectric_f0 = min(p.specular_color * f0_from_ior(p.ior), 1.0) * p.specular;
+    float3 f_dielectric = fresnel_schlick(dielectric_f0, p.specular, v_dot_h);
+    float3 f_metal = fresnel_schlick(p.base_color, 1.0, v_dot_h);
+
+    float specular = specular_lobe(n_dot_l, n_dot_v, n_dot_h, p.roughness);
+    float3 diffuse = p.base_color / PI * (1.0 - p.transmission);
+
+    float3 dielectric = diffuse * (1.0 - f_dielectric) + f_dielectric * specular;
+    float3 metal = f_metal * specular;
+    float3 material = lerp(dielectric, metal, p.metallic);
+
+    float3 sheen = p.sheen_color * d_charlie(n_dot_h, p.sheen_roughness) * v_sheen(n_dot_l, n_dot_v);
+    float3 with_sheen = sheen + material * sheen_albedo_scaling(p, n_dot_l, n_dot_v);
+
+    float coat_n_dot_l = saturate(dot(coat_n, l));
+    float coat_n_dot_v = clamp(dot(coat_n, v), 1e-4, 1.0);
+    float coat_n_dot_h = saturate(dot(coat_n, h));
+    float coat_fresnel = fresnel_schlick(0.04, 1.0, coat_n_dot_v).x * p.clearcoat;
+    float coat = specular_lobe(coat_n_dot_l, coat_n_dot_v, coat_n_dot_h, p.clearcoat_roughness);
+
+    return with_sheen * n_dot_l * (1.0 - coat_fresnel) + coat * coat_fresnel * coat_n_dot_l;
+}
@@ -0,0 +1,54 @@
+// Punctual lights, see GpuLight in viewer.rs and the attenuation reference in brdf.rs.
+
+#define MAX_LIGHTS 16
+
+#define LIGHT_DIRECTIONAL 0
+#define LIGHT_POINT 1
+#define LIGHT_SPOT 2
+
+struct light {
+    float3 position;
+    // Zero for infinite range.
+    float range;
+    float3 direction;
+    uint kind;
+    float3 color;
+    float intensity;
+    float spot_scale;
+    float spot_offset;
+    float2 padding;
+};
+
+cbuffer light_constants : register(b2) {
+    uint light_count;
+    uint3 light_padding;
+    light lights[MAX_LIGHTS];
+};
+
+float range_attenuation(float distance, float range) {
+    float inv_square = 1.0 / max(distance * distance, 1e-4);
+    if (range <= 0.0) {
+        return inv_square;
+    }
+    float ratio = distance / range;
+    return saturate(1.0 - pow(ratio, 4.0)) * inv_square;
+}
+
+// Radiance arriving at world_pos from l, and the normalized direction towards the light.
+float3 incident_light(light l, float3 world_pos, out float3 to_light) {
+    if (l.kind == LIGHT_DIRECTIONAL) {
+        to_light = -l.direction;
+        return l.color * l.intensity;
+    }
+
+    float3 delta = l.position - world_pos;
+    float distance = length(delta);
+    to_light = delta / max(distance, 1e-4);
+    float attenuation = range_attenuation(distance, l.range);
+
+    if (l.kind == LIGHT_SPOT) {
+        float t = saturate(dot(l.direction, -to_light) * l.spot_scale + l.spot_offset);
+        attenuation *= t * t;
+    }
+    return l.color * l.intensity * attenuation;
+}
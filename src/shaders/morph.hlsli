@@ -0,0 +1,34 @@
+#define MAX_MORPH_TARGETS 8
+
+struct morph_target {
+    uint index;
+    float weight;
+    float2 padding;
+};
+
+// Matches MorphConstants in viewer.rs. Primitives without morph targets bind a count of zero.
+cbuffer morph_constants : register(b5) {
+    uint morph_count;
+    uint morph_vertex_count;
+    float2 morph_padding;
+    morph_target morph_targets[MAX_MORPH_TARGETS];
+};
+
+// Position, normal then tangent delta of every vertex, one block per target, see
+// gpu_deltas() in morph.rs.
+Buffer<float4> morph_deltas : register(t0);
+
+// Same as GPU_DELTAS_PER_VERTEX in morph.rs.
+#define MORPH_DELTAS_PER_VERTEX 3
+
+// Same sum as blend() in morph.rs, before renormalizing. The tangent handedness is kept.
+void apply_morph_targets(uint vertex_id, inout float3 position, inout float3 normal,
+                         inout float4 tangent) {
+    for (uint i = 0; i < morph_count; ++i) {
+        uint base = (morph_targets[i].index * morph_vertex_count + vertex_id)
+            * MORPH_DELTAS_PER_VERTEX;
+        position += morph_deltas[base].xyz * morph_targets[i].weight;
+        normal += morph_deltas[base + 1].xyz * morph_targets[i].weight;
+        tangent.xyz += morph_deltas[base + 2].xyz * morph_targets[i].weight;
+    }
+}
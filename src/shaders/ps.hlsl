@@ -1,7 +1,88 @@
 #include "base_pass.hlsli"
+#include "brdf.hlsli"
+#include "lights.hlsli"
+
+Texture2D material_textures[SLOT_COUNT] : register(t0);
+SamplerState linear_sampler : register(s0);
+
+float4 sample_slot(uint slot, ps_in pin, float4 fallback) {
+    if (!has_texture(slot)) {
+        return fallback;
+    }
+    return material_textures[slot].Sample(linear_sampler, transform_uv(slot, pin.uv0, pin.uv1));
+}
+
+// Applies a tangent-space normal map, see the normalTexture description in the glTF spec.
+// Meshes without tangents keep the interpolated normal.
+float3 perturb_normal(uint slot, float scale, ps_in pin, float3 n) {
+    if (!has_texture(slot) || dot(pin.tangent.xyz, pin.tangent.xyz) == 0.0) {
+        return n;
+    }
+    float3 t = normalize(pin.tangent.xyz - n * dot(n, pin.tangent.xyz));
+    float3 b = cross(n, t) * (pin.tangent.w < 0.0 ? -1.0 : 1.0);
+    float3 s = sample_slot(slot, pin, 0.0).xyz * 2.0 - 1.0;
+    s.xy *= scale;
+    return normalize(s.x * t + s.y * b + s.z * n);
+}
+
+float3 highlight(ps_in pin, float3 color) {
+    if (pin.instance != highlight_instance) {
+        return color;
+    }
+    return lerp(color, highlight_color.rgb, highlight_color.a);
+}
 
 ps_out main(ps_in pin) {
     ps_out pout = (ps_out)0;
-    pout.color = float4(1.0, 1.0, 0, 1.0);
+
+    float4 base_color = base_color_factor * sample_slot(SLOT_BASE_COLOR, pin, 1.0);
+
+    if ((flags & MATERIAL_ALPHA_MASK) != 0 && base_color.a < alpha_cutoff) {
+        discard;
+    }
+
+    if ((flags & MATERIAL_UNLIT) != 0) {
+        pout.color = float4(highlight(pin, base_color.rgb), base_color.a);
+        return pout;
+    }
+
+    float4 metallic_roughness = sample_slot(SLOT_METALLIC_ROUGHNESS, pin, 1.0);
+    float4 specular_sample = sample_slot(SLOT_SPECULAR, pin, 1.0);
+
+    shading_params p;
+    p.base_color = base_color.rgb;
+    p.metallic = metallic_factor * metallic_roughness.b;
+    p.roughness = roughness_factor * metallic_roughness.g;
+    p.ior = ior;
+    p.specular = specular_factor * specular_sample.a;
+    p.specular_color = specular_color_factor * sample_slot(SLOT_SPECULAR_COLOR, pin, 1.0).rgb;
+    p.transmission = transmission_factor * sample_slot(SLOT_TRANSMISSION, pin, 1.0).r;
+    p.clearcoat = clearcoat_factor * sample_slot(SLOT_CLEARCOAT, pin, 1.0).r;
+    p.clearcoat_roughness = clearcoat_roughness_factor
+        * sample_slot(SLOT_CLEARCOAT_ROUGHNESS, pin, 1.0).g;
+    p.sheen_color = sheen_color_factor * sample_slot(SLOT_SHEEN_COLOR, pin, 1.0).rgb;
+    p.sheen_roughness = sheen_roughness_factor * sample_slot(SLOT_SHEEN_ROUGHNESS, pin, 1.0).a;
+    p.attenuation_color = attenuation_color;
+    p.attenuation_distance = attenuation_distance;
+    p.thickness = thickness_factor * sample_slot(SLOT_THICKNESS, pin, 1.0).g;
+
+    float3 emissive = emissive_factor * emissive_strength
+        * sample_slot(SLOT_EMISSIVE, pin, 1.0).rgb;
+    float occlusion = lerp(1.0, sample_slot(SLOT_OCCLUSION, pin, 1.0).r, occlusion_strength);
+
+    // Without its own normal texture the clearcoat keeps the geometric normal.
+    float3 geometric_n = normalize(pin.normal);
+    float3 n = perturb_normal(SLOT_NORMAL, normal_scale, pin, geometric_n);
+    float3 coat_n = perturb_normal(
+        SLOT_CLEARCOAT_NORMAL, clearcoat_normal_scale, pin, geometric_n);
+    float3 to_eye = normalize(camera_position - pin.world_position);
+    float3 color = base_color.rgb * 0.03 * occlusion + emissive;
+    for (uint i = 0; i < light_count; ++i) {
+        float3 to_light;
+        float3 radiance = incident_light(lights[i], pin.world_position, to_light);
+        color += evaluate_brdf(p, n, coat_n, to_eye, to_light) * radiance;
+    }
+
+    pout.color = float4(highlight(pin, color), base_color.a);
     return pout;
-}
\ No newline at end of file
+}
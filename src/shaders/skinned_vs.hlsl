@@ -0,0 +1,53 @@
+#include "base_pass.hlsli"
+#include "morph.hlsli"
+
+#define MAX_JOINTS 256
+
+// Matches SkinConstants in viewer.rs. World-space joint matrices, unused entries are zero.
+cbuffer skin_constants : register(b4) {
+    float4x4 joint_matrices[MAX_JOINTS];
+};
+
+struct skinned_vs_in {
+    float3 position : POSITION;
+    float3 normal : NORMAL;
+    float2 uv0 : TEXCOORD0;
+    float2 uv1 : TEXCOORD1;
+    float4 tangent : TANGENT;
+    uint4 joints0 : BLENDINDICES0;
+    uint4 joints1 : BLENDINDICES1;
+    float4 weights0 : BLENDWEIGHT0;
+    float4 weights1 : BLENDWEIGHT1;
+    uint vertex_id : SV_VertexID;
+    uint instance_id : SV_InstanceID;
+};
+
+// Same sum as skin_matrix() in skinning.rs.
+float4x4 skin_matrix(skinned_vs_in vin) {
+    float4x4 m = (float4x4)0;
+    [unroll]
+    for (uint i = 0; i < 4; ++i) {
+        m += joint_matrices[min(vin.joints0[i], MAX_JOINTS - 1)] * vin.weights0[i];
+        m += joint_matrices[min(vin.joints1[i], MAX_JOINTS - 1)] * vin.weights1[i];
+    }
+    return m;
+}
+
+// The skin already places vertices in world space, so there is no instance stream.
+ps_in main(skinned_vs_in vin) {
+    ps_in vout = (ps_in)0;
+    decode_vertex(vin.position, vin.normal);
+    apply_morph_targets(vin.vertex_id, vin.position, vin.normal, vin.tangent);
+    float4x4 skin = skin_matrix(vin);
+    float4 world_position = mul(skin, float4(vin.position, 1.0));
+    vout.position = mul(view_projection, world_position);
+    vout.world_position = world_position.xyz;
+    vout.normal = normalize(mul((float3x3)skin, vin.normal));
+    float3x3 linear_part = (float3x3)skin;
+    vout.tangent.xyz = mul(linear_part, vin.tangent.xyz);
+    vout.tangent.w = vin.tangent.w * sign(determinant(linear_part));
+    vout.uv0 = vin.uv0;
+    vout.uv1 = vin.uv1;
+    vout.instance = vin.instance_id;
+    return vout;
+}
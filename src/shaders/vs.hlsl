@@ -1,7 +1,24 @@
 #include "base_pass.hlsli"
+#include "morph.hlsli"
 
 ps_in main(vs_in vin) {
     ps_in vout = (ps_in)0;
-    vout.position = float4(vin.position, 1.0);
+    decode_vertex(vin.position, vin.normal);
+    apply_morph_targets(vin.vertex_id, vin.position, vin.normal, vin.tangent);
+    float4 world_position = vin.world0 * vin.position.x + vin.world1 * vin.position.y +
+                            vin.world2 * vin.position.z + vin.world3;
+    vout.position = mul(view_projection, world_position);
+    vout.world_position = world_position.xyz;
+    vout.normal = vin.normal0 * vin.normal.x + vin.normal1 * vin.normal.y +
+                  vin.normal2 * vin.normal.z;
+    // Tangents follow the surface, so they take the linear part of the world matrix. A
+    // mirroring transform flips the handedness of the frame.
+    float3x3 linear_part = float3x3(vin.world0.xyz, vin.world1.xyz, vin.world2.xyz);
+    vout.tangent.xyz = vin.world0.xyz * vin.tangent.x + vin.world1.xyz * vin.tangent.y +
+                       vin.world2.xyz * vin.tangent.z;
+    vout.tangent.w = vin.tangent.w * sign(determinant(linear_part));
+    vout.uv0 = vin.uv0;
+    vout.uv1 = vin.uv1;
+    vout.instance = vin.instance_id;
     return vout;
-}
\ No newline at end of file
+}
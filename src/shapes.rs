@@ -0,0 +1,600 @@
+pub mod shapes {
+
+    //! Procedural meshes for default content, debug views and fixtures. Shapes are centred
+    //! on the origin with +y up and come with unit normals, texture coordinates, MikkTSpace
+    //! tangents and triangles wound counter-clockwise seen from outside. Closed shapes are
+    //! watertight: vertices split at seams and creases have identical positions.
+
+    use std::collections::HashMap;
+    use std::f32::consts::TAU;
+
+    use crate::math::math::{vec3_add, vec3_normalize, vec3_scale};
+    use crate::mesh::mesh::MeshData;
+    use crate::mikktspace::mikktspace::generate_tangents;
+
+    /// The generated shapes, by name.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Shape {
+        Plane,
+        Cube,
+        UvSphere,
+        Icosphere,
+        Cylinder,
+        Cone,
+        Torus,
+        Capsule,
+    }
+
+    impl Shape {
+        pub const ALL: [Shape; 8] = [
+            Shape::Plane,
+            Shape::Cube,
+            Shape::UvSphere,
+            Shape::Icosphere,
+            Shape::Cylinder,
+            Shape::Cone,
+            Shape::Torus,
+            Shape::Capsule,
+        ];
+
+        pub fn name(self) -> &'static str {
+            match self {
+                Shape::Plane => "plane",
+                Shape::Cube => "cube",
+                Shape::UvSphere => "uv-sphere",
+                Shape::Icosphere => "icosphere",
+                Shape::Cylinder => "cylinder",
+                Shape::Cone => "cone",
+                Shape::Torus => "torus",
+                Shape::Capsule => "capsule",
+            }
+        }
+
+        pub fn parse(name: &str) -> Option<Self> {
+            Shape::ALL.into_iter().find(|shape| shape.name() == name)
+        }
+
+        /// The shape fitting a unit cube, with enough segments to look smooth.
+        pub fn mesh(self) -> MeshData {
+            match self {
+                Shape::Plane => plane(1.0, 1),
+                Shape::Cube => cube(1.0),
+                Shape::UvSphere => uv_sphere(0.5, 32, 16),
+                Shape::Icosphere => icosphere(0.5, 3),
+                Shape::Cylinder => cylinder(0.5, 1.0, 32),
+                Shape::Cone => cone(0.5, 1.0, 32),
+                Shape::Torus => torus(0.35, 0.15, 48, 24),
+                Shape::Capsule => capsule(0.25, 0.5, 32, 8),
+            }
+        }
+    }
+
+    /// Sine and cosine of `turns` full turns, exact at every quarter turn so that seams,
+    /// poles and rims computed from different angles meet.
+    fn sin_cos(turns: f32) -> (f32, f32) {
+        let quarters = turns * 4.0;
+        if quarters.fract() != 0.0 {
+            return (TAU * turns).sin_cos();
+        }
+        match (quarters as i32).rem_euclid(4) {
+            0 => (0.0, 1.0),
+            1 => (1.0, 0.0),
+            2 => (0.0, -1.0),
+            _ => (-1.0, 0.0),
+        }
+    }
+
+    /// Triangulates a grid of `columns + 1` by `rows + 1` vertices starting at `first`, row
+    /// by row. Triangles face along `dp/dv × dp/du`, the direction from which `u` runs to
+    /// the right and `v` downwards. Triangles with two corners at the same position, as at
+    /// poles and apexes, are left out.
+    fn grid_indices(mesh: &mut MeshData, first: u32, columns: u32, rows: u32) {
+        let index = |column: u32, row: u32| first + row * (columns + 1) + column;
+        for row in 0..rows {
+            for column in 0..columns {
+                let top_left = index(column, row);
+                let bottom_left = index(column, row + 1);
+                let bottom_right = index(column + 1, row + 1);
+                let top_right = index(column + 1, row);
+                for triangle in [
+                    [top_left, bottom_left, bottom_right],
+                    [top_left, bottom_right, top_right],
+                ] {
+                    let [a, b, c] = triangle.map(|i| mesh.positions[i as usize]);
+                    if a != b && b != c && c != a {
+                        mesh.indices.extend(triangle);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends a flat grid of `subdivisions` by `subdivisions` quads whose vertex at
+    /// `(u, v)` in [0, 1]² is placed at `origin + u * right + v * down`. The texture is
+    /// mapped once across it.
+    fn quad_grid(
+        mesh: &mut MeshData,
+        origin: [f32; 3],
+        right: [f32; 3],
+        down: [f32; 3],
+        normal: [f32; 3],
+        subdivisions: u32,
+    ) {
+        let first = mesh.vertex_count() as u32;
+        for row in 0..=subdivisions {
+            for column in 0..=subdivisions {
+                let u = column as f32 / subdivisions as f32;
+                let v = row as f32 / subdivisions as f32;
+                let position =
+                    vec3_add(origin, vec3_add(vec3_scale(right, u), vec3_scale(down, v)));
+                mesh.positions.push(position);
+                mesh.normals.push(normal);
+                mesh.tex_coords0.push([u, v]);
+            }
+        }
+        grid_indices(mesh, first, subdivisions, subdivisions);
+    }
+
+    /// A ring of a surface of revolution about +y.
+    #[derive(Clone, Copy, Debug)]
+    struct ProfilePoint {
+        radius: f32,
+        y: f32,
+        /// Unit normal as its components away from the axis and along +y.
+        normal: [f32; 2],
+        /// Texture coordinate down the profile.
+        v: f32,
+    }
+
+    /// Appends the surface swept by `profile` around +y in `segments` steps. `u` runs once
+    /// around from +z towards +x, so the surface faces outwards when the profile runs
+    /// downwards on the outside. Rings of zero radius take the angle half a segment on,
+    /// making each pole or apex triangle symmetric.
+    fn revolve(mesh: &mut MeshData, segments: u32, profile: &[ProfilePoint]) {
+        let first = mesh.vertex_count() as u32;
+        for point in profile {
+            for column in 0..=segments {
+                let mut u = column as f32 / segments as f32;
+                if point.radius == 0.0 {
+                    u += 0.5 / segments as f32;
+                }
+                let (sin, cos) = sin_cos(u);
+                let [out, up] = point.normal;
+                // Adding zero turns -0.0 into 0.0, so the vertices of a pole share their bits.
+                mesh.positions
+                    .push([point.radius * sin + 0.0, point.y, point.radius * cos + 0.0]);
+                mesh.normals.push([out * sin, up, out * cos]);
+                mesh.tex_coords0.push([u, point.v]);
+            }
+        }
+        grid_indices(mesh, first, segments, profile.len() as u32 - 1);
+    }
+
+    /// Appends a flat disk of `radius` at height `y` as a fan, facing +y or -y, with the
+    /// texture projected along y and upright seen from the front as on the cube.
+    fn disk(mesh: &mut MeshData, radius: f32, y: f32, up: bool, segments: u32) {
+        let sign = if up { 1.0 } else { -1.0 };
+        let center = mesh.vertex_count() as u32;
+        mesh.positions.push([0.0, y, 0.0]);
+        for column in 0..segments {
+            let (sin, cos) = sin_cos(column as f32 / segments as f32);
+            mesh.positions.push([radius * sin, y, radius * cos]);
+        }
+        for _ in 0..=segments {
+            mesh.normals.push([0.0, sign, 0.0]);
+        }
+        let uvs: Vec<[f32; 2]> = mesh.positions[center as usize..]
+            .iter()
+            .map(|&[x, _, z]| [0.5 + x / (2.0 * radius), 0.5 + sign * z / (2.0 * radius)])
+            .collect();
+        mesh.tex_coords0.extend(uvs);
+        for column in 0..segments {
+            let a = center + 1 + column;
+            let b = center + 1 + (column + 1) % segments;
+            let triangle = if up { [center, a, b] } else { [center, b, a] };
+            mesh.indices.extend(triangle);
+        }
+    }
+
+    fn finish(mut mesh: MeshData) -> MeshData {
+        generate_tangents(&mut mesh, 0);
+        mesh
+    }
+
+    /// Square of side `size` in the xz plane facing +y, in `subdivisions` quads along
+    /// each side. Texture coordinates follow +x and +z.
+    pub fn plane(size: f32, subdivisions: u32) -> MeshData {
+        let mut mesh = MeshData::default();
+        let half = size * 0.5;
+        quad_grid(
+            &mut mesh,
+            [-half, 0.0, -half],
+            [size, 0.0, 0.0],
+            [0.0, 0.0, size],
+            [0.0, 1.0, 0.0],
+            subdivisions.max(1),
+        );
+        finish(mesh)
+    }
+
+    /// Cube of side `size` with flat faces, the texture mapped upright on each of the
+    /// sides and on the top and bottom as seen from the front.
+    pub fn cube(size: f32) -> MeshData {
+        // Normal and the directions of increasing u and v, with v × u the normal.
+        const FACES: [[[f32; 3]; 3]; 6] = [
+            [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+            [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, -1.0, 0.0]],
+            [[0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+            [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]],
+            [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+            [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+        ];
+        let mut mesh = MeshData::default();
+        for [normal, right, down] in FACES {
+            let origin = [0, 1, 2].map(|c| (normal[c] - right[c] - down[c]) * 0.5 * size);
+            let (right, down) = (vec3_scale(right, size), vec3_scale(down, size));
+            quad_grid(&mut mesh, origin, right, down, normal, 1);
+        }
+        finish(mesh)
+    }
+
+    /// Sphere of `segments` around by `rings` from pole to pole, with the texture wrapped
+    /// once around it, the seam at +z.
+    pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> MeshData {
+        let rings = rings.max(2);
+        let profile: Vec<ProfilePoint> = (0..=rings)
+            .map(|ring| {
+                let v = ring as f32 / rings as f32;
+                let (sin, cos) = sin_cos(v * 0.5);
+                ProfilePoint {
+                    radius: radius * sin,
+                    y: radius * cos,
+                    normal: [sin, cos],
+                    v,
+                }
+            })
+            .collect();
+        let mut mesh = MeshData::default();
+        revolve(&mut mesh, segments.max(3), &profile);
+        finish(mesh)
+    }
+
+    /// Icosahedron with each triangle split into four `subdivisions` times and projected
+    /// onto the sphere. Texture coordinates are the UV sphere's, with vertices split along
+    /// the seam and poles taking the mean angle of their triangle.
+    pub fn icosphere(radius: f32, subdivisions: u32) -> MeshData {
+        let t = (1.0 + 5f32.sqrt()) * 0.5;
+        let mut directions: Vec<[f32; 3]> = [
+            [-1.0, t, 0.0],
+            [1.0, t, 0.0],
+            [-1.0, -t, 0.0],
+            [1.0, -t, 0.0],
+            [0.0, -1.0, t],
+            [0.0, 1.0, t],
+            [0.0, -1.0, -t],
+            [0.0, 1.0, -t],
+            [t, 0.0, -1.0],
+            [t, 0.0, 1.0],
+            [-t, 0.0, -1.0],
+            [-t, 0.0, 1.0],
+        ]
+        .map(vec3_normalize)
+        .to_vec();
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let m = vec3_add(directions[a as usize], directions[b as usize]);
+                    directions.push(vec3_normalize(m));
+                    (directions.len() - 1) as u32
+                })
+            };
+            triangles = triangles
+                .into_iter()
+                .flat_map(|[a, b, c]| {
+                    let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let mut mesh = MeshData {
+            positions: directions.iter().map(|&d| vec3_scale(d, radius)).collect(),
+            normals: directions.clone(),
+            indices: triangles.iter().flatten().copied().collect(),
+            ..Default::default()
+        };
+        let mut corner_uvs = Vec::with_capacity(mesh.indices.len());
+        for triangle in &triangles {
+            let d = triangle.map(|i| directions[i as usize]);
+            let v = d.map(|d| d[1].clamp(-1.0, 1.0).acos() / TAU * 2.0);
+            let on_axis = d.map(|d| d[0] == 0.0 && d[2] == 0.0);
+            let mut u = d.map(|d| {
+                let u = d[0].atan2(d[2]) / TAU;
+                if u < 0.0 {
+                    u + 1.0
+                } else {
+                    u
+                }
+            });
+            // Corners across the seam from the rest of the triangle move a turn over.
+            let around = (0..3).filter(|&i| !on_axis[i]);
+            let high = around.clone().map(|i| u[i]).fold(0.0, f32::max);
+            let low = around.clone().map(|i| u[i]).fold(1.0, f32::min);
+            for i in around.clone() {
+                if high - low > 0.5 && u[i] < 0.5 {
+                    u[i] += 1.0;
+                }
+            }
+            let count = around.clone().count() as f32;
+            let mean = around.map(|i| u[i]).sum::<f32>() / count;
+            for (i, v) in v.into_iter().enumerate() {
+                let u = if on_axis[i] { mean } else { u[i] };
+                corner_uvs.push([u, v]);
+            }
+        }
+        mesh.tex_coords0 = mesh.split_corners(&corner_uvs);
+        finish(mesh)
+    }
+
+    /// Cylinder of `height` along y with flat caps.
+    pub fn cylinder(radius: f32, height: f32, segments: u32) -> MeshData {
+        let segments = segments.max(3);
+        let half = height * 0.5;
+        let side = |y: f32, v: f32| ProfilePoint {
+            radius,
+            y,
+            normal: [1.0, 0.0],
+            v,
+        };
+        let mut mesh = MeshData::default();
+        revolve(&mut mesh, segments, &[side(half, 0.0), side(-half, 1.0)]);
+        disk(&mut mesh, radius, half, true, segments);
+        disk(&mut mesh, radius, -half, false, segments);
+        finish(mesh)
+    }
+
+    /// Cone of `height` along y with its apex at the top and a flat base.
+    pub fn cone(radius: f32, height: f32, segments: u32) -> MeshData {
+        let segments = segments.max(3);
+        let half = height * 0.5;
+        let slant = (radius * radius + height * height).sqrt();
+        let normal = [height / slant, radius / slant];
+        let profile = [
+            ProfilePoint {
+                radius: 0.0,
+                y: half,
+                normal,
+                v: 0.0,
+            },
+            ProfilePoint {
+                radius,
+                y: -half,
+                normal,
+                v: 1.0,
+            },
+        ];
+        let mut mesh = MeshData::default();
+        revolve(&mut mesh, segments, &profile);
+        disk(&mut mesh, radius, -half, false, segments);
+        finish(mesh)
+    }
+
+    /// Torus around y of `major_radius` to the middle of a tube of `minor_radius`, in
+    /// `segments` around y and `sides` around the tube. `v` runs once round the tube from
+    /// its outer equator, downwards.
+    pub fn torus(major_radius: f32, minor_radius: f32, segments: u32, sides: u32) -> MeshData {
+        let sides = sides.max(3);
+        let profile: Vec<ProfilePoint> = (0..=sides)
+            .map(|side| {
+                let v = side as f32 / sides as f32;
+                let (sin, cos) = sin_cos(-v);
+                ProfilePoint {
+                    radius: major_radius + minor_radius * cos,
+                    y: minor_radius * sin,
+                    normal: [cos, sin],
+                    v,
+                }
+            })
+            .collect();
+        let mut mesh = MeshData::default();
+        revolve(&mut mesh, segments.max(3), &profile);
+        finish(mesh)
+    }
+
+    /// Cylinder of `height` along y between two hemispheres of `radius`, each in `rings`
+    /// from pole to equator. `v` is proportional to the length along the profile.
+    pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> MeshData {
+        let rings = rings.max(1);
+        let half = height * 0.5;
+        let arc = radius * TAU * 0.25;
+        let length = 2.0 * arc + height;
+        let mut profile = Vec::new();
+        for (y, start, first_turn) in [(half, 0.0, 0.0), (-half, arc + height, 0.25)] {
+            for ring in 0..=rings {
+                let t = ring as f32 / rings as f32;
+                let (sin, cos) = sin_cos(first_turn + t * 0.25);
+                profile.push(ProfilePoint {
+                    radius: radius * sin,
+                    y: y + radius * cos,
+                    normal: [sin, cos],
+                    v: (start + t * arc) / length,
+                });
+            }
+        }
+        let mut mesh = MeshData::default();
+        revolve(&mut mesh, segments.max(3), &profile);
+        finish(mesh)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::collections::HashSet;
+
+        use super::*;
+        use crate::math::math::{vec3_cross, vec3_dot, vec3_length, vec3_sub};
+
+        type PositionId = [u32; 3];
+
+        fn id(mesh: &MeshData, v: u32) -> PositionId {
+            mesh.positions[v as usize].map(f32::to_bits)
+        }
+
+        fn directed_edges(mesh: &MeshData) -> HashMap<(PositionId, PositionId), u32> {
+            let mut edges = HashMap::new();
+            for t in mesh.indices.chunks_exact(3) {
+                for i in 0..3 {
+                    *edges
+                        .entry((id(mesh, t[i]), id(mesh, t[(i + 1) % 3])))
+                        .or_default() += 1;
+                }
+            }
+            edges
+        }
+
+        fn closed_shapes() -> impl Iterator<Item = Shape> {
+            Shape::ALL
+                .into_iter()
+                .filter(|&shape| shape != Shape::Plane)
+        }
+
+        fn face_normal(mesh: &MeshData, t: &[u32]) -> [f32; 3] {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[t[i] as usize]);
+            vec3_cross(vec3_sub(b, a), vec3_sub(c, a))
+        }
+
+        #[test]
+        fn names_round_trip() {
+            for shape in Shape::ALL {
+                assert_eq!(Shape::parse(shape.name()), Some(shape));
+            }
+            assert_eq!(Shape::parse("teapot"), None);
+        }
+
+        #[test]
+        fn closed_shapes_are_watertight() {
+            for shape in closed_shapes() {
+                let edges = directed_edges(&shape.mesh());
+                for (&(a, b), &count) in &edges {
+                    assert_eq!(count, 1, "{}", shape.name());
+                    assert_eq!(edges.get(&(b, a)), Some(&1), "{}", shape.name());
+                }
+            }
+        }
+
+        #[test]
+        fn closed_shapes_have_the_expected_genus() {
+            for shape in closed_shapes() {
+                let mesh = shape.mesh();
+                let vertices: HashSet<PositionId> =
+                    mesh.indices.iter().map(|&v| id(&mesh, v)).collect();
+                let edges = directed_edges(&mesh).len() / 2;
+                let euler = vertices.len() as i64 - edges as i64 + mesh.triangle_count() as i64;
+                let expected = if shape == Shape::Torus { 0 } else { 2 };
+                assert_eq!(euler, expected, "{}", shape.name());
+            }
+        }
+
+        #[test]
+        fn triangles_wind_towards_their_normals() {
+            for shape in Shape::ALL {
+                let mesh = shape.mesh();
+                for t in mesh.indices.chunks_exact(3) {
+                    let face = face_normal(&mesh, t);
+                    assert!(vec3_length(face) > 0.0, "{}", shape.name());
+                    for &v in t {
+                        let n = mesh.normals[v as usize];
+                        assert!(vec3_dot(face, n) > 0.0, "{}", shape.name());
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn normals_are_unit_and_point_outwards() {
+            for shape in closed_shapes() {
+                let mesh = shape.mesh();
+                for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+                    assert!((vec3_length(*n) - 1.0).abs() < 1e-5, "{}", shape.name());
+                    let outwards = if shape == Shape::Torus {
+                        // Away from the middle of the tube.
+                        let ring = vec3_scale(vec3_normalize([p[0], 0.0, p[2]]), 0.35);
+                        vec3_sub(*p, ring)
+                    } else {
+                        *p
+                    };
+                    assert!(vec3_dot(outwards, *n) > 0.0, "{}", shape.name());
+                }
+            }
+            for shape in [Shape::UvSphere, Shape::Icosphere] {
+                let mesh = shape.mesh();
+                for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+                    let expected = vec3_scale(*p, 2.0);
+                    assert!(
+                        vec3_length(vec3_sub(expected, *n)) < 1e-5,
+                        "{}",
+                        shape.name()
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn tangents_are_orthonormal_with_a_handedness() {
+            for shape in Shape::ALL {
+                let mesh = shape.mesh();
+                assert_eq!(mesh.tangents.len(), mesh.vertex_count());
+                assert_eq!(mesh.tex_coords0.len(), mesh.vertex_count());
+                for (t, n) in mesh.tangents.iter().zip(&mesh.normals) {
+                    let tangent = [t[0], t[1], t[2]];
+                    assert!(
+                        (vec3_length(tangent) - 1.0).abs() < 1e-3,
+                        "{}",
+                        shape.name()
+                    );
+                    assert!(vec3_dot(tangent, *n).abs() < 1e-3, "{}", shape.name());
+                    assert_eq!(t[3].abs(), 1.0);
+                }
+            }
+        }
+
+        #[test]
+        fn shapes_fit_the_unit_cube() {
+            for shape in Shape::ALL {
+                let mesh = shape.mesh();
+                let mut extent = [0.0f32; 3];
+                for p in &mesh.positions {
+                    for c in 0..3 {
+                        assert!(p[c].abs() <= 0.5 + 1e-6, "{}", shape.name());
+                        extent[c] = extent[c].max(p[c].abs());
+                    }
+                }
+                let largest = extent.into_iter().fold(0.0, f32::max);
+                assert!((largest - 0.5).abs() < 1e-6, "{}", shape.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,628 @@
+pub mod simplify {
+
+    //! Edge collapse simplification driven by quadric error metrics (Garland and Heckbert).
+    //! Open borders and attribute seams only collapse along themselves, so holes keep their
+    //! outline and UV islands stay stitched.
+
+    use std::collections::{HashMap, HashSet};
+
+    use crate::math::math::{vec3_cross, vec3_dot, vec3_length, vec3_normalize, vec3_sub};
+    use crate::mesh::mesh::MeshData;
+
+    /// Weights of squared attribute differences, added to the squared position error
+    /// measured in units of the mesh extent.
+    #[derive(Clone, Copy, Debug)]
+    pub struct AttributeWeights {
+        pub normal: f32,
+        pub tex_coord: f32,
+    }
+
+    impl Default for AttributeWeights {
+        fn default() -> Self {
+            AttributeWeights {
+                normal: 0.001,
+                tex_coord: 0.01,
+            }
+        }
+    }
+
+    /// Border edges resist sliding more than the faces next to them.
+    const BORDER_WEIGHT: f64 = 2.0;
+    const SEAM_WEIGHT: f64 = 1.0;
+
+    /// Collapses may not turn a face by more than this, as the cosine of the angle.
+    const MIN_FLIP_COS: f32 = 0.25;
+
+    /// Sum of squared distances to weighted planes.
+    #[derive(Clone, Copy, Debug, Default)]
+    struct Quadric {
+        aa: f64,
+        bb: f64,
+        cc: f64,
+        ab: f64,
+        ac: f64,
+        bc: f64,
+        ad: f64,
+        bd: f64,
+        cd: f64,
+        dd: f64,
+        weight: f64,
+    }
+
+    impl Quadric {
+        /// Plane through `p` with unit normal `n`.
+        fn plane(n: [f32; 3], p: [f32; 3], weight: f64) -> Self {
+            let [a, b, c] = n.map(|x| x as f64);
+            let d = -vec3_dot(n, p) as f64;
+            Quadric {
+                aa: a * a * weight,
+                bb: b * b * weight,
+                cc: c * c * weight,
+                ab: a * b * weight,
+                ac: a * c * weight,
+                bc: b * c * weight,
+                ad: a * d * weight,
+                bd: b * d * weight,
+                cd: c * d * weight,
+                dd: d * d * weight,
+                weight,
+            }
+        }
+
+        fn add(&mut self, other: &Quadric) {
+            self.aa += other.aa;
+            self.bb += other.bb;
+            self.cc += other.cc;
+            self.ab += other.ab;
+            self.ac += other.ac;
+            self.bc += other.bc;
+            self.ad += other.ad;
+            self.bd += other.bd;
+            self.cd += other.cd;
+            self.dd += other.dd;
+            self.weight += other.weight;
+        }
+
+        /// Weighted mean squared distance of `p` to the planes.
+        fn error(&self, p: [f32; 3]) -> f64 {
+            if self.weight <= 0.0 {
+                return 0.0;
+            }
+            let [x, y, z] = p.map(|x| x as f64);
+            let r = self.aa * x * x
+                + self.bb * y * y
+                + self.cc * z * z
+                + 2.0 * (self.ab * x * y + self.ac * x * z + self.bc * y * z)
+                + 2.0 * (self.ad * x + self.bd * y + self.cd * z)
+                + self.dd;
+            r.abs() / self.weight
+        }
+    }
+
+    /// How a position may move, shared by all of its vertices.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Kind {
+        /// Inside a closed surface with one set of attributes.
+        Manifold,
+        /// On an open border; moves along it.
+        Border,
+        /// On a seam between two sets of attributes; both sides move along it together.
+        Seam,
+        /// Anything else: corners of borders and seams, non-manifold fans.
+        Locked,
+    }
+
+    struct Collapse {
+        from: usize,
+        to: usize,
+        /// The other side of a seam, collapsing alongside.
+        pair: Option<(usize, usize)>,
+        error: f64,
+    }
+
+    /// Topology and attributes of the input, fixed for the whole run.
+    struct Analysis<'a> {
+        mesh: &'a MeshData,
+        weights: AttributeWeights,
+        /// Positions scaled into the unit cube.
+        positions: Vec<[f32; 3]>,
+        /// First vertex with the same position as each vertex.
+        pids: Vec<usize>,
+        /// Vertices sharing each position, stored at its first vertex.
+        wedges: Vec<Vec<usize>>,
+        kinds: Vec<Kind>,
+        /// Directed edges between vertices.
+        edges: HashSet<(usize, usize)>,
+        /// Directed edges between positions, and how many faces use them.
+        position_edges: HashMap<(usize, usize), u32>,
+    }
+
+    impl Analysis<'_> {
+        fn is_open(&self, a: usize, b: usize) -> bool {
+            let (pa, pb) = (self.pids[a], self.pids[b]);
+            !self.position_edges.contains_key(&(pb, pa))
+        }
+
+        /// Half of a seam: no face across in vertex terms, but one across in positions.
+        fn is_seam(&self, a: usize, b: usize) -> bool {
+            let (pa, pb) = (self.pids[a], self.pids[b]);
+            !self.edges.contains(&(b, a)) && self.position_edges.contains_key(&(pb, pa))
+        }
+
+        fn has_edge(&self, a: usize, b: usize) -> bool {
+            self.edges.contains(&(a, b)) || self.edges.contains(&(b, a))
+        }
+
+        fn attribute_error(&self, a: usize, b: usize) -> f64 {
+            let mesh = self.mesh;
+            let mut error = 0.0;
+            if let (Some(&na), Some(&nb)) = (mesh.normals.get(a), mesh.normals.get(b)) {
+                let d = vec3_sub(na, nb);
+                error += (self.weights.normal * vec3_dot(d, d)) as f64;
+            }
+            for uvs in [&mesh.tex_coords0, &mesh.tex_coords1] {
+                if let (Some(ta), Some(tb)) = (uvs.get(a), uvs.get(b)) {
+                    let (du, dv) = (ta[0] - tb[0], ta[1] - tb[1]);
+                    error += (self.weights.tex_coord * (du * du + dv * dv)) as f64;
+                }
+            }
+            error
+        }
+
+        /// The collapse of `from` onto `to` along the edge between them, if the kind of
+        /// `from` allows it.
+        fn collapse(&self, from: usize, to: usize, quadrics: &[Quadric]) -> Option<Collapse> {
+            let pid = self.pids[from];
+            if pid == self.pids[to] {
+                return None;
+            }
+            let pair = match self.kinds[pid] {
+                Kind::Manifold => None,
+                Kind::Border if self.is_open(from, to) || self.is_open(to, from) => None,
+                Kind::Seam if self.is_seam(from, to) || self.is_seam(to, from) => {
+                    let other = *self.wedges[pid].iter().find(|&&w| w != from)?;
+                    // Where the seam ends in a single vertex, both sides collapse onto it.
+                    let wedges = &self.wedges[self.pids[to]];
+                    let target = *wedges
+                        .iter()
+                        .find(|&&w| (w != to || wedges.len() == 1) && self.has_edge(other, w))?;
+                    Some((other, target))
+                }
+                _ => return None,
+            };
+            let mut error = quadrics[pid].error(self.positions[to]);
+            error += self.attribute_error(from, to);
+            if let Some((other, target)) = pair {
+                error += self.attribute_error(other, target);
+            }
+            Some(Collapse {
+                from,
+                to,
+                pair,
+                error,
+            })
+        }
+    }
+
+    fn classify(
+        pids: &[usize],
+        wedges: &[Vec<usize>],
+        triangles: &[[usize; 3]],
+        edges: &HashSet<(usize, usize)>,
+        position_edges: &HashMap<(usize, usize), u32>,
+    ) -> Vec<Kind> {
+        let n = pids.len();
+        let mut open_out = vec![0; n];
+        let mut open_in = vec![0; n];
+        let mut seam_out = vec![0; n];
+        let mut seam_in = vec![0; n];
+        let mut non_manifold = vec![false; n];
+        for t in triangles {
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                let (pa, pb) = (pids[a], pids[b]);
+                if position_edges[&(pa, pb)] > 1 {
+                    non_manifold[pa] = true;
+                    non_manifold[pb] = true;
+                }
+                if !position_edges.contains_key(&(pb, pa)) {
+                    open_out[pa] += 1;
+                    open_in[pb] += 1;
+                } else if !edges.contains(&(b, a)) {
+                    seam_out[a] += 1;
+                    seam_in[b] += 1;
+                }
+            }
+        }
+
+        (0..n)
+            .map(|v| {
+                if pids[v] != v {
+                    return Kind::Locked;
+                }
+                let border = open_out[v] + open_in[v] > 0;
+                let seam_ok = |w: &usize| seam_out[*w] == 1 && seam_in[*w] == 1;
+                match wedges[v].len() {
+                    _ if non_manifold[v] => Kind::Locked,
+                    1 if !border => Kind::Manifold,
+                    1 if open_out[v] == 1 && open_in[v] == 1 => Kind::Border,
+                    2 if !border && wedges[v].iter().all(seam_ok) => Kind::Seam,
+                    _ => Kind::Locked,
+                }
+            })
+            .collect()
+    }
+
+    /// Collapses edges of `mesh`, cheapest first, until at most `target_index_count`
+    /// indices remain or nothing may collapse. Returns indices into the unchanged vertices
+    /// and the largest error introduced, in mesh units.
+    pub fn simplify(
+        mesh: &MeshData,
+        target_index_count: usize,
+        weights: AttributeWeights,
+    ) -> (Vec<u32>, f32) {
+        let n = mesh.vertex_count();
+        let mut indices: Vec<u32> = mesh.indices[..mesh.triangle_count() * 3].to_vec();
+        if indices.len() <= target_index_count || n == 0 {
+            return (indices, 0.0);
+        }
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &mesh.positions {
+            for (c, v) in p.iter().enumerate() {
+                min[c] = min[c].min(*v);
+                max[c] = max[c].max(*v);
+            }
+        }
+        let extent = (0..3).map(|c| max[c] - min[c]).fold(0.0, f32::max);
+        let scale = if extent > 0.0 { 1.0 / extent } else { 1.0 };
+        let positions: Vec<[f32; 3]> = mesh
+            .positions
+            .iter()
+            .map(|p| [0, 1, 2].map(|c| (p[c] - min[c]) * scale))
+            .collect();
+
+        let mut first: HashMap<[u32; 3], usize> = HashMap::new();
+        let pids: Vec<usize> = (0..n)
+            .map(|v| {
+                *first
+                    .entry(mesh.positions[v].map(f32::to_bits))
+                    .or_insert(v)
+            })
+            .collect();
+        let mut wedges = vec![Vec::new(); n];
+        for (v, &pid) in pids.iter().enumerate() {
+            wedges[pid].push(v);
+        }
+
+        let triangles: Vec<[usize; 3]> = indices
+            .chunks_exact(3)
+            .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+            .collect();
+        let mut edges = HashSet::new();
+        let mut position_edges: HashMap<(usize, usize), u32> = HashMap::new();
+        for t in &triangles {
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                edges.insert((a, b));
+                *position_edges.entry((pids[a], pids[b])).or_default() += 1;
+            }
+        }
+        let kinds = classify(&pids, &wedges, &triangles, &edges, &position_edges);
+
+        // Face planes, plus planes perpendicular to the faces along borders and seams.
+        let mut quadrics = vec![Quadric::default(); n];
+        for t in &triangles {
+            let [p0, p1, p2] = t.map(|v| positions[v]);
+            let cross = vec3_cross(vec3_sub(p1, p0), vec3_sub(p2, p0));
+            let area = vec3_length(cross) as f64 * 0.5;
+            let normal = vec3_normalize(cross);
+            let face = Quadric::plane(normal, p0, area);
+            for &v in t {
+                quadrics[pids[v]].add(&face);
+            }
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                let (pa, pb) = (pids[a], pids[b]);
+                let weight = if !position_edges.contains_key(&(pb, pa)) {
+                    BORDER_WEIGHT
+                } else if !edges.contains(&(b, a)) {
+                    SEAM_WEIGHT
+                } else {
+                    continue;
+                };
+                let edge = vec3_sub(positions[b], positions[a]);
+                let length = vec3_length(edge) as f64;
+                let side = vec3_normalize(vec3_cross(edge, normal));
+                let plane = Quadric::plane(side, positions[a], length * length * weight);
+                quadrics[pa].add(&plane);
+                quadrics[pb].add(&plane);
+            }
+        }
+
+        let analysis = Analysis {
+            mesh,
+            weights,
+            positions,
+            pids,
+            wedges,
+            kinds,
+            edges,
+            position_edges,
+        };
+        let pids = &analysis.pids;
+        let positions = &analysis.positions;
+
+        let mut max_error = 0.0f64;
+        while indices.len() > target_index_count {
+            let mut candidates: Vec<Collapse> = indices
+                .chunks_exact(3)
+                .flat_map(|t| {
+                    (0..3).flat_map(move |i| {
+                        let (a, b) = (t[i] as usize, t[(i + 1) % 3] as usize);
+                        [(a, b), (b, a)]
+                    })
+                })
+                .filter_map(|(from, to)| analysis.collapse(from, to, &quadrics))
+                .collect();
+            candidates.sort_by(|a, b| a.error.total_cmp(&b.error));
+
+            // Faces around every position, to check for flips and lock neighborhoods.
+            let mut fans: Vec<Vec<usize>> = vec![Vec::new(); n];
+            for (f, t) in indices.chunks_exact(3).enumerate() {
+                for &v in t {
+                    fans[pids[v as usize]].push(f);
+                }
+            }
+
+            // Each collapse removes about two faces.
+            let goal = ((indices.len() - target_index_count) / 6).max(1);
+            let mut locked = vec![false; n];
+            let mut targets: Vec<usize> = (0..n).collect();
+            let mut applied = 0;
+            for collapse in &candidates {
+                if applied >= goal {
+                    break;
+                }
+                let (pf, pt) = (pids[collapse.from], pids[collapse.to]);
+                if locked[pf] || locked[pt] {
+                    continue;
+                }
+                let flips = fans[pf].iter().any(|&f| {
+                    let t = &indices[f * 3..f * 3 + 3];
+                    if t.iter().any(|&v| pids[v as usize] == pt) {
+                        return false;
+                    }
+                    let before = [0, 1, 2].map(|i| positions[t[i] as usize]);
+                    let after = [0, 1, 2].map(|i| {
+                        let p = pids[t[i] as usize];
+                        positions[if p == pf { collapse.to } else { p }]
+                    });
+                    let normal = |p: [[f32; 3]; 3]| {
+                        vec3_normalize(vec3_cross(vec3_sub(p[1], p[0]), vec3_sub(p[2], p[0])))
+                    };
+                    vec3_dot(normal(before), normal(after)) < MIN_FLIP_COS
+                });
+                if flips {
+                    continue;
+                }
+
+                targets[collapse.from] = collapse.to;
+                if let Some((other, target)) = collapse.pair {
+                    targets[other] = target;
+                }
+                let source = quadrics[pf];
+                quadrics[pt].add(&source);
+                for &f in &fans[pf] {
+                    for &v in &indices[f * 3..f * 3 + 3] {
+                        locked[pids[v as usize]] = true;
+                    }
+                }
+                max_error = max_error.max(collapse.error);
+                applied += 1;
+            }
+            if applied == 0 {
+                break;
+            }
+
+            indices = indices
+                .chunks_exact(3)
+                .map(|t| [0, 1, 2].map(|i| targets[t[i] as usize] as u32))
+                .filter(|t| {
+                    let [a, b, c] = t.map(|v| pids[v as usize]);
+                    a != b && a != c && b != c
+                })
+                .flatten()
+                .collect();
+        }
+
+        (indices, max_error.sqrt() as f32 * extent)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::f32::consts::{PI, TAU};
+
+        use super::*;
+        use crate::bvh::bvh::closest_point;
+
+        /// Unit square in the xz plane facing +y, in `subdivisions` quads along each side,
+        /// with the texture mapped once across it.
+        fn plane(subdivisions: u32) -> MeshData {
+            let mut mesh = MeshData::default();
+            for row in 0..=subdivisions {
+                for column in 0..=subdivisions {
+                    let u = column as f32 / subdivisions as f32;
+                    let v = row as f32 / subdivisions as f32;
+                    mesh.positions.push([u - 0.5, 0.0, v - 0.5]);
+                    mesh.normals.push([0.0, 1.0, 0.0]);
+                    mesh.tex_coords0.push([u, v]);
+                }
+            }
+            let index = |column: u32, row: u32| row * (subdivisions + 1) + column;
+            for row in 0..subdivisions {
+                for column in 0..subdivisions {
+                    let [tl, bl, br, tr] =
+                        [(0, 0), (0, 1), (1, 1), (1, 0)].map(|(c, r)| index(column + c, row + r));
+                    mesh.indices.extend([tl, bl, br, tl, br, tr]);
+                }
+            }
+            mesh
+        }
+
+        /// Unit sphere of `segments` around by `rings` from pole to pole, with the texture
+        /// wrapped around once. Vertices split along the seam and at the poles keep
+        /// identical positions, so the sphere is watertight.
+        fn uv_sphere(segments: u32, rings: u32) -> MeshData {
+            let mut mesh = MeshData::default();
+            for ring in 0..=rings {
+                let v = ring as f32 / rings as f32;
+                let pole = ring == 0 || ring == rings;
+                for column in 0..=segments {
+                    let mut u = column as f32 / segments as f32;
+                    let p = if pole {
+                        u += 0.5 / segments as f32;
+                        [0.0, 1.0 - 2.0 * v, 0.0]
+                    } else {
+                        let (sin_polar, cos_polar) = (PI * v).sin_cos();
+                        let angle = (column % segments) as f32 / segments as f32;
+                        let (sin, cos) = (TAU * angle).sin_cos();
+                        [sin_polar * sin, cos_polar, sin_polar * cos]
+                    };
+                    mesh.positions.push(p);
+                    mesh.normals.push(p);
+                    mesh.tex_coords0.push([u, v]);
+                }
+            }
+            let index = |column: u32, row: u32| row * (segments + 1) + column;
+            for row in 0..rings {
+                for column in 0..segments {
+                    let [tl, bl, br, tr] =
+                        [(0, 0), (0, 1), (1, 1), (1, 0)].map(|(c, r)| index(column + c, row + r));
+                    // Triangles with two corners on a pole are left out.
+                    if row != rings - 1 {
+                        mesh.indices.extend([tl, bl, br]);
+                    }
+                    if row != 0 {
+                        mesh.indices.extend([tl, br, tr]);
+                    }
+                }
+            }
+            mesh
+        }
+
+        /// Largest distance from a vertex of `mesh` to the triangles of `indices`.
+        fn deviation(mesh: &MeshData, indices: &[u32]) -> f32 {
+            let triangles: Vec<[[f32; 3]; 3]> = indices
+                .chunks_exact(3)
+                .map(|t| [0, 1, 2].map(|i| mesh.positions[t[i] as usize]))
+                .collect();
+            mesh.positions
+                .iter()
+                .map(|&p| {
+                    triangles
+                        .iter()
+                        .map(|&t| vec3_length(vec3_sub(p, closest_point(p, &t))))
+                        .fold(f32::MAX, f32::min)
+                })
+                .fold(0.0, f32::max)
+        }
+
+        fn area(mesh: &MeshData, indices: &[u32]) -> f32 {
+            indices
+                .chunks_exact(3)
+                .map(|t| {
+                    let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[t[i] as usize]);
+                    vec3_length(vec3_cross(vec3_sub(b, a), vec3_sub(c, a))) * 0.5
+                })
+                .sum()
+        }
+
+        /// Whether every edge between positions is used once in each direction, which also
+        /// catches cracks along seams.
+        fn is_watertight(mesh: &MeshData, indices: &[u32]) -> bool {
+            let id = |v: u32| mesh.positions[v as usize].map(f32::to_bits);
+            let mut edges: HashMap<_, u32> = HashMap::new();
+            for t in indices.chunks_exact(3) {
+                for i in 0..3 {
+                    *edges.entry((id(t[i]), id(t[(i + 1) % 3]))).or_default() += 1;
+                }
+            }
+            edges
+                .iter()
+                .all(|(&(a, b), &count)| count == 1 && edges.get(&(b, a)) == Some(&1))
+        }
+
+        #[test]
+        fn flat_grids_collapse_to_their_corners() {
+            let mesh = plane(8);
+            let flat = AttributeWeights {
+                normal: 0.0,
+                tex_coord: 0.0,
+            };
+            let (indices, error) = simplify(&mesh, 6, flat);
+            assert_eq!(indices.len(), 6);
+            assert_eq!(error, 0.0);
+            for &i in &indices {
+                let p = mesh.positions[i as usize];
+                assert_eq!([p[0].abs(), p[2].abs()], [0.5, 0.5]);
+            }
+            assert!((area(&mesh, &indices) - 1.0).abs() < 1e-5);
+        }
+
+        #[test]
+        fn borders_keep_their_outline() {
+            let mesh = plane(8);
+            let (indices, error) = simplify(&mesh, mesh.indices.len() / 4, Default::default());
+            assert!(indices.len() <= mesh.indices.len() / 4);
+            // Only the texture coordinates change.
+            assert!(error > 0.0);
+            assert!(deviation(&mesh, &indices) < 1e-6);
+            assert!((area(&mesh, &indices) - 1.0).abs() < 1e-5);
+            for t in indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[t[i] as usize]);
+                assert!(vec3_cross(vec3_sub(b, a), vec3_sub(c, a))[1] > 0.0);
+            }
+        }
+
+        #[test]
+        fn closed_meshes_stay_watertight() {
+            for mesh in [uv_sphere(16, 8), uv_sphere(32, 16)] {
+                assert!(is_watertight(&mesh, &mesh.indices));
+                for divisor in [2, 4, 10] {
+                    let (indices, _) =
+                        simplify(&mesh, mesh.indices.len() / divisor, Default::default());
+                    assert!(indices.len() <= mesh.indices.len() / divisor);
+                    assert!(is_watertight(&mesh, &indices));
+                }
+            }
+        }
+
+        #[test]
+        fn the_error_tracks_the_deviation() {
+            for mesh in [uv_sphere(16, 8), uv_sphere(32, 16)] {
+                let mut previous = 0.0;
+                for divisor in [2, 4, 10] {
+                    let (indices, error) =
+                        simplify(&mesh, mesh.indices.len() / divisor, Default::default());
+                    assert!(error > previous);
+                    previous = error;
+                    // Quadrics estimate the distance to the original surface, they don't
+                    // bound it.
+                    let deviation = deviation(&mesh, &indices);
+                    assert!(deviation < error * 1.25, "{} vs {}", deviation, error);
+                    assert!(deviation > error * 0.25, "{} vs {}", deviation, error);
+                }
+            }
+        }
+
+        #[test]
+        fn targets_above_the_index_count_change_nothing() {
+            let mesh = uv_sphere(8, 4);
+            let (indices, error) = simplify(&mesh, mesh.indices.len(), Default::default());
+            assert_eq!(indices, mesh.indices);
+            assert_eq!(error, 0.0);
+        }
+    }
+}
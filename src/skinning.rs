@@ -0,0 +1,207 @@
+pub mod skinning {
+
+    //! CPU reference of skinned_vs.hlsl, for tools that need posed geometry and for
+    //! checking the GPU path.
+
+    use glm::Mat4;
+
+    use crate::math::math::{
+        from_cols_array, identity, to_cols_array, transform_point, transform_vector,
+    };
+    use crate::mesh::mesh::MeshData;
+    use crate::scene::scene::Skin;
+
+    /// World-space joint matrices, `world(joint) * inverse_bind`. glTF ignores the transform
+    /// of the node holding a skinned mesh, so no mesh node term appears.
+    pub fn joint_matrices(skin: &Skin, worlds: &[Mat4]) -> Vec<Mat4> {
+        skin.joints
+            .iter()
+            .enumerate()
+            .map(|(i, &joint)| {
+                let inverse_bind = skin
+                    .inverse_bind_matrices
+                    .get(i)
+                    .copied()
+                    .unwrap_or_else(identity);
+                worlds[joint] * inverse_bind
+            })
+            .collect()
+    }
+
+    /// Weighted sum of the joint matrices influencing vertex `i`. Out of range joints
+    /// contribute nothing, like the zeroed tail of the shader's palette.
+    pub fn skin_matrix(mesh: &MeshData, i: usize, palette: &[Mat4]) -> Mat4 {
+        let mut sum = [[0f32; 4]; 4];
+        for (joint, weight) in mesh.influences(i) {
+            if weight == 0.0 {
+                continue;
+            }
+            if let Some(m) = palette.get(joint) {
+                for (sum_col, col) in sum.iter_mut().zip(to_cols_array(m)) {
+                    for (s, v) in sum_col.iter_mut().zip(col) {
+                        *s += v * weight;
+                    }
+                }
+            }
+        }
+        from_cols_array(&sum)
+    }
+
+    /// Poses `mesh` with `palette`. Positions and normals come back in world space, other
+    /// attributes are copied.
+    pub fn skin_mesh(mesh: &MeshData, palette: &[Mat4]) -> MeshData {
+        let mut posed = mesh.clone();
+        for i in 0..mesh.vertex_count() {
+            let m = skin_matrix(mesh, i, palette);
+            let p = mesh.positions[i];
+            let p = transform_point(&m, glm::vec3(p[0], p[1], p[2]));
+            posed.positions[i] = [p.x, p.y, p.z];
+
+            if let Some(n) = mesh.normals.get(i) {
+                let n = transform_vector(&m, glm::vec3(n[0], n[1], n[2]));
+                let len = glm::length(n);
+                if len > 0.0 {
+                    posed.normals[i] = [n.x / len, n.y / len, n.z / len];
+                }
+            }
+            if let Some(t) = mesh.tangents.get(i) {
+                let v = transform_vector(&m, glm::vec3(t[0], t[1], t[2]));
+                let len = glm::length(v);
+                if len > 0.0 {
+                    posed.tangents[i] = [v.x / len, v.y / len, v.z / len, t[3]];
+                }
+            }
+        }
+        posed
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use glm::GenSquareMat;
+
+        use super::*;
+        use crate::math::math::trs_matrix;
+
+        const MAX_JOINTS: usize = 256;
+
+        fn translation(t: [f32; 3]) -> Mat4 {
+            trs_matrix(t, [0.0, 0.0, 0.0, 1.0], [1.0; 3])
+        }
+
+        /// Two joints bound at their rest worlds, a root and a child one unit up.
+        fn skin() -> (Skin, Vec<Mat4>) {
+            let rest = vec![identity(), translation([0.0, 1.0, 0.0])];
+            let skin = Skin {
+                name: None,
+                joints: vec![0, 1],
+                inverse_bind_matrices: rest.iter().map(|m| m.inverse().unwrap()).collect(),
+            };
+            (skin, rest)
+        }
+
+        /// Rigid, blended, eight-influence and out-of-range joint vertices.
+        fn mesh() -> MeshData {
+            MeshData {
+                positions: vec![
+                    [1.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0],
+                    [0.5, 2.0, 0.5],
+                    [0.0, 0.0, 1.0],
+                ],
+                normals: vec![[0.0, 0.0, 1.0]; 4],
+                tangents: vec![[1.0, 0.0, 0.0, -1.0]; 4],
+                joints0: vec![[0, 0, 0, 0], [0, 1, 0, 0], [0, 1, 0, 1], [1, 7, 0, 0]],
+                weights0: vec![
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.5, 0.5, 0.0, 0.0],
+                    [0.25, 0.25, 0.0, 0.0],
+                    [0.75, 0.25, 0.0, 0.0],
+                ],
+                joints1: vec![[0; 4], [0; 4], [1, 0, 1, 0], [0; 4]],
+                weights1: vec![[0.0; 4], [0.0; 4], [0.125, 0.125, 0.125, 0.125], [0.0; 4]],
+                ..Default::default()
+            }
+        }
+
+        fn assert_near(a: [f32; 3], b: [f32; 3]) {
+            for c in 0..3 {
+                assert!((a[c] - b[c]).abs() < 1e-5, "{:?} vs {:?}", a, b);
+            }
+        }
+
+        fn mul(m: &Mat4, v: [f32; 3], w: f32) -> [f32; 3] {
+            let v = *m * glm::vec4(v[0], v[1], v[2], w);
+            [v.x, v.y, v.z]
+        }
+
+        #[test]
+        fn the_bind_pose_leaves_vertices_in_place() {
+            let (skin, rest) = skin();
+            let palette = joint_matrices(&skin, &rest);
+            let source = mesh();
+            let posed = skin_mesh(&source, &palette);
+            for (a, b) in posed.positions.iter().zip(&source.positions).take(3) {
+                assert_near(*a, *b);
+            }
+            // The out-of-range joint takes its quarter of the weight with it.
+            assert_near(posed.positions[3], [0.0, 0.0, 0.75]);
+        }
+
+        #[test]
+        fn rigid_vertices_follow_their_joint() {
+            let (skin, _) = skin();
+            let worlds = [translation([2.0, 0.0, 0.0]), translation([0.0, 1.0, 3.0])];
+            let posed = skin_mesh(&mesh(), &joint_matrices(&skin, &worlds));
+            assert_near(posed.positions[0], [3.0, 0.0, 0.0]);
+            // Halfway between both joints' offsets.
+            assert_near(posed.positions[1], [1.0, 1.0, 1.5]);
+        }
+
+        #[test]
+        fn cpu_skinning_matches_the_shader() {
+            let (skin, _) = skin();
+            let worlds = [
+                trs_matrix([0.0, 0.5, 0.0], [0.0, 0.0, 0.38268343, 0.9238795], [1.0; 3]),
+                trs_matrix([1.0, 1.0, 0.0], [0.5, 0.5, 0.5, 0.5], [2.0, 1.0, 1.0]),
+            ];
+            let source = mesh();
+            let posed = skin_mesh(&source, &joint_matrices(&skin, &worlds));
+
+            // skin_constants: the palette padded with zeros to MAX_JOINTS.
+            let mut constants = vec![[[0.0; 4]; 4]; MAX_JOINTS];
+            for (gpu, m) in constants.iter_mut().zip(joint_matrices(&skin, &worlds)) {
+                *gpu = to_cols_array(&m);
+            }
+            // skin_matrix() and main() in skinned_vs.hlsl over the uploaded stream.
+            for (i, v) in source.skin_vertices().unwrap().iter().enumerate() {
+                let mut sum = [[0.0f32; 4]; 4];
+                for k in 0..4 {
+                    for (joint, weight) in
+                        [(v.joints0[k], v.weights0[k]), (v.joints1[k], v.weights1[k])]
+                    {
+                        let m = constants[(joint as usize).min(MAX_JOINTS - 1)];
+                        for (s, col) in sum.iter_mut().zip(m) {
+                            for (s, x) in s.iter_mut().zip(col) {
+                                *s += x * weight;
+                            }
+                        }
+                    }
+                }
+                let m = from_cols_array(&sum);
+                assert_near(posed.positions[i], mul(&m, source.positions[i], 1.0));
+                let n = glm::normalize(transform_vector(&m, glm::vec3(0.0, 0.0, 1.0)));
+                assert_near(posed.normals[i], [n.x, n.y, n.z]);
+                let t = glm::normalize(transform_vector(&m, glm::vec3(1.0, 0.0, 0.0)));
+                assert_near(
+                    [
+                        posed.tangents[i][0],
+                        posed.tangents[i][1],
+                        posed.tangents[i][2],
+                    ],
+                    [t.x, t.y, t.z],
+                );
+                assert_eq!(posed.tangents[i][3], -1.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,653 @@
+pub mod state_machine {
+
+    //! Data-driven animation states. A config is a JSON document:
+    //!
+    //! ```json
+    //! {
+    //!   "parameters": [{ "name": "speed", "value": 0 }],
+    //!   "masks": [{ "name": "upper_body", "root": "Spine", "weight": 1 }],
+    //!   "states": [
+    //!     { "name": "idle", "clip": "Idle" },
+    //!     { "name": "walk", "clip": "Walk", "speed": 1.2,
+    //!       "layers": [{ "clip": "Wave", "mode": "additive", "weight": 1, "mask": "upper_body" }] }
+    //!   ],
+    //!   "transitions": [
+    //!     { "from": "idle", "to": "walk", "duration": 0.25,
+    //!       "conditions": [{ "parameter": "speed", "op": ">", "value": 0.1 }] },
+    //!     { "from": "*", "to": "idle", "duration": 0.4, "exit_time": 0.9 }
+    //!   ]
+    //! }
+    //! ```
+    //!
+    //! Clips are glTF animation names or indices, mask roots are node names or indices.
+    //! The first state is the initial one. Transitions are checked in file order and never
+    //! interrupt a running cross-fade, so the same inputs always give the same poses.
+
+    use std::fmt;
+
+    use serde_json::Value;
+
+    use crate::animation::animation::AnimationClip;
+    use crate::blending::blending::{add, blend, NodeMask};
+    use crate::scene::scene::{Node, Pose};
+
+    #[derive(Debug)]
+    pub enum ConfigError {
+        Io(std::io::Error),
+        Json(serde_json::Error),
+        Invalid(String),
+    }
+
+    impl fmt::Display for ConfigError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ConfigError::Io(err) => write!(f, "{}", err),
+                ConfigError::Json(err) => write!(f, "{}", err),
+                ConfigError::Invalid(message) => write!(f, "{}", message),
+            }
+        }
+    }
+
+    impl From<std::io::Error> for ConfigError {
+        fn from(err: std::io::Error) -> Self {
+            ConfigError::Io(err)
+        }
+    }
+
+    impl From<serde_json::Error> for ConfigError {
+        fn from(err: serde_json::Error) -> Self {
+            ConfigError::Json(err)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Comparison {
+        Less,
+        LessEqual,
+        Greater,
+        GreaterEqual,
+        Equal,
+        NotEqual,
+    }
+
+    impl Comparison {
+        fn parse(op: &str) -> Option<Self> {
+            Some(match op {
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessEqual,
+                ">" => Comparison::Greater,
+                ">=" => Comparison::GreaterEqual,
+                "==" => Comparison::Equal,
+                "!=" => Comparison::NotEqual,
+                _ => return None,
+            })
+        }
+
+        pub fn test(&self, a: f32, b: f32) -> bool {
+            match self {
+                Comparison::Less => a < b,
+                Comparison::LessEqual => a <= b,
+                Comparison::Greater => a > b,
+                Comparison::GreaterEqual => a >= b,
+                Comparison::Equal => a == b,
+                Comparison::NotEqual => a != b,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Condition {
+        /// Index into `StateMachineConfig::parameters`.
+        pub parameter: usize,
+        pub comparison: Comparison,
+        pub value: f32,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum LayerMode {
+        /// Blends towards the layer clip.
+        Override,
+        /// Adds the layer clip's motion relative to its first frame.
+        Additive,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Layer {
+        pub clip: usize,
+        pub mode: LayerMode,
+        pub weight: f32,
+        /// Index into `StateMachineConfig::masks`, `None` for the whole hierarchy.
+        pub mask: Option<usize>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct State {
+        pub name: String,
+        pub clip: usize,
+        pub looping: bool,
+        pub speed: f32,
+        pub layers: Vec<Layer>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Transition {
+        /// `None` allows the transition from any other state.
+        pub from: Option<usize>,
+        pub to: usize,
+        /// Cross-fade length in seconds.
+        pub duration: f32,
+        /// Normalized time the source state has to reach first.
+        pub exit_time: Option<f32>,
+        pub conditions: Vec<Condition>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Parameter {
+        pub name: String,
+        pub value: f32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct StateMachineConfig {
+        pub parameters: Vec<Parameter>,
+        pub masks: Vec<NodeMask>,
+        pub states: Vec<State>,
+        pub transitions: Vec<Transition>,
+    }
+
+    fn invalid<T>(message: String) -> Result<T, ConfigError> {
+        Err(ConfigError::Invalid(message))
+    }
+
+    fn number(value: Option<&Value>, default: f32) -> f32 {
+        match value {
+            Some(Value::Bool(b)) => *b as u32 as f32,
+            Some(v) => v.as_f64().map_or(default, |v| v as f32),
+            None => default,
+        }
+    }
+
+    fn str_field<'a>(value: &'a Value, key: &str, what: &str) -> Result<&'a str, ConfigError> {
+        match value.get(key).and_then(Value::as_str) {
+            Some(s) => Ok(s),
+            None => invalid(format!("{} is missing \"{}\"", what, key)),
+        }
+    }
+
+    fn array<'a>(root: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+        root.get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+    }
+
+    fn lookup(names: &[String], name: &str, what: &str) -> Result<usize, ConfigError> {
+        match names.iter().position(|n| n == name) {
+            Some(index) => Ok(index),
+            None => invalid(format!("unknown {} \"{}\"", what, name)),
+        }
+    }
+
+    /// Finds a clip by name, or by index when `value` is a number.
+    fn find_clip(clips: &[AnimationClip], value: Option<&Value>) -> Result<usize, ConfigError> {
+        let index = match value {
+            Some(Value::String(name)) => clips
+                .iter()
+                .position(|clip| clip.name.as_deref() == Some(name.as_str())),
+            Some(v) => v.as_u64().map(|i| i as usize).filter(|&i| i < clips.len()),
+            None => None,
+        };
+        match index {
+            Some(index) => Ok(index),
+            None => invalid(format!("unknown clip {}", value.unwrap_or(&Value::Null))),
+        }
+    }
+
+    fn find_node(nodes: &[Node], value: Option<&Value>) -> Result<usize, ConfigError> {
+        let index = match value {
+            Some(Value::String(name)) => nodes
+                .iter()
+                .position(|node| node.name.as_deref() == Some(name.as_str())),
+            Some(v) => v.as_u64().map(|i| i as usize).filter(|&i| i < nodes.len()),
+            None => None,
+        };
+        match index {
+            Some(index) => Ok(index),
+            None => invalid(format!("unknown node {}", value.unwrap_or(&Value::Null))),
+        }
+    }
+
+    impl StateMachineConfig {
+        pub fn load(
+            path: &str,
+            clips: &[AnimationClip],
+            nodes: &[Node],
+        ) -> Result<Self, ConfigError> {
+            let text = std::fs::read_to_string(path)?;
+            Self::parse(&text, clips, nodes)
+        }
+
+        /// Resolves every name against the loaded clips and nodes.
+        pub fn parse(
+            text: &str,
+            clips: &[AnimationClip],
+            nodes: &[Node],
+        ) -> Result<Self, ConfigError> {
+            let root: Value = serde_json::from_str(text)?;
+
+            let mut parameters = Vec::new();
+            for p in array(&root, "parameters") {
+                parameters.push(Parameter {
+                    name: str_field(p, "name", "parameter")?.to_string(),
+                    value: number(p.get("value"), 0.0),
+                });
+            }
+            let parameter_names: Vec<String> = parameters.iter().map(|p| p.name.clone()).collect();
+
+            let mut mask_names = Vec::new();
+            let mut masks = Vec::new();
+            for m in array(&root, "masks") {
+                mask_names.push(str_field(m, "name", "mask")?.to_string());
+                let root_node = find_node(nodes, m.get("root"))?;
+                masks.push(NodeMask::subtree(
+                    nodes,
+                    root_node,
+                    number(m.get("weight"), 1.0),
+                ));
+            }
+
+            let mut state_names = Vec::new();
+            for s in array(&root, "states") {
+                state_names.push(str_field(s, "name", "state")?.to_string());
+            }
+            if state_names.is_empty() {
+                return invalid("the state machine has no states".to_string());
+            }
+
+            let mut states = Vec::new();
+            for (s, name) in array(&root, "states").zip(&state_names) {
+                let mut layers = Vec::new();
+                for l in array(s, "layers") {
+                    let mode = match l.get("mode").and_then(Value::as_str) {
+                        None | Some("override") => LayerMode::Override,
+                        Some("additive") => LayerMode::Additive,
+                        Some(other) => return invalid(format!("unknown layer mode \"{}\"", other)),
+                    };
+                    let mask = match l.get("mask").and_then(Value::as_str) {
+                        Some(mask) => Some(lookup(&mask_names, mask, "mask")?),
+                        None => None,
+                    };
+                    layers.push(Layer {
+                        clip: find_clip(clips, l.get("clip"))?,
+                        mode,
+                        weight: number(l.get("weight"), 1.0),
+                        mask,
+                    });
+                }
+
+                states.push(State {
+                    name: name.clone(),
+                    clip: find_clip(clips, s.get("clip"))?,
+                    looping: s.get("loop").and_then(Value::as_bool).unwrap_or(true),
+                    speed: number(s.get("speed"), 1.0),
+                    layers,
+                });
+            }
+
+            let mut transitions = Vec::new();
+            for t in array(&root, "transitions") {
+                let from = match str_field(t, "from", "transition")? {
+                    "*" => None,
+                    name => Some(lookup(&state_names, name, "state")?),
+                };
+                let to = lookup(&state_names, str_field(t, "to", "transition")?, "state")?;
+
+                let mut conditions = Vec::new();
+                for c in array(t, "conditions") {
+                    let parameter = str_field(c, "parameter", "condition")?;
+                    let op = str_field(c, "op", "condition")?;
+                    let comparison = match Comparison::parse(op) {
+                        Some(comparison) => comparison,
+                        None => return invalid(format!("unknown comparison \"{}\"", op)),
+                    };
+                    conditions.push(Condition {
+                        parameter: lookup(&parameter_names, parameter, "parameter")?,
+                        comparison,
+                        value: number(c.get("value"), 0.0),
+                    });
+                }
+
+                transitions.push(Transition {
+                    from,
+                    to,
+                    duration: number(t.get("duration"), 0.0).max(0.0),
+                    exit_time: t.get("exit_time").and_then(Value::as_f64).map(|v| v as f32),
+                    conditions,
+                });
+            }
+
+            Ok(StateMachineConfig {
+                parameters,
+                masks,
+                states,
+                transitions,
+            })
+        }
+    }
+
+    /// A state and how far into its clip it is, in seconds.
+    #[derive(Clone, Copy, Debug)]
+    struct Playback {
+        state: usize,
+        time: f32,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct Fade {
+        from: Playback,
+        elapsed: f32,
+        duration: f32,
+    }
+
+    pub struct StateMachine {
+        pub config: StateMachineConfig,
+        /// Current values, indexed like `config.parameters`.
+        pub parameters: Vec<f32>,
+        current: Playback,
+        fade: Option<Fade>,
+    }
+
+    fn clip_pose(clip: &AnimationClip, time: f32, rest: &Pose) -> Pose {
+        let mut pose = rest.clone();
+        clip.apply(time, &mut pose);
+        pose
+    }
+
+    impl StateMachine {
+        pub fn new(config: StateMachineConfig) -> Self {
+            StateMachine {
+                parameters: config.parameters.iter().map(|p| p.value).collect(),
+                config,
+                current: Playback {
+                    state: 0,
+                    time: 0.0,
+                },
+                fade: None,
+            }
+        }
+
+        pub fn state_name(&self) -> &str {
+            &self.config.states[self.current.state].name
+        }
+
+        fn advance(&self, playback: &mut Playback, dt: f32, clips: &[AnimationClip]) {
+            let state = &self.config.states[playback.state];
+            let duration = clips[state.clip].duration;
+            playback.time += dt * state.speed;
+            if duration <= 0.0 {
+                playback.time = 0.0;
+            } else if state.looping {
+                playback.time = playback.time.rem_euclid(duration);
+            } else {
+                playback.time = playback.time.clamp(0.0, duration);
+            }
+        }
+
+        fn normalized_time(&self, playback: &Playback, clips: &[AnimationClip]) -> f32 {
+            let duration = clips[self.config.states[playback.state].clip].duration;
+            if duration > 0.0 {
+                playback.time / duration
+            } else {
+                1.0
+            }
+        }
+
+        fn can_take(&self, transition: &Transition, clips: &[AnimationClip]) -> bool {
+            let state = self.current.state;
+            if transition.to == state || transition.from.is_some_and(|from| from != state) {
+                return false;
+            }
+            if let Some(exit_time) = transition.exit_time {
+                if self.normalized_time(&self.current, clips) < exit_time {
+                    return false;
+                }
+            }
+            transition
+                .conditions
+                .iter()
+                .all(|c| c.comparison.test(self.parameters[c.parameter], c.value))
+        }
+
+        /// Steps time by `dt` seconds and takes the first transition whose conditions hold.
+        /// Returns `true` when the state changed.
+        pub fn update(&mut self, dt: f32, clips: &[AnimationClip]) -> bool {
+            let mut current = self.current;
+            self.advance(&mut current, dt, clips);
+            self.current = current;
+
+            if let Some(mut fade) = self.fade {
+                self.advance(&mut fade.from, dt, clips);
+                fade.elapsed += dt;
+                self.fade = if fade.elapsed < fade.duration {
+                    Some(fade)
+                } else {
+                    None
+                };
+            }
+            if self.fade.is_some() {
+                return false;
+            }
+
+            let taken = self
+                .config
+                .transitions
+                .iter()
+                .find(|t| self.can_take(t, clips))
+                .map(|t| (t.to, t.duration));
+            match taken {
+                Some((to, duration)) => {
+                    if duration > 0.0 {
+                        self.fade = Some(Fade {
+                            from: self.current,
+                            elapsed: 0.0,
+                            duration,
+                        });
+                    }
+                    self.current = Playback {
+                        state: to,
+                        time: 0.0,
+                    };
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn state_pose(&self, playback: &Playback, clips: &[AnimationClip], rest: &Pose) -> Pose {
+            let state = &self.config.states[playback.state];
+            let mut pose = clip_pose(&clips[state.clip], playback.time, rest);
+
+            for layer in &state.layers {
+                let clip = &clips[layer.clip];
+                // Layers share the state's clock, wrapped to their own length.
+                let time = if clip.duration > 0.0 {
+                    playback.time.rem_euclid(clip.duration)
+                } else {
+                    0.0
+                };
+                let layer_pose = clip_pose(clip, time, rest);
+                let mask = layer.mask.map(|m| &self.config.masks[m]);
+                pose = match layer.mode {
+                    LayerMode::Override => blend(&pose, &layer_pose, layer.weight, mask),
+                    LayerMode::Additive => {
+                        let reference = clip_pose(clip, 0.0, rest);
+                        add(&pose, &layer_pose, &reference, layer.weight, mask)
+                    }
+                };
+            }
+            pose
+        }
+
+        /// Pose of the current state, cross-faded linearly from the previous one while a
+        /// transition is running.
+        pub fn evaluate(&self, clips: &[AnimationClip], rest: &Pose) -> Pose {
+            let pose = self.state_pose(&self.current, clips, rest);
+            match &self.fade {
+                Some(fade) => {
+                    let from = self.state_pose(&fade.from, clips, rest);
+                    blend(&from, &pose, fade.elapsed / fade.duration, None)
+                }
+                None => pose,
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::animation::animation::{Channel, Interpolation, Property, Sampler};
+        use crate::scene::scene::NodeTransform;
+
+        /// Moves the only node along x between two keys.
+        fn clip(name: &str, from: f32, to: f32) -> AnimationClip {
+            AnimationClip {
+                name: Some(name.to_string()),
+                channels: vec![Channel {
+                    node: 0,
+                    property: Property::Translation,
+                    sampler: Sampler {
+                        times: vec![0.0, 1.0],
+                        values: vec![from, 0.0, 0.0, to, 0.0, 0.0],
+                        components: 3,
+                        interpolation: Interpolation::Linear,
+                    },
+                }],
+                duration: 1.0,
+            }
+        }
+
+        fn clips() -> Vec<AnimationClip> {
+            vec![clip("Idle", 0.0, 1.0), clip("Walk", 10.0, 10.0)]
+        }
+
+        fn nodes() -> Vec<Node> {
+            vec![Node {
+                name: Some("Root".to_string()),
+                parent: None,
+                children: Vec::new(),
+                transform: NodeTransform::default(),
+                weights: Vec::new(),
+                extras: Value::Null,
+            }]
+        }
+
+        fn rest() -> Pose {
+            Pose {
+                transforms: vec![NodeTransform::default()],
+                weights: vec![Vec::new()],
+            }
+        }
+
+        fn machine(text: &str) -> StateMachine {
+            StateMachine::new(StateMachineConfig::parse(text, &clips(), &nodes()).unwrap())
+        }
+
+        fn x(machine: &StateMachine) -> f32 {
+            machine.evaluate(&clips(), &rest()).transforms[0].translation[0]
+        }
+
+        fn assert_near(a: f32, b: f32) {
+            assert!((a - b).abs() < 1e-5, "{} vs {}", a, b);
+        }
+
+        const CROSS_FADE: &str = r#"{
+            "parameters": [{ "name": "speed", "value": 0 }],
+            "states": [{ "name": "idle", "clip": "Idle" }, { "name": "walk", "clip": 1 }],
+            "transitions": [
+                { "from": "idle", "to": "walk", "duration": 0.5,
+                  "conditions": [{ "parameter": "speed", "op": ">", "value": 0.1 }] },
+                { "from": "*", "to": "idle", "duration": 0.5,
+                  "conditions": [{ "parameter": "speed", "op": "<=", "value": 0.1 }] }
+            ]
+        }"#;
+
+        #[test]
+        fn looping_states_wrap_their_clock() {
+            let mut machine = machine(CROSS_FADE);
+            assert_eq!(machine.state_name(), "idle");
+            assert!(!machine.update(1.25, &clips()));
+            assert_near(x(&machine), 0.25);
+        }
+
+        #[test]
+        fn cross_fades_run_for_the_transition_duration() {
+            let clips = clips();
+            let mut machine = machine(CROSS_FADE);
+            assert!(!machine.update(0.1, &clips));
+            machine.parameters[0] = 1.0;
+            assert!(machine.update(0.1, &clips));
+            assert_eq!(machine.state_name(), "walk");
+            // The fade starts from the idle pose and keeps its clock running.
+            assert_near(x(&machine), 0.2);
+            assert!(!machine.update(0.25, &clips));
+            assert_near(x(&machine), 0.45 * 0.5 + 10.0 * 0.5);
+            assert!(!machine.update(0.25, &clips));
+            assert_near(x(&machine), 10.0);
+        }
+
+        #[test]
+        fn running_cross_fades_are_not_interrupted() {
+            let clips = clips();
+            let mut machine = machine(CROSS_FADE);
+            machine.parameters[0] = 1.0;
+            assert!(machine.update(0.0, &clips));
+            machine.parameters[0] = 0.0;
+            assert!(!machine.update(0.25, &clips));
+            assert_eq!(machine.state_name(), "walk");
+            // The fade ends on this step, so the pending transition is taken right away.
+            assert!(machine.update(0.25, &clips));
+            assert_eq!(machine.state_name(), "idle");
+        }
+
+        #[test]
+        fn exit_times_wait_for_the_source_clip() {
+            let clips = clips();
+            let mut machine = machine(
+                r#"{
+                    "states": [
+                        { "name": "idle", "clip": "Idle", "loop": false, "speed": 2 },
+                        { "name": "walk", "clip": "Walk" }
+                    ],
+                    "transitions": [{ "from": "idle", "to": "walk", "exit_time": 0.9 }]
+                }"#,
+            );
+            assert!(!machine.update(0.4, &clips));
+            assert_near(x(&machine), 0.8);
+            // Past the end of a clip that doesn't loop, which holds its last frame.
+            assert!(machine.update(0.2, &clips));
+            // Without a duration the switch is immediate.
+            assert_near(x(&machine), 10.0);
+        }
+
+        #[test]
+        fn unknown_names_are_rejected() {
+            let parse = |text| StateMachineConfig::parse(text, &clips(), &nodes());
+            assert!(matches!(
+                parse(r#"{ "states": [] }"#),
+                Err(ConfigError::Invalid(_))
+            ));
+            assert!(matches!(
+                parse(r#"{ "states": [{ "name": "a", "clip": "Run" }] }"#),
+                Err(ConfigError::Invalid(_))
+            ));
+            assert!(matches!(
+                parse(
+                    r#"{ "states": [{ "name": "a", "clip": 0 }],
+                         "transitions": [{ "from": "a", "to": "b" }] }"#
+                ),
+                Err(ConfigError::Invalid(_))
+            ));
+            assert!(matches!(parse("{"), Err(ConfigError::Json(_))));
+        }
+    }
+}
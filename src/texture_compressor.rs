@@ -0,0 +1,638 @@
+pub mod texture_compressor {
+
+    // DXGI_FORMAT values of the BCn formats, as stored in DDS files.
+    const DXGI_FORMAT_BC1_UNORM: u32 = 71;
+    const DXGI_FORMAT_BC1_UNORM_SRGB: u32 = 72;
+    const DXGI_FORMAT_BC3_UNORM: u32 = 77;
+    const DXGI_FORMAT_BC3_UNORM_SRGB: u32 = 78;
+    const DXGI_FORMAT_BC4_UNORM: u32 = 80;
+    const DXGI_FORMAT_BC5_UNORM: u32 = 83;
+    const DXGI_FORMAT_BC7_UNORM: u32 = 98;
+    const DXGI_FORMAT_BC7_UNORM_SRGB: u32 = 99;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum BcFormat {
+        Bc1,
+        Bc3,
+        Bc4,
+        Bc5,
+        Bc7,
+    }
+
+    impl BcFormat {
+        pub fn block_size(self) -> usize {
+            match self {
+                BcFormat::Bc1 | BcFormat::Bc4 => 8,
+                BcFormat::Bc3 | BcFormat::Bc5 | BcFormat::Bc7 => 16,
+            }
+        }
+
+        /// BC4 and BC5 have no sRGB variant, so `srgb` is ignored for them.
+        pub fn dxgi_format(self, srgb: bool) -> u32 {
+            match (self, srgb) {
+                (BcFormat::Bc1, false) => DXGI_FORMAT_BC1_UNORM,
+                (BcFormat::Bc1, true) => DXGI_FORMAT_BC1_UNORM_SRGB,
+                (BcFormat::Bc3, false) => DXGI_FORMAT_BC3_UNORM,
+                (BcFormat::Bc3, true) => DXGI_FORMAT_BC3_UNORM_SRGB,
+                (BcFormat::Bc4, _) => DXGI_FORMAT_BC4_UNORM,
+                (BcFormat::Bc5, _) => DXGI_FORMAT_BC5_UNORM,
+                (BcFormat::Bc7, false) => DXGI_FORMAT_BC7_UNORM,
+                (BcFormat::Bc7, true) => DXGI_FORMAT_BC7_UNORM_SRGB,
+            }
+        }
+
+        /// Number of leading RGBA channels the format stores.
+        pub fn channels(self) -> usize {
+            match self {
+                BcFormat::Bc4 => 1,
+                BcFormat::Bc5 => 2,
+                BcFormat::Bc1 => 3,
+                BcFormat::Bc3 | BcFormat::Bc7 => 4,
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct RgbaImage {
+        pub width: u32,
+        pub height: u32,
+        pub pixels: Vec<u8>,
+    }
+
+    impl RgbaImage {
+        pub fn from_gltf(data: &gltf::image::Data) -> Option<Self> {
+            use gltf::image::Format;
+
+            let (channels, bytes_per_channel) = match data.format {
+                Format::R8 => (1, 1),
+                Format::R8G8 => (2, 1),
+                Format::R8G8B8 => (3, 1),
+                Format::R8G8B8A8 => (4, 1),
+                Format::R16 => (1, 2),
+                Format::R16G16 => (2, 2),
+                Format::R16G16B16 => (3, 2),
+                Format::R16G16B16A16 => (4, 2),
+                _ => return None,
+            };
+
+            let texel_count = (data.width * data.height) as usize;
+            let mut pixels = Vec::with_capacity(texel_count * 4);
+            for texel in data.pixels.chunks_exact(channels * bytes_per_channel) {
+                let mut rgba = [0u8, 0, 0, 255];
+                for c in 0..channels {
+                    // 16-bit channels are little-endian, keep the high byte.
+                    rgba[c] = texel[c * bytes_per_channel + bytes_per_channel - 1];
+                }
+                if channels == 1 {
+                    rgba[1] = rgba[0];
+                    rgba[2] = rgba[0];
+                }
+                pixels.extend_from_slice(&rgba);
+            }
+
+            Some(RgbaImage {
+                width: data.width,
+                height: data.height,
+                pixels,
+            })
+        }
+
+        /// Decodes a PNG or JPEG file held in memory.
+        pub fn decode(bytes: &[u8]) -> Option<Self> {
+            let image = image::load_from_memory(bytes).ok()?.to_rgba8();
+            Some(RgbaImage {
+                width: image.width(),
+                height: image.height(),
+                pixels: image.into_raw(),
+            })
+        }
+
+        pub fn save_png(&self, path: &std::path::Path) -> image::ImageResult<()> {
+            image::save_buffer(
+                path,
+                &self.pixels,
+                self.width,
+                self.height,
+                image::ColorType::Rgba8,
+            )
+        }
+
+        pub fn has_alpha(&self) -> bool {
+            self.pixels.chunks_exact(4).any(|p| p[3] != 255)
+        }
+
+        fn texel(&self, x: u32, y: u32) -> [u8; 4] {
+            let x = x.min(self.width - 1);
+            let y = y.min(self.height - 1);
+            let i = ((y * self.width + x) * 4) as usize;
+            [
+                self.pixels[i],
+                self.pixels[i + 1],
+                self.pixels[i + 2],
+                self.pixels[i + 3],
+            ]
+        }
+
+        fn block(&self, bx: u32, by: u32) -> [[u8; 4]; 16] {
+            let mut block = [[0u8; 4]; 16];
+            for (i, texel) in block.iter_mut().enumerate() {
+                *texel = self.texel(bx * 4 + (i % 4) as u32, by * 4 + (i / 4) as u32);
+            }
+            block
+        }
+
+        /// 2x2 box filter; odd edges repeat the last row/column.
+        pub fn downsample(&self) -> RgbaImage {
+            let width = (self.width / 2).max(1);
+            let height = (self.height / 2).max(1);
+            let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+            for y in 0..height {
+                for x in 0..width {
+                    let quad = [
+                        self.texel(x * 2, y * 2),
+                        self.texel(x * 2 + 1, y * 2),
+                        self.texel(x * 2, y * 2 + 1),
+                        self.texel(x * 2 + 1, y * 2 + 1),
+                    ];
+                    for c in 0..4 {
+                        let sum: u32 = quad.iter().map(|t| t[c] as u32).sum();
+                        pixels.push(((sum + 2) / 4) as u8);
+                    }
+                }
+            }
+            RgbaImage {
+                width,
+                height,
+                pixels,
+            }
+        }
+    }
+
+    pub fn compress(image: &RgbaImage, format: BcFormat) -> Vec<u8> {
+        let blocks_x = image.width.div_ceil(4);
+        let blocks_y = image.height.div_ceil(4);
+        let mut out = Vec::with_capacity((blocks_x * blocks_y) as usize * format.block_size());
+
+        for by in 0..blocks_y {
+            for bx in 0..blocks_x {
+                let block = image.block(bx, by);
+                match format {
+                    BcFormat::Bc1 => out.extend_from_slice(&encode_bc1(&block)),
+                    BcFormat::Bc3 => {
+                        out.extend_from_slice(&encode_bc4(&channel(&block, 3)));
+                        out.extend_from_slice(&encode_color(&block, false));
+                    }
+                    BcFormat::Bc4 => out.extend_from_slice(&encode_bc4(&channel(&block, 0))),
+                    BcFormat::Bc5 => {
+                        out.extend_from_slice(&encode_bc4(&channel(&block, 0)));
+                        out.extend_from_slice(&encode_bc4(&channel(&block, 1)));
+                    }
+                    BcFormat::Bc7 => out.extend_from_slice(&encode_bc7_mode6(&block)),
+                }
+            }
+        }
+        out
+    }
+
+    pub fn decompress(data: &[u8], width: u32, height: u32, format: BcFormat) -> RgbaImage {
+        let blocks_x = width.div_ceil(4);
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+
+        for (b, bytes) in data.chunks_exact(format.block_size()).enumerate() {
+            let bx = b as u32 % blocks_x;
+            let by = b as u32 / blocks_x;
+            let block = match format {
+                BcFormat::Bc1 => decode_color(bytes, true),
+                BcFormat::Bc3 => {
+                    let mut block = decode_color(&bytes[8..], false);
+                    for (texel, a) in block.iter_mut().zip(decode_bc4(&bytes[..8])) {
+                        texel[3] = a;
+                    }
+                    block
+                }
+                BcFormat::Bc4 => decode_bc4(bytes).map(|r| [r, 0, 0, 255]),
+                BcFormat::Bc5 => {
+                    let r = decode_bc4(&bytes[..8]);
+                    let g = decode_bc4(&bytes[8..]);
+                    let mut block = [[0u8, 0, 0, 255]; 16];
+                    for i in 0..16 {
+                        block[i][0] = r[i];
+                        block[i][1] = g[i];
+                    }
+                    block
+                }
+                BcFormat::Bc7 => decode_bc7_mode6(bytes),
+            };
+
+            for (i, texel) in block.iter().enumerate() {
+                let x = bx * 4 + (i % 4) as u32;
+                let y = by * 4 + (i / 4) as u32;
+                if x < width && y < height {
+                    let o = ((y * width + x) * 4) as usize;
+                    pixels[o..o + 4].copy_from_slice(texel);
+                }
+            }
+        }
+
+        RgbaImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// PSNR in dB over the channels `format` stores. Identical images give infinity.
+    pub fn psnr(reference: &RgbaImage, decoded: &RgbaImage, format: BcFormat) -> f64 {
+        let channels = format.channels();
+        let mut sum = 0f64;
+        let mut count = 0usize;
+        for (a, b) in reference
+            .pixels
+            .chunks_exact(4)
+            .zip(decoded.pixels.chunks_exact(4))
+        {
+            for c in 0..channels {
+                let d = a[c] as f64 - b[c] as f64;
+                sum += d * d;
+                count += 1;
+            }
+        }
+        if count == 0 || sum == 0.0 {
+            return f64::INFINITY;
+        }
+        let mse = sum / count as f64;
+        10.0 * (255.0 * 255.0 / mse).log10()
+    }
+
+    fn channel(block: &[[u8; 4]; 16], c: usize) -> [u8; 16] {
+        let mut values = [0u8; 16];
+        for i in 0..16 {
+            values[i] = block[i][c];
+        }
+        values
+    }
+
+    /// Mean and dominant axis of `points` found by power iteration on the covariance matrix.
+    fn principal_axis<const N: usize>(points: &[[f32; N]]) -> ([f32; N], [f32; N]) {
+        let mut mean = [0f32; N];
+        for p in points {
+            for c in 0..N {
+                mean[c] += p[c];
+            }
+        }
+        for m in &mut mean {
+            *m /= points.len() as f32;
+        }
+
+        let mut cov = [[0f32; N]; N];
+        for p in points {
+            for i in 0..N {
+                for j in 0..N {
+                    cov[i][j] += (p[i] - mean[i]) * (p[j] - mean[j]);
+                }
+            }
+        }
+
+        let mut axis = [1f32; N];
+        for _ in 0..8 {
+            let mut next = [0f32; N];
+            for i in 0..N {
+                for j in 0..N {
+                    next[i] += cov[i][j] * axis[j];
+                }
+            }
+            let len = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+            if len < 1e-6 {
+                break;
+            }
+            for c in 0..N {
+                axis[c] = next[c] / len;
+            }
+        }
+        (mean, axis)
+    }
+
+    /// Endpoints at the extremes of the block's projection onto its principal axis.
+    fn fit_endpoints<const N: usize>(points: &[[f32; N]]) -> ([f32; N], [f32; N]) {
+        let (mean, axis) = principal_axis(points);
+        let mut t_min = f32::MAX;
+        let mut t_max = f32::MIN;
+        for p in points {
+            let t: f32 = (0..N).map(|c| (p[c] - mean[c]) * axis[c]).sum();
+            t_min = t_min.min(t);
+            t_max = t_max.max(t);
+        }
+        let mut e0 = [0f32; N];
+        let mut e1 = [0f32; N];
+        for c in 0..N {
+            e0[c] = (mean[c] + axis[c] * t_min).clamp(0.0, 255.0);
+            e1[c] = (mean[c] + axis[c] * t_max).clamp(0.0, 255.0);
+        }
+        (e0, e1)
+    }
+
+    fn nearest<const N: usize>(palette: &[[u8; N]], texel: &[u8]) -> usize {
+        let mut best = 0;
+        let mut best_dist = u32::MAX;
+        for (i, entry) in palette.iter().enumerate() {
+            let dist: u32 = (0..N)
+                .map(|c| {
+                    let d = entry[c] as i32 - texel[c] as i32;
+                    (d * d) as u32
+                })
+                .sum();
+            if dist < best_dist {
+                best = i;
+                best_dist = dist;
+            }
+        }
+        best
+    }
+
+    fn to_565(c: &[f32; 3]) -> u16 {
+        let r = (c[0] * 31.0 / 255.0 + 0.5) as u16;
+        let g = (c[1] * 63.0 / 255.0 + 0.5) as u16;
+        let b = (c[2] * 31.0 / 255.0 + 0.5) as u16;
+        (r << 11) | (g << 5) | b
+    }
+
+    fn from_565(c: u16) -> [u8; 3] {
+        let r = ((c >> 11) & 31) as u8;
+        let g = ((c >> 5) & 63) as u8;
+        let b = (c & 31) as u8;
+        [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+    }
+
+    /// BC1 interprets `c0 <= c1` as three colors plus transparent black; BC3 never does.
+    fn color_palette(c0: u16, c1: u16, allow_three_color: bool) -> [[u8; 4]; 4] {
+        let a = from_565(c0);
+        let b = from_565(c1);
+        let mut palette = [[0u8; 4]; 4];
+        palette[0] = [a[0], a[1], a[2], 255];
+        palette[1] = [b[0], b[1], b[2], 255];
+        if c0 > c1 || !allow_three_color {
+            for c in 0..3 {
+                palette[2][c] = ((2 * a[c] as u32 + b[c] as u32 + 1) / 3) as u8;
+                palette[3][c] = ((a[c] as u32 + 2 * b[c] as u32 + 1) / 3) as u8;
+            }
+            palette[2][3] = 255;
+            palette[3][3] = 255;
+        } else {
+            for c in 0..3 {
+                palette[2][c] = ((a[c] as u32 + b[c] as u32) / 2) as u8;
+            }
+            palette[2][3] = 255;
+            palette[3] = [0, 0, 0, 0];
+        }
+        palette
+    }
+
+    fn pack_color_block(c0: u16, c1: u16, indices: &[usize; 16]) -> [u8; 8] {
+        let mut bits = 0u32;
+        for (i, index) in indices.iter().enumerate() {
+            bits |= (*index as u32) << (i * 2);
+        }
+        let mut out = [0u8; 8];
+        out[0..2].copy_from_slice(&c0.to_le_bytes());
+        out[2..4].copy_from_slice(&c1.to_le_bytes());
+        out[4..8].copy_from_slice(&bits.to_le_bytes());
+        out
+    }
+
+    fn encode_color(block: &[[u8; 4]; 16], allow_three_color: bool) -> [u8; 8] {
+        let points: Vec<[f32; 3]> = block
+            .iter()
+            .map(|t| [t[0] as f32, t[1] as f32, t[2] as f32])
+            .collect();
+        let (e0, e1) = fit_endpoints(&points);
+        let mut c0 = to_565(&e1);
+        let mut c1 = to_565(&e0);
+        if c0 < c1 {
+            std::mem::swap(&mut c0, &mut c1);
+        }
+
+        let mut indices = [0usize; 16];
+        if c0 != c1 {
+            let palette = color_palette(c0, c1, allow_three_color);
+            let rgb: Vec<[u8; 3]> = palette.iter().map(|p| [p[0], p[1], p[2]]).collect();
+            for (i, texel) in block.iter().enumerate() {
+                indices[i] = nearest(&rgb, texel);
+            }
+        }
+        pack_color_block(c0, c1, &indices)
+    }
+
+    /// Opaque blocks use four-color mode; blocks with cut-out alpha use three colors plus
+    /// transparent black.
+    fn encode_bc1(block: &[[u8; 4]; 16]) -> [u8; 8] {
+        if block.iter().all(|t| t[3] >= 128) {
+            return encode_color(block, true);
+        }
+
+        let opaque: Vec<[f32; 3]> = block
+            .iter()
+            .filter(|t| t[3] >= 128)
+            .map(|t| [t[0] as f32, t[1] as f32, t[2] as f32])
+            .collect();
+        if opaque.is_empty() {
+            return pack_color_block(0, 0xFFFF, &[3; 16]);
+        }
+
+        let (e0, e1) = fit_endpoints(&opaque);
+        let mut c0 = to_565(&e0);
+        let mut c1 = to_565(&e1);
+        if c0 > c1 {
+            std::mem::swap(&mut c0, &mut c1);
+        }
+
+        let palette = color_palette(c0, c1, true);
+        let rgb: Vec<[u8; 3]> = palette[..3].iter().map(|p| [p[0], p[1], p[2]]).collect();
+        let mut indices = [0usize; 16];
+        for (i, texel) in block.iter().enumerate() {
+            indices[i] = if texel[3] < 128 {
+                3
+            } else {
+                nearest(&rgb, texel)
+            };
+        }
+        pack_color_block(c0, c1, &indices)
+    }
+
+    fn decode_color(bytes: &[u8], allow_three_color: bool) -> [[u8; 4]; 16] {
+        let c0 = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let c1 = u16::from_le_bytes([bytes[2], bytes[3]]);
+        let bits = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let palette = color_palette(c0, c1, allow_three_color);
+        let mut block = [[0u8; 4]; 16];
+        for (i, texel) in block.iter_mut().enumerate() {
+            *texel = palette[((bits >> (i * 2)) & 3) as usize];
+        }
+        block
+    }
+
+    /// Only the eight-value mode (`r0 > r1`) is emitted; equal endpoints decode as a constant.
+    fn bc4_palette(r0: u8, r1: u8) -> [[u8; 1]; 8] {
+        let mut palette = [[r0], [r1], [0], [0], [0], [0], [0], [0]];
+        if r0 > r1 {
+            for k in 1..7u32 {
+                palette[k as usize + 1][0] =
+                    (((7 - k) * r0 as u32 + k * r1 as u32 + 3) / 7) as u8;
+            }
+        } else {
+            for entry in palette.iter_mut().skip(2) {
+                entry[0] = r0;
+            }
+        }
+        palette
+    }
+
+    fn encode_bc4(values: &[u8; 16]) -> [u8; 8] {
+        let r0 = *values.iter().max().unwrap();
+        let r1 = *values.iter().min().unwrap();
+        let palette = bc4_palette(r0, r1);
+
+        let mut bits = 0u64;
+        if r0 != r1 {
+            for (i, v) in values.iter().enumerate() {
+                bits |= (nearest(&palette, &[*v]) as u64) << (i * 3);
+            }
+        }
+
+        let mut out = [0u8; 8];
+        out[0] = r0;
+        out[1] = r1;
+        out[2..8].copy_from_slice(&bits.to_le_bytes()[..6]);
+        out
+    }
+
+    fn decode_bc4(bytes: &[u8]) -> [u8; 16] {
+        let palette = bc4_palette(bytes[0], bytes[1]);
+        let mut raw = [0u8; 8];
+        raw[..6].copy_from_slice(&bytes[2..8]);
+        let bits = u64::from_le_bytes(raw);
+        let mut values = [0u8; 16];
+        for (i, v) in values.iter_mut().enumerate() {
+            *v = palette[((bits >> (i * 3)) & 7) as usize][0];
+        }
+        values
+    }
+
+    const BC7_WEIGHTS4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+    fn bc7_palette(e0: [u8; 4], e1: [u8; 4]) -> [[u8; 4]; 16] {
+        let mut palette = [[0u8; 4]; 16];
+        for (i, entry) in palette.iter_mut().enumerate() {
+            let w = BC7_WEIGHTS4[i];
+            for c in 0..4 {
+                entry[c] = (((64 - w) * e0[c] as u32 + w * e1[c] as u32 + 32) >> 6) as u8;
+            }
+        }
+        palette
+    }
+
+    /// Quantizes an endpoint to 7 bits per channel plus a shared p-bit, picking the p-bit
+    /// with the lower error. Returns the 7-bit values, the p-bit and the expanded color.
+    fn bc7_quantize(e: &[f32; 4]) -> ([u8; 4], u8, [u8; 4]) {
+        let mut best = ([0u8; 4], 0u8, [0u8; 4]);
+        let mut best_err = f32::MAX;
+        for p in 0..2u8 {
+            let mut q = [0u8; 4];
+            let mut expanded = [0u8; 4];
+            let mut err = 0f32;
+            for c in 0..4 {
+                q[c] = ((e[c] - p as f32) / 2.0).round().clamp(0.0, 127.0) as u8;
+                expanded[c] = (q[c] << 1) | p;
+                let d = expanded[c] as f32 - e[c];
+                err += d * d;
+            }
+            if err < best_err {
+                best_err = err;
+                best = (q, p, expanded);
+            }
+        }
+        best
+    }
+
+    /// Every block is written in mode 6: one subset, RGBA 7.7.7.7 endpoints with unique
+    /// p-bits and 4-bit indices.
+    fn encode_bc7_mode6(block: &[[u8; 4]; 16]) -> [u8; 16] {
+        let points: Vec<[f32; 4]> = block
+            .iter()
+            .map(|t| [t[0] as f32, t[1] as f32, t[2] as f32, t[3] as f32])
+            .collect();
+        let (e0, e1) = fit_endpoints(&points);
+        let (mut q0, mut p0, mut x0) = bc7_quantize(&e0);
+        let (mut q1, mut p1, mut x1) = bc7_quantize(&e1);
+
+        let palette = bc7_palette(x0, x1);
+        let mut indices = [0usize; 16];
+        for (i, texel) in block.iter().enumerate() {
+            indices[i] = nearest(&palette, texel);
+        }
+
+        // The anchor index is stored with its top bit implied to be zero.
+        if indices[0] >= 8 {
+            std::mem::swap(&mut q0, &mut q1);
+            std::mem::swap(&mut p0, &mut p1);
+            std::mem::swap(&mut x0, &mut x1);
+            for index in indices.iter_mut() {
+                *index = 15 - *index;
+            }
+        }
+
+        let mut bits = 0u128;
+        let mut offset = 0;
+        let mut put = |value: u128, count: u32| {
+            bits |= value << offset;
+            offset += count;
+        };
+
+        put(1 << 6, 7);
+        for c in 0..4 {
+            put(q0[c] as u128, 7);
+            put(q1[c] as u128, 7);
+        }
+        put(p0 as u128, 1);
+        put(p1 as u128, 1);
+        for (i, index) in indices.iter().enumerate() {
+            put(*index as u128, if i == 0 { 3 } else { 4 });
+        }
+
+        bits.to_le_bytes()
+    }
+
+    /// Decodes mode 6 blocks, which is all `compress` emits; other modes decode as black.
+    fn decode_bc7_mode6(bytes: &[u8]) -> [[u8; 4]; 16] {
+        let mut raw = [0u8; 16];
+        raw.copy_from_slice(&bytes[..16]);
+        let bits = u128::from_le_bytes(raw);
+        if bits & 0x7F != 1 << 6 {
+            return [[0, 0, 0, 255]; 16];
+        }
+
+        let mut offset = 7;
+        let mut take = |count: u32| {
+            let value = ((bits >> offset) & ((1u128 << count) - 1)) as u8;
+            offset += count;
+            value
+        };
+
+        let mut q0 = [0u8; 4];
+        let mut q1 = [0u8; 4];
+        for c in 0..4 {
+            q0[c] = take(7);
+            q1[c] = take(7);
+        }
+        let p0 = take(1);
+        let p1 = take(1);
+        let e0 = q0.map(|v| (v << 1) | p0);
+        let e1 = q1.map(|v| (v << 1) | p1);
+        let palette = bc7_palette(e0, e1);
+
+        let mut block = [[0u8; 4]; 16];
+        for (i, texel) in block.iter_mut().enumerate() {
+            *texel = palette[take(if i == 0 { 3 } else { 4 }) as usize];
+        }
+        block
+    }
+}
@@ -0,0 +1,322 @@
+pub mod vertex_compression {
+
+    //! Compact vertex buffers: positions as UNORM16 relative to the mesh bounds, normals
+    //! octahedrally encoded in RG16_SNORM, tangents in RGBA16_SNORM and texture coordinates
+    //! as half floats. The vertex shaders undo the position and normal encodings with
+    //! `decode_vertex()` in base_pass.hlsli.
+    //!
+    //! Positions are off by at most half a 1/65535 step of the bounds along each axis, and
+    //! decoded normals by less than 0.01 degrees.
+
+    use crate::mesh::mesh::{ElementFormat, MeshData, PackedVertices, VertexLayout};
+
+    /// Texture coordinates beyond this magnitude lose too much precision as half floats
+    /// and stay 32-bit.
+    pub const MAX_HALF_TEX_COORD: f32 = 2.0;
+
+    /// Octahedral encoding of the unit vector `n`. Zero vectors encode as +z.
+    pub fn oct_encode(n: [f32; 3]) -> [i16; 2] {
+        let l1 = n[0].abs() + n[1].abs() + n[2].abs();
+        if l1 == 0.0 {
+            return [0, 0];
+        }
+        let (x, y) = (n[0] / l1, n[1] / l1);
+        let sign = |v: f32| if v >= 0.0 { 1.0 } else { -1.0 };
+        // The lower hemisphere folds over the diagonals.
+        let (x, y) = if n[2] < 0.0 {
+            ((1.0 - y.abs()) * sign(x), (1.0 - x.abs()) * sign(y))
+        } else {
+            (x, y)
+        };
+        [x, y].map(|v| (v.clamp(-1.0, 1.0) * 32767.0).round() as i16)
+    }
+
+    /// CPU reference of `oct_decode()` in base_pass.hlsli.
+    pub fn oct_decode(e: [i16; 2]) -> [f32; 3] {
+        let [x, y] = e.map(|v| (v as f32 / 32767.0).max(-1.0));
+        let z = 1.0 - x.abs() - y.abs();
+        let t = (-z).clamp(0.0, 1.0);
+        let unfold = |v: f32| if v >= 0.0 { v - t } else { v + t };
+        let n = [unfold(x), unfold(y), z];
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        n.map(|c| c / length)
+    }
+
+    /// IEEE half float nearest to `value`, ties to even.
+    pub fn f32_to_f16(value: f32) -> u16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) & 0x8000) as u16;
+        let exponent = ((bits >> 23) & 0xff) as i32;
+        let mantissa = bits & 0x7f_ffff;
+        if exponent == 0xff {
+            let nan = if mantissa != 0 { 0x200 } else { 0 };
+            return sign | 0x7c00 | nan;
+        }
+
+        let round = |kept: u32, rest: u32, halfway: u32| {
+            if rest > halfway || (rest == halfway && kept & 1 == 1) {
+                kept + 1
+            } else {
+                kept
+            }
+        };
+        let e = exponent - 127 + 15;
+        if e >= 0x1f {
+            sign | 0x7c00
+        } else if e <= 0 {
+            // Subnormal, or zero once the implicit bit is shifted out.
+            if e < -10 {
+                return sign;
+            }
+            let m = mantissa | 0x80_0000;
+            let shift = (14 - e) as u32;
+            let kept = m >> shift;
+            sign | round(kept, m & ((1 << shift) - 1), 1 << (shift - 1)) as u16
+        } else {
+            // A carry out of the mantissa bumps the exponent, up to infinity.
+            let kept = ((e as u32) << 10) | (mantissa >> 13);
+            sign | round(kept, mantissa & 0x1fff, 0x1000) as u16
+        }
+    }
+
+    /// CPU reference of the R16G16_FLOAT input format.
+    pub fn f16_to_f32(half: u16) -> f32 {
+        let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = ((half >> 10) & 0x1f) as i32;
+        let mantissa = (half & 0x3ff) as f32;
+        sign * match exponent {
+            0 => mantissa * 2f32.powi(-24),
+            0x1f if mantissa == 0.0 => f32::INFINITY,
+            0x1f => f32::NAN,
+            _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+        }
+    }
+
+    fn write<const N: usize>(out: &mut [u8], values: impl IntoIterator<Item = [u8; N]>) {
+        for (o, v) in out.chunks_exact_mut(N).zip(values) {
+            o.copy_from_slice(&v);
+        }
+    }
+
+    /// Packs the vertices of `mesh` in the compact formats, starting from the float
+    /// streams. Quantized accessors are re-encoded like any other attribute.
+    pub fn compress_vertices(mesh: &MeshData) -> PackedVertices {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &mesh.positions {
+            for (c, v) in p.iter().enumerate() {
+                min[c] = min[c].min(*v);
+                max[c] = max[c].max(*v);
+            }
+        }
+        if mesh.positions.is_empty() {
+            (min, max) = ([0.0; 3], [0.0; 3]);
+        }
+        let extent = [0, 1, 2].map(|c| max[c] - min[c]);
+
+        let tex_coord_format = |tex_coords: &[[f32; 2]]| {
+            let fits = tex_coords
+                .iter()
+                .flatten()
+                .all(|v| v.abs() <= MAX_HALF_TEX_COORD);
+            if fits {
+                ElementFormat::Half2
+            } else {
+                ElementFormat::Float2
+            }
+        };
+        let layout = VertexLayout {
+            position: ElementFormat::Unorm16x4,
+            normal: ElementFormat::Snorm16x2,
+            tex_coord0: tex_coord_format(&mesh.tex_coords0),
+            tex_coord1: tex_coord_format(&mesh.tex_coords1),
+            tangent: ElementFormat::Snorm16x4,
+        };
+        let (offsets, stride) = layout.offsets();
+
+        let floats = mesh.vertices();
+        let mut bytes = vec![0u8; stride as usize * mesh.vertex_count()];
+        for (vertex, float) in bytes.chunks_exact_mut(stride as usize).zip(&floats) {
+            let position = [0, 1, 2].map(|c| {
+                let t = if extent[c] > 0.0 {
+                    (float.position[c] - min[c]) / extent[c]
+                } else {
+                    0.0
+                };
+                (t.clamp(0.0, 1.0) * 65535.0).round() as u16
+            });
+            write(
+                &mut vertex[offsets[0] as usize..],
+                position.map(u16::to_le_bytes),
+            );
+            write(
+                &mut vertex[offsets[1] as usize..],
+                oct_encode(float.normal).map(i16::to_le_bytes),
+            );
+            let tangent = float
+                .tangent
+                .map(|v| (v.clamp(-1.0, 1.0) * 32767.0).round() as i16);
+            write(
+                &mut vertex[offsets[4] as usize..],
+                tangent.map(i16::to_le_bytes),
+            );
+            let tex_coords = [
+                (layout.tex_coord0, float.tex_coord0, offsets[2]),
+                (layout.tex_coord1, float.tex_coord1, offsets[3]),
+            ];
+            for (format, tex_coord, offset) in tex_coords {
+                let out = &mut vertex[offset as usize..];
+                if format == ElementFormat::Half2 {
+                    write(out, tex_coord.map(|v| f32_to_f16(v).to_le_bytes()));
+                } else {
+                    write(out, tex_coord.map(f32::to_le_bytes));
+                }
+            }
+        }
+        PackedVertices {
+            layout,
+            bytes,
+            position_offset: min,
+            position_scale: extent,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::f32::consts::TAU;
+
+        use super::*;
+        use crate::math::math::{vec3_cross, vec3_dot, vec3_length, vec3_normalize};
+
+        /// Vertices of a torus around +y with texture coordinates wrapping once each way.
+        /// Compression only looks at the vertices, so there are no triangles.
+        fn torus(major: f32, minor: f32, segments: u32, sides: u32) -> MeshData {
+            let mut mesh = MeshData::default();
+            for segment in 0..=segments {
+                let u = segment as f32 / segments as f32;
+                let (sin, cos) = (TAU * u).sin_cos();
+                for side in 0..=sides {
+                    let v = side as f32 / sides as f32;
+                    let (sin_side, cos_side) = (TAU * v).sin_cos();
+                    let normal = [cos_side * sin, sin_side, cos_side * cos];
+                    let radius = major + minor * cos_side;
+                    mesh.positions
+                        .push([radius * sin, minor * sin_side, radius * cos]);
+                    mesh.normals.push(normal);
+                    mesh.tex_coords0.push([u, v]);
+                }
+            }
+            mesh
+        }
+
+        fn read_u16(bytes: &[u8], at: usize) -> u16 {
+            u16::from_le_bytes([bytes[at], bytes[at + 1]])
+        }
+
+        /// Unit vectors spread over the sphere, plus the axes and the octahedron's edges.
+        fn directions() -> Vec<[f32; 3]> {
+            let golden = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+            let mut directions: Vec<[f32; 3]> = (0..2000)
+                .map(|i| {
+                    let y = 1.0 - (i as f32 + 0.5) / 1000.0;
+                    let r = (1.0 - y * y).sqrt();
+                    let (sin, cos) = (golden * i as f32).sin_cos();
+                    [r * cos, y, r * sin]
+                })
+                .collect();
+            for c in 0..3 {
+                for sign in [1.0, -1.0] {
+                    let mut axis = [0.0; 3];
+                    axis[c] = sign;
+                    directions.push(axis);
+                }
+            }
+            for [x, y] in [[1.0, 1.0], [1.0, -1.0], [-1.0, 1.0], [-1.0, -1.0]] {
+                directions.push(vec3_normalize([x, y, 0.0]));
+                directions.push(vec3_normalize([x, 0.0, y]));
+                directions.push(vec3_normalize([x, y, -1e-3]));
+            }
+            directions
+        }
+
+        #[test]
+        fn decoded_normals_stay_within_a_hundredth_of_a_degree() {
+            let limit = 0.01f32.to_radians();
+            for n in directions() {
+                let d = oct_decode(oct_encode(n));
+                let angle = vec3_length(vec3_cross(n, d)).atan2(vec3_dot(n, d));
+                assert!(angle < limit, "{:?} decodes to {:?}", n, d);
+            }
+            assert_eq!(oct_decode(oct_encode([0.0; 3])), [0.0, 0.0, 1.0]);
+        }
+
+        #[test]
+        fn half_floats_round_to_nearest_even() {
+            for v in [0.0, 1.0, -2.0, 0.5, 65504.0, 2f32.powi(-14), 2f32.powi(-24)] {
+                assert_eq!(f16_to_f32(f32_to_f16(v)), v);
+            }
+            let ulp = 2f32.powi(-10);
+            assert_eq!(f16_to_f32(f32_to_f16(1.0 + ulp * 0.5)), 1.0);
+            assert_eq!(f16_to_f32(f32_to_f16(1.0 + ulp * 1.5)), 1.0 + ulp * 2.0);
+            assert_eq!(f16_to_f32(f32_to_f16(1.0 + ulp * 0.51)), 1.0 + ulp);
+            assert_eq!(f32_to_f16(-0.0), 0x8000);
+            assert_eq!(f32_to_f16(1e-9), 0);
+            assert_eq!(f16_to_f32(f32_to_f16(65519.0)), 65504.0);
+            assert_eq!(f16_to_f32(f32_to_f16(65520.0)), f32::INFINITY);
+            assert_eq!(f16_to_f32(f32_to_f16(f32::NEG_INFINITY)), f32::NEG_INFINITY);
+            assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+
+            // Texture coordinates in range keep 11 significant bits.
+            for i in -2000..=2000 {
+                let v = i as f32 / 1000.0 * MAX_HALF_TEX_COORD + 1e-4;
+                let error = (f16_to_f32(f32_to_f16(v)) - v).abs();
+                assert!(error <= v.abs() * 2f32.powi(-11), "{}", v);
+            }
+        }
+
+        #[test]
+        fn positions_are_off_by_at_most_half_a_step() {
+            let mut mesh = torus(3.0, 0.75, 64, 32);
+            for p in &mut mesh.positions {
+                p[0] += 100.0;
+            }
+            let packed = compress_vertices(&mesh);
+            let (offsets, stride) = packed.layout.offsets();
+            let (offset, scale) = (packed.position_offset, packed.position_scale);
+            for (vertex, p) in packed
+                .bytes
+                .chunks_exact(stride as usize)
+                .zip(&mesh.positions)
+            {
+                for c in 0..3 {
+                    let unorm = read_u16(vertex, offsets[0] as usize + c * 2) as f32 / 65535.0;
+                    let decoded = offset[c] + unorm * scale[c];
+                    // Allow for the f32 rounding of the decode itself.
+                    let limit = scale[c] / 65535.0 * 0.5 + 1e-5 * (offset[c].abs() + scale[c]);
+                    assert!((decoded - p[c]).abs() <= limit, "{} vs {}", decoded, p[c]);
+                }
+            }
+        }
+
+        #[test]
+        fn tangents_keep_their_handedness_and_large_tex_coords_stay_float() {
+            let mut mesh = torus(1.0, 0.25, 8, 4);
+            let n = mesh.vertex_count();
+            mesh.tangents = (0..n)
+                .map(|i| [1.0, 0.0, 0.0, if i % 2 == 0 { 1.0 } else { -1.0 }])
+                .collect();
+            mesh.tex_coords1 = (0..n).map(|i| [i as f32, 0.0]).collect();
+            let packed = compress_vertices(&mesh);
+            assert_eq!(packed.layout.tex_coord0, ElementFormat::Half2);
+            assert_eq!(packed.layout.tex_coord1, ElementFormat::Float2);
+            let (offsets, stride) = packed.layout.offsets();
+            for (i, vertex) in packed.bytes.chunks_exact(stride as usize).enumerate() {
+                let w = read_u16(vertex, offsets[4] as usize + 6) as i16;
+                assert_eq!(w, if i % 2 == 0 { 32767 } else { -32767 });
+                let at = offsets[3] as usize;
+                let u = f32::from_le_bytes(vertex[at..at + 4].try_into().unwrap());
+                assert_eq!(u, i as f32);
+            }
+        }
+    }
+}
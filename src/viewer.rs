@@ -0,0 +1,1615 @@
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+
+use windows::{
+    s,
+    Win32::{
+        Foundation::{BOOL, HWND, LPARAM, LRESULT, S_OK, WPARAM},
+        Graphics::{
+            Direct3D::D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
+            Direct3D11::{
+                ID3D11Buffer, ID3D11DeviceContext, ID3D11InputLayout, ID3D11RasterizerState,
+                ID3D11ShaderResourceView, D3D11_BIND_CONSTANT_BUFFER, D3D11_BIND_INDEX_BUFFER,
+                D3D11_BIND_VERTEX_BUFFER, D3D11_CLEAR_DEPTH, D3D11_CLEAR_STENCIL, D3D11_CULL_BACK,
+                D3D11_FILL_SOLID, D3D11_RASTERIZER_DESC,
+            },
+            Dxgi::Common::{DXGI_FORMAT, DXGI_FORMAT_R16_UINT, DXGI_FORMAT_R32_UINT},
+        },
+        System::LibraryLoader::GetModuleHandleA,
+        UI::WindowsAndMessaging::{
+            CreateWindowExA, DefWindowProcA, DispatchMessageA, GetMessageA, GetWindowLongPtrA,
+            PostQuitMessage, RegisterClassA, SetWindowLongPtrA, ShowWindow, TranslateMessage,
+            CW_USEDEFAULT, GWLP_USERDATA, MSG, SW_SHOWDEFAULT, WINDOW_EX_STYLE, WM_DESTROY,
+            WM_KEYDOWN, WM_LBUTTONDOWN, WM_PAINT, WM_QUIT, WNDCLASSA, WS_OVERLAPPEDWINDOW,
+        },
+    },
+};
+
+use windows::core::{Result as WinResult, Vtable};
+
+use hello_window::animation::animation::{AnimationClip, AnimationPlayer};
+use hello_window::brdf::brdf::spot_scale_offset;
+use hello_window::bvh::bvh::Bvh;
+use hello_window::camera::camera::{Camera, CameraRig, FreeCamera};
+use hello_window::cooker::cooker::cook;
+use hello_window::culling::culling::{mesh_bounds, world_bounds, CullStats, Frustum, WorldBounds};
+use hello_window::gltf_loader::gltf_loader::{load, ImportSettings, LoadError};
+use hello_window::indices::indices::{narrow_indices, split_mesh, MAX_16BIT_VERTICES};
+use hello_window::instancing::instancing::{batch_primitives, Batch};
+use hello_window::lod::lod::{bounding_sphere, LodView};
+use hello_window::math::math::{
+    identity, normal_matrix, to_cols_array, transform_point, vec3_add, vec3_scale,
+};
+use hello_window::mesh::mesh::{
+    MeshData, PackedVertices, SkinVertex, Vertex, VertexLayout, VertexSettings,
+};
+use hello_window::morph::morph::{active_targets, blend, gpu_deltas, MorphSettings};
+use hello_window::picking::picking::{unproject, Pick};
+use hello_window::scene::scene::{
+    world_transforms, AlphaMode, Light, LightKind, Material, Node, Pose,
+    Primitive as ScenePrimitive, Scene, Skin, TextureTransform, TEXTURE_SLOT_COUNT,
+};
+use hello_window::shapes::shapes::Shape;
+use hello_window::skinning::skinning::joint_matrices;
+use hello_window::state_machine::state_machine::{StateMachine, StateMachineConfig};
+use hello_window::vertex_compression::vertex_compression::compress_vertices;
+
+use crate::device_resources::device_resources::{DeviceResources, DEFAULT_HEIGHT, DEFAULT_WIDTH};
+
+#[allow(unused_macros)]
+macro_rules! result {
+    ($x:expr) => {
+        match $x {
+            Ok(t) => t,
+            Err(err) => {
+                panic!(
+                    "DirectX command failed: {}:{}. Error: {}",
+                    file!(),
+                    line!(),
+                    err.message()
+                );
+            }
+        }
+    };
+}
+
+struct Primitive {
+    /// `(first index, index count, error)` in `ib` of the full mesh, then of every level of
+    /// detail.
+    lods: Vec<(u32, u32, f32)>,
+    /// Bounding sphere of the vertices, for measuring LOD errors on screen.
+    sphere: ([f32; 3], f32),
+    /// Mesh-space `(min, max)` box, including how far morph targets reach.
+    aabb: ([f32; 3], [f32; 3]),
+    /// World volumes of every instance, for frustum culling. `None` for skinned
+    /// primitives, which the skin can move anywhere.
+    bounds: Option<Vec<WorldBounds>>,
+    /// Mesh-space triangles of the full mesh for `Model::bvh`, empty for skinned
+    /// primitives.
+    triangles: Vec<[[f32; 3]; 3]>,
+    /// Index of the first triangle in the scene primitive's index list, nonzero for the
+    /// later parts of split primitives.
+    first_triangle: usize,
+    /// glTF mesh and primitive index.
+    source: Option<(usize, usize)>,
+    /// Current world transforms of the instances in `batch`.
+    worlds: Vec<glm::Mat4>,
+    num_vertices: u32,
+    vb: Option<ID3D11Buffer>,
+    /// Vertex size in `vb`, which holds `Vertex`es unless the primitive kept quantized
+    /// attributes.
+    stride: u32,
+    /// Input layout of packed vertices, `None` for `Vertex`.
+    il: Option<ID3D11InputLayout>,
+    ib: ID3D11Buffer,
+    ib_format: DXGI_FORMAT,
+    /// Index into `Model::materials` for the active variant.
+    material: usize,
+    /// `material` without an active variant, then for each of `Model::variants`.
+    variant_materials: Vec<usize>,
+    node: Option<usize>,
+    /// `InstanceData` stream of `batch`. Updated every frame when the model is animated,
+    /// immutable otherwise.
+    instances: ID3D11Buffer,
+    num_instances: u32,
+    batch: Batch,
+    /// `SkinVertex` stream and index into `Model::skins` for GPU skinned primitives.
+    skin: Option<(ID3D11Buffer, usize)>,
+    morph: Option<GpuMorph>,
+    /// `VertexConstants` decoding the attributes in `vb`.
+    vertex_constants: ID3D11Buffer,
+}
+
+enum GpuMorph {
+    /// Deltas read by the vertex shader; `constants` holds the active targets.
+    Gpu {
+        deltas: ID3D11ShaderResourceView,
+        constants: ID3D11Buffer,
+    },
+    /// Blended on the CPU into the primitive's vertex buffer whenever the weights change.
+    Cpu {
+        mesh: Box<MeshData>,
+        weights: Vec<f32>,
+    },
+}
+
+const MAX_MORPH_TARGETS: usize = 8;
+
+/// Matches `morph_target` in morph.hlsli.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct GpuMorphTarget {
+    index: u32,
+    weight: f32,
+    padding: [f32; 2],
+}
+
+/// Matches `morph_constants` in morph.hlsli. Targets past `MAX_MORPH_TARGETS` are dropped.
+#[repr(C)]
+struct MorphConstants {
+    count: u32,
+    vertex_count: u32,
+    padding: [f32; 2],
+    targets: [GpuMorphTarget; MAX_MORPH_TARGETS],
+}
+
+impl MorphConstants {
+    fn new(active: &[(usize, f32)], vertex_count: usize) -> Self {
+        let mut constants = MorphConstants {
+            count: active.len().min(MAX_MORPH_TARGETS) as u32,
+            vertex_count: vertex_count as u32,
+            padding: [0.0; 2],
+            targets: [GpuMorphTarget::default(); MAX_MORPH_TARGETS],
+        };
+        for (gpu, &(index, weight)) in constants.targets.iter_mut().zip(active) {
+            gpu.index = index as u32;
+            gpu.weight = weight;
+        }
+        constants
+    }
+}
+
+const MATERIAL_UNLIT: u32 = 1 << 16;
+const MATERIAL_ALPHA_MASK: u32 = 1 << 17;
+
+/// Matches `material_constants` in base_pass.hlsli.
+#[repr(C)]
+struct MaterialConstants {
+    base_color_factor: [f32; 4],
+    emissive_factor: [f32; 3],
+    emissive_strength: f32,
+    metallic_factor: f32,
+    roughness_factor: f32,
+    alpha_cutoff: f32,
+    flags: u32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    ior: f32,
+    specular_factor: f32,
+    specular_color_factor: [f32; 3],
+    transmission_factor: f32,
+    sheen_color_factor: [f32; 3],
+    sheen_roughness_factor: f32,
+    clearcoat_factor: f32,
+    clearcoat_roughness_factor: f32,
+    clearcoat_normal_scale: f32,
+    thickness_factor: f32,
+    attenuation_color: [f32; 3],
+    attenuation_distance: f32,
+    uv_transform: [[f32; 4]; TEXTURE_SLOT_COUNT * 2],
+}
+
+impl MaterialConstants {
+    fn new(material: &Material) -> Self {
+        let mut flags = 0;
+        let mut uv_transform = [[0f32; 4]; TEXTURE_SLOT_COUNT * 2];
+        for (i, slot) in material.texture_slots().iter().enumerate() {
+            let (rows, uv_set) = match slot {
+                Some(slot) => {
+                    flags |= 1 << i;
+                    (slot.transform.rows(), slot.uv_set())
+                }
+                None => (TextureTransform::default().rows(), 0),
+            };
+            uv_transform[i * 2] = [rows[0][0], rows[0][1], rows[0][2], uv_set as f32];
+            uv_transform[i * 2 + 1] = [rows[1][0], rows[1][1], rows[1][2], 0.0];
+        }
+        if material.unlit {
+            flags |= MATERIAL_UNLIT;
+        }
+        if material.alpha_mode == AlphaMode::Mask {
+            flags |= MATERIAL_ALPHA_MASK;
+        }
+
+        let clearcoat = material.clearcoat.unwrap_or_default();
+        let sheen = material.sheen.unwrap_or_default();
+        let specular = material.specular.map_or((1.0, [1.0; 3]), |s| (s.factor, s.color_factor));
+        let volume = material.volume.map_or((0.0, [1.0; 3], 0.0), |v| {
+            let distance = if v.attenuation_distance.is_finite() {
+                v.attenuation_distance
+            } else {
+                0.0
+            };
+            (v.thickness_factor, v.attenuation_color, distance)
+        });
+
+        MaterialConstants {
+            base_color_factor: material.base_color_factor,
+            emissive_factor: material.emissive_factor,
+            emissive_strength: material.emissive_strength,
+            metallic_factor: material.metallic_factor,
+            roughness_factor: material.roughness_factor,
+            alpha_cutoff: material.alpha_cutoff,
+            flags,
+            normal_scale: material.normal_scale,
+            occlusion_strength: material.occlusion_strength,
+            ior: material.ior,
+            specular_factor: specular.0,
+            specular_color_factor: specular.1,
+            transmission_factor: material.transmission.map_or(0.0, |t| t.factor),
+            sheen_color_factor: sheen.color_factor,
+            sheen_roughness_factor: sheen.roughness_factor,
+            clearcoat_factor: clearcoat.factor,
+            clearcoat_roughness_factor: clearcoat.roughness_factor,
+            clearcoat_normal_scale: clearcoat.normal_scale,
+            thickness_factor: volume.0,
+            attenuation_color: volume.1,
+            attenuation_distance: volume.2,
+            uv_transform,
+        }
+    }
+}
+
+struct GpuMaterial {
+    constants: ID3D11Buffer,
+    textures: Vec<Option<ID3D11ShaderResourceView>>,
+}
+
+/// Matches the `INSTANCE_*` elements of `vs_in` in base_pass.hlsli.
+#[repr(C)]
+struct InstanceData {
+    world: [[f32; 4]; 4],
+    normal_matrix: [[f32; 3]; 3],
+}
+
+impl InstanceData {
+    fn new(world: &glm::Mat4) -> Self {
+        let n = to_cols_array(&normal_matrix(world));
+        InstanceData {
+            world: to_cols_array(world),
+            normal_matrix: [0, 1, 2].map(|c| [n[c][0], n[c][1], n[c][2]]),
+        }
+    }
+}
+
+const MAX_JOINTS: usize = 256;
+
+/// Matches `skin_constants` in skinned_vs.hlsl.
+#[repr(C)]
+struct SkinConstants {
+    joint_matrices: [[[f32; 4]; 4]; MAX_JOINTS],
+}
+
+impl SkinConstants {
+    fn new(skin: &Skin, worlds: &[glm::Mat4]) -> Self {
+        let mut constants = SkinConstants {
+            joint_matrices: [[[0.0; 4]; 4]; MAX_JOINTS],
+        };
+        for (gpu, m) in constants
+            .joint_matrices
+            .iter_mut()
+            .zip(joint_matrices(skin, worlds))
+        {
+            *gpu = to_cols_array(&m);
+        }
+        constants
+    }
+}
+
+struct GpuSkin {
+    skin: Skin,
+    palette: ID3D11Buffer,
+}
+
+const MAX_LIGHTS: usize = 16;
+
+const LIGHT_DIRECTIONAL: u32 = 0;
+const LIGHT_POINT: u32 = 1;
+const LIGHT_SPOT: u32 = 2;
+
+/// Matches `light` in lights.hlsli.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct GpuLight {
+    position: [f32; 3],
+    /// Zero for infinite range.
+    range: f32,
+    direction: [f32; 3],
+    kind: u32,
+    color: [f32; 3],
+    intensity: f32,
+    spot_scale: f32,
+    spot_offset: f32,
+    padding: [f32; 2],
+}
+
+impl GpuLight {
+    fn new(light: &Light) -> Self {
+        let (kind, spot_scale, spot_offset) = match light.kind {
+            LightKind::Directional => (LIGHT_DIRECTIONAL, 0.0, 0.0),
+            LightKind::Point => (LIGHT_POINT, 0.0, 0.0),
+            LightKind::Spot {
+                inner_cone_angle,
+                outer_cone_angle,
+            } => {
+                let (scale, offset) = spot_scale_offset(inner_cone_angle, outer_cone_angle);
+                (LIGHT_SPOT, scale, offset)
+            }
+        };
+
+        GpuLight {
+            position: light.position,
+            range: light.range.unwrap_or(0.0),
+            direction: light.direction,
+            kind,
+            color: light.color,
+            intensity: light.intensity,
+            spot_scale,
+            spot_offset,
+            padding: [0.0; 2],
+        }
+    }
+}
+
+/// Matches `light_constants` in lights.hlsli. Lights past `MAX_LIGHTS` are dropped.
+#[repr(C)]
+struct LightConstants {
+    count: u32,
+    padding: [u32; 3],
+    lights: [GpuLight; MAX_LIGHTS],
+}
+
+impl LightConstants {
+    fn new(lights: &[Light]) -> Self {
+        let mut constants = LightConstants {
+            count: lights.len().min(MAX_LIGHTS) as u32,
+            padding: [0; 3],
+            lights: [GpuLight::default(); MAX_LIGHTS],
+        };
+        for (gpu, light) in constants.lights.iter_mut().zip(lights) {
+            *gpu = GpuLight::new(light);
+        }
+        constants
+    }
+}
+
+/// Matches `frame_constants` in base_pass.hlsli.
+#[repr(C)]
+struct FrameConstants {
+    view_projection: [[f32; 4]; 4],
+    camera_position: [f32; 3],
+    padding: f32,
+}
+
+/// Matches `vertex_constants` in base_pass.hlsli.
+#[repr(C)]
+struct VertexConstants {
+    position_offset: [f32; 3],
+    octahedral_normal: u32,
+    position_scale: [f32; 3],
+    padding: f32,
+}
+
+impl VertexConstants {
+    fn new(packed: Option<&PackedVertices>) -> Self {
+        match packed {
+            Some(packed) => VertexConstants {
+                position_offset: packed.position_offset,
+                octahedral_normal: packed.layout.octahedral_normal() as u32,
+                position_scale: packed.position_scale,
+                padding: 0.0,
+            },
+            None => VertexConstants {
+                position_offset: [0.0; 3],
+                octahedral_normal: 0,
+                position_scale: [1.0; 3],
+                padding: 0.0,
+            },
+        }
+    }
+}
+
+/// Matches `highlight_constants` in base_pass.hlsli.
+#[repr(C)]
+struct HighlightConstants {
+    color: [f32; 4],
+    instance: u32,
+    padding: [f32; 3],
+}
+
+/// Blended over the selected instance, by its alpha.
+const HIGHLIGHT_COLOR: [f32; 4] = [1.0, 0.6, 0.0, 0.5];
+
+impl HighlightConstants {
+    fn new(color: [f32; 4], instance: u32) -> Self {
+        HighlightConstants {
+            color,
+            instance,
+            padding: [0.0; 3],
+        }
+    }
+}
+
+/// Why a model could not be shown.
+#[derive(Debug)]
+enum ModelError {
+    Load(LoadError),
+    Device(windows::core::Error),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Load(err) => write!(f, "{}", err),
+            ModelError::Device(err) => write!(f, "DirectX error: {}", err.message()),
+        }
+    }
+}
+
+impl From<LoadError> for ModelError {
+    fn from(err: LoadError) -> Self {
+        ModelError::Load(err)
+    }
+}
+
+impl From<windows::core::Error> for ModelError {
+    fn from(err: windows::core::Error) -> Self {
+        ModelError::Device(err)
+    }
+}
+
+struct Model {
+    primitives: Vec<Primitive>,
+    materials: Vec<GpuMaterial>,
+    lights: ID3D11Buffer,
+    cameras: Vec<Camera>,
+    skins: Vec<GpuSkin>,
+    /// Bound for primitives without morph targets.
+    no_morph: ID3D11Buffer,
+    morph_settings: MorphSettings,
+    nodes: Vec<Node>,
+    rest_pose: Pose,
+    animations: Vec<AnimationClip>,
+    /// KHR_materials_variants names and the active one.
+    variants: Vec<String>,
+    variant: Option<usize>,
+    bounds: Option<([f32; 3], [f32; 3])>,
+    /// World-space triangles of every instance of the non-skinned primitives, in
+    /// `world_triangles` order.
+    bvh: Bvh,
+}
+
+impl Model {
+    /// A generated shape with the default material, lit by the key light.
+    fn from_shape(
+        shape: Shape,
+        dr: &DeviceResources,
+        vertex_settings: VertexSettings,
+    ) -> WinResult<Self> {
+        let scene = Scene {
+            primitives: vec![ScenePrimitive {
+                mesh: shape.mesh(),
+                material: None,
+                node: None,
+                skin: None,
+                world: identity(),
+                source: None,
+                instances: Vec::new(),
+                variant_materials: Vec::new(),
+            }],
+            lights: vec![Light::key_light()],
+            ..Default::default()
+        };
+
+        Model::from_scene(&scene, dr, MorphSettings::default(), vertex_settings)
+    }
+
+    fn from_gltf(
+        path: &str,
+        dr: &DeviceResources,
+        import_settings: &ImportSettings,
+        morph_settings: MorphSettings,
+        vertex_settings: VertexSettings,
+    ) -> Result<Self, ModelError> {
+        let scene = load(path, import_settings)?;
+        print!("{}", scene.report);
+        Ok(Model::from_scene(
+            &scene,
+            dr,
+            morph_settings,
+            vertex_settings,
+        )?)
+    }
+
+    fn from_scene(
+        scene: &Scene,
+        dr: &DeviceResources,
+        morph_settings: MorphSettings,
+        vertex_settings: VertexSettings,
+    ) -> WinResult<Self> {
+        let device = &dr.device;
+        let mut textures = Vec::new();
+        for (i, image) in scene.images.iter().enumerate() {
+            textures.push(DeviceResources::create_texture(
+                device,
+                image,
+                scene.is_srgb_image(i),
+            )?);
+        }
+
+        // The glTF default material goes last, for primitives without one.
+        let fallback = Material::default();
+        let mut materials = Vec::new();
+        for material in scene.materials.iter().chain(std::iter::once(&fallback)) {
+            let constants = DeviceResources::create_buffer(
+                device,
+                &[MaterialConstants::new(material)],
+                D3D11_BIND_CONSTANT_BUFFER,
+            )?;
+            let textures = material
+                .texture_slots()
+                .iter()
+                .map(|slot| slot.map(|slot| textures[slot.image].clone()))
+                .collect();
+            materials.push(GpuMaterial {
+                constants,
+                textures,
+            });
+        }
+
+        let mut skins = Vec::new();
+        // Index into `skins` for every scene skin, `None` for the ones drawn unskinned.
+        let mut gpu_skins = Vec::new();
+        for (i, skin) in scene.skins.iter().enumerate() {
+            if skin.joints.len() > MAX_JOINTS {
+                println!(
+                    "Skin {} has {} joints, more than {}; drawing it unskinned",
+                    i,
+                    skin.joints.len(),
+                    MAX_JOINTS
+                );
+                gpu_skins.push(None);
+                continue;
+            }
+            gpu_skins.push(Some(skins.len()));
+            skins.push(GpuSkin {
+                skin: skin.clone(),
+                palette: DeviceResources::create_constant_buffer::<SkinConstants>(device)?,
+            });
+        }
+
+        let mut primitives = Vec::new();
+        // Shared by primitives with the same packed layout and skinning.
+        let mut input_layouts: HashMap<(VertexLayout, bool), ID3D11InputLayout> = HashMap::new();
+        let rest_worlds = world_transforms(&scene.nodes, &scene.rest_pose().transforms);
+        // Primitives too large for 16-bit indices are drawn in parts when asked to.
+        let mut parts = Vec::new();
+        for batch in batch_primitives(&scene.primitives) {
+            let mesh = &scene.primitives[batch.primitive].mesh;
+            if vertex_settings.split_16bit && mesh.vertex_count() > MAX_16BIT_VERTICES {
+                let mut first_triangle = 0;
+                for part in split_mesh(mesh, MAX_16BIT_VERTICES) {
+                    let triangles = part.triangle_count();
+                    parts.push((batch.clone(), Cow::Owned(part), first_triangle));
+                    first_triangle += triangles;
+                }
+            } else {
+                parts.push((batch, Cow::Borrowed(mesh), 0));
+            }
+        }
+        for (batch, mesh, first_triangle) in parts {
+            let prim = &scene.primitives[batch.primitive];
+            let gpu_skin = prim.skin.and_then(|i| gpu_skins[i]);
+            let skin = match (gpu_skin, mesh.skin_vertices()) {
+                (Some(index), Some(vertices)) => Some((
+                    DeviceResources::create_buffer(device, &vertices, D3D11_BIND_VERTEX_BUFFER)?,
+                    index,
+                )),
+                _ => None,
+            };
+            // Skinned vertices come out of the skin in world space.
+            let worlds = if skin.is_some() {
+                vec![identity()]
+            } else {
+                batch.worlds(&rest_worlds)
+            };
+            let instance_data: Vec<InstanceData> = worlds.iter().map(InstanceData::new).collect();
+            let aabb = mesh_bounds(&mesh);
+            let triangles = if skin.is_none() {
+                let corner = |i: u32| mesh.positions[i as usize];
+                let triangles = mesh.indices.chunks_exact(3);
+                triangles
+                    .map(|t| [corner(t[0]), corner(t[1]), corner(t[2])])
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            let morph = if mesh.morph_targets.is_empty() || prim.node.is_none() {
+                None
+            } else if morph_settings.cpu {
+                Some(GpuMorph::Cpu {
+                    mesh: Box::new((*mesh).clone()),
+                    weights: Vec::new(),
+                })
+            } else {
+                Some(GpuMorph::Gpu {
+                    deltas: DeviceResources::create_buffer_srv(device, &gpu_deltas(&mesh))?,
+                    constants: DeviceResources::create_constant_buffer::<MorphConstants>(device)?,
+                })
+            };
+
+            // CPU morphing re-uploads `Vertex`es, so those primitives stay unpacked.
+            let cpu_morph = matches!(morph, Some(GpuMorph::Cpu { .. }));
+            let packed = if cpu_morph {
+                None
+            } else if vertex_settings.compress {
+                Some(compress_vertices(&mesh))
+            } else if vertex_settings.dequantize {
+                None
+            } else {
+                mesh.packed_vertices()
+            };
+            let vertex_constants = DeviceResources::create_buffer(
+                device,
+                &[VertexConstants::new(packed.as_ref())],
+                D3D11_BIND_CONSTANT_BUFFER,
+            )?;
+            let (vb, stride, il) = match packed {
+                Some(packed) => {
+                    let key = (packed.layout, skin.is_some());
+                    if let Entry::Vacant(entry) = input_layouts.entry(key) {
+                        entry.insert(dr.create_packed_input_layout(&key.0, key.1)?);
+                    }
+                    (
+                        DeviceResources::create_buffer(
+                            device,
+                            &packed.bytes,
+                            D3D11_BIND_VERTEX_BUFFER,
+                        )?,
+                        packed.layout.offsets().1,
+                        Some(input_layouts[&key].clone()),
+                    )
+                }
+                None => {
+                    let vertices = mesh.vertices();
+                    let vb = if cpu_morph {
+                        DeviceResources::create_default_buffer(
+                            device,
+                            &vertices,
+                            D3D11_BIND_VERTEX_BUFFER,
+                        )?
+                    } else {
+                        DeviceResources::create_buffer(device, &vertices, D3D11_BIND_VERTEX_BUFFER)?
+                    };
+                    (vb, std::mem::size_of::<Vertex>() as u32, None)
+                }
+            };
+            // Levels of detail follow the full index list in the same buffer.
+            let mut indices = mesh.indices.clone();
+            let mut lods = vec![(0, indices.len() as u32, 0.0)];
+            for lod in &mesh.lods {
+                lods.push((indices.len() as u32, lod.indices.len() as u32, lod.error));
+                indices.extend_from_slice(&lod.indices);
+            }
+            // 16-bit indices whenever the vertices allow, LODs included.
+            let (ib, ib_format) = match narrow_indices(&indices) {
+                Some(indices) => (
+                    DeviceResources::create_buffer(device, &indices, D3D11_BIND_INDEX_BUFFER)?,
+                    DXGI_FORMAT_R16_UINT,
+                ),
+                None => (
+                    DeviceResources::create_buffer(device, &indices, D3D11_BIND_INDEX_BUFFER)?,
+                    DXGI_FORMAT_R32_UINT,
+                ),
+            };
+            let instances = if scene.animations.is_empty() || skin.is_some() {
+                DeviceResources::create_buffer(device, &instance_data, D3D11_BIND_VERTEX_BUFFER)?
+            } else {
+                DeviceResources::create_default_buffer(
+                    device,
+                    &instance_data,
+                    D3D11_BIND_VERTEX_BUFFER,
+                )?
+            };
+
+            // The last material stands in for the glTF default material.
+            let variant_materials = std::iter::once(None)
+                .chain((0..scene.variants.len()).map(Some))
+                .map(|variant| {
+                    prim.variant_material(variant)
+                        .unwrap_or(scene.materials.len())
+                })
+                .collect::<Vec<_>>();
+            primitives.push(Primitive {
+                lods,
+                sphere: bounding_sphere(&mesh.positions),
+                aabb,
+                bounds: skin.is_none().then(|| world_bounds(aabb, &worlds)),
+                triangles,
+                first_triangle,
+                source: prim.source,
+                worlds,
+                num_vertices: mesh.vertex_count() as u32,
+                vb: Some(vb),
+                stride,
+                il,
+                ib,
+                ib_format,
+                material: variant_materials[0],
+                variant_materials,
+                node: prim.node,
+                instances,
+                num_instances: instance_data.len() as u32,
+                batch,
+                skin,
+                morph,
+                vertex_constants,
+            });
+        }
+
+        let lights = DeviceResources::create_buffer(
+            device,
+            &[LightConstants::new(&scene.lights)],
+            D3D11_BIND_CONSTANT_BUFFER,
+        )?;
+
+        let bvh = Bvh::build(world_triangles(&primitives));
+        Ok(Model {
+            primitives,
+            materials,
+            lights,
+            cameras: scene.cameras.clone(),
+            skins,
+            no_morph: DeviceResources::create_buffer(
+                device,
+                &[MorphConstants::new(&[], 0)],
+                D3D11_BIND_CONSTANT_BUFFER,
+            )?,
+            morph_settings,
+            nodes: scene.nodes.clone(),
+            rest_pose: scene.rest_pose(),
+            animations: scene.animations.clone(),
+            variants: scene.variants.clone(),
+            variant: None,
+            bounds: scene.bounds(),
+            bvh,
+        })
+    }
+
+    /// Nearest triangle of a non-skinned primitive along the ray from `origin` in the
+    /// unit `direction`.
+    fn pick(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<Pick> {
+        let hit = self.bvh.intersect_ray(origin, direction, f32::MAX)?;
+        // BVH triangles are numbered in `world_triangles` order.
+        let mut first = 0;
+        for (index, prim) in self.primitives.iter().enumerate() {
+            let count = prim.triangles.len() * prim.worlds.len();
+            if hit.triangle >= first + count {
+                first += count;
+                continue;
+            }
+            let local = hit.triangle - first;
+            let instance = local / prim.triangles.len();
+            return Some(Pick {
+                primitive: index,
+                instance,
+                node: prim.batch.instances[instance].node,
+                source: prim.source,
+                triangle: prim.first_triangle + local % prim.triangles.len(),
+                barycentrics: [1.0 - hit.u - hit.v, hit.u, hit.v],
+                point: vec3_add(origin, vec3_scale(direction, hit.t)),
+            });
+        }
+        None
+    }
+
+    /// Switches every primitive to the materials of `variant`, an index into `variants`,
+    /// or back to the default materials for `None`.
+    fn set_variant(&mut self, variant: Option<usize>) {
+        self.variant = variant.filter(|&v| v < self.variants.len());
+        let slot = self.variant.map_or(0, |v| v + 1);
+        for prim in &mut self.primitives {
+            prim.material = prim.variant_materials[slot];
+        }
+    }
+
+    fn variant_name(&self) -> &str {
+        self.variant.map_or("default", |v| &self.variants[v])
+    }
+}
+
+// The windows crate's wrappers take slices of bound views only; these also unbind the
+// slots that are `None`.
+
+unsafe fn vs_set_shader_resources(
+    context: &ID3D11DeviceContext,
+    start: u32,
+    views: &[Option<ID3D11ShaderResourceView>],
+) {
+    (Vtable::vtable(context).VSSetShaderResources)(
+        Vtable::as_raw(context),
+        start,
+        views.len() as u32,
+        views.as_ptr() as *const _,
+    );
+}
+
+unsafe fn ps_set_shader_resources(
+    context: &ID3D11DeviceContext,
+    start: u32,
+    views: &[Option<ID3D11ShaderResourceView>],
+) {
+    (Vtable::vtable(context).PSSetShaderResources)(
+        Vtable::as_raw(context),
+        start,
+        views.len() as u32,
+        views.as_ptr() as *const _,
+    );
+}
+
+/// Triangles of `primitives` placed by each of their current instance transforms.
+fn world_triangles(primitives: &[Primitive]) -> Vec<[[f32; 3]; 3]> {
+    let mut triangles = Vec::new();
+    for prim in primitives {
+        for world in &prim.worlds {
+            triangles.extend(prim.triangles.iter().map(|t| {
+                t.map(|p| {
+                    let w = transform_point(world, glm::vec3(p[0], p[1], p[2]));
+                    [w.x, w.y, w.z]
+                })
+            }));
+        }
+    }
+    triangles
+}
+
+struct App {
+    dr: DeviceResources,
+    model: Model,
+    /// Counter-clockwise then clockwise front faces, indexed by `Batch::mirrored`.
+    rs: Vec<ID3D11RasterizerState>,
+    frame: ID3D11Buffer,
+    cameras: CameraRig,
+    player: AnimationPlayer,
+    /// Replaces `player` when a state machine config was given.
+    state_machine: Option<StateMachine>,
+    /// Largest on-screen error in pixels a level of detail may have to be drawn.
+    lod_threshold: f32,
+    /// Draws the last frame made and skipped.
+    cull_stats: CullStats,
+    /// What the last click hit.
+    selection: Option<Pick>,
+    /// `HighlightConstants` of the selected primitive, and the ones of all others.
+    highlight: ID3D11Buffer,
+    no_highlight: ID3D11Buffer,
+    last_frame: std::time::Instant,
+}
+
+const VK_SPACE: usize = 0x20;
+const VK_1: usize = 0x31;
+const VK_9: usize = 0x39;
+const VK_A: usize = 0x41;
+const VK_C: usize = 0x43;
+const VK_D: usize = 0x44;
+const VK_E: usize = 0x45;
+const VK_I: usize = 0x49;
+const VK_L: usize = 0x4C;
+const VK_N: usize = 0x4E;
+const VK_Q: usize = 0x51;
+const VK_S: usize = 0x53;
+const VK_V: usize = 0x56;
+const VK_W: usize = 0x57;
+const VK_OEM_PLUS: usize = 0xBB;
+const VK_OEM_MINUS: usize = 0xBD;
+const VK_LEFT: usize = 0x25;
+const VK_UP: usize = 0x26;
+const VK_RIGHT: usize = 0x27;
+const VK_DOWN: usize = 0x28;
+
+impl App {
+    /// Advances the current clip and uploads the posed transforms, joint palettes and
+    /// morph weights. Static models keep their immutable instance buffers.
+    fn animate(&mut self, dt: f32) {
+        let animated = !self.model.animations.is_empty();
+        let morphed = self
+            .model
+            .primitives
+            .iter()
+            .any(|prim| prim.morph.is_some());
+        if !animated && !morphed && self.model.skins.is_empty() {
+            return;
+        }
+
+        let mut pose = self.model.rest_pose.clone();
+        if let Some(machine) = &mut self.state_machine {
+            if machine.update(dt, &self.model.animations) {
+                println!("State: {}", machine.state_name());
+            }
+            pose = machine.evaluate(&self.model.animations, &self.model.rest_pose);
+        } else if let Some(clip) = self.model.animations.get(self.player.clip) {
+            self.player.advance(dt, clip.duration);
+            clip.apply(self.player.time, &mut pose);
+        }
+        let worlds = world_transforms(&self.model.nodes, &pose.transforms);
+
+        for skin in &self.model.skins {
+            self.dr
+                .update_buffer(&skin.palette, &SkinConstants::new(&skin.skin, &worlds));
+        }
+
+        let max_targets = self.model.morph_settings.max_active_targets;
+        for prim in &mut self.model.primitives {
+            let (node, morph) = match (prim.node, &mut prim.morph) {
+                (Some(node), Some(morph)) => (node, morph),
+                _ => continue,
+            };
+            let weights = &pose.weights[node];
+            match morph {
+                GpuMorph::Gpu { constants, .. } => {
+                    let vertex_count = prim.num_vertices as usize;
+                    let active = active_targets(weights, max_targets);
+                    self.dr
+                        .update_buffer(constants, &MorphConstants::new(&active, vertex_count));
+                }
+                GpuMorph::Cpu {
+                    mesh,
+                    weights: applied,
+                } => {
+                    if *applied != *weights {
+                        let active = active_targets(weights, max_targets);
+                        let vertices = blend(mesh, &active).vertices();
+                        if let Some(vb) = &prim.vb {
+                            self.dr.update_buffer_slice(vb, &vertices);
+                        }
+                        *applied = weights.clone();
+                    }
+                }
+            }
+        }
+
+        if !animated {
+            return;
+        }
+        let mut moved = false;
+        for prim in &mut self.model.primitives {
+            if prim.skin.is_none() && prim.batch.instances.iter().any(|i| i.node.is_some()) {
+                moved = true;
+                prim.worlds = prim.batch.worlds(&worlds);
+                prim.bounds = Some(world_bounds(prim.aabb, &prim.worlds));
+                let instances: Vec<InstanceData> =
+                    prim.worlds.iter().map(InstanceData::new).collect();
+                self.dr.update_buffer_slice(&prim.instances, &instances);
+            }
+        }
+        if moved {
+            let triangles = world_triangles(&self.model.primitives);
+            self.model.bvh.refit(triangles);
+        }
+        for camera in &mut self.cameras.cameras {
+            camera.world = worlds[camera.node];
+        }
+    }
+
+    /// Draws the current frame into the back buffer without presenting it.
+    fn render(&mut self) {
+        unsafe {
+            let dr = &self.dr;
+
+            dr.context.ClearDepthStencilView(
+                &dr.dsv,
+                (D3D11_CLEAR_DEPTH | D3D11_CLEAR_STENCIL).0,
+                1f32,
+                0,
+            );
+
+            let clear_color = [1f32, 0f32, 1f32, 1f32];
+            dr.context
+                .ClearRenderTargetView(dr.rtv.first(), clear_color.as_ptr());
+
+            let aspect = dr.viewport.Width / dr.viewport.Height;
+            let position = self.cameras.position();
+            let view_projection = self.cameras.view_projection(aspect);
+            let frame = FrameConstants {
+                view_projection: to_cols_array(&view_projection),
+                camera_position: [position.x, position.y, position.z],
+                padding: 0.0,
+            };
+            dr.update_buffer(&self.frame, &frame);
+            dr.context
+                .VSSetConstantBuffers(3, Some(std::slice::from_ref(&self.frame)));
+            dr.context
+                .PSSetConstantBuffers(3, Some(std::slice::from_ref(&self.frame)));
+
+            dr.context.PSSetShader(&dr.ps, None);
+            dr.context
+                .IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            dr.context.OMSetRenderTargets(Some(&dr.rtv), &dr.dsv);
+            dr.context.RSSetViewports(Some(&[dr.viewport]));
+            dr.context
+                .PSSetSamplers(0, Some(std::slice::from_ref(&dr.sampler)));
+            dr.context
+                .PSSetConstantBuffers(2, Some(std::slice::from_ref(&self.model.lights)));
+
+            if self.rs.is_empty() {
+                for front_ccw in [true, false] {
+                    let desc = D3D11_RASTERIZER_DESC {
+                        FillMode: D3D11_FILL_SOLID,
+                        CullMode: D3D11_CULL_BACK,
+                        FrontCounterClockwise: BOOL::from(front_ccw),
+                        DepthBias: 0,
+                        DepthBiasClamp: 0f32,
+                        SlopeScaledDepthBias: 0f32,
+                        DepthClipEnable: BOOL::from(false),
+                        ScissorEnable: BOOL::from(false),
+                        MultisampleEnable: BOOL::from(false),
+                        AntialiasedLineEnable: BOOL::from(false),
+                    };
+                    let mut rs = None;
+                    result!(dr.device.CreateRasterizerState(&desc, Some(&mut rs)));
+                    self.rs.extend(rs);
+                }
+            }
+
+            let lod_view = LodView {
+                eye: position,
+                projection: self.cameras.projection(aspect),
+                viewport_height: dr.viewport.Height,
+                threshold: self.lod_threshold,
+            };
+            let frustum = Frustum::from_view_projection(&view_projection);
+            if let Some(pick) = &self.selection {
+                let highlight = HighlightConstants::new(HIGHLIGHT_COLOR, pick.instance as u32);
+                dr.update_buffer(&self.highlight, &highlight);
+            }
+            let mut cull_stats = CullStats::default();
+            let offsets = [0u32; 3];
+            for (index, prim) in self.model.primitives.iter().enumerate() {
+                // Skinned primitives are always drawn.
+                let culled = match &prim.bounds {
+                    Some(bounds) => !bounds.iter().any(|b| frustum.is_visible(b)),
+                    None => false,
+                };
+                if culled {
+                    cull_stats.culled += 1;
+                    continue;
+                }
+                cull_stats.visible += 1;
+                dr.context
+                    .RSSetState(&self.rs[usize::from(prim.batch.mirrored)]);
+                let material = &self.model.materials[prim.material];
+                let strides = [
+                    prim.stride,
+                    std::mem::size_of::<SkinVertex>() as u32,
+                    std::mem::size_of::<InstanceData>() as u32,
+                ];
+                let buffers = [
+                    prim.vb.clone(),
+                    prim.skin.as_ref().map(|(skin_vb, _)| skin_vb.clone()),
+                    Some(prim.instances.clone()),
+                ];
+                dr.context.IASetVertexBuffers(
+                    0,
+                    3,
+                    Some(buffers.as_ptr()),
+                    Some(strides.as_ptr()),
+                    Some(offsets.as_ptr()),
+                );
+                match &prim.skin {
+                    Some((_, skin)) => {
+                        dr.context.VSSetShader(&dr.skinned_vs, None);
+                        dr.context
+                            .IASetInputLayout(prim.il.as_ref().unwrap_or(&dr.skinned_il));
+                        let palette = self.model.skins[*skin].palette.clone();
+                        dr.context.VSSetConstantBuffers(4, Some(&[palette]));
+                    }
+                    None => {
+                        dr.context.VSSetShader(&dr.vs, None);
+                        dr.context
+                            .IASetInputLayout(prim.il.as_ref().unwrap_or(&dr.il));
+                    }
+                }
+                let (morph_constants, morph_deltas) = match &prim.morph {
+                    Some(GpuMorph::Gpu { deltas, constants }) => {
+                        (constants.clone(), Some(deltas.clone()))
+                    }
+                    _ => (self.model.no_morph.clone(), None),
+                };
+                dr.context.VSSetConstantBuffers(5, Some(&[morph_constants]));
+                dr.context
+                    .VSSetConstantBuffers(6, Some(std::slice::from_ref(&prim.vertex_constants)));
+                vs_set_shader_resources(&dr.context, 0, &[morph_deltas]);
+                dr.context.IASetIndexBuffer(&prim.ib, prim.ib_format, 0);
+                dr.context
+                    .PSSetConstantBuffers(0, Some(std::slice::from_ref(&material.constants)));
+                ps_set_shader_resources(&dr.context, 0, &material.textures);
+                let highlight = match &self.selection {
+                    Some(pick) if pick.primitive == index => &self.highlight,
+                    _ => &self.no_highlight,
+                };
+                dr.context
+                    .PSSetConstantBuffers(7, Some(std::slice::from_ref(highlight)));
+                let lod = if prim.lods.len() > 1 {
+                    let errors: Vec<f32> = prim.lods.iter().map(|lod| lod.2).collect();
+                    lod_view.select(&errors, prim.sphere, &prim.worlds)
+                } else {
+                    0
+                };
+                let (first_index, num_indices, _) = prim.lods[lod];
+                dr.context
+                    .DrawIndexedInstanced(num_indices, prim.num_instances, first_index, 0, 0);
+            }
+            self.cull_stats = cull_stats;
+        }
+    }
+
+    /// Flips parameter `index` between zero and one.
+    fn toggle_parameter(&mut self, index: usize) {
+        if let Some(machine) = &mut self.state_machine {
+            if let Some(value) = machine.parameters.get_mut(index) {
+                *value = if *value != 0.0 { 0.0 } else { 1.0 };
+                println!("{} = {}", machine.config.parameters[index].name, value);
+            }
+        }
+    }
+
+    fn print_playback(&self) {
+        if let Some(machine) = &self.state_machine {
+            println!("State: {} (keys 1-9 toggle parameters)", machine.state_name());
+            return;
+        }
+        if let Some(clip) = self.model.animations.get(self.player.clip) {
+            let state = if self.player.playing {
+                "playing"
+            } else {
+                "paused"
+            };
+            let mode = if self.player.looping {
+                "looping"
+            } else {
+                "once"
+            };
+            println!(
+                "Animation: {} ({}, {}, {}x)",
+                clip.display_name(self.player.clip),
+                state,
+                mode,
+                self.player.speed
+            );
+        }
+    }
+
+    /// Selects what is under the cursor at client position `x`, `y`, or clears the
+    /// selection.
+    fn on_click(&mut self, x: f32, y: f32) {
+        let viewport = [self.dr.viewport.Width, self.dr.viewport.Height];
+        let view_projection = self.cameras.view_projection(viewport[0] / viewport[1]);
+        // Through the pixel center.
+        self.selection = unproject(&view_projection, [x + 0.5, y + 0.5], viewport)
+            .and_then(|(origin, direction)| self.model.pick(origin, direction));
+        let pick = match &self.selection {
+            Some(pick) => pick,
+            None => {
+                println!("Picked nothing");
+                return;
+            }
+        };
+        let node = match pick.node {
+            Some(node) => {
+                let name = self.model.nodes[node].name.as_deref().unwrap_or("unnamed");
+                format!("node {} ({})", node, name)
+            }
+            None => "no node".to_string(),
+        };
+        let mesh = match pick.source {
+            Some((mesh, primitive)) => format!("mesh {} primitive {}", mesh, primitive),
+            None => "no mesh".to_string(),
+        };
+        let [u, v, w] = pick.barycentrics;
+        let [x, y, z] = pick.point;
+        println!(
+            "Picked {}, {}, triangle {} at ({:.3}, {:.3}, {:.3}), barycentrics \
+             ({:.2}, {:.2}, {:.2})",
+            node, mesh, pick.triangle, x, y, z, u, v, w
+        );
+    }
+
+    fn on_key(&mut self, key: usize) {
+        let step = self
+            .model
+            .bounds
+            .map_or(0.1, |(min, max)| {
+                let extent = glm::vec3(max[0] - min[0], max[1] - min[1], max[2] - min[2]);
+                glm::length(extent) * 0.02
+            })
+            .max(1e-3);
+        let turn = 2f32.to_radians();
+
+        match key {
+            VK_C => {
+                self.cameras.cycle();
+                println!("Camera: {}", self.cameras.name());
+            }
+            VK_I => println!("Draws: {}", self.cull_stats),
+            VK_W => self.cameras.free.move_by(step, 0.0, 0.0),
+            VK_S => self.cameras.free.move_by(-step, 0.0, 0.0),
+            VK_D => self.cameras.free.move_by(0.0, step, 0.0),
+            VK_A => self.cameras.free.move_by(0.0, -step, 0.0),
+            VK_E => self.cameras.free.move_by(0.0, 0.0, step),
+            VK_Q => self.cameras.free.move_by(0.0, 0.0, -step),
+            VK_LEFT => self.cameras.free.rotate(turn, 0.0),
+            VK_RIGHT => self.cameras.free.rotate(-turn, 0.0),
+            VK_UP => self.cameras.free.rotate(0.0, turn),
+            VK_DOWN => self.cameras.free.rotate(0.0, -turn),
+            VK_SPACE => {
+                let duration = self
+                    .model
+                    .animations
+                    .get(self.player.clip)
+                    .map_or(0.0, |clip| clip.duration);
+                self.player.toggle_pause(duration);
+                self.print_playback();
+            }
+            VK_L => {
+                self.player.looping = !self.player.looping;
+                self.print_playback();
+            }
+            VK_N => {
+                self.player.next_clip(self.model.animations.len());
+                self.print_playback();
+            }
+            VK_OEM_PLUS => {
+                self.player.scale_speed(2.0);
+                self.print_playback();
+            }
+            VK_OEM_MINUS => {
+                self.player.scale_speed(0.5);
+                self.print_playback();
+            }
+            VK_V if !self.model.variants.is_empty() => {
+                let next = match self.model.variant {
+                    None => Some(0),
+                    Some(v) if v + 1 < self.model.variants.len() => Some(v + 1),
+                    Some(_) => None,
+                };
+                self.model.set_variant(next);
+                println!("Variant: {}", self.model.variant_name());
+            }
+            VK_1..=VK_9 => self.toggle_parameter(key - VK_1),
+            _ => {}
+        }
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    u_msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    match u_msg {
+        WM_DESTROY => {
+            PostQuitMessage(0);
+            LRESULT(0)
+        }
+        WM_PAINT => {
+            unsafe {
+                let ptr = GetWindowLongPtrA(hwnd, GWLP_USERDATA);
+                if let Some(mut app) = std::ptr::NonNull::<App>::new(ptr as _) {
+                    let app = app.as_mut();
+
+                    let now = std::time::Instant::now();
+                    app.animate((now - app.last_frame).as_secs_f32());
+                    app.last_frame = now;
+
+                    app.render();
+                    if S_OK != app.dr.swapchain.Present(1, 0) {
+                        panic!("Failed to present!");
+                    }
+                }
+            }
+            LRESULT(0)
+        }
+        WM_KEYDOWN => {
+            if w_param == WPARAM(0x1B) {
+                unsafe { PostQuitMessage(0) }
+            } else {
+                let ptr = GetWindowLongPtrA(hwnd, GWLP_USERDATA);
+                if let Some(mut app) = std::ptr::NonNull::<App>::new(ptr as _) {
+                    app.as_mut().on_key(w_param.0);
+                }
+            }
+            LRESULT(0)
+        }
+        WM_LBUTTONDOWN => {
+            let ptr = GetWindowLongPtrA(hwnd, GWLP_USERDATA);
+            if let Some(mut app) = std::ptr::NonNull::<App>::new(ptr as _) {
+                // Signed client coordinates in the low and high words.
+                let x = (l_param.0 & 0xffff) as i16 as f32;
+                let y = ((l_param.0 >> 16) & 0xffff) as i16 as f32;
+                app.as_mut().on_click(x, y);
+            }
+            LRESULT(0)
+        }
+        _ => DefWindowProcA(hwnd, u_msg, w_param, l_param),
+    }
+}
+
+pub fn run() -> WinResult<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("cook") {
+        if args.len() != 4 {
+            println!("Usage: {} cook <input.gltf> <output_dir>", args[0]);
+            std::process::exit(1);
+        }
+        match cook(std::path::Path::new(&args[2]), std::path::Path::new(&args[3])) {
+            Ok(report) => print!("{}", report),
+            Err(err) => {
+                println!("Error: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    let mut morph_settings = MorphSettings::default();
+    let mut vertex_settings = VertexSettings::default();
+    let mut import_settings = ImportSettings::default();
+    let mut lod_threshold = 1.0;
+    let mut state_machine_path = None;
+    let mut variant_name = None;
+    let mut offscreen_path = None;
+    let mut shape = None;
+    let mut path = "C:\\Source\\glTF-Sample-Models\\2.0\\Triangle\\glTF\\Triangle.gltf".to_string();
+    let mut options = args.iter().skip(1);
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--morph-cpu" => morph_settings.cpu = true,
+            "--dequantize" => vertex_settings.dequantize = true,
+            "--split-16bit" => vertex_settings.split_16bit = true,
+            "--compress-vertices" => vertex_settings.compress = true,
+            "--optimize" => import_settings.optimize = true,
+            "--meshlets" => import_settings.meshlets = true,
+            "--max-morph-targets" => match options.next().and_then(|n| n.parse().ok()) {
+                Some(count) => morph_settings.max_active_targets = count,
+                None => {
+                    println!("--max-morph-targets expects a target count");
+                    std::process::exit(1);
+                }
+            },
+            "--crease-angle" => match options.next().and_then(|a| a.parse::<f32>().ok()) {
+                Some(degrees) => import_settings.normals.crease_angle = degrees.to_radians(),
+                None => {
+                    println!("--crease-angle expects an angle in degrees");
+                    std::process::exit(1);
+                }
+            },
+            "--lods" => match options.next().and_then(|n| n.parse().ok()) {
+                Some(count) => import_settings.lods.count = count,
+                None => {
+                    println!("--lods expects a level count");
+                    std::process::exit(1);
+                }
+            },
+            "--lod-error" => match options.next().and_then(|p| p.parse().ok()) {
+                Some(pixels) => lod_threshold = pixels,
+                None => {
+                    println!("--lod-error expects a size in pixels");
+                    std::process::exit(1);
+                }
+            },
+            "--state-machine" => match options.next() {
+                Some(path) => state_machine_path = Some(path.clone()),
+                None => {
+                    println!("--state-machine expects a config file");
+                    std::process::exit(1);
+                }
+            },
+            "--variant" => match options.next() {
+                Some(name) => variant_name = Some(name.clone()),
+                None => {
+                    println!("--variant expects a variant name");
+                    std::process::exit(1);
+                }
+            },
+            "--offscreen" => match options.next() {
+                Some(path) => offscreen_path = Some(path.clone()),
+                None => {
+                    println!("--offscreen expects an output PNG path");
+                    std::process::exit(1);
+                }
+            },
+            "--shape" => match options.next().map(|name| (name, Shape::parse(name))) {
+                Some((_, Some(parsed))) => shape = Some(parsed),
+                _ => {
+                    let names: Vec<&str> = Shape::ALL.iter().map(|s| s.name()).collect();
+                    println!("--shape expects one of {}", names.join(", "));
+                    std::process::exit(1);
+                }
+            },
+            model if !model.starts_with("--") => path = model.to_string(),
+            _ => {
+                println!(
+                    "Usage: {} [--morph-cpu] [--max-morph-targets <count>] [--dequantize] \
+                     [--compress-vertices] [--split-16bit] [--crease-angle <degrees>] \
+                     [--optimize] [--lods <count>] [--meshlets] [--lod-error <pixels>] \
+                     [--state-machine <config.json>] [--variant <name>] \
+                     [--offscreen <output.png>] [--shape <name>] [model.gltf|model.glb]",
+                    args[0]
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let class_name = s!("Sample Class Name");
+    let mut wc = WNDCLASSA {
+        ..Default::default()
+    };
+
+    let instance = unsafe { GetModuleHandleA(None)? };
+
+    wc.lpfnWndProc = Some(window_proc);
+    wc.hInstance = instance;
+    wc.lpszClassName = class_name;
+
+    unsafe {
+        RegisterClassA(&wc);
+    }
+
+    let hwnd = unsafe {
+        CreateWindowExA(
+            WINDOW_EX_STYLE::default(),
+            class_name,
+            s!("Lear to Program Windows"),
+            WS_OVERLAPPEDWINDOW,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            DEFAULT_WIDTH as i32,
+            DEFAULT_HEIGHT as i32,
+            None,
+            None,
+            instance,
+            None,
+        )
+    };
+
+    // Offscreen renders go to the back buffer of a window that is never shown.
+    if offscreen_path.is_none() {
+        unsafe {
+            ShowWindow(hwnd, SW_SHOWDEFAULT);
+        }
+    }
+
+    let device_resources = DeviceResources::bind_to_wnd(hwnd)?;
+    let loaded = match shape {
+        Some(shape) => {
+            path = shape.name().to_string();
+            Model::from_shape(shape, &device_resources, vertex_settings).map_err(ModelError::from)
+        }
+        None => Model::from_gltf(
+            &path,
+            &device_resources,
+            &import_settings,
+            morph_settings,
+            vertex_settings,
+        ),
+    };
+    let mut model = match loaded {
+        Ok(model) => model,
+        Err(err) => {
+            println!("Failed to load {}: {}", path, err);
+            std::process::exit(1);
+        }
+    };
+    if let Some(name) = &variant_name {
+        match model.variants.iter().position(|variant| variant == name) {
+            Some(index) => model.set_variant(Some(index)),
+            None => {
+                println!(
+                    "{} has no variant named {}, available: {}",
+                    path,
+                    name,
+                    model.variants.join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+    let state_machine = state_machine_path.map(|config_path| {
+        match StateMachineConfig::load(&config_path, &model.animations, &model.nodes) {
+            Ok(config) => StateMachine::new(config),
+            Err(err) => {
+                println!("Failed to load {}: {}", config_path, err);
+                std::process::exit(1);
+            }
+        }
+    });
+    let free_camera = match model.bounds {
+        Some((min, max)) => FreeCamera::framing(min, max),
+        None => FreeCamera::framing([-1.0; 3], [1.0; 3]),
+    };
+    let cameras = CameraRig::new(model.cameras.clone(), free_camera);
+    println!("Camera: {} (press C to cycle)", cameras.name());
+
+    let frame =
+        DeviceResources::create_constant_buffer::<FrameConstants>(&device_resources.device)?;
+    let highlight =
+        DeviceResources::create_constant_buffer::<HighlightConstants>(&device_resources.device)?;
+    let no_highlight = DeviceResources::create_buffer(
+        &device_resources.device,
+        &[HighlightConstants::new([0.0; 4], 0)],
+        D3D11_BIND_CONSTANT_BUFFER,
+    )?;
+    let mut app = App {
+        dr: device_resources,
+        model,
+        rs: Vec::new(),
+        frame,
+        cameras,
+        player: AnimationPlayer::default(),
+        state_machine,
+        lod_threshold,
+        cull_stats: CullStats::default(),
+        selection: None,
+        highlight,
+        no_highlight,
+        last_frame: std::time::Instant::now(),
+    };
+
+    if let Some(output) = offscreen_path {
+        app.animate(0.0);
+        app.render();
+        match app.dr.read_back()?.save_png(std::path::Path::new(&output)) {
+            Ok(()) => println!("Wrote {} ({} variant)", output, app.model.variant_name()),
+            Err(err) => {
+                println!("Failed to write {}: {}", output, err);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    app.print_playback();
+    if !app.model.variants.is_empty() {
+        println!(
+            "Variant: {} (press V to cycle {} variants)",
+            app.model.variant_name(),
+            app.model.variants.len()
+        );
+    }
+
+    unsafe {
+        SetWindowLongPtrA(hwnd, GWLP_USERDATA, &app as *const _ as _);
+    }
+
+    let mut msg = MSG::default();
+
+    loop {
+        unsafe {
+            GetMessageA(&mut msg, None, 0, 0);
+            TranslateMessage(&msg);
+            DispatchMessageA(&msg);
+
+            if msg.message == WM_QUIT {
+                break;
+            }
+        }
+    }
+
+    println!("All is OK!");
+
+    Ok(())
+}